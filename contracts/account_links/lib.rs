@@ -0,0 +1,216 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::account_links::{AccountLinksRef, Error};
+
+#[ink::contract]
+mod account_links {
+    use hub_types::HubError;
+    use ink::primitives::{AccountIdMapper, H160};
+    use ink::storage::Mapping;
+
+    /// Events
+    /// Emitted when an address proposes a link to a native account
+    #[ink(event)]
+    pub struct LinkProposed {
+        #[ink(topic)]
+        address: H160,
+        account: AccountId,
+    }
+    /// Emitted when the native account accepts a link
+    #[ink(event)]
+    pub struct AccountLinked {
+        #[ink(topic)]
+        address: H160,
+        #[ink(topic)]
+        native: H160,
+        account: AccountId,
+    }
+    /// Emitted when either side removes a link
+    #[ink(event)]
+    pub struct AccountUnlinked {
+        #[ink(topic)]
+        address: H160,
+        #[ink(topic)]
+        native: H160,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the address or native account is already linked
+        AlreadyLinked,
+        /// Emitted when the address has not proposed a link
+        LinkNotFound,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::AlreadyLinked => HubError::AlreadyExists,
+                Error::LinkNotFound => HubError::NotFound,
+            }
+        }
+    }
+
+    /// Links between H160 addresses and native Substrate accounts
+    ///
+    /// A native `AccountId` calls contracts from the H160 address derived
+    /// from it, which differs from any EVM-style address the same user
+    /// holds. Once both sides agree, hub contracts treat the derived
+    /// address as acting for the linked one.
+    #[ink(storage)]
+    pub struct AccountLinks {
+        // Native account each address proposed to link, awaiting acceptance
+        proposals: Mapping<H160, AccountId>,
+        // Derived address of a linked native account -> address it acts for
+        links: Mapping<H160, H160>,
+        accounts: Mapping<H160, AccountId>,
+    }
+
+    impl AccountLinks {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                proposals: Mapping::default(),
+                links: Mapping::default(),
+                accounts: Mapping::default(),
+            }
+        }
+
+        /// Propose linking the caller's address to a native account
+        ///
+        /// Takes effect once the native account calls `accept_link`.
+        #[ink(message)]
+        pub fn propose_link(&mut self, account: AccountId) -> Result<()> {
+            let address = self.env().caller();
+            if self.accounts.contains(address) {
+                return Err(Error::AlreadyLinked);
+            }
+            if Self::native_address(&account) == address {
+                return Err(Error::InvalidInput);
+            }
+
+            self.proposals.insert(address, &account);
+            self.env().emit_event(LinkProposed { address, account });
+
+            Ok(())
+        }
+
+        /// Accept a link proposed by `address` to the caller's native account
+        #[ink(message)]
+        pub fn accept_link(&mut self, address: H160) -> Result<()> {
+            let native = self.env().caller();
+            let account = self.proposals.get(address).ok_or(Error::LinkNotFound)?;
+            if Self::native_address(&account) != native {
+                return Err(Error::Unauthorized);
+            }
+            if self.links.contains(native) || self.accounts.contains(address) {
+                return Err(Error::AlreadyLinked);
+            }
+
+            self.proposals.remove(address);
+            self.links.insert(native, &address);
+            self.accounts.insert(address, &account);
+
+            self.env().emit_event(AccountLinked {
+                address,
+                native,
+                account,
+            });
+
+            Ok(())
+        }
+
+        /// Remove the caller's link, from either side
+        #[ink(message)]
+        pub fn unlink(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let (address, native) = if let Some(address) = self.links.get(caller) {
+                (address, caller)
+            } else if let Some(account) = self.accounts.get(caller) {
+                (caller, Self::native_address(&account))
+            } else {
+                return Err(Error::LinkNotFound);
+            };
+
+            self.links.remove(native);
+            self.accounts.remove(address);
+
+            self.env().emit_event(AccountUnlinked { address, native });
+
+            Ok(())
+        }
+
+        /// Address a caller acts for, if it is a linked native account
+        #[ink(message)]
+        pub fn resolve(&self, caller: H160) -> Option<H160> {
+            self.links.get(caller)
+        }
+
+        /// Whether `caller` is `address` or the native account linked to it
+        #[ink(message)]
+        pub fn acts_for(&self, caller: H160, address: H160) -> bool {
+            caller == address || self.links.get(caller) == Some(address)
+        }
+
+        /// Native account linked to an address
+        #[ink(message)]
+        pub fn get_account(&self, address: H160) -> Option<AccountId> {
+            self.accounts.get(address)
+        }
+
+        /// Native account an address proposed to link, if not accepted yet
+        #[ink(message)]
+        pub fn get_proposal(&self, address: H160) -> Option<AccountId> {
+            self.proposals.get(address)
+        }
+
+        /// Address a native account calls contracts from
+        fn native_address(account: &AccountId) -> H160 {
+            AccountIdMapper::to_address(account.as_ref())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn native_account_acts_for_linked_address() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AccountLinks::new();
+            // Bob's address is derived from this native account
+            let bob_account = AccountId::from([0x02; 32]);
+
+            contract.propose_link(bob_account).unwrap();
+            assert!(!contract.acts_for(accounts.bob, accounts.alice));
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.accept_link(accounts.alice),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.accept_link(accounts.alice).unwrap();
+            assert!(contract.acts_for(accounts.bob, accounts.alice));
+            assert_eq!(contract.resolve(accounts.bob), Some(accounts.alice));
+            assert_eq!(contract.get_account(accounts.alice), Some(bob_account));
+            assert_eq!(contract.get_proposal(accounts.alice), None);
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.unlink().unwrap();
+            assert!(!contract.acts_for(accounts.bob, accounts.alice));
+            assert_eq!(contract.unlink(), Err(Error::LinkNotFound));
+        }
+    }
+}
@@ -0,0 +1,448 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::agent_registry::{Agent, AgentRegistryRef, Error};
+
+#[ink::contract]
+mod agent_registry {
+    use hub_types::{HubError, ServiceCategory};
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// Upper bound on operational keys per agent
+    const MAX_KEYS: usize = 10;
+    /// Upper bound on categories an agent may list
+    const MAX_CATEGORIES: usize = 8;
+
+    /// An agent identity, independent of the keys it currently operates with
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Agent {
+        pub id: u64,
+        // Root key that manages the identity and its operational keys
+        pub owner: H160,
+        // Keys allowed to act for the agent day to day
+        pub keys: Vec<H160>,
+        // DID document or other profile metadata
+        pub metadata_uri: String,
+        // Kinds of work the agent offers
+        pub categories: Vec<ServiceCategory>,
+        // Compressed ECDSA key the agent signs its responses with
+        pub public_key: Option<[u8; 33]>,
+        // Deactivated agents no longer count as a verified identity
+        pub active: bool,
+        pub created_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a new agent registers
+    #[ink(event)]
+    pub struct AgentRegistered {
+        #[ink(topic)]
+        agent_id: u64,
+        #[ink(topic)]
+        owner: H160,
+    }
+    /// Emitted when an operational key is added to an agent
+    #[ink(event)]
+    pub struct KeyAdded {
+        #[ink(topic)]
+        agent_id: u64,
+        #[ink(topic)]
+        key: H160,
+    }
+    /// Emitted when an operational key is removed from an agent
+    #[ink(event)]
+    pub struct KeyRemoved {
+        #[ink(topic)]
+        agent_id: u64,
+        #[ink(topic)]
+        key: H160,
+    }
+    /// Emitted when an agent's root key changes
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        agent_id: u64,
+        previous_owner: H160,
+        new_owner: H160,
+    }
+
+    /// Emitted when an agent's categories or signing key change
+    #[ink(event)]
+    pub struct ProfileUpdated {
+        #[ink(topic)]
+        agent_id: u64,
+        categories: Vec<ServiceCategory>,
+        public_key: Option<[u8; 33]>,
+    }
+    /// Emitted when an agent is deactivated or reactivated
+    #[ink(event)]
+    pub struct AgentStatusChanged {
+        #[ink(topic)]
+        agent_id: u64,
+        active: bool,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the agent is not found
+        AgentNotFound,
+        /// Emitted when the caller is not the agent's owner
+        Unauthorized,
+        /// Emitted when the key already belongs to an agent
+        KeyInUse,
+        /// Emitted when the key does not belong to the agent
+        KeyNotFound,
+        /// Emitted when the agent already has the maximum number of keys
+        TooManyKeys,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::AgentNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::KeyInUse => HubError::AlreadyExists,
+                Error::KeyNotFound => HubError::NotFound,
+                Error::TooManyKeys => HubError::LimitExceeded,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct AgentRegistry {
+        agents: Mapping<u64, Agent>,
+        agent_count: u64,
+        // Each operational key belongs to at most one agent
+        key_agents: Mapping<H160, u64>,
+    }
+
+    impl AgentRegistry {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                agents: Mapping::default(),
+                agent_count: 0,
+                key_agents: Mapping::default(),
+            }
+        }
+
+        /// Register an agent; the caller becomes its owner and first key
+        #[ink(message)]
+        pub fn register_agent(&mut self, metadata_uri: String) -> Result<u64> {
+            let owner = self.env().caller();
+            if metadata_uri.is_empty() {
+                return Err(Error::InvalidInput);
+            }
+            if self.key_agents.contains(owner) {
+                return Err(Error::KeyInUse);
+            }
+
+            self.agent_count = self.agent_count.checked_add(1).ok_or(Error::Overflow)?;
+            let agent_id = self.agent_count;
+
+            let agent = Agent {
+                id: agent_id,
+                owner,
+                keys: Vec::from([owner]),
+                metadata_uri,
+                categories: Vec::new(),
+                public_key: None,
+                active: true,
+                created_at: self.env().block_timestamp(),
+            };
+            self.agents.insert(agent_id, &agent);
+            self.key_agents.insert(owner, &agent_id);
+
+            self.env().emit_event(AgentRegistered { agent_id, owner });
+
+            Ok(agent_id)
+        }
+
+        /// Add an operational key to an agent
+        #[ink(message)]
+        pub fn add_key(&mut self, agent_id: u64, key: H160) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            if self.key_agents.contains(key) {
+                return Err(Error::KeyInUse);
+            }
+            if agent.keys.len() >= MAX_KEYS {
+                return Err(Error::TooManyKeys);
+            }
+
+            agent.keys.push(key);
+            self.agents.insert(agent_id, &agent);
+            self.key_agents.insert(key, &agent_id);
+
+            self.env().emit_event(KeyAdded { agent_id, key });
+
+            Ok(())
+        }
+
+        /// Remove an operational key from an agent
+        #[ink(message)]
+        pub fn remove_key(&mut self, agent_id: u64, key: H160) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            let index = agent
+                .keys
+                .iter()
+                .position(|k| *k == key)
+                .ok_or(Error::KeyNotFound)?;
+
+            agent.keys.swap_remove(index);
+            self.agents.insert(agent_id, &agent);
+            self.key_agents.remove(key);
+
+            self.env().emit_event(KeyRemoved { agent_id, key });
+
+            Ok(())
+        }
+
+        /// Replace one operational key with another in a single step
+        #[ink(message)]
+        pub fn rotate_key(&mut self, agent_id: u64, old_key: H160, new_key: H160) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            if self.key_agents.contains(new_key) {
+                return Err(Error::KeyInUse);
+            }
+            let index = agent
+                .keys
+                .iter()
+                .position(|k| *k == old_key)
+                .ok_or(Error::KeyNotFound)?;
+
+            agent.keys[index] = new_key;
+            self.agents.insert(agent_id, &agent);
+            self.key_agents.remove(old_key);
+            self.key_agents.insert(new_key, &agent_id);
+
+            self.env().emit_event(KeyRemoved {
+                agent_id,
+                key: old_key,
+            });
+            self.env().emit_event(KeyAdded {
+                agent_id,
+                key: new_key,
+            });
+
+            Ok(())
+        }
+
+        /// Hand the agent's root key over to another account
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, agent_id: u64, new_owner: H160) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            let previous_owner = agent.owner;
+
+            agent.owner = new_owner;
+            self.agents.insert(agent_id, &agent);
+
+            self.env().emit_event(OwnershipTransferred {
+                agent_id,
+                previous_owner,
+                new_owner,
+            });
+
+            Ok(())
+        }
+
+        /// Update the agent's metadata URI
+        #[ink(message)]
+        pub fn set_metadata(&mut self, agent_id: u64, metadata_uri: String) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            if metadata_uri.is_empty() {
+                return Err(Error::InvalidInput);
+            }
+
+            agent.metadata_uri = metadata_uri;
+            self.agents.insert(agent_id, &agent);
+
+            Ok(())
+        }
+
+        /// Set the categories an agent offers and the key it signs responses with
+        #[ink(message)]
+        pub fn set_profile(
+            &mut self,
+            agent_id: u64,
+            categories: Vec<ServiceCategory>,
+            public_key: Option<[u8; 33]>,
+        ) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            if categories.len() > MAX_CATEGORIES {
+                return Err(Error::InvalidInput);
+            }
+
+            agent.categories = categories.clone();
+            agent.public_key = public_key;
+            self.agents.insert(agent_id, &agent);
+
+            self.env().emit_event(ProfileUpdated {
+                agent_id,
+                categories,
+                public_key,
+            });
+
+            Ok(())
+        }
+
+        /// Deactivate or reactivate an agent
+        ///
+        /// Its keys stay registered, but a deactivated agent no longer counts
+        /// as a verified identity for services or counterparties.
+        #[ink(message)]
+        pub fn set_active(&mut self, agent_id: u64, active: bool) -> Result<()> {
+            let mut agent = self.owned_agent(agent_id)?;
+            agent.active = active;
+            self.agents.insert(agent_id, &agent);
+
+            self.env()
+                .emit_event(AgentStatusChanged { agent_id, active });
+
+            Ok(())
+        }
+
+        /// Check if an agent exists and is active
+        #[ink(message)]
+        pub fn is_active(&self, agent_id: u64) -> bool {
+            self.agents.get(agent_id).is_some_and(|agent| agent.active)
+        }
+
+        /// Check if an account is a current operational key of an agent
+        #[ink(message)]
+        pub fn is_agent_key(&self, agent_id: u64, account: H160) -> bool {
+            self.key_agents.get(account) == Some(agent_id)
+        }
+
+        /// Get the agent an operational key belongs to
+        #[ink(message)]
+        pub fn get_agent_by_key(&self, key: H160) -> Option<u64> {
+            self.key_agents.get(key)
+        }
+
+        /// Get agent details
+        #[ink(message)]
+        pub fn get_agent(&self, agent_id: u64) -> Result<Agent> {
+            self.agents.get(agent_id).ok_or(Error::AgentNotFound)
+        }
+
+        /// Get total agent count
+        #[ink(message)]
+        pub fn get_agent_count(&self) -> u64 {
+            self.agent_count
+        }
+
+        fn owned_agent(&self, agent_id: u64) -> Result<Agent> {
+            let agent = self.get_agent(agent_id)?;
+            if agent.owner != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            Ok(agent)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn register_agent_works() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AgentRegistry::new();
+
+            let agent_id = contract
+                .register_agent(String::from("ipfs://agent"))
+                .unwrap();
+            assert_eq!(agent_id, 1);
+            assert!(contract.is_agent_key(agent_id, accounts.alice));
+            assert_eq!(contract.get_agent_by_key(accounts.alice), Some(agent_id));
+        }
+
+        #[ink::test]
+        fn rotate_key_moves_authority() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AgentRegistry::new();
+            let agent_id = contract
+                .register_agent(String::from("ipfs://agent"))
+                .unwrap();
+
+            contract.add_key(agent_id, accounts.bob).unwrap();
+            contract
+                .rotate_key(agent_id, accounts.bob, accounts.charlie)
+                .unwrap();
+
+            assert!(!contract.is_agent_key(agent_id, accounts.bob));
+            assert!(contract.is_agent_key(agent_id, accounts.charlie));
+            assert_eq!(contract.get_agent_by_key(accounts.bob), None);
+        }
+
+        #[ink::test]
+        fn keys_belong_to_one_agent() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AgentRegistry::new();
+            let agent_id = contract
+                .register_agent(String::from("ipfs://agent"))
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.add_key(agent_id, accounts.bob),
+                Err(Error::Unauthorized)
+            );
+            contract
+                .register_agent(String::from("ipfs://other"))
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.add_key(agent_id, accounts.bob),
+                Err(Error::KeyInUse)
+            );
+        }
+
+        #[ink::test]
+        fn owners_manage_profile_and_status() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AgentRegistry::new();
+            let agent_id = contract
+                .register_agent(String::from("did:web:agent.example"))
+                .unwrap();
+            assert!(contract.is_active(agent_id));
+
+            contract
+                .set_profile(
+                    agent_id,
+                    vec![ServiceCategory::Translation],
+                    Some([0x02; 33]),
+                )
+                .unwrap();
+            let agent = contract.get_agent(agent_id).unwrap();
+            assert_eq!(agent.categories, vec![ServiceCategory::Translation]);
+            assert_eq!(agent.public_key, Some([0x02; 33]));
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_active(agent_id, false),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_active(agent_id, false).unwrap();
+            assert!(!contract.is_active(agent_id));
+            assert!(contract.is_agent_key(agent_id, accounts.alice));
+        }
+    }
+}
@@ -0,0 +1,1474 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::arbitration::{
+    ArbitrationRef, Dispute, DisputeStatus, Error, EscalationLevel, TierConfig,
+};
+
+#[ink::contract]
+mod arbitration {
+    use event_hub::{EventHubRef, HubEventKind};
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use payment_escrow::PaymentEscrowRef;
+
+    const DAY: u64 = 86_400_000;
+
+    /// Different statuses of a dispute
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum DisputeStatus {
+        // Waiting for the other party to match the bond
+        AwaitingResponse,
+        // Both bonds posted, waiting for a ruling
+        Bonded,
+        Resolved,
+    }
+
+    /// How far a bonded dispute has been escalated
+    ///
+    /// Parties first try to settle directly; either may escalate to a single
+    /// arbitrator and then to a jury, paying that level's fee each time.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum EscalationLevel {
+        Negotiation,
+        Arbitrator,
+        Jury,
+    }
+
+    impl EscalationLevel {
+        fn next(self) -> Option<Self> {
+            match self {
+                EscalationLevel::Negotiation => Some(EscalationLevel::Arbitrator),
+                EscalationLevel::Arbitrator => Some(EscalationLevel::Jury),
+                EscalationLevel::Jury => None,
+            }
+        }
+
+        /// Tier used until the owner configures the level
+        fn default_tier(self) -> TierConfig {
+            let duration = match self {
+                EscalationLevel::Negotiation => 3 * DAY,
+                EscalationLevel::Arbitrator | EscalationLevel::Jury => 7 * DAY,
+            };
+            TierConfig { duration, fee: 0 }
+        }
+    }
+
+    /// Time allowed at an escalation level and the fee to enter it
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TierConfig {
+        pub duration: u64,
+        pub fee: Balance,
+    }
+
+    /// A bonded dispute over an escrow
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Dispute {
+        pub id: u64,
+        pub escrow_id: u64,
+        pub disputer: H160,
+        pub respondent: H160,
+        // Bond each side posts
+        pub bond: Balance,
+        pub opened_at: u64,
+        pub response_deadline: u64,
+        pub status: DisputeStatus,
+        pub winner: Option<H160>,
+        pub arbitrator: Option<H160>,
+        pub resolved_at: Option<u64>,
+        pub level: EscalationLevel,
+        // End of the current level; a level that runs out can only be escalated
+        pub level_deadline: u64,
+        // Escalation fees paid by each side, paid out to whoever rules
+        pub disputer_fees: Balance,
+        pub respondent_fees: Balance,
+    }
+
+    /// Events
+    /// Emitted when a party opens a dispute and posts a bond
+    #[ink(event)]
+    pub struct DisputeOpened {
+        #[ink(topic)]
+        dispute_id: u64,
+        #[ink(topic)]
+        escrow_id: u64,
+        disputer: H160,
+        bond: Balance,
+        response_deadline: u64,
+    }
+    /// Emitted when the respondent matches the bond
+    #[ink(event)]
+    pub struct BondPosted {
+        #[ink(topic)]
+        dispute_id: u64,
+        respondent: H160,
+    }
+    /// Emitted when a party proposes a negotiated outcome
+    #[ink(event)]
+    pub struct SettlementProposed {
+        #[ink(topic)]
+        dispute_id: u64,
+        party: H160,
+        winner: H160,
+    }
+    /// Emitted when a party escalates a dispute to the next level
+    #[ink(event)]
+    pub struct DisputeEscalated {
+        #[ink(topic)]
+        dispute_id: u64,
+        level: EscalationLevel,
+        level_deadline: u64,
+        fee: Balance,
+    }
+    /// Emitted when a juror votes on a dispute
+    #[ink(event)]
+    pub struct JuryVoteCast {
+        #[ink(topic)]
+        dispute_id: u64,
+        #[ink(topic)]
+        juror: H160,
+        winner: H160,
+    }
+    /// Emitted when a jury ends without a majority and everything is refunded
+    #[ink(event)]
+    pub struct DisputeDeadlocked {
+        #[ink(topic)]
+        dispute_id: u64,
+    }
+    /// Emitted when a dispute is resolved and the bonds are settled
+    #[ink(event)]
+    pub struct DisputeResolved {
+        #[ink(topic)]
+        dispute_id: u64,
+        #[ink(topic)]
+        winner: H160,
+        arbitrator: Option<H160>,
+        forfeited: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the transferred value does not match the bond
+        InvalidBond,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the dispute is not found
+        DisputeNotFound,
+        /// Emitted when the escrow is not found
+        EscrowNotFound,
+        /// Emitted when the escrow already has a dispute
+        AlreadyDisputed,
+        /// Emitted when the dispute is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the response window has closed
+        ResponseWindowClosed,
+        /// Emitted when the response window is still open
+        ResponseWindowOpen,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+        /// Emitted when the dispute is not at the level the call needs
+        WrongLevel,
+        /// Emitted when the current level's deadline has passed
+        DeadlinePassed,
+        /// Emitted when the current level's deadline has not passed yet
+        DeadlineNotPassed,
+        /// Emitted when the juror already voted on the dispute
+        AlreadyVoted,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidBond => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::DisputeNotFound => HubError::NotFound,
+                Error::EscrowNotFound => HubError::NotFound,
+                Error::AlreadyDisputed => HubError::AlreadyExists,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::ResponseWindowClosed => HubError::Expired,
+                Error::ResponseWindowOpen => HubError::TooEarly,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+                Error::WrongLevel => HubError::InvalidStatus,
+                Error::DeadlinePassed => HubError::Expired,
+                Error::DeadlineNotPassed => HubError::TooEarly,
+                Error::AlreadyVoted => HubError::AlreadyExists,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Arbitration {
+        owner: H160,
+        escrow: H160,
+        arbitrators: Mapping<H160, ()>,
+        bond_amount: Balance,
+        // Time the respondent has to match the bond, in milliseconds
+        response_period: u64,
+        disputes: Mapping<u64, Dispute>,
+        dispute_count: u64,
+        escrow_disputes: Mapping<u64, u64>,
+        // Optional hub lifecycle events are re-emitted through
+        event_hub: Option<H160>,
+        tiers: Mapping<EscalationLevel, TierConfig>,
+        // Votes a jury needs in total; a side wins with a strict majority
+        jury_size: u32,
+        // Outcome each party proposed while negotiating
+        proposals: Mapping<(u64, H160), H160>,
+        // Jurors and the side they voted for
+        jury_votes: Mapping<u64, Vec<(H160, H160)>>,
+    }
+
+    impl Arbitration {
+        #[ink(constructor)]
+        pub fn new(escrow: H160, bond_amount: Balance, response_period: u64) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                escrow,
+                arbitrators: Mapping::default(),
+                bond_amount,
+                response_period,
+                disputes: Mapping::default(),
+                dispute_count: 0,
+                escrow_disputes: Mapping::default(),
+                event_hub: None,
+                tiers: Mapping::default(),
+                jury_size: 3,
+                proposals: Mapping::default(),
+                jury_votes: Mapping::default(),
+            }
+        }
+
+        /// Open a dispute over an escrow, posting the bond as transferred value
+        #[ink(message, payable)]
+        pub fn open_dispute(&mut self, escrow_id: u64) -> Result<u64> {
+            let disputer = self.env().caller();
+            let bond: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if bond != self.bond_amount {
+                return Err(Error::InvalidBond);
+            }
+            if self.escrow_disputes.contains(escrow_id) {
+                return Err(Error::AlreadyDisputed);
+            }
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let details = escrow
+                .get_escrow(escrow_id)
+                .map_err(|_| Error::EscrowNotFound)?;
+            let respondent = if details.payer == disputer {
+                details.payee
+            } else if details.payee == disputer {
+                details.payer
+            } else {
+                return Err(Error::Unauthorized);
+            };
+            escrow
+                .open_bonded_dispute(escrow_id, disputer)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            self.dispute_count = self.dispute_count.checked_add(1).ok_or(Error::Overflow)?;
+            let dispute_id = self.dispute_count;
+
+            let opened_at = self.env().block_timestamp();
+            let response_deadline = opened_at.saturating_add(self.response_period);
+            let dispute = Dispute {
+                id: dispute_id,
+                escrow_id,
+                disputer,
+                respondent,
+                bond,
+                opened_at,
+                response_deadline,
+                status: DisputeStatus::AwaitingResponse,
+                winner: None,
+                arbitrator: None,
+                resolved_at: None,
+                level: EscalationLevel::Negotiation,
+                level_deadline: 0,
+                disputer_fees: 0,
+                respondent_fees: 0,
+            };
+            self.disputes.insert(dispute_id, &dispute);
+            self.escrow_disputes.insert(escrow_id, &dispute_id);
+
+            self.env().emit_event(DisputeOpened {
+                dispute_id,
+                escrow_id,
+                disputer,
+                bond,
+                response_deadline,
+            });
+            self.publish(HubEventKind::DisputeOpened, dispute_id, disputer, bond);
+
+            Ok(dispute_id)
+        }
+
+        /// Match the disputer's bond, starting direct negotiation
+        #[ink(message, payable)]
+        pub fn post_bond(&mut self, dispute_id: u64) -> Result<()> {
+            let mut dispute = self.get_dispute(dispute_id)?;
+            if dispute.respondent != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if dispute.status != DisputeStatus::AwaitingResponse {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() > dispute.response_deadline {
+                return Err(Error::ResponseWindowClosed);
+            }
+            let bond: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if bond != dispute.bond {
+                return Err(Error::InvalidBond);
+            }
+
+            dispute.status = DisputeStatus::Bonded;
+            dispute.level = EscalationLevel::Negotiation;
+            dispute.level_deadline = self.deadline_for(EscalationLevel::Negotiation);
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(BondPosted {
+                dispute_id,
+                respondent: dispute.respondent,
+            });
+
+            Ok(())
+        }
+
+        /// Propose a negotiated outcome while the dispute is in negotiation
+        ///
+        /// Once both parties propose the same winner the escrow is settled in
+        /// its favour and both bonds are returned, without paying for
+        /// arbitration.
+        #[ink(message)]
+        pub fn propose_settlement(&mut self, dispute_id: u64, winner: H160) -> Result<()> {
+            let party = self.env().caller();
+            let mut dispute = self.get_dispute(dispute_id)?;
+            let other = if party == dispute.disputer {
+                dispute.respondent
+            } else if party == dispute.respondent {
+                dispute.disputer
+            } else {
+                return Err(Error::Unauthorized);
+            };
+            self.ensure_level(&dispute, EscalationLevel::Negotiation)?;
+            if winner != dispute.disputer && winner != dispute.respondent {
+                return Err(Error::InvalidInput);
+            }
+
+            self.proposals.insert((dispute_id, party), &winner);
+            self.env().emit_event(SettlementProposed {
+                dispute_id,
+                party,
+                winner,
+            });
+            if self.proposals.get((dispute_id, other)) != Some(winner) {
+                return Ok(());
+            }
+
+            self.settle_escrow(&dispute, Some(winner))?;
+            if self
+                .env()
+                .transfer(dispute.disputer, dispute.bond.into())
+                .is_err()
+                || self
+                    .env()
+                    .transfer(dispute.respondent, dispute.bond.into())
+                    .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            dispute.status = DisputeStatus::Resolved;
+            dispute.winner = Some(winner);
+            dispute.resolved_at = Some(self.env().block_timestamp());
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(DisputeResolved {
+                dispute_id,
+                winner,
+                arbitrator: None,
+                forfeited: 0,
+            });
+            self.publish(HubEventKind::DisputeResolved, dispute_id, winner, 0);
+
+            Ok(())
+        }
+
+        /// Escalate a bonded dispute to the next level, paying its fee
+        #[ink(message, payable)]
+        pub fn escalate(&mut self, dispute_id: u64) -> Result<()> {
+            let party = self.env().caller();
+            let mut dispute = self.get_dispute(dispute_id)?;
+            if party != dispute.disputer && party != dispute.respondent {
+                return Err(Error::Unauthorized);
+            }
+            if dispute.status != DisputeStatus::Bonded {
+                return Err(Error::InvalidStatus);
+            }
+            let level = dispute.level.next().ok_or(Error::WrongLevel)?;
+            let tier = self.get_tier(level);
+            let fee: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if fee != tier.fee {
+                return Err(Error::InvalidBond);
+            }
+
+            if party == dispute.disputer {
+                dispute.disputer_fees = dispute
+                    .disputer_fees
+                    .checked_add(fee)
+                    .ok_or(Error::Overflow)?;
+            } else {
+                dispute.respondent_fees = dispute
+                    .respondent_fees
+                    .checked_add(fee)
+                    .ok_or(Error::Overflow)?;
+            }
+            dispute.level = level;
+            dispute.level_deadline = self.deadline_for(level);
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(DisputeEscalated {
+                dispute_id,
+                level,
+                level_deadline: dispute.level_deadline,
+                fee,
+            });
+
+            Ok(())
+        }
+
+        /// Rule on a dispute escalated to a single arbitrator
+        ///
+        /// The escrow is settled in the winner's favour and its bond returned;
+        /// the loser's bond, with any escalation fees, is paid to the ruling
+        /// arbitrator to cover the cost of arbitration.
+        #[ink(message)]
+        pub fn rule(&mut self, dispute_id: u64, winner: H160) -> Result<()> {
+            let arbitrator = self.env().caller();
+            if !self.arbitrators.contains(arbitrator) {
+                return Err(Error::Unauthorized);
+            }
+            let mut dispute = self.get_dispute(dispute_id)?;
+            self.ensure_level(&dispute, EscalationLevel::Arbitrator)?;
+            if winner != dispute.disputer && winner != dispute.respondent {
+                return Err(Error::InvalidInput);
+            }
+
+            let payout = dispute
+                .bond
+                .checked_add(dispute.disputer_fees + dispute.respondent_fees)
+                .ok_or(Error::Overflow)?;
+            self.settle_escrow(&dispute, Some(winner))?;
+            if self.env().transfer(winner, dispute.bond.into()).is_err()
+                || self.env().transfer(arbitrator, payout.into()).is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            dispute.status = DisputeStatus::Resolved;
+            dispute.winner = Some(winner);
+            dispute.arbitrator = Some(arbitrator);
+            dispute.resolved_at = Some(self.env().block_timestamp());
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(DisputeResolved {
+                dispute_id,
+                winner,
+                arbitrator: Some(arbitrator),
+                forfeited: dispute.bond,
+            });
+            self.publish(
+                HubEventKind::DisputeResolved,
+                dispute_id,
+                winner,
+                dispute.bond,
+            );
+
+            Ok(())
+        }
+
+        /// Vote on a dispute escalated to a jury
+        ///
+        /// The first side to reach a strict majority of `jury_size` wins the
+        /// escrow and gets its bond back; the loser's bond and escalation fees
+        /// are split between the jurors who voted for it.
+        #[ink(message)]
+        pub fn cast_jury_vote(&mut self, dispute_id: u64, winner: H160) -> Result<()> {
+            let juror = self.env().caller();
+            if !self.arbitrators.contains(juror) {
+                return Err(Error::Unauthorized);
+            }
+            let mut dispute = self.get_dispute(dispute_id)?;
+            self.ensure_level(&dispute, EscalationLevel::Jury)?;
+            if winner != dispute.disputer && winner != dispute.respondent {
+                return Err(Error::InvalidInput);
+            }
+            let mut votes = self.jury_votes.get(dispute_id).unwrap_or_default();
+            if votes.iter().any(|(voter, _)| *voter == juror) {
+                return Err(Error::AlreadyVoted);
+            }
+            votes.push((juror, winner));
+            self.jury_votes.insert(dispute_id, &votes);
+            self.env().emit_event(JuryVoteCast {
+                dispute_id,
+                juror,
+                winner,
+            });
+
+            let majority: Vec<H160> = votes
+                .iter()
+                .filter(|(_, side)| *side == winner)
+                .map(|(voter, _)| *voter)
+                .collect();
+            if majority.len() as u32 <= self.jury_size / 2 {
+                return Ok(());
+            }
+
+            let pot = dispute
+                .bond
+                .checked_add(dispute.disputer_fees + dispute.respondent_fees)
+                .ok_or(Error::Overflow)?;
+            let share = pot / majority.len() as Balance;
+            self.settle_escrow(&dispute, Some(winner))?;
+            if self.env().transfer(winner, dispute.bond.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            for (i, voter) in majority.iter().enumerate() {
+                // The first juror also takes the rounding remainder
+                let amount = if i == 0 {
+                    pot - share * (majority.len() as Balance - 1)
+                } else {
+                    share
+                };
+                if self.env().transfer(*voter, amount.into()).is_err() {
+                    return Err(Error::TransferFailed);
+                }
+            }
+
+            dispute.status = DisputeStatus::Resolved;
+            dispute.winner = Some(winner);
+            dispute.resolved_at = Some(self.env().block_timestamp());
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(DisputeResolved {
+                dispute_id,
+                winner,
+                arbitrator: None,
+                forfeited: dispute.bond,
+            });
+            self.publish(
+                HubEventKind::DisputeResolved,
+                dispute_id,
+                winner,
+                dispute.bond,
+            );
+
+            Ok(())
+        }
+
+        /// Close a jury that ran out of time without a majority
+        ///
+        /// The escrow is split evenly, and bonds and escalation fees go back to
+        /// the parties that paid them.
+        #[ink(message)]
+        pub fn close_deadlocked_jury(&mut self, dispute_id: u64) -> Result<()> {
+            let mut dispute = self.get_dispute(dispute_id)?;
+            if dispute.status != DisputeStatus::Bonded {
+                return Err(Error::InvalidStatus);
+            }
+            if dispute.level != EscalationLevel::Jury {
+                return Err(Error::WrongLevel);
+            }
+            if self.env().block_timestamp() <= dispute.level_deadline {
+                return Err(Error::DeadlineNotPassed);
+            }
+
+            let disputer_refund = dispute.bond + dispute.disputer_fees;
+            let respondent_refund = dispute.bond + dispute.respondent_fees;
+            self.settle_escrow(&dispute, None)?;
+            if self
+                .env()
+                .transfer(dispute.disputer, disputer_refund.into())
+                .is_err()
+                || self
+                    .env()
+                    .transfer(dispute.respondent, respondent_refund.into())
+                    .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            dispute.status = DisputeStatus::Resolved;
+            dispute.resolved_at = Some(self.env().block_timestamp());
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(DisputeDeadlocked { dispute_id });
+
+            Ok(())
+        }
+
+        /// Resolve in the disputer's favour when the respondent never posted
+        /// a bond, settling the escrow for the disputer and returning its bond
+        #[ink(message)]
+        pub fn claim_default(&mut self, dispute_id: u64) -> Result<()> {
+            let mut dispute = self.get_dispute(dispute_id)?;
+            if dispute.disputer != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if dispute.status != DisputeStatus::AwaitingResponse {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() <= dispute.response_deadline {
+                return Err(Error::ResponseWindowOpen);
+            }
+
+            self.settle_escrow(&dispute, Some(dispute.disputer))?;
+            if self
+                .env()
+                .transfer(dispute.disputer, dispute.bond.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            dispute.status = DisputeStatus::Resolved;
+            dispute.winner = Some(dispute.disputer);
+            dispute.resolved_at = Some(self.env().block_timestamp());
+            self.disputes.insert(dispute_id, &dispute);
+
+            self.env().emit_event(DisputeResolved {
+                dispute_id,
+                winner: dispute.disputer,
+                arbitrator: None,
+                forfeited: 0,
+            });
+            self.publish(
+                HubEventKind::DisputeResolved,
+                dispute_id,
+                dispute.disputer,
+                0,
+            );
+
+            Ok(())
+        }
+
+        /// Approve or remove an arbitrator
+        #[ink(message)]
+        pub fn set_arbitrator(&mut self, arbitrator: H160, approved: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if approved {
+                self.arbitrators.insert(arbitrator, &());
+            } else {
+                self.arbitrators.remove(arbitrator);
+            }
+            Ok(())
+        }
+
+        /// Set the bond required for new disputes
+        #[ink(message)]
+        pub fn set_bond_amount(&mut self, bond_amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.bond_amount = bond_amount;
+            Ok(())
+        }
+
+        /// Set how long respondents have to match a bond
+        #[ink(message)]
+        pub fn set_response_period(&mut self, response_period: u64) -> Result<()> {
+            self.ensure_owner()?;
+            self.response_period = response_period;
+            Ok(())
+        }
+
+        /// Set the duration and entry fee of an escalation level
+        ///
+        /// The fee of `Negotiation` is never charged; only its duration is used.
+        /// Levels default to three days of negotiation and a week for each
+        /// escalation, without fees.
+        #[ink(message)]
+        pub fn set_tier(&mut self, level: EscalationLevel, tier: TierConfig) -> Result<()> {
+            self.ensure_owner()?;
+            if tier.duration == 0 {
+                return Err(Error::InvalidInput);
+            }
+            self.tiers.insert(level, &tier);
+            Ok(())
+        }
+
+        /// Set how many votes a jury has; a side needs a strict majority
+        #[ink(message)]
+        pub fn set_jury_size(&mut self, jury_size: u32) -> Result<()> {
+            self.ensure_owner()?;
+            if jury_size == 0 {
+                return Err(Error::InvalidInput);
+            }
+            self.jury_size = jury_size;
+            Ok(())
+        }
+
+        /// Set (or clear) the event hub lifecycle events are re-emitted through
+        #[ink(message)]
+        pub fn set_event_hub(&mut self, event_hub: Option<H160>) -> Result<()> {
+            self.ensure_owner()?;
+            self.event_hub = event_hub;
+            Ok(())
+        }
+
+        /// Get dispute details
+        #[ink(message)]
+        pub fn get_dispute(&self, dispute_id: u64) -> Result<Dispute> {
+            self.disputes.get(dispute_id).ok_or(Error::DisputeNotFound)
+        }
+
+        /// Get the dispute opened over an escrow, if any
+        #[ink(message)]
+        pub fn get_escrow_dispute(&self, escrow_id: u64) -> Option<u64> {
+            self.escrow_disputes.get(escrow_id)
+        }
+
+        /// Check if an account is an approved arbitrator
+        #[ink(message)]
+        pub fn is_arbitrator(&self, account: H160) -> bool {
+            self.arbitrators.contains(account)
+        }
+
+        /// Get the duration and entry fee of an escalation level
+        #[ink(message)]
+        pub fn get_tier(&self, level: EscalationLevel) -> TierConfig {
+            self.tiers
+                .get(level)
+                .unwrap_or_else(|| level.default_tier())
+        }
+
+        /// Jurors who voted on a dispute and the side each chose
+        #[ink(message)]
+        pub fn get_jury_votes(&self, dispute_id: u64) -> Vec<(H160, H160)> {
+            self.jury_votes.get(dispute_id).unwrap_or_default()
+        }
+
+        /// Get the bond required for new disputes
+        #[ink(message)]
+        pub fn get_bond_amount(&self) -> Balance {
+            self.bond_amount
+        }
+
+        /// Get total dispute count
+        #[ink(message)]
+        pub fn get_dispute_count(&self) -> u64 {
+            self.dispute_count
+        }
+
+        /// A bonded dispute at `level` whose deadline hasn't passed
+        fn ensure_level(&self, dispute: &Dispute, level: EscalationLevel) -> Result<()> {
+            if dispute.status != DisputeStatus::Bonded {
+                return Err(Error::InvalidStatus);
+            }
+            if dispute.level != level {
+                return Err(Error::WrongLevel);
+            }
+            if self.env().block_timestamp() > dispute.level_deadline {
+                return Err(Error::DeadlinePassed);
+            }
+            Ok(())
+        }
+
+        /// Settles the disputed escrow by the dispute's outcome
+        ///
+        /// The winner takes the whole escrow. Without one it is split evenly,
+        /// the payee taking the odd unit.
+        fn settle_escrow(&self, dispute: &Dispute, winner: Option<H160>) -> Result<()> {
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let details = escrow
+                .get_escrow(dispute.escrow_id)
+                .map_err(|_| Error::EscrowNotFound)?;
+            let payer_share = match winner {
+                Some(winner) if winner == details.payer => details.amount,
+                Some(_) => 0,
+                None => details.amount / 2,
+            };
+            escrow
+                .resolve_dispute(dispute.escrow_id, payer_share, details.amount - payer_share)
+                .map_err(|_| Error::EscrowCallFailed)
+        }
+
+        fn deadline_for(&self, level: EscalationLevel) -> u64 {
+            self.env()
+                .block_timestamp()
+                .saturating_add(self.get_tier(level).duration)
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Re-emits a lifecycle event through the event hub, if one is set
+        fn publish(&self, kind: HubEventKind, subject_id: u64, actor: H160, amount: Balance) {
+            let Some(event_hub) = self.event_hub else {
+                return;
+            };
+            let mut event_hub: EventHubRef = FromAddr::from_addr(event_hub);
+            // A hub that hasn't approved this contract must not block the action
+            let _ = event_hub.publish(kind, subject_id, actor, amount);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::U256;
+
+        const BOND: Balance = 100;
+        const RESPONSE_PERIOD: u64 = 1_000;
+
+        fn arbitration() -> Arbitration {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            Arbitration::new(accounts.django, BOND, RESPONSE_PERIOD)
+        }
+
+        /// Stores a dispute of Alice against Bob over `escrow_id`
+        ///
+        /// Opening one goes through the escrow contract, which the off-chain
+        /// environment can't call; the e2e tests cover that round trip.
+        fn seed_dispute(contract: &mut Arbitration, escrow_id: u64) -> u64 {
+            let accounts = ink::env::test::default_accounts();
+            contract.dispute_count += 1;
+            let dispute_id = contract.dispute_count;
+            contract.disputes.insert(
+                dispute_id,
+                &Dispute {
+                    id: dispute_id,
+                    escrow_id,
+                    disputer: accounts.alice,
+                    respondent: accounts.bob,
+                    bond: BOND,
+                    opened_at: 0,
+                    response_deadline: RESPONSE_PERIOD,
+                    status: DisputeStatus::AwaitingResponse,
+                    winner: None,
+                    arbitrator: None,
+                    resolved_at: None,
+                    level: EscalationLevel::Negotiation,
+                    level_deadline: 0,
+                    disputer_fees: 0,
+                    respondent_fees: 0,
+                },
+            );
+            contract.escrow_disputes.insert(escrow_id, &dispute_id);
+            dispute_id
+        }
+
+        fn post_bond(contract: &mut Arbitration, dispute_id: u64, bond: Balance) -> Result<()> {
+            ink::env::test::set_caller(ink::env::test::default_accounts().bob);
+            ink::env::test::set_value_transferred(U256::from(bond));
+            let result = contract.post_bond(dispute_id);
+            ink::env::test::set_value_transferred(U256::zero());
+            result
+        }
+
+        #[ink::test]
+        fn disputes_need_the_exact_bond() {
+            let mut contract = arbitration();
+
+            ink::env::test::set_value_transferred(U256::from(BOND - 1));
+            assert_eq!(contract.open_dispute(1), Err(Error::InvalidBond));
+
+            seed_dispute(&mut contract, 1);
+            ink::env::test::set_value_transferred(U256::from(BOND));
+            assert_eq!(contract.open_dispute(1), Err(Error::AlreadyDisputed));
+        }
+
+        #[ink::test]
+        fn respondent_matches_the_bond_in_time() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = arbitration();
+            let dispute_id = seed_dispute(&mut contract, 1);
+            let late = seed_dispute(&mut contract, 2);
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(contract.post_bond(dispute_id), Err(Error::Unauthorized));
+            assert_eq!(
+                post_bond(&mut contract, dispute_id, BOND / 2),
+                Err(Error::InvalidBond)
+            );
+            post_bond(&mut contract, dispute_id, BOND).unwrap();
+
+            let dispute = contract.get_dispute(dispute_id).unwrap();
+            assert_eq!(dispute.status, DisputeStatus::Bonded);
+            assert_eq!(dispute.level, EscalationLevel::Negotiation);
+            assert_eq!(
+                post_bond(&mut contract, dispute_id, BOND),
+                Err(Error::InvalidStatus)
+            );
+
+            ink::env::test::set_block_timestamp(RESPONSE_PERIOD + 1);
+            assert_eq!(
+                post_bond(&mut contract, late, BOND),
+                Err(Error::ResponseWindowClosed)
+            );
+        }
+
+        #[ink::test]
+        fn default_is_claimable_only_after_the_response_window() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = arbitration();
+            let unmatched = seed_dispute(&mut contract, 1);
+            let matched = seed_dispute(&mut contract, 2);
+            post_bond(&mut contract, matched, BOND).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.claim_default(unmatched), Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.claim_default(unmatched),
+                Err(Error::ResponseWindowOpen)
+            );
+
+            ink::env::test::set_block_timestamp(RESPONSE_PERIOD + 1);
+            assert_eq!(contract.claim_default(matched), Err(Error::InvalidStatus));
+        }
+
+        #[ink::test]
+        fn only_approved_arbitrators_rule_escalated_disputes() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = arbitration();
+            let dispute_id = seed_dispute(&mut contract, 1);
+            post_bond(&mut contract, dispute_id, BOND).unwrap();
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.rule(dispute_id, accounts.alice),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                contract.set_arbitrator(accounts.charlie, true),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_arbitrator(accounts.charlie, true).unwrap();
+            assert!(contract.is_arbitrator(accounts.charlie));
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.rule(dispute_id, accounts.alice),
+                Err(Error::WrongLevel)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.escalate(dispute_id).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.rule(dispute_id, accounts.eve),
+                Err(Error::InvalidInput)
+            );
+        }
+
+        #[ink::test]
+        fn negotiation_runs_for_the_tier_duration() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = arbitration();
+            let dispute_id = seed_dispute(&mut contract, 1);
+            post_bond(&mut contract, dispute_id, BOND).unwrap();
+
+            let negotiation = contract.get_tier(EscalationLevel::Negotiation);
+            assert_eq!(negotiation.duration, 3 * DAY);
+            assert_eq!(
+                contract.get_dispute(dispute_id).unwrap().level_deadline,
+                3 * DAY
+            );
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.set_tier(
+                    EscalationLevel::Negotiation,
+                    TierConfig {
+                        duration: 0,
+                        fee: 0
+                    }
+                ),
+                Err(Error::InvalidInput)
+            );
+
+            // A proposal the other side hasn't matched leaves the dispute open
+            ink::env::test::set_block_timestamp(DAY);
+            contract
+                .propose_settlement(dispute_id, accounts.alice)
+                .unwrap();
+            assert_eq!(
+                contract.propose_settlement(dispute_id, accounts.eve),
+                Err(Error::InvalidInput)
+            );
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.propose_settlement(dispute_id, accounts.alice),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            ink::env::test::set_block_timestamp(3 * DAY + 1);
+            assert_eq!(
+                contract.propose_settlement(dispute_id, accounts.alice),
+                Err(Error::DeadlinePassed)
+            );
+        }
+
+        #[ink::test]
+        fn escalation_charges_each_level_fee() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = arbitration();
+            let tier = |fee| TierConfig { duration: DAY, fee };
+            ink::env::test::set_caller(accounts.alice);
+            contract
+                .set_tier(EscalationLevel::Arbitrator, tier(10))
+                .unwrap();
+            contract.set_tier(EscalationLevel::Jury, tier(20)).unwrap();
+            let dispute_id = seed_dispute(&mut contract, 1);
+            post_bond(&mut contract, dispute_id, BOND).unwrap();
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(contract.escalate(dispute_id), Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.alice);
+            ink::env::test::set_value_transferred(U256::from(5));
+            assert_eq!(contract.escalate(dispute_id), Err(Error::InvalidBond));
+            ink::env::test::set_value_transferred(U256::from(10));
+            contract.escalate(dispute_id).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            ink::env::test::set_value_transferred(U256::from(20));
+            contract.escalate(dispute_id).unwrap();
+            assert_eq!(contract.escalate(dispute_id), Err(Error::WrongLevel));
+
+            let dispute = contract.get_dispute(dispute_id).unwrap();
+            assert_eq!(dispute.level, EscalationLevel::Jury);
+            assert_eq!(dispute.level_deadline, DAY);
+            assert_eq!(dispute.disputer_fees, 10);
+            assert_eq!(dispute.respondent_fees, 20);
+        }
+
+        #[ink::test]
+        fn jurors_vote_once_until_the_deadline() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = arbitration();
+            let dispute_id = seed_dispute(&mut contract, 1);
+            post_bond(&mut contract, dispute_id, BOND).unwrap();
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_arbitrator(accounts.charlie, true).unwrap();
+            contract.escalate(dispute_id).unwrap();
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.cast_jury_vote(dispute_id, accounts.alice),
+                Err(Error::WrongLevel)
+            );
+            ink::env::test::set_caller(accounts.alice);
+            contract.escalate(dispute_id).unwrap();
+
+            // One vote of three is no majority yet
+            ink::env::test::set_caller(accounts.charlie);
+            contract.cast_jury_vote(dispute_id, accounts.alice).unwrap();
+            assert_eq!(
+                contract.cast_jury_vote(dispute_id, accounts.bob),
+                Err(Error::AlreadyVoted)
+            );
+            assert_eq!(
+                contract.get_jury_votes(dispute_id),
+                vec![(accounts.charlie, accounts.alice)]
+            );
+            assert_eq!(
+                contract.close_deadlocked_jury(dispute_id),
+                Err(Error::DeadlineNotPassed)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.cast_jury_vote(dispute_id, accounts.bob),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_arbitrator(accounts.eve, true).unwrap();
+            ink::env::test::set_caller(accounts.eve);
+            ink::env::test::set_block_timestamp(7 * DAY + 1);
+            assert_eq!(
+                contract.cast_jury_vote(dispute_id, accounts.alice),
+                Err(Error::DeadlinePassed)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use hub_types::PaymentAsset;
+        use ink::env::DefaultEnvironment;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::{EscrowStatus, PaymentEscrow};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const AMOUNT: Balance = 1_000_000_000_000;
+        const BOND: Balance = 100_000_000_000;
+        const RESPONSE_PERIOD: u64 = 60_000;
+
+        #[ink_e2e::test]
+        async fn ruling_settles_the_escrow_and_pays_the_bonds<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Alice);
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+            let charlie = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Charlie);
+
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(DAY),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let mut escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let arbitration = client
+                .instantiate(
+                    "arbitration",
+                    &ink_e2e::alice(),
+                    &mut ArbitrationRef::new(escrow.addr, BOND, RESPONSE_PERIOD),
+                )
+                .submit()
+                .await
+                .expect("arbitration instantiate failed");
+            let mut calls = arbitration.call_builder::<Arbitration>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.set_arbitration(Some(arbitration.addr)),
+                )
+                .submit()
+                .await
+                .expect("call failed");
+            client
+                .call(&ink_e2e::alice(), &calls.set_arbitrator(charlie, true))
+                .submit()
+                .await
+                .expect("call failed");
+            let escrow_id = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.create_escrow(bob, 1, String::from("job"), PaymentAsset::Native),
+                )
+                .value(AMOUNT)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            // Bob disputes the payment and Alice matches his bond
+            let dispute_id = client
+                .call(&ink_e2e::bob(), &calls.open_dispute(escrow_id))
+                .value(BOND)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Disputed);
+            client
+                .call(&ink_e2e::alice(), &calls.post_bond(dispute_id))
+                .value(BOND)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            client
+                .call(&ink_e2e::alice(), &calls.escalate(dispute_id))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let before = client
+                .free_balance(ink_e2e::account_id(Sr25519Keyring::Alice))
+                .await
+                .expect("balance query failed");
+            client
+                .call(&ink_e2e::charlie(), &calls.rule(dispute_id, alice))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let after = client
+                .free_balance(ink_e2e::account_id(Sr25519Keyring::Alice))
+                .await
+                .expect("balance query failed");
+
+            // Alice gets the escrow back along with her bond
+            assert_eq!(after - before, AMOUNT + BOND);
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Refunded);
+            let dispute = client
+                .call(&ink_e2e::alice(), &calls.get_dispute(dispute_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(dispute.winner, Some(alice));
+            assert_eq!(dispute.arbitrator, Some(charlie));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn default_settles_the_escrow_for_the_disputer<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(DAY),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let mut escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            // Without a response period the default is claimable right away
+            let arbitration = client
+                .instantiate(
+                    "arbitration",
+                    &ink_e2e::alice(),
+                    &mut ArbitrationRef::new(escrow.addr, BOND, 0),
+                )
+                .submit()
+                .await
+                .expect("arbitration instantiate failed");
+            let mut calls = arbitration.call_builder::<Arbitration>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.set_arbitration(Some(arbitration.addr)),
+                )
+                .submit()
+                .await
+                .expect("call failed");
+            let escrow_id = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.create_escrow(bob, 1, String::from("job"), PaymentAsset::Native),
+                )
+                .value(AMOUNT)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let dispute_id = client
+                .call(&ink_e2e::alice(), &calls.open_dispute(escrow_id))
+                .value(BOND)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            // Bob never matches the bond, so Alice's comes back with the escrow
+            client
+                .call(&ink_e2e::alice(), &calls.claim_default(dispute_id))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Refunded);
+            let dispute = client
+                .call(&ink_e2e::alice(), &calls.get_dispute(dispute_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(dispute.status, DisputeStatus::Resolved);
+            assert_eq!(dispute.winner, Some(dispute.disputer));
+            assert_eq!(
+                client
+                    .call(&ink_e2e::alice(), &calls.claim_default(dispute_id))
+                    .dry_run()
+                    .await?
+                    .return_value(),
+                Err(Error::InvalidStatus)
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn jury_majority_settles_the_escrow<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+            let charlie = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Charlie);
+            let dave = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Dave);
+
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(DAY),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let mut escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let arbitration = client
+                .instantiate(
+                    "arbitration",
+                    &ink_e2e::alice(),
+                    &mut ArbitrationRef::new(escrow.addr, BOND, RESPONSE_PERIOD),
+                )
+                .submit()
+                .await
+                .expect("arbitration instantiate failed");
+            let mut calls = arbitration.call_builder::<Arbitration>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.set_arbitration(Some(arbitration.addr)),
+                )
+                .submit()
+                .await
+                .expect("call failed");
+            for juror in [charlie, dave] {
+                client
+                    .call(&ink_e2e::alice(), &calls.set_arbitrator(juror, true))
+                    .submit()
+                    .await
+                    .expect("call failed");
+            }
+            let escrow_id = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.create_escrow(bob, 1, String::from("job"), PaymentAsset::Native),
+                )
+                .value(AMOUNT)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let dispute_id = client
+                .call(&ink_e2e::alice(), &calls.open_dispute(escrow_id))
+                .value(BOND)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            client
+                .call(&ink_e2e::bob(), &calls.post_bond(dispute_id))
+                .value(BOND)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            // Negotiation and a single arbitrator are skipped on the way to the jury
+            for _ in 0..2 {
+                client
+                    .call(&ink_e2e::bob(), &calls.escalate(dispute_id))
+                    .submit()
+                    .await
+                    .expect("call failed")
+                    .return_value()
+                    .unwrap();
+            }
+            for juror in [ink_e2e::charlie(), ink_e2e::dave()] {
+                client
+                    .call(&juror, &calls.cast_jury_vote(dispute_id, bob))
+                    .submit()
+                    .await
+                    .expect("call failed")
+                    .return_value()
+                    .unwrap();
+            }
+
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Completed);
+            let dispute = client
+                .call(&ink_e2e::alice(), &calls.get_dispute(dispute_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(dispute.level, EscalationLevel::Jury);
+            assert_eq!(dispute.winner, Some(bob));
+
+            Ok(())
+        }
+    }
+}
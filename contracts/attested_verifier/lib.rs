@@ -0,0 +1,146 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::attested_verifier::{AttestedVerifier, AttestedVerifierRef, Error};
+
+/// Proof-of-compute verifier backed by a trusted attestor
+///
+/// The attestor runs (or watches) each job and records the hash of its
+/// expected output; a proof is accepted when it hashes to that record. It's
+/// the simplest [`ComputeVerifier`](compute_verifier::ComputeVerifier) an
+/// escrow can point at until zk or TEE verifiers are deployed.
+#[ink::contract]
+mod attested_verifier {
+    use compute_verifier::ComputeVerifier;
+    use hub_types::HubError;
+    use ink::env::hash::Keccak256;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// Events
+    /// Emitted when the attestor records a job's expected output
+    #[ink(event)]
+    pub struct JobAttested {
+        #[ink(topic)]
+        job_hash: H256,
+        output_hash: H256,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when the caller is not the attestor
+        Unauthorized,
+        /// Emitted when the job was already attested
+        AlreadyAttested,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::AlreadyAttested => HubError::AlreadyExists,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct AttestedVerifier {
+        attestor: H160,
+        output_hashes: Mapping<H256, H256>,
+    }
+
+    impl AttestedVerifier {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                attestor: Self::env().caller(),
+                output_hashes: Mapping::default(),
+            }
+        }
+
+        /// Record the keccak-256 hash of a job's expected output
+        ///
+        /// Attestations are final, so an escrow's payout can't be redirected
+        /// to a different result after the fact.
+        #[ink(message)]
+        pub fn attest(&mut self, job_hash: H256, output_hash: H256) -> Result<()> {
+            if self.env().caller() != self.attestor {
+                return Err(Error::Unauthorized);
+            }
+            if self.output_hashes.contains(job_hash) {
+                return Err(Error::AlreadyAttested);
+            }
+
+            self.output_hashes.insert(job_hash, &output_hash);
+            self.env().emit_event(JobAttested {
+                job_hash,
+                output_hash,
+            });
+
+            Ok(())
+        }
+
+        /// Get the expected output hash of a job, if attested
+        #[ink(message)]
+        pub fn get_attestation(&self, job_hash: H256) -> Option<H256> {
+            self.output_hashes.get(job_hash)
+        }
+
+        /// Get the account whose attestations this verifier trusts
+        #[ink(message)]
+        pub fn get_attestor(&self) -> H160 {
+            self.attestor
+        }
+    }
+
+    impl ComputeVerifier for AttestedVerifier {
+        /// Accept `proof` if it is the output attested for `job_hash`
+        #[ink(message)]
+        fn verify(&self, job_hash: H256, proof: Vec<u8>) -> bool {
+            let output_hash = H256::from(self.env().hash_bytes::<Keccak256>(&proof));
+            self.output_hashes.get(job_hash) == Some(output_hash)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn keccak(input: &[u8]) -> H256 {
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(input, &mut output);
+            H256::from(output)
+        }
+
+        #[ink::test]
+        fn accepts_only_the_attested_output() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AttestedVerifier::new();
+            let job_hash = H256::from([1; 32]);
+            let output = Vec::from(*b"42");
+            let output_hash = keccak(&output);
+            assert!(!contract.verify(job_hash, output.clone()));
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.attest(job_hash, output_hash),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.attest(job_hash, output_hash).unwrap();
+            assert_eq!(
+                contract.attest(job_hash, H256::zero()),
+                Err(Error::AlreadyAttested)
+            );
+            assert!(contract.verify(job_hash, output.clone()));
+            assert!(!contract.verify(job_hash, Vec::from(*b"41")));
+            assert!(!contract.verify(H256::from([2; 32]), output));
+        }
+    }
+}
@@ -0,0 +1,299 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::audit_log::{AuditAction, AuditLogRef, Error};
+
+#[ink::contract]
+mod audit_log {
+    use hub_types::HubError;
+    use ink::env::hash::Keccak256;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// Maximum entries returned by a single range query
+    const MAX_PAGE_SIZE: u32 = 100;
+
+    /// Categories of privileged actions
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AuditAction {
+        Suspension,
+        Slash,
+        AdminIntervention,
+        Upgrade,
+        ParameterChange,
+        RoleChange,
+        Other,
+    }
+
+    /// A single audit entry
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AuditEntry {
+        pub id: u64,
+        // Hub contract (or account) that wrote the entry
+        pub source: H160,
+        pub actor: H160,
+        pub action: AuditAction,
+        pub target: H160,
+        pub reason_code: u32,
+        pub timestamp: u64,
+        // Hash chaining this entry to the previous one
+        pub prev_hash: H256,
+    }
+
+    /// Events
+    /// Emitted when an entry is appended
+    #[ink(event)]
+    pub struct EntryRecorded {
+        #[ink(topic)]
+        entry_id: u64,
+        #[ink(topic)]
+        actor: H160,
+        #[ink(topic)]
+        target: H160,
+        action: AuditAction,
+        reason_code: u32,
+    }
+    /// Emitted when a writer is approved or removed
+    #[ink(event)]
+    pub struct WriterUpdated {
+        #[ink(topic)]
+        writer: H160,
+        allowed: bool,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the entry is not found
+        EntryNotFound,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::Overflow => HubError::Overflow,
+                Error::EntryNotFound => HubError::NotFound,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct AuditLog {
+        owner: H160,
+        writers: Mapping<H160, ()>,
+        entries: Mapping<u64, AuditEntry>,
+        entry_count: u64,
+        head_hash: H256,
+    }
+
+    impl AuditLog {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                writers: Mapping::default(),
+                entries: Mapping::default(),
+                entry_count: 0,
+                head_hash: H256::zero(),
+            }
+        }
+
+        /// Allow or disallow a contract or account to append entries
+        #[ink(message)]
+        pub fn set_writer(&mut self, writer: H160, allowed: bool) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            if allowed {
+                self.writers.insert(writer, &());
+            } else {
+                self.writers.remove(writer);
+            }
+
+            self.env().emit_event(WriterUpdated { writer, allowed });
+
+            Ok(())
+        }
+
+        /// Append an entry; entries can never be edited or removed
+        #[ink(message)]
+        pub fn record(
+            &mut self,
+            actor: H160,
+            action: AuditAction,
+            target: H160,
+            reason_code: u32,
+        ) -> Result<u64> {
+            let source = self.env().caller();
+            if !self.writers.contains(source) {
+                return Err(Error::Unauthorized);
+            }
+
+            self.entry_count = self.entry_count.checked_add(1).ok_or(Error::Overflow)?;
+            let entry_id = self.entry_count;
+
+            let entry = AuditEntry {
+                id: entry_id,
+                source,
+                actor,
+                action,
+                target,
+                reason_code,
+                timestamp: self.env().block_timestamp(),
+                prev_hash: self.head_hash,
+            };
+            self.entries.insert(entry_id, &entry);
+            self.head_hash = H256::from(self.env().hash_encoded::<Keccak256, _>(&entry));
+
+            self.env().emit_event(EntryRecorded {
+                entry_id,
+                actor,
+                target,
+                action,
+                reason_code,
+            });
+
+            Ok(entry_id)
+        }
+
+        /// Get a single entry
+        #[ink(message)]
+        pub fn get_entry(&self, entry_id: u64) -> Result<AuditEntry> {
+            self.entries.get(entry_id).ok_or(Error::EntryNotFound)
+        }
+
+        /// Get up to `limit` entries starting at `from_id`
+        #[ink(message)]
+        pub fn get_entries(&self, from_id: u64, limit: u32) -> Vec<AuditEntry> {
+            let limit = u64::from(limit.min(MAX_PAGE_SIZE));
+            let start = from_id.max(1);
+            let end = start
+                .saturating_add(limit)
+                .min(self.entry_count.saturating_add(1));
+
+            (start..end).filter_map(|id| self.entries.get(id)).collect()
+        }
+
+        /// Get up to `limit` entries recorded within `[from, to]`
+        ///
+        /// Timestamps never decrease with ids, so the start of the range is
+        /// found by binary search rather than a scan.
+        #[ink(message)]
+        pub fn get_entries_by_time(&self, from: u64, to: u64, limit: u32) -> Vec<AuditEntry> {
+            let start = self.first_entry_at_or_after(from);
+            self.get_entries(start, limit)
+                .into_iter()
+                .take_while(|entry| entry.timestamp <= to)
+                .collect()
+        }
+
+        /// Get the hash of the latest entry
+        #[ink(message)]
+        pub fn get_head_hash(&self) -> H256 {
+            self.head_hash
+        }
+
+        /// Get total entry count
+        #[ink(message)]
+        pub fn get_entry_count(&self) -> u64 {
+            self.entry_count
+        }
+
+        /// Check if an account may append entries
+        #[ink(message)]
+        pub fn is_writer(&self, account: H160) -> bool {
+            self.writers.contains(account)
+        }
+
+        fn first_entry_at_or_after(&self, timestamp: u64) -> u64 {
+            let (mut low, mut high) = (1, self.entry_count.saturating_add(1));
+            while low < high {
+                let mid = low + (high - low) / 2;
+                let mid_timestamp = self
+                    .entries
+                    .get(mid)
+                    .map(|e| e.timestamp)
+                    .unwrap_or(u64::MAX);
+                if mid_timestamp < timestamp {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            low
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn log_at(contract: &mut AuditLog, timestamp: u64) -> u64 {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(timestamp);
+            contract
+                .record(accounts.alice, AuditAction::Suspension, accounts.bob, 1)
+                .unwrap()
+        }
+
+        #[ink::test]
+        fn only_writers_can_record() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AuditLog::new();
+
+            assert_eq!(
+                contract.record(accounts.alice, AuditAction::Upgrade, accounts.bob, 0),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn entries_are_hash_chained() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AuditLog::new();
+            contract.set_writer(accounts.alice, true).unwrap();
+
+            log_at(&mut contract, 10);
+            let head_after_first = contract.get_head_hash();
+            let second = log_at(&mut contract, 20);
+
+            assert_ne!(head_after_first, H256::zero());
+            assert_eq!(
+                contract.get_entry(second).unwrap().prev_hash,
+                head_after_first
+            );
+        }
+
+        #[ink::test]
+        fn range_queries_work() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = AuditLog::new();
+            contract.set_writer(accounts.alice, true).unwrap();
+            for timestamp in [10, 20, 20, 30, 40] {
+                log_at(&mut contract, timestamp);
+            }
+
+            let ids = |entries: Vec<AuditEntry>| entries.iter().map(|e| e.id).collect::<Vec<_>>();
+            assert_eq!(ids(contract.get_entries(2, 2)), vec![2, 3]);
+            assert_eq!(ids(contract.get_entries_by_time(15, 30, 10)), vec![2, 3, 4]);
+            assert_eq!(
+                ids(contract.get_entries_by_time(50, 60, 10)),
+                Vec::<u64>::new()
+            );
+        }
+    }
+}
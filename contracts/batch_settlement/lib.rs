@@ -0,0 +1,329 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::batch_settlement::{BatchSettlementRef, Error, Receipt};
+
+#[ink::contract]
+mod batch_settlement {
+    use hub_types::HubError;
+    use ink::env::hash::Keccak256;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// A single x402 payment receipt; the Merkle leaves are keccak hashes
+    /// of the SCALE-encoded receipt
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Receipt {
+        pub payment_hash: H256,
+        pub payer: H160,
+        pub payee: H160,
+        pub amount: Balance,
+        pub service_id: u64,
+    }
+
+    /// Root posted by a facilitator for one epoch
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct EpochRoot {
+        pub root: H256,
+        pub receipt_count: u32,
+        pub posted_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a facilitator is approved or removed
+    #[ink(event)]
+    pub struct FacilitatorUpdated {
+        #[ink(topic)]
+        facilitator: H160,
+        approved: bool,
+    }
+    /// Emitted when a facilitator posts an epoch root
+    #[ink(event)]
+    pub struct RootPosted {
+        #[ink(topic)]
+        facilitator: H160,
+        #[ink(topic)]
+        epoch: u64,
+        root: H256,
+        receipt_count: u32,
+    }
+    /// Emitted when a receipt is proven and consumed
+    #[ink(event)]
+    pub struct ReceiptConsumed {
+        #[ink(topic)]
+        payment_hash: H256,
+        #[ink(topic)]
+        consumer: H160,
+        epoch: u64,
+        amount: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the caller is not an approved facilitator
+        NotFacilitator,
+        /// Emitted when a root was already posted for the epoch
+        RootAlreadyPosted,
+        /// Emitted when no root exists for the epoch
+        RootNotFound,
+        /// Emitted when the inclusion proof does not match the root
+        InvalidProof,
+        /// Emitted when the receipt was already consumed
+        ReceiptAlreadyConsumed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NotFacilitator => HubError::Unauthorized,
+                Error::RootAlreadyPosted => HubError::AlreadyExists,
+                Error::RootNotFound => HubError::NotFound,
+                Error::InvalidProof => HubError::InvalidProof,
+                Error::ReceiptAlreadyConsumed => HubError::AlreadyExists,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct BatchSettlement {
+        owner: H160,
+        facilitators: Mapping<H160, ()>,
+        roots: Mapping<(H160, u64), EpochRoot>,
+        // payment_hash -> account that consumed the receipt
+        consumed: Mapping<H256, H160>,
+    }
+
+    impl BatchSettlement {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                facilitators: Mapping::default(),
+                roots: Mapping::default(),
+                consumed: Mapping::default(),
+            }
+        }
+
+        /// Approve or remove a facilitator
+        #[ink(message)]
+        pub fn set_facilitator(&mut self, facilitator: H160, approved: bool) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            if approved {
+                self.facilitators.insert(facilitator, &());
+            } else {
+                self.facilitators.remove(facilitator);
+            }
+
+            self.env().emit_event(FacilitatorUpdated {
+                facilitator,
+                approved,
+            });
+
+            Ok(())
+        }
+
+        /// Post the Merkle root of all receipts settled in an epoch
+        #[ink(message)]
+        pub fn post_root(&mut self, epoch: u64, root: H256, receipt_count: u32) -> Result<()> {
+            let facilitator = self.env().caller();
+            if !self.facilitators.contains(facilitator) {
+                return Err(Error::NotFacilitator);
+            }
+            // Roots are immutable once posted so proofs can't be invalidated
+            if self.roots.contains((facilitator, epoch)) {
+                return Err(Error::RootAlreadyPosted);
+            }
+
+            let epoch_root = EpochRoot {
+                root,
+                receipt_count,
+                posted_at: self.env().block_timestamp(),
+            };
+            self.roots.insert((facilitator, epoch), &epoch_root);
+
+            self.env().emit_event(RootPosted {
+                facilitator,
+                epoch,
+                root,
+                receipt_count,
+            });
+
+            Ok(())
+        }
+
+        /// Check that a receipt is included in a facilitator's epoch root
+        #[ink(message)]
+        pub fn verify_receipt(
+            &self,
+            facilitator: H160,
+            epoch: u64,
+            receipt: Receipt,
+            proof: Vec<H256>,
+        ) -> bool {
+            match self.roots.get((facilitator, epoch)) {
+                Some(epoch_root) => verify_proof(epoch_root.root, leaf_hash(&receipt), &proof),
+                None => false,
+            }
+        }
+
+        /// Prove a receipt and mark it consumed so it can't back a second payment
+        #[ink(message)]
+        pub fn consume_receipt(
+            &mut self,
+            facilitator: H160,
+            epoch: u64,
+            receipt: Receipt,
+            proof: Vec<H256>,
+        ) -> Result<()> {
+            let consumer = self.env().caller();
+            let epoch_root = self
+                .roots
+                .get((facilitator, epoch))
+                .ok_or(Error::RootNotFound)?;
+
+            if self.consumed.contains(receipt.payment_hash) {
+                return Err(Error::ReceiptAlreadyConsumed);
+            }
+            if !verify_proof(epoch_root.root, leaf_hash(&receipt), &proof) {
+                return Err(Error::InvalidProof);
+            }
+
+            self.consumed.insert(receipt.payment_hash, &consumer);
+
+            self.env().emit_event(ReceiptConsumed {
+                payment_hash: receipt.payment_hash,
+                consumer,
+                epoch,
+                amount: receipt.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Get the root a facilitator posted for an epoch
+        #[ink(message)]
+        pub fn get_root(&self, facilitator: H160, epoch: u64) -> Option<EpochRoot> {
+            self.roots.get((facilitator, epoch))
+        }
+
+        /// Get the account that consumed a receipt, if any
+        #[ink(message)]
+        pub fn get_consumer(&self, payment_hash: H256) -> Option<H160> {
+            self.consumed.get(payment_hash)
+        }
+
+        /// Check if an account is an approved facilitator
+        #[ink(message)]
+        pub fn is_facilitator(&self, account: H160) -> bool {
+            self.facilitators.contains(account)
+        }
+    }
+
+    fn keccak(input: &[u8]) -> H256 {
+        let mut output = [0u8; 32];
+        ink::env::hash_bytes::<Keccak256>(input, &mut output);
+        H256::from(output)
+    }
+
+    fn leaf_hash(receipt: &Receipt) -> H256 {
+        keccak(&scale::Encode::encode(receipt))
+    }
+
+    /// Pairs are hashed in sorted order, so proofs don't need position bits
+    fn hash_pair(a: H256, b: H256) -> H256 {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let mut input = [0u8; 64];
+        input[..32].copy_from_slice(first.as_bytes());
+        input[32..].copy_from_slice(second.as_bytes());
+        keccak(&input)
+    }
+
+    fn verify_proof(root: H256, leaf: H256, proof: &[H256]) -> bool {
+        let computed = proof
+            .iter()
+            .fold(leaf, |node, sibling| hash_pair(node, *sibling));
+        computed == root
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn receipt(seed: u8) -> Receipt {
+            Receipt {
+                payment_hash: H256::from([seed; 32]),
+                payer: H160::from([1; 20]),
+                payee: H160::from([2; 20]),
+                amount: 100 * u128::from(seed),
+                service_id: 1,
+            }
+        }
+
+        /// Four-leaf tree over receipts 1..=4 and the proof for receipt 3
+        fn tree() -> (H256, Vec<H256>) {
+            let leaves: Vec<H256> = (1..=4).map(|i| leaf_hash(&receipt(i))).collect();
+            let left = hash_pair(leaves[0], leaves[1]);
+            let right = hash_pair(leaves[2], leaves[3]);
+            (hash_pair(left, right), vec![leaves[3], left])
+        }
+
+        #[ink::test]
+        fn verify_proof_works() {
+            let (root, proof) = tree();
+
+            assert!(verify_proof(root, leaf_hash(&receipt(3)), &proof));
+            assert!(!verify_proof(root, leaf_hash(&receipt(5)), &proof));
+        }
+
+        #[ink::test]
+        fn consume_receipt_works_once() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = BatchSettlement::new();
+            let (root, proof) = tree();
+            contract.set_facilitator(accounts.alice, true).unwrap();
+            contract.post_root(7, root, 4).unwrap();
+
+            assert!(contract.verify_receipt(accounts.alice, 7, receipt(3), proof.clone()));
+            assert!(contract
+                .consume_receipt(accounts.alice, 7, receipt(3), proof.clone())
+                .is_ok());
+            assert_eq!(
+                contract.consume_receipt(accounts.alice, 7, receipt(3), proof),
+                Err(Error::ReceiptAlreadyConsumed)
+            );
+        }
+
+        #[ink::test]
+        fn only_facilitators_post_roots_once() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = BatchSettlement::new();
+
+            assert_eq!(
+                contract.post_root(1, H256::zero(), 0),
+                Err(Error::NotFacilitator)
+            );
+
+            contract.set_facilitator(accounts.alice, true).unwrap();
+            contract.post_root(1, H256::zero(), 0).unwrap();
+            assert_eq!(
+                contract.post_root(1, H256::zero(), 0),
+                Err(Error::RootAlreadyPosted)
+            );
+        }
+    }
+}
@@ -0,0 +1,578 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod bounty {
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use service_registry::ServiceRegistryRef;
+
+    /// Different statuses of a bounty
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum BountyStatus {
+        Open,
+        Awarded,
+        Cancelled,
+    }
+
+    /// Bounty details
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Bounty {
+        pub id: u64,
+        pub funder: H160,
+        pub reward: Balance,
+        // Hash of the off-chain task spec and acceptance criteria
+        pub spec_hash: H256,
+        pub deadline: u64,
+        // A single judge is simply a committee of one
+        pub judges: Vec<H160>,
+        pub approvals_required: u32,
+        pub status: BountyStatus,
+        pub winning_submission: Option<u64>,
+        pub created_at: u64,
+    }
+
+    /// A solution submitted against a bounty
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Submission {
+        pub id: u64,
+        pub bounty_id: u64,
+        pub provider: H160,
+        pub solution_hash: H256,
+        pub approvals: u32,
+        pub submitted_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a bounty is funded
+    #[ink(event)]
+    pub struct BountyCreated {
+        #[ink(topic)]
+        bounty_id: u64,
+        #[ink(topic)]
+        funder: H160,
+        reward: Balance,
+        deadline: u64,
+    }
+    /// Emitted when a provider submits a solution
+    #[ink(event)]
+    pub struct SolutionSubmitted {
+        #[ink(topic)]
+        bounty_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        submission_id: u64,
+        solution_hash: H256,
+    }
+    /// Emitted when a judge approves a submission
+    #[ink(event)]
+    pub struct SubmissionApproved {
+        #[ink(topic)]
+        submission_id: u64,
+        #[ink(topic)]
+        judge: H160,
+        approvals: u32,
+    }
+    /// Emitted when the reward is paid out to the winning provider
+    #[ink(event)]
+    pub struct BountyAwarded {
+        #[ink(topic)]
+        bounty_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        submission_id: u64,
+        reward: Balance,
+    }
+    /// Emitted when the funder reclaims an unawarded bounty
+    #[ink(event)]
+    pub struct BountyCancelled {
+        #[ink(topic)]
+        bounty_id: u64,
+        #[ink(topic)]
+        funder: H160,
+        reward: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the reward is zero
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the bounty is not found
+        BountyNotFound,
+        /// Emitted when the submission is not found
+        SubmissionNotFound,
+        /// Emitted when the bounty is not open
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the submitter has no service listed in the registry
+        NotRegisteredProvider,
+        /// Emitted when the submission deadline has passed
+        DeadlinePassed,
+        /// Emitted when the judging window is still running
+        JudgingNotOver,
+        /// Emitted when a judge votes twice for the same submission
+        AlreadyApproved,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::BountyNotFound => HubError::NotFound,
+                Error::SubmissionNotFound => HubError::NotFound,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NotRegisteredProvider => HubError::Unauthorized,
+                Error::DeadlinePassed => HubError::Expired,
+                Error::JudgingNotOver => HubError::TooEarly,
+                Error::AlreadyApproved => HubError::AlreadyExists,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct BountyBoard {
+        registry: H160,
+        bounties: Mapping<u64, Bounty>,
+        bounty_count: u64,
+        submissions: Mapping<u64, Submission>,
+        submission_count: u64,
+        bounty_submissions: Mapping<u64, Vec<u64>>,
+        approvals: Mapping<(u64, H160), ()>,
+        // Time judges have after the deadline before the funder may reclaim
+        judging_period: u64,
+    }
+
+    impl BountyBoard {
+        #[ink(constructor)]
+        pub fn new(registry: H160, judging_period: u64) -> Self {
+            Self {
+                registry,
+                bounties: Mapping::default(),
+                bounty_count: 0,
+                submissions: Mapping::default(),
+                submission_count: 0,
+                bounty_submissions: Mapping::default(),
+                approvals: Mapping::default(),
+                judging_period,
+            }
+        }
+
+        /// Fund a new bounty with the transferred value
+        #[ink(message, payable)]
+        pub fn create_bounty(
+            &mut self,
+            spec_hash: H256,
+            deadline: u64,
+            judges: Vec<H160>,
+            approvals_required: u32,
+        ) -> Result<u64> {
+            let funder = self.env().caller();
+            let reward: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if reward == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if deadline <= self.env().block_timestamp()
+                || judges.is_empty()
+                || approvals_required == 0
+                || approvals_required as usize > judges.len()
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            self.bounty_count = self.bounty_count.checked_add(1).ok_or(Error::Overflow)?;
+            let bounty_id = self.bounty_count;
+
+            let bounty = Bounty {
+                id: bounty_id,
+                funder,
+                reward,
+                spec_hash,
+                deadline,
+                judges,
+                approvals_required,
+                status: BountyStatus::Open,
+                winning_submission: None,
+                created_at: self.env().block_timestamp(),
+            };
+            self.bounties.insert(bounty_id, &bounty);
+
+            self.env().emit_event(BountyCreated {
+                bounty_id,
+                funder,
+                reward,
+                deadline,
+            });
+
+            Ok(bounty_id)
+        }
+
+        /// Submit a solution; only providers with a registered service may compete
+        #[ink(message)]
+        pub fn submit_solution(&mut self, bounty_id: u64, solution_hash: H256) -> Result<u64> {
+            let provider = self.env().caller();
+            let bounty = self.bounties.get(bounty_id).ok_or(Error::BountyNotFound)?;
+
+            if bounty.status != BountyStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() > bounty.deadline {
+                return Err(Error::DeadlinePassed);
+            }
+
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            if registry.get_provider_services(provider).is_empty() {
+                return Err(Error::NotRegisteredProvider);
+            }
+
+            self.submission_count = self
+                .submission_count
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            let submission_id = self.submission_count;
+
+            let submission = Submission {
+                id: submission_id,
+                bounty_id,
+                provider,
+                solution_hash,
+                approvals: 0,
+                submitted_at: self.env().block_timestamp(),
+            };
+            self.submissions.insert(submission_id, &submission);
+
+            let mut submissions = self.bounty_submissions.get(bounty_id).unwrap_or_default();
+            submissions.push(submission_id);
+            self.bounty_submissions.insert(bounty_id, &submissions);
+
+            self.env().emit_event(SolutionSubmitted {
+                bounty_id,
+                provider,
+                submission_id,
+                solution_hash,
+            });
+
+            Ok(submission_id)
+        }
+
+        /// Approve a submission; the reward is paid once enough judges agree
+        #[ink(message)]
+        pub fn approve_submission(&mut self, submission_id: u64) -> Result<()> {
+            let judge = self.env().caller();
+            let mut submission = self
+                .submissions
+                .get(submission_id)
+                .ok_or(Error::SubmissionNotFound)?;
+            let mut bounty = self
+                .bounties
+                .get(submission.bounty_id)
+                .ok_or(Error::BountyNotFound)?;
+
+            if !bounty.judges.contains(&judge) {
+                return Err(Error::Unauthorized);
+            }
+            if bounty.status != BountyStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            if self.approvals.contains((submission_id, judge)) {
+                return Err(Error::AlreadyApproved);
+            }
+
+            self.approvals.insert((submission_id, judge), &());
+            submission.approvals = submission.approvals.checked_add(1).ok_or(Error::Overflow)?;
+            self.submissions.insert(submission_id, &submission);
+
+            self.env().emit_event(SubmissionApproved {
+                submission_id,
+                judge,
+                approvals: submission.approvals,
+            });
+
+            if submission.approvals < bounty.approvals_required {
+                return Ok(());
+            }
+
+            // Transfer the reward to the winning provider
+            if self
+                .env()
+                .transfer(submission.provider, bounty.reward.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            bounty.status = BountyStatus::Awarded;
+            bounty.winning_submission = Some(submission_id);
+            self.bounties.insert(bounty.id, &bounty);
+
+            self.env().emit_event(BountyAwarded {
+                bounty_id: bounty.id,
+                provider: submission.provider,
+                submission_id,
+                reward: bounty.reward,
+            });
+
+            Ok(())
+        }
+
+        /// Reclaim the reward once the deadline and judging window have passed
+        #[ink(message)]
+        pub fn reclaim_bounty(&mut self, bounty_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut bounty = self.bounties.get(bounty_id).ok_or(Error::BountyNotFound)?;
+
+            if bounty.funder != caller {
+                return Err(Error::Unauthorized);
+            }
+            if bounty.status != BountyStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            let judging_ends = bounty.deadline.saturating_add(self.judging_period);
+            if self.env().block_timestamp() <= judging_ends {
+                return Err(Error::JudgingNotOver);
+            }
+
+            if self
+                .env()
+                .transfer(bounty.funder, bounty.reward.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            bounty.status = BountyStatus::Cancelled;
+            self.bounties.insert(bounty_id, &bounty);
+
+            self.env().emit_event(BountyCancelled {
+                bounty_id,
+                funder: bounty.funder,
+                reward: bounty.reward,
+            });
+
+            Ok(())
+        }
+
+        /// Get bounty details
+        #[ink(message)]
+        pub fn get_bounty(&self, bounty_id: u64) -> Result<Bounty> {
+            self.bounties.get(bounty_id).ok_or(Error::BountyNotFound)
+        }
+
+        /// Get submission details
+        #[ink(message)]
+        pub fn get_submission(&self, submission_id: u64) -> Result<Submission> {
+            self.submissions
+                .get(submission_id)
+                .ok_or(Error::SubmissionNotFound)
+        }
+
+        /// Get all submissions made against a bounty
+        #[ink(message)]
+        pub fn get_bounty_submissions(&self, bounty_id: u64) -> Vec<u64> {
+            self.bounty_submissions.get(bounty_id).unwrap_or_default()
+        }
+
+        /// Get total bounty count
+        #[ink(message)]
+        pub fn get_bounty_count(&self) -> u64 {
+            self.bounty_count
+        }
+
+        /// Get the judging window that follows each deadline
+        #[ink(message)]
+        pub fn get_judging_period(&self) -> u64 {
+            self.judging_period
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::{balance_of, deploy_at};
+        use ink::{ToAddr, U256};
+        use service_registry::{PaymentAsset, ServiceCategory};
+
+        const JUDGING_PERIOD: u64 = 100;
+        const DEADLINE: u64 = 1_000;
+        const REWARD: Balance = 500;
+
+        /// Deploys a registry in which `provider` lists one service
+        fn registry_listing(provider: H160) -> H160 {
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, ServiceRegistryRef>();
+            let mut registry = ServiceRegistryRef::new()
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            ink::env::test::set_caller(provider);
+            registry
+                .register_service(
+                    String::from("Solver"),
+                    String::from("Solves posted tasks"),
+                    ServiceCategory::TextProcessing,
+                    10,
+                    String::from("https://solver.example"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            registry.to_addr()
+        }
+
+        /// A board where bob is a registered provider and alice has funded a
+        /// bounty judged by django and eve, both of whom must approve
+        fn board_with_bounty() -> (BountyBoard, u64) {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xB0; 20]), REWARD);
+
+            let mut board = BountyBoard::new(registry_listing(accounts.bob), JUDGING_PERIOD);
+
+            ink::env::test::set_caller(accounts.alice);
+            ink::env::test::set_value_transferred(U256::from(REWARD));
+            let bounty_id = board
+                .create_bounty(
+                    H256::from([1; 32]),
+                    DEADLINE,
+                    vec![accounts.django, accounts.eve],
+                    2,
+                )
+                .unwrap();
+            // Registry calls carry no value
+            ink::env::test::set_value_transferred(U256::zero());
+            (board, bounty_id)
+        }
+
+        #[ink::test]
+        fn bounties_need_a_reward_and_a_reachable_quorum() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut board = BountyBoard::new(accounts.frank, JUDGING_PERIOD);
+            let spec = H256::from([1; 32]);
+
+            assert_eq!(
+                board.create_bounty(spec, DEADLINE, vec![accounts.django], 1),
+                Err(Error::InvalidAmount)
+            );
+            ink::env::test::set_value_transferred(U256::from(REWARD));
+            assert_eq!(
+                board.create_bounty(spec, DEADLINE, vec![accounts.django], 2),
+                Err(Error::InvalidInput)
+            );
+            ink::env::test::set_block_timestamp(DEADLINE);
+            assert_eq!(
+                board.create_bounty(spec, DEADLINE, vec![accounts.django], 1),
+                Err(Error::InvalidInput)
+            );
+
+            let bounty_id = board
+                .create_bounty(spec, DEADLINE + 1, vec![accounts.django], 1)
+                .unwrap();
+            let bounty = board.get_bounty(bounty_id).unwrap();
+            assert_eq!(bounty.funder, accounts.alice);
+            assert_eq!(bounty.reward, REWARD);
+            assert_eq!(bounty.status, BountyStatus::Open);
+            assert_eq!(board.get_bounty_count(), 1);
+        }
+
+        #[ink::test]
+        fn judges_award_the_reward_once() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut board, bounty_id) = board_with_bounty();
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                board.submit_solution(bounty_id, H256::from([2; 32])),
+                Err(Error::NotRegisteredProvider)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            let first = board
+                .submit_solution(bounty_id, H256::from([2; 32]))
+                .unwrap();
+            let second = board
+                .submit_solution(bounty_id, H256::from([3; 32]))
+                .unwrap();
+            assert_eq!(board.get_bounty_submissions(bounty_id), vec![first, second]);
+
+            assert_eq!(board.approve_submission(first), Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.django);
+            board.approve_submission(first).unwrap();
+            assert_eq!(board.approve_submission(first), Err(Error::AlreadyApproved));
+            assert_eq!(
+                board.get_bounty(bounty_id).unwrap().status,
+                BountyStatus::Open
+            );
+
+            let before = balance_of(accounts.bob);
+            ink::env::test::set_caller(accounts.eve);
+            board.approve_submission(first).unwrap();
+            assert_eq!(balance_of(accounts.bob), before + REWARD);
+            let bounty = board.get_bounty(bounty_id).unwrap();
+            assert_eq!(bounty.status, BountyStatus::Awarded);
+            assert_eq!(bounty.winning_submission, Some(first));
+
+            // The reward is gone, so no other submission can win it
+            assert_eq!(board.approve_submission(second), Err(Error::InvalidStatus));
+            ink::env::test::set_caller(accounts.django);
+            assert_eq!(board.approve_submission(second), Err(Error::InvalidStatus));
+        }
+
+        #[ink::test]
+        fn funders_reclaim_after_the_judging_window() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut board, bounty_id) = board_with_bounty();
+
+            ink::env::test::set_block_timestamp(DEADLINE + 1);
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                board.submit_solution(bounty_id, H256::from([2; 32])),
+                Err(Error::DeadlinePassed)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            ink::env::test::set_block_timestamp(DEADLINE + JUDGING_PERIOD);
+            assert_eq!(board.reclaim_bounty(bounty_id), Err(Error::JudgingNotOver));
+
+            ink::env::test::set_block_timestamp(DEADLINE + JUDGING_PERIOD + 1);
+            ink::env::test::set_caller(accounts.django);
+            assert_eq!(board.reclaim_bounty(bounty_id), Err(Error::Unauthorized));
+
+            let before = balance_of(accounts.alice);
+            ink::env::test::set_caller(accounts.alice);
+            board.reclaim_bounty(bounty_id).unwrap();
+            assert_eq!(balance_of(accounts.alice), before + REWARD);
+            assert_eq!(
+                board.get_bounty(bounty_id).unwrap().status,
+                BountyStatus::Cancelled
+            );
+            assert_eq!(board.reclaim_bounty(bounty_id), Err(Error::InvalidStatus));
+        }
+    }
+}
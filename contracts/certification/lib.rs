@@ -0,0 +1,326 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::certification::{Certification, CertificationsRef, Error};
+
+#[ink::contract]
+mod certification {
+    use hub_types::HubError;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// Scores are expressed in basis points of the benchmark maximum
+    const MAX_SCORE: u32 = 10_000;
+    /// Upper bound on the length of a benchmark name
+    const MAX_BENCHMARK_LEN: usize = 64;
+
+    /// A certifier's record that an agent passed a capability benchmark
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Certification {
+        pub id: u64,
+        pub certifier: H160,
+        pub subject: H160,
+        pub benchmark: String,
+        // Hash of the evaluation dataset the score was measured on
+        pub dataset_hash: H256,
+        pub score: u32,
+        pub certified_at: u64,
+        pub expires_at: Option<u64>,
+        pub revoked: bool,
+    }
+
+    /// Events
+    /// Emitted when a certifier is accredited or removed
+    #[ink(event)]
+    pub struct CertifierUpdated {
+        #[ink(topic)]
+        certifier: H160,
+        accredited: bool,
+    }
+    /// Emitted when a certification is recorded
+    #[ink(event)]
+    pub struct Certified {
+        #[ink(topic)]
+        certification_id: u64,
+        #[ink(topic)]
+        subject: H160,
+        #[ink(topic)]
+        certifier: H160,
+        benchmark: String,
+        score: u32,
+    }
+    /// Emitted when a certification is revoked
+    #[ink(event)]
+    pub struct CertificationRevoked {
+        #[ink(topic)]
+        certification_id: u64,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the certification is not found
+        CertificationNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the caller is not an accredited certifier
+        NotCertifier,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::CertificationNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NotCertifier => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Certifications {
+        owner: H160,
+        certifiers: Mapping<H160, ()>,
+        certifications: Mapping<u64, Certification>,
+        certification_count: u64,
+        subject_certifications: Mapping<H160, Vec<u64>>,
+    }
+
+    impl Certifications {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                certifiers: Mapping::default(),
+                certifications: Mapping::default(),
+                certification_count: 0,
+                subject_certifications: Mapping::default(),
+            }
+        }
+
+        /// Accredit or remove a certifier
+        #[ink(message)]
+        pub fn set_certifier(&mut self, certifier: H160, accredited: bool) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            if accredited {
+                self.certifiers.insert(certifier, &());
+            } else {
+                self.certifiers.remove(certifier);
+            }
+
+            self.env().emit_event(CertifierUpdated {
+                certifier,
+                accredited,
+            });
+
+            Ok(())
+        }
+
+        /// Record that `subject` passed a benchmark
+        #[ink(message)]
+        pub fn certify(
+            &mut self,
+            subject: H160,
+            benchmark: String,
+            dataset_hash: H256,
+            score: u32,
+            expires_at: Option<u64>,
+        ) -> Result<u64> {
+            let certifier = self.env().caller();
+            if !self.certifiers.contains(certifier) {
+                return Err(Error::NotCertifier);
+            }
+            if benchmark.is_empty() || benchmark.len() > MAX_BENCHMARK_LEN || score > MAX_SCORE {
+                return Err(Error::InvalidInput);
+            }
+            let certified_at = self.env().block_timestamp();
+            if expires_at.is_some_and(|expires_at| expires_at <= certified_at) {
+                return Err(Error::InvalidInput);
+            }
+
+            self.certification_count = self
+                .certification_count
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            let certification_id = self.certification_count;
+
+            let certification = Certification {
+                id: certification_id,
+                certifier,
+                subject,
+                benchmark: benchmark.clone(),
+                dataset_hash,
+                score,
+                certified_at,
+                expires_at,
+                revoked: false,
+            };
+            self.certifications.insert(certification_id, &certification);
+
+            let mut ids = self.subject_certifications.get(subject).unwrap_or_default();
+            ids.push(certification_id);
+            self.subject_certifications.insert(subject, &ids);
+
+            self.env().emit_event(Certified {
+                certification_id,
+                subject,
+                certifier,
+                benchmark,
+                score,
+            });
+
+            Ok(certification_id)
+        }
+
+        /// Revoke a certification; only its certifier or the owner may
+        #[ink(message)]
+        pub fn revoke(&mut self, certification_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut certification = self.get_certification(certification_id)?;
+            if caller != certification.certifier && caller != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            certification.revoked = true;
+            self.certifications.insert(certification_id, &certification);
+
+            self.env()
+                .emit_event(CertificationRevoked { certification_id });
+
+            Ok(())
+        }
+
+        /// Certifications of a subject that are still valid
+        ///
+        /// Revoked and expired certifications are left out, as are those
+        /// whose certifier has since lost its accreditation.
+        #[ink(message)]
+        pub fn get_valid_certifications(&self, subject: H160) -> Vec<Certification> {
+            let now = self.env().block_timestamp();
+            self.subject_certifications
+                .get(subject)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.certifications.get(id))
+                .filter(|certification| {
+                    !certification.revoked
+                        && certification
+                            .expires_at
+                            .is_none_or(|expires_at| now <= expires_at)
+                        && self.certifiers.contains(certification.certifier)
+                })
+                .collect()
+        }
+
+        /// Get certification details
+        #[ink(message)]
+        pub fn get_certification(&self, certification_id: u64) -> Result<Certification> {
+            self.certifications
+                .get(certification_id)
+                .ok_or(Error::CertificationNotFound)
+        }
+
+        /// Get all certifications ever recorded for a subject
+        #[ink(message)]
+        pub fn get_subject_certifications(&self, subject: H160) -> Vec<u64> {
+            self.subject_certifications.get(subject).unwrap_or_default()
+        }
+
+        /// Check if an account is an accredited certifier
+        #[ink(message)]
+        pub fn is_certifier(&self, account: H160) -> bool {
+            self.certifiers.contains(account)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn only_accredited_certifiers_can_certify() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Certifications::new();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.certify(
+                    accounts.charlie,
+                    String::from("translation-en-fr"),
+                    H256::from([0x11; 32]),
+                    9_000,
+                    None
+                ),
+                Err(Error::NotCertifier)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_certifier(accounts.bob, true).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            let id = contract
+                .certify(
+                    accounts.charlie,
+                    String::from("translation-en-fr"),
+                    H256::from([0x11; 32]),
+                    9_000,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(contract.get_valid_certifications(accounts.charlie).len(), 1);
+            assert_eq!(contract.get_certification(id).unwrap().score, 9_000);
+        }
+
+        #[ink::test]
+        fn revoked_and_deaccredited_certifications_are_hidden() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Certifications::new();
+            contract.set_certifier(accounts.bob, true).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            let first = contract
+                .certify(
+                    accounts.charlie,
+                    String::from("summarization"),
+                    H256::from([0x22; 32]),
+                    8_000,
+                    None,
+                )
+                .unwrap();
+            contract
+                .certify(
+                    accounts.charlie,
+                    String::from("qa"),
+                    H256::from([0x33; 32]),
+                    7_000,
+                    None,
+                )
+                .unwrap();
+
+            contract.revoke(first).unwrap();
+            assert_eq!(contract.get_valid_certifications(accounts.charlie).len(), 1);
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_certifier(accounts.bob, false).unwrap();
+            assert!(contract
+                .get_valid_certifications(accounts.charlie)
+                .is_empty());
+        }
+    }
+}
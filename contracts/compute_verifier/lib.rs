@@ -0,0 +1,18 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Interface for proof-of-compute verifiers
+//!
+//! Escrows for computation services can point at any contract implementing
+//! [`ComputeVerifier`]; the escrow only pays out on a submitted proof once the
+//! verifier accepts it. Verifiers may check zk proofs, TEE attestations or
+//! anything else that binds a result to a job.
+
+use ink::prelude::vec::Vec;
+use ink::H256;
+
+#[ink::trait_definition]
+pub trait ComputeVerifier {
+    /// Check that `proof` attests correct execution of the job `job_hash`
+    #[ink(message)]
+    fn verify(&self, job_hash: H256, proof: Vec<u8>) -> bool;
+}
@@ -0,0 +1,542 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod credential_handoff {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::format;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use payment_escrow::PaymentEscrowRef;
+
+    /// Upper bound on the payer's encryption key length
+    const MAX_KEY_LEN: usize = 128;
+
+    /// Different statuses of a credential hand-off
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum HandoffStatus {
+        // Paid, waiting for the provider to commit the ciphertext
+        Requested,
+        // Ciphertext committed, waiting for the payer's acknowledgment
+        Committed,
+        // Payer confirmed decryption, payment released
+        Acknowledged,
+        // Provider collected after the acknowledgment window lapsed
+        Claimed,
+        // Provider never committed, payment returned
+        Reclaimed,
+    }
+
+    /// A paid request for encrypted credentials
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Handoff {
+        pub id: u64,
+        pub escrow_id: u64,
+        pub payer: H160,
+        pub provider: H160,
+        pub service_id: u64,
+        pub amount: Balance,
+        // Public key the provider must encrypt the credentials to
+        pub payer_key: Vec<u8>,
+        pub ciphertext_hash: Option<H256>,
+        pub status: HandoffStatus,
+        pub requested_at: u64,
+        pub committed_at: Option<u64>,
+    }
+
+    /// Events
+    /// Emitted when a payer pays for credentials
+    #[ink(event)]
+    pub struct CredentialsRequested {
+        #[ink(topic)]
+        handoff_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        escrow_id: u64,
+        payer_key: Vec<u8>,
+    }
+    /// Emitted when the provider commits the encrypted credentials
+    #[ink(event)]
+    pub struct CiphertextCommitted {
+        #[ink(topic)]
+        handoff_id: u64,
+        #[ink(topic)]
+        payer: H160,
+        ciphertext_hash: H256,
+    }
+    /// Emitted when a hand-off is settled either way
+    #[ink(event)]
+    pub struct HandoffSettled {
+        #[ink(topic)]
+        handoff_id: u64,
+        status: HandoffStatus,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the amount is zero
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the hand-off is not found
+        HandoffNotFound,
+        /// Emitted when the hand-off is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the commit deadline has passed
+        DeadlinePassed,
+        /// Emitted when the relevant window is still open
+        WindowOpen,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::HandoffNotFound => HubError::NotFound,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::DeadlinePassed => HubError::Expired,
+                Error::WindowOpen => HubError::TooEarly,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct CredentialHandoff {
+        escrow: H160,
+        handoffs: Mapping<u64, Handoff>,
+        handoff_count: u64,
+        user_handoffs: Mapping<H160, Vec<u64>>,
+        // Time the provider has to commit after payment, in milliseconds
+        commit_window: u64,
+        // Time the payer has to acknowledge after the commit, in milliseconds
+        ack_window: u64,
+    }
+
+    impl CredentialHandoff {
+        #[ink(constructor)]
+        pub fn new(escrow: H160, commit_window: u64, ack_window: u64) -> Self {
+            Self {
+                escrow,
+                handoffs: Mapping::default(),
+                handoff_count: 0,
+                user_handoffs: Mapping::default(),
+                commit_window,
+                ack_window,
+            }
+        }
+
+        /// Pay for credentials; the transferred value is held in escrow
+        /// until the payer acknowledges receipt
+        #[ink(message, payable)]
+        pub fn request_credentials(
+            &mut self,
+            provider: H160,
+            service_id: u64,
+            payer_key: Vec<u8>,
+        ) -> Result<u64> {
+            let payer = self.env().caller();
+            let amount: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if provider == payer || payer_key.is_empty() || payer_key.len() > MAX_KEY_LEN {
+                return Err(Error::InvalidInput);
+            }
+
+            self.handoff_count = self.handoff_count.checked_add(1).ok_or(Error::Overflow)?;
+            let handoff_id = self.handoff_count;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let escrow_id = escrow
+                .call_mut()
+                .create_escrow(
+                    provider,
+                    service_id,
+                    format!("handoff:{}", handoff_id),
+                    PaymentAsset::Native,
+                )
+                .transferred_value(amount.into())
+                .invoke()
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            let handoff = Handoff {
+                id: handoff_id,
+                escrow_id,
+                payer,
+                provider,
+                service_id,
+                amount,
+                payer_key: payer_key.clone(),
+                ciphertext_hash: None,
+                status: HandoffStatus::Requested,
+                requested_at: self.env().block_timestamp(),
+                committed_at: None,
+            };
+            self.handoffs.insert(handoff_id, &handoff);
+
+            for user in [payer, provider] {
+                let mut handoffs = self.user_handoffs.get(user).unwrap_or_default();
+                handoffs.push(handoff_id);
+                self.user_handoffs.insert(user, &handoffs);
+            }
+
+            self.env().emit_event(CredentialsRequested {
+                handoff_id,
+                provider,
+                escrow_id,
+                payer_key,
+            });
+
+            Ok(handoff_id)
+        }
+
+        /// Commit the hash of the credentials encrypted to the payer's key
+        ///
+        /// The ciphertext itself is delivered off-chain.
+        #[ink(message)]
+        pub fn commit_ciphertext(&mut self, handoff_id: u64, ciphertext_hash: H256) -> Result<()> {
+            let mut handoff = self.get_handoff(handoff_id)?;
+            if handoff.provider != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if handoff.status != HandoffStatus::Requested {
+                return Err(Error::InvalidStatus);
+            }
+            let now = self.env().block_timestamp();
+            if now > handoff.requested_at.saturating_add(self.commit_window) {
+                return Err(Error::DeadlinePassed);
+            }
+
+            handoff.ciphertext_hash = Some(ciphertext_hash);
+            handoff.committed_at = Some(now);
+            handoff.status = HandoffStatus::Committed;
+            self.handoffs.insert(handoff_id, &handoff);
+
+            self.env().emit_event(CiphertextCommitted {
+                handoff_id,
+                payer: handoff.payer,
+                ciphertext_hash,
+            });
+
+            Ok(())
+        }
+
+        /// Acknowledge that the committed ciphertext was received and
+        /// decrypted, releasing payment to the provider
+        #[ink(message)]
+        pub fn acknowledge(&mut self, handoff_id: u64) -> Result<()> {
+            let mut handoff = self.get_handoff(handoff_id)?;
+            if handoff.payer != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if handoff.status != HandoffStatus::Committed {
+                return Err(Error::InvalidStatus);
+            }
+
+            self.release(&handoff)?;
+            self.settle(&mut handoff, HandoffStatus::Acknowledged);
+
+            Ok(())
+        }
+
+        /// Collect payment when the payer stays silent past the
+        /// acknowledgment window
+        #[ink(message)]
+        pub fn claim(&mut self, handoff_id: u64) -> Result<()> {
+            let mut handoff = self.get_handoff(handoff_id)?;
+            if handoff.provider != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if handoff.status != HandoffStatus::Committed {
+                return Err(Error::InvalidStatus);
+            }
+            let committed_at = handoff.committed_at.unwrap_or(handoff.requested_at);
+            if self.env().block_timestamp() <= committed_at.saturating_add(self.ack_window) {
+                return Err(Error::WindowOpen);
+            }
+
+            self.release(&handoff)?;
+            self.settle(&mut handoff, HandoffStatus::Claimed);
+
+            Ok(())
+        }
+
+        /// Take the payment back when the provider never committed
+        #[ink(message)]
+        pub fn reclaim(&mut self, handoff_id: u64) -> Result<()> {
+            let mut handoff = self.get_handoff(handoff_id)?;
+            if handoff.payer != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if handoff.status != HandoffStatus::Requested {
+                return Err(Error::InvalidStatus);
+            }
+            let deadline = handoff.requested_at.saturating_add(self.commit_window);
+            if self.env().block_timestamp() <= deadline {
+                return Err(Error::WindowOpen);
+            }
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .refund(handoff.escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+            if self
+                .env()
+                .transfer(handoff.payer, handoff.amount.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            self.settle(&mut handoff, HandoffStatus::Reclaimed);
+
+            Ok(())
+        }
+
+        /// Get hand-off details
+        #[ink(message)]
+        pub fn get_handoff(&self, handoff_id: u64) -> Result<Handoff> {
+            self.handoffs.get(handoff_id).ok_or(Error::HandoffNotFound)
+        }
+
+        /// Get all hand-offs a user is party to
+        #[ink(message)]
+        pub fn get_user_handoffs(&self, user: H160) -> Vec<u64> {
+            self.user_handoffs.get(user).unwrap_or_default()
+        }
+
+        /// Get total hand-off count
+        #[ink(message)]
+        pub fn get_handoff_count(&self) -> u64 {
+            self.handoff_count
+        }
+
+        fn release(&self, handoff: &Handoff) -> Result<()> {
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .release_payment(handoff.escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)
+        }
+
+        fn settle(&mut self, handoff: &mut Handoff, status: HandoffStatus) {
+            handoff.status = status.clone();
+            self.handoffs.insert(handoff.id, handoff);
+
+            self.env().emit_event(HandoffSettled {
+                handoff_id: handoff.id,
+                status,
+            });
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::U256;
+
+        const WINDOW: u64 = 1_000;
+
+        #[ink::test]
+        fn requests_need_payment_and_a_usable_key() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut handoff = CredentialHandoff::new(H160::from([0xE5; 20]), WINDOW, WINDOW);
+            let key = vec![4; 33];
+
+            assert_eq!(
+                handoff.request_credentials(accounts.bob, 1, key.clone()),
+                Err(Error::InvalidAmount)
+            );
+            ink::env::test::set_value_transferred(U256::from(100));
+            assert_eq!(
+                handoff.request_credentials(accounts.alice, 1, key),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                handoff.request_credentials(accounts.bob, 1, Vec::new()),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                handoff.request_credentials(accounts.bob, 1, vec![4; MAX_KEY_LEN + 1]),
+                Err(Error::InvalidInput)
+            );
+            ink::env::test::set_value_transferred(U256::zero());
+
+            assert_eq!(handoff.get_handoff_count(), 0);
+            assert_eq!(handoff.get_handoff(1), Err(Error::HandoffNotFound));
+            assert_eq!(
+                handoff.commit_ciphertext(1, H256::from([1; 32])),
+                Err(Error::HandoffNotFound)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::{EscrowStatus, PaymentEscrow};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const AMOUNT: Balance = 1_000_000_000_000;
+        const ESCROW_TIMEOUT: u64 = 86_400_000;
+        const WINDOW: u64 = 86_400_000;
+
+        #[ink_e2e::test]
+        async fn only_the_parties_move_a_handoff_to_payment<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Alice);
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(ESCROW_TIMEOUT),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let contract = client
+                .instantiate(
+                    "credential_handoff",
+                    &ink_e2e::alice(),
+                    &mut CredentialHandoffRef::new(escrow.addr, WINDOW, WINDOW),
+                )
+                .submit()
+                .await
+                .expect("credential handoff instantiate failed");
+            let mut calls = contract.call_builder::<CredentialHandoff>();
+
+            let handoff_id = client
+                .call(
+                    &ink_e2e::alice(),
+                    &calls.request_credentials(bob, 1, vec![4; 33]),
+                )
+                .value(AMOUNT)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let handoff = client
+                .call(&ink_e2e::alice(), &calls.get_handoff(handoff_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(handoff.payer, alice);
+            let details = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.get_escrow(handoff.escrow_id),
+                )
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.payee, bob);
+            assert_eq!(details.amount, AMOUNT);
+
+            let ciphertext_hash = H256::from([1; 32]);
+            let result = client
+                .call(
+                    &ink_e2e::charlie(),
+                    &calls.commit_ciphertext(handoff_id, ciphertext_hash),
+                )
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(result, Err(Error::Unauthorized));
+            let result = client
+                .call(&ink_e2e::bob(), &calls.reclaim(handoff_id))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(result, Err(Error::Unauthorized));
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.commit_ciphertext(handoff_id, ciphertext_hash),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let result = client
+                .call(&ink_e2e::bob(), &calls.acknowledge(handoff_id))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(result, Err(Error::Unauthorized));
+            let result = client
+                .call(&ink_e2e::bob(), &calls.claim(handoff_id))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(result, Err(Error::WindowOpen));
+            client
+                .call(&ink_e2e::alice(), &calls.acknowledge(handoff_id))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let details = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.get_escrow(handoff.escrow_id),
+                )
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Completed);
+            let handoff = client
+                .call(&ink_e2e::alice(), &calls.get_handoff(handoff_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(handoff.status, HandoffStatus::Acknowledged);
+            assert_eq!(handoff.ciphertext_hash, Some(ciphertext_hash));
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,662 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::dataset_marketplace::{Dataset, DatasetMarketplaceRef, Error};
+
+#[ink::contract]
+mod dataset_marketplace {
+    use hub_types::HubError;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// Dataset listing
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Dataset {
+        pub id: u64,
+        pub provider: H160,
+        pub name: String,
+        pub content_hash: H256,
+        pub license_hash: H256,
+        pub price: Balance,
+        pub sample_uri: String,
+        pub is_active: bool,
+        pub total_sales: u32,
+        pub created_at: u64,
+    }
+
+    /// Different statuses of a dataset purchase
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum PurchaseStatus {
+        Pending,
+        Delivered,
+        Completed,
+        Refunded,
+    }
+
+    /// Escrowed purchase of a dataset
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Purchase {
+        pub id: u64,
+        pub dataset_id: u64,
+        pub buyer: H160,
+        pub seller: H160,
+        pub amount: Balance,
+        // Licence terms the buyer agreed to at purchase time
+        pub license_hash: H256,
+        pub status: PurchaseStatus,
+        pub created_at: u64,
+        // e.g. hash of the access key encrypted to the buyer
+        pub delivery_hash: Option<H256>,
+        pub delivered_at: Option<u64>,
+        pub completed_at: Option<u64>,
+    }
+
+    /// Events
+    /// Emitted when a new dataset is registered
+    #[ink(event)]
+    pub struct DatasetRegistered {
+        #[ink(topic)]
+        dataset_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        content_hash: H256,
+        price: Balance,
+    }
+    /// Emitted when the dataset status is updated
+    #[ink(event)]
+    pub struct DatasetUpdated {
+        #[ink(topic)]
+        dataset_id: u64,
+        is_active: bool,
+        price: Balance,
+    }
+    /// Emitted when a buyer pays for a dataset
+    #[ink(event)]
+    pub struct DatasetPurchased {
+        #[ink(topic)]
+        purchase_id: u64,
+        #[ink(topic)]
+        dataset_id: u64,
+        #[ink(topic)]
+        buyer: H160,
+        amount: Balance,
+    }
+    /// Emitted when the seller records delivery
+    #[ink(event)]
+    pub struct DatasetDelivered {
+        #[ink(topic)]
+        purchase_id: u64,
+        delivery_hash: H256,
+    }
+    /// Emitted when funds are released to the seller
+    #[ink(event)]
+    pub struct PurchaseCompleted {
+        #[ink(topic)]
+        purchase_id: u64,
+        #[ink(topic)]
+        seller: H160,
+        amount: Balance,
+    }
+    /// Emitted when funds are returned to the buyer
+    #[ink(event)]
+    pub struct PurchaseRefunded {
+        #[ink(topic)]
+        purchase_id: u64,
+        #[ink(topic)]
+        buyer: H160,
+        amount: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the amount is invalid
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the dataset is not found
+        DatasetNotFound,
+        /// Emitted when the dataset is not for sale
+        DatasetInactive,
+        /// Emitted when the purchase is not found
+        PurchaseNotFound,
+        /// Emitted when the buyer already has an unrefunded purchase of the dataset
+        AlreadyPurchased,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the status is invalid
+        InvalidStatus,
+        /// Emitted when a timeout has not elapsed yet
+        NotExpired,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::DatasetNotFound => HubError::NotFound,
+                Error::DatasetInactive => HubError::Inactive,
+                Error::PurchaseNotFound => HubError::NotFound,
+                Error::AlreadyPurchased => HubError::AlreadyExists,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::NotExpired => HubError::TooEarly,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct DatasetMarketplace {
+        datasets: Mapping<u64, Dataset>,
+        dataset_count: u64,
+        provider_datasets: Mapping<H160, Vec<u64>>,
+        purchases: Mapping<u64, Purchase>,
+        purchase_count: u64,
+        user_purchases: Mapping<H160, Vec<u64>>,
+        // Latest purchase of each dataset by each buyer
+        buyer_purchases: Mapping<(u64, H160), u64>,
+        // Window for the seller to deliver and for the buyer to acknowledge,
+        // in milliseconds
+        delivery_timeout: u64,
+    }
+
+    impl DatasetMarketplace {
+        #[ink(constructor)]
+        pub fn new(delivery_timeout: u64) -> Self {
+            Self {
+                datasets: Mapping::default(),
+                dataset_count: 0,
+                provider_datasets: Mapping::default(),
+                purchases: Mapping::default(),
+                purchase_count: 0,
+                user_purchases: Mapping::default(),
+                buyer_purchases: Mapping::default(),
+                delivery_timeout,
+            }
+        }
+
+        /// Register a new dataset
+        #[ink(message)]
+        pub fn register_dataset(
+            &mut self,
+            name: String,
+            content_hash: H256,
+            license_hash: H256,
+            price: Balance,
+            sample_uri: String,
+        ) -> Result<u64> {
+            let caller = self.env().caller();
+            if name.is_empty() || price == 0 || content_hash == H256::zero() {
+                return Err(Error::InvalidInput);
+            }
+
+            self.dataset_count = self.dataset_count.checked_add(1).ok_or(Error::Overflow)?;
+            let dataset_id = self.dataset_count;
+
+            let dataset = Dataset {
+                id: dataset_id,
+                provider: caller,
+                name,
+                content_hash,
+                license_hash,
+                price,
+                sample_uri,
+                is_active: true,
+                total_sales: 0,
+                created_at: self.env().block_timestamp(),
+            };
+            self.datasets.insert(dataset_id, &dataset);
+
+            let mut provider_datasets = self.provider_datasets.get(caller).unwrap_or_default();
+            provider_datasets.push(dataset_id);
+            self.provider_datasets.insert(caller, &provider_datasets);
+
+            self.env().emit_event(DatasetRegistered {
+                dataset_id,
+                provider: caller,
+                content_hash,
+                price,
+            });
+
+            Ok(dataset_id)
+        }
+
+        /// Update the listing status and price of a dataset
+        #[ink(message)]
+        pub fn update_dataset(
+            &mut self,
+            dataset_id: u64,
+            is_active: bool,
+            price: Balance,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            let mut dataset = self
+                .datasets
+                .get(dataset_id)
+                .ok_or(Error::DatasetNotFound)?;
+
+            if dataset.provider != caller {
+                return Err(Error::Unauthorized);
+            }
+            if price == 0 {
+                return Err(Error::InvalidInput);
+            }
+
+            dataset.is_active = is_active;
+            dataset.price = price;
+            self.datasets.insert(dataset_id, &dataset);
+
+            self.env().emit_event(DatasetUpdated {
+                dataset_id,
+                is_active,
+                price,
+            });
+
+            Ok(())
+        }
+
+        /// Pay for a dataset; funds stay escrowed until delivery is acknowledged
+        ///
+        /// A buyer may only buy the same dataset again once their previous
+        /// purchase of it was refunded.
+        #[ink(message, payable)]
+        pub fn purchase_dataset(&mut self, dataset_id: u64) -> Result<u64> {
+            let buyer = self.env().caller();
+            let amount: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            let dataset = self
+                .datasets
+                .get(dataset_id)
+                .ok_or(Error::DatasetNotFound)?;
+
+            if !dataset.is_active {
+                return Err(Error::DatasetInactive);
+            }
+            if amount != dataset.price {
+                return Err(Error::InvalidAmount);
+            }
+            if let Some(previous) = self.buyer_purchases.get((dataset_id, buyer)) {
+                if self.get_purchase(previous)?.status != PurchaseStatus::Refunded {
+                    return Err(Error::AlreadyPurchased);
+                }
+            }
+
+            self.purchase_count = self.purchase_count.checked_add(1).ok_or(Error::Overflow)?;
+            let purchase_id = self.purchase_count;
+
+            let purchase = Purchase {
+                id: purchase_id,
+                dataset_id,
+                buyer,
+                seller: dataset.provider,
+                amount,
+                license_hash: dataset.license_hash,
+                status: PurchaseStatus::Pending,
+                created_at: self.env().block_timestamp(),
+                delivery_hash: None,
+                delivered_at: None,
+                completed_at: None,
+            };
+            self.purchases.insert(purchase_id, &purchase);
+            self.buyer_purchases
+                .insert((dataset_id, buyer), &purchase_id);
+
+            for user in [buyer, dataset.provider] {
+                let mut purchases = self.user_purchases.get(user).unwrap_or_default();
+                purchases.push(purchase_id);
+                self.user_purchases.insert(user, &purchases);
+            }
+
+            self.env().emit_event(DatasetPurchased {
+                purchase_id,
+                dataset_id,
+                buyer,
+                amount,
+            });
+
+            Ok(purchase_id)
+        }
+
+        /// Record delivery of the dataset to the buyer
+        #[ink(message)]
+        pub fn mark_delivered(&mut self, purchase_id: u64, delivery_hash: H256) -> Result<()> {
+            let caller = self.env().caller();
+            let mut purchase = self.get_purchase(purchase_id)?;
+
+            if purchase.seller != caller {
+                return Err(Error::Unauthorized);
+            }
+            if purchase.status != PurchaseStatus::Pending {
+                return Err(Error::InvalidStatus);
+            }
+
+            purchase.status = PurchaseStatus::Delivered;
+            purchase.delivery_hash = Some(delivery_hash);
+            purchase.delivered_at = Some(self.env().block_timestamp());
+            self.purchases.insert(purchase_id, &purchase);
+
+            self.env().emit_event(DatasetDelivered {
+                purchase_id,
+                delivery_hash,
+            });
+
+            Ok(())
+        }
+
+        /// Acknowledge delivery and release the funds to the seller
+        #[ink(message)]
+        pub fn acknowledge_delivery(&mut self, purchase_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let purchase = self.get_purchase(purchase_id)?;
+
+            if purchase.buyer != caller {
+                return Err(Error::Unauthorized);
+            }
+            if purchase.status != PurchaseStatus::Delivered {
+                return Err(Error::InvalidStatus);
+            }
+
+            self.complete(purchase)
+        }
+
+        /// Release the funds to the seller when the buyer never acknowledged
+        #[ink(message)]
+        pub fn claim_payment(&mut self, purchase_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let purchase = self.get_purchase(purchase_id)?;
+
+            if purchase.seller != caller {
+                return Err(Error::Unauthorized);
+            }
+            if purchase.status != PurchaseStatus::Delivered {
+                return Err(Error::InvalidStatus);
+            }
+            let delivered_at = purchase.delivered_at.unwrap_or_default();
+            if !self.is_elapsed(delivered_at) {
+                return Err(Error::NotExpired);
+            }
+
+            self.complete(purchase)
+        }
+
+        /// Refund the buyer
+        ///
+        /// The seller may refund a pending purchase at any time; the buyer
+        /// may reclaim it once the seller missed the delivery window.
+        #[ink(message)]
+        pub fn refund(&mut self, purchase_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut purchase = self.get_purchase(purchase_id)?;
+
+            if purchase.status != PurchaseStatus::Pending {
+                return Err(Error::InvalidStatus);
+            }
+            if purchase.seller != caller {
+                if purchase.buyer != caller {
+                    return Err(Error::Unauthorized);
+                }
+                if !self.is_elapsed(purchase.created_at) {
+                    return Err(Error::NotExpired);
+                }
+            }
+
+            if self
+                .env()
+                .transfer(purchase.buyer, purchase.amount.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            purchase.status = PurchaseStatus::Refunded;
+            purchase.completed_at = Some(self.env().block_timestamp());
+            self.purchases.insert(purchase_id, &purchase);
+
+            self.env().emit_event(PurchaseRefunded {
+                purchase_id,
+                buyer: purchase.buyer,
+                amount: purchase.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Get dataset details
+        #[ink(message)]
+        pub fn get_dataset(&self, dataset_id: u64) -> Result<Dataset> {
+            self.datasets.get(dataset_id).ok_or(Error::DatasetNotFound)
+        }
+
+        /// Get purchase details
+        #[ink(message)]
+        pub fn get_purchase(&self, purchase_id: u64) -> Result<Purchase> {
+            self.purchases
+                .get(purchase_id)
+                .ok_or(Error::PurchaseNotFound)
+        }
+
+        /// Get all datasets listed by a provider
+        #[ink(message)]
+        pub fn get_provider_datasets(&self, provider: H160) -> Vec<u64> {
+            self.provider_datasets.get(provider).unwrap_or_default()
+        }
+
+        /// Get all purchases a user is party to
+        #[ink(message)]
+        pub fn get_user_purchases(&self, user: H160) -> Vec<u64> {
+            self.user_purchases.get(user).unwrap_or_default()
+        }
+
+        /// Get total dataset count
+        #[ink(message)]
+        pub fn get_dataset_count(&self) -> u64 {
+            self.dataset_count
+        }
+
+        /// Get the delivery/acknowledgment window
+        #[ink(message)]
+        pub fn get_delivery_timeout(&self) -> u64 {
+            self.delivery_timeout
+        }
+
+        fn is_elapsed(&self, since: u64) -> bool {
+            self.env().block_timestamp().saturating_sub(since) > self.delivery_timeout
+        }
+
+        fn complete(&mut self, mut purchase: Purchase) -> Result<()> {
+            if self
+                .env()
+                .transfer(purchase.seller, purchase.amount.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            purchase.status = PurchaseStatus::Completed;
+            purchase.completed_at = Some(self.env().block_timestamp());
+            self.purchases.insert(purchase.id, &purchase);
+
+            let mut dataset = self
+                .datasets
+                .get(purchase.dataset_id)
+                .ok_or(Error::DatasetNotFound)?;
+            dataset.total_sales = dataset.total_sales.saturating_add(1);
+            self.datasets.insert(dataset.id, &dataset);
+
+            self.env().emit_event(PurchaseCompleted {
+                purchase_id: purchase.id,
+                seller: purchase.seller,
+                amount: purchase.amount,
+            });
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::{balance_of, deploy_at};
+        use ink::U256;
+
+        const PRICE: Balance = 100;
+        const TIMEOUT: u64 = 1_000;
+
+        /// An empty marketplace in which bob lists one dataset
+        fn marketplace_with_dataset() -> (DatasetMarketplace, u64) {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xD0; 20]), 0);
+
+            let mut market = DatasetMarketplace::new(TIMEOUT);
+            ink::env::test::set_caller(accounts.bob);
+            let dataset_id = market
+                .register_dataset(
+                    String::from("Support tickets"),
+                    H256::from([1; 32]),
+                    H256::from([2; 32]),
+                    PRICE,
+                    String::from("ipfs://sample"),
+                )
+                .unwrap();
+            (market, dataset_id)
+        }
+
+        fn purchase(
+            market: &mut DatasetMarketplace,
+            dataset_id: u64,
+            value: Balance,
+        ) -> Result<u64> {
+            ink::env::test::set_caller(ink::env::test::default_accounts().alice);
+            ink::env::test::transfer_in(U256::from(value));
+            let result = market.purchase_dataset(dataset_id);
+            ink::env::test::set_value_transferred(U256::zero());
+            result
+        }
+
+        #[ink::test]
+        fn purchases_pay_exactly_the_listed_price() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut market, dataset_id) = marketplace_with_dataset();
+
+            assert_eq!(
+                market.register_dataset(
+                    String::from("Empty"),
+                    H256::zero(),
+                    H256::from([2; 32]),
+                    PRICE,
+                    String::new(),
+                ),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                purchase(&mut market, dataset_id + 1, PRICE),
+                Err(Error::DatasetNotFound)
+            );
+            assert_eq!(
+                purchase(&mut market, dataset_id, PRICE - 1),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                purchase(&mut market, dataset_id, PRICE + 1),
+                Err(Error::InvalidAmount)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            market.update_dataset(dataset_id, false, PRICE).unwrap();
+            assert_eq!(
+                purchase(&mut market, dataset_id, PRICE),
+                Err(Error::DatasetInactive)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            market.update_dataset(dataset_id, true, 2 * PRICE).unwrap();
+            assert_eq!(
+                purchase(&mut market, dataset_id, PRICE),
+                Err(Error::InvalidAmount)
+            );
+            let purchase_id = purchase(&mut market, dataset_id, 2 * PRICE).unwrap();
+            let bought = market.get_purchase(purchase_id).unwrap();
+            assert_eq!(bought.amount, 2 * PRICE);
+            assert_eq!(bought.seller, accounts.bob);
+            assert_eq!(bought.license_hash, H256::from([2; 32]));
+        }
+
+        #[ink::test]
+        fn acknowledged_deliveries_forward_the_payment() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut market, dataset_id) = marketplace_with_dataset();
+            let purchase_id = purchase(&mut market, dataset_id, PRICE).unwrap();
+
+            assert_eq!(
+                market.mark_delivered(purchase_id, H256::from([3; 32])),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                market.acknowledge_delivery(purchase_id),
+                Err(Error::InvalidStatus)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            market
+                .mark_delivered(purchase_id, H256::from([3; 32]))
+                .unwrap();
+            assert_eq!(market.claim_payment(purchase_id), Err(Error::NotExpired));
+
+            let before = balance_of(accounts.bob);
+            ink::env::test::set_caller(accounts.alice);
+            market.acknowledge_delivery(purchase_id).unwrap();
+            assert_eq!(balance_of(accounts.bob), before + PRICE);
+            assert_eq!(
+                market.get_purchase(purchase_id).unwrap().status,
+                PurchaseStatus::Completed
+            );
+            assert_eq!(market.get_dataset(dataset_id).unwrap().total_sales, 1);
+        }
+
+        #[ink::test]
+        fn buyers_cannot_purchase_the_same_dataset_twice() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut market, dataset_id) = marketplace_with_dataset();
+            let purchase_id = purchase(&mut market, dataset_id, PRICE).unwrap();
+            assert_eq!(
+                purchase(&mut market, dataset_id, PRICE),
+                Err(Error::AlreadyPurchased)
+            );
+
+            // The buyer may buy again once the seller missed delivery and refunded
+            ink::env::test::set_block_timestamp(TIMEOUT);
+            assert_eq!(market.refund(purchase_id), Err(Error::NotExpired));
+            ink::env::test::set_block_timestamp(TIMEOUT + 1);
+            let before = balance_of(accounts.alice);
+            market.refund(purchase_id).unwrap();
+            assert_eq!(balance_of(accounts.alice), before + PRICE);
+
+            let again = purchase(&mut market, dataset_id, PRICE).unwrap();
+            assert_ne!(again, purchase_id);
+            assert_eq!(
+                market.get_user_purchases(accounts.alice),
+                vec![purchase_id, again]
+            );
+        }
+    }
+}
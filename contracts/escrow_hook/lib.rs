@@ -0,0 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Interface for per-service escrow hooks
+//!
+//! A provider can point its listing at any contract implementing
+//! [`EscrowHook`] to run its own logic (loyalty points, notifications,
+//! accounting) as escrows for the service are created and settled. Calls
+//! are best-effort: the escrow caps the gas they may use and ignores
+//! failures, so a broken hook never blocks a payment.
+
+use ink::env::Balance;
+use ink::primitives::H160;
+
+#[ink::trait_definition]
+pub trait EscrowHook {
+    /// Called after an escrow for `service_id` was created
+    #[ink(message)]
+    fn on_escrow_created(&mut self, escrow_id: u64, service_id: u64, payer: H160, amount: Balance);
+
+    /// Called after an escrow for `service_id` was released or refunded
+    #[ink(message)]
+    fn on_escrow_settled(
+        &mut self,
+        escrow_id: u64,
+        service_id: u64,
+        payer: H160,
+        amount: Balance,
+        released: bool,
+    );
+}
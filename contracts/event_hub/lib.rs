@@ -0,0 +1,209 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::event_hub::{Error, EventHubRef, HubEventKind};
+
+#[ink::contract]
+mod event_hub {
+    use hub_types::HubError;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// Lifecycle events published through the hub
+    ///
+    /// Variants are only ever appended so the encoded topic of an existing
+    /// kind never changes for indexers.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum HubEventKind {
+        ServiceRegistered,
+        ServiceUpdated,
+        EscrowCreated,
+        EscrowCompleted,
+        EscrowRefunded,
+        EscrowDisputed,
+        DisputeOpened,
+        DisputeResolved,
+        EscrowRejected,
+    }
+
+    impl HubEventKind {
+        /// Dotted name indexers and SDKs subscribe by
+        pub fn name(&self) -> &'static str {
+            match self {
+                HubEventKind::ServiceRegistered => "service.registered",
+                HubEventKind::ServiceUpdated => "service.updated",
+                HubEventKind::EscrowCreated => "escrow.created",
+                HubEventKind::EscrowCompleted => "escrow.completed",
+                HubEventKind::EscrowRefunded => "escrow.refunded",
+                HubEventKind::EscrowDisputed => "escrow.disputed",
+                HubEventKind::DisputeOpened => "dispute.opened",
+                HubEventKind::DisputeResolved => "dispute.resolved",
+                HubEventKind::EscrowRejected => "escrow.rejected",
+            }
+        }
+    }
+
+    /// Events
+    /// Normalized lifecycle event re-emitted on behalf of a hub contract
+    ///
+    /// Topics are always (kind, subject_id, source), in that order.
+    #[ink(event)]
+    pub struct HubEvent {
+        #[ink(topic)]
+        kind: HubEventKind,
+        #[ink(topic)]
+        subject_id: u64,
+        #[ink(topic)]
+        source: H160,
+        actor: H160,
+        amount: Balance,
+        sequence: u64,
+    }
+    /// Emitted when a publisher is approved or removed
+    #[ink(event)]
+    pub struct PublisherUpdated {
+        #[ink(topic)]
+        publisher: H160,
+        approved: bool,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::Overflow => HubError::Overflow,
+                Error::Unauthorized => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct EventHub {
+        owner: H160,
+        // Hub contracts allowed to publish
+        publishers: Mapping<H160, ()>,
+        // Global sequence so indexers can detect gaps
+        sequence: u64,
+    }
+
+    impl EventHub {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                publishers: Mapping::default(),
+                sequence: 0,
+            }
+        }
+
+        /// Approve or remove a contract allowed to publish
+        #[ink(message)]
+        pub fn set_publisher(&mut self, publisher: H160, approved: bool) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+
+            if approved {
+                self.publishers.insert(publisher, &());
+            } else {
+                self.publishers.remove(publisher);
+            }
+
+            self.env().emit_event(PublisherUpdated {
+                publisher,
+                approved,
+            });
+
+            Ok(())
+        }
+
+        /// Re-emit a lifecycle event from the calling hub contract
+        #[ink(message)]
+        pub fn publish(
+            &mut self,
+            kind: HubEventKind,
+            subject_id: u64,
+            actor: H160,
+            amount: Balance,
+        ) -> Result<u64> {
+            let source = self.env().caller();
+            if !self.publishers.contains(source) {
+                return Err(Error::Unauthorized);
+            }
+
+            self.sequence = self.sequence.checked_add(1).ok_or(Error::Overflow)?;
+
+            self.env().emit_event(HubEvent {
+                kind,
+                subject_id,
+                source,
+                actor,
+                amount,
+                sequence: self.sequence,
+            });
+
+            Ok(self.sequence)
+        }
+
+        /// Check if a contract may publish
+        #[ink(message)]
+        pub fn is_publisher(&self, account: H160) -> bool {
+            self.publishers.contains(account)
+        }
+
+        /// Get the sequence number of the last published event
+        #[ink(message)]
+        pub fn get_sequence(&self) -> u64 {
+            self.sequence
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn only_publishers_can_publish() {
+            let accounts = ink::env::test::default_accounts();
+            let mut hub = EventHub::new();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                hub.publish(HubEventKind::EscrowCreated, 1, accounts.charlie, 100),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            hub.set_publisher(accounts.bob, true).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                hub.publish(HubEventKind::EscrowCreated, 1, accounts.charlie, 100),
+                Ok(1)
+            );
+            assert_eq!(
+                hub.publish(HubEventKind::EscrowCompleted, 1, accounts.charlie, 100),
+                Ok(2)
+            );
+        }
+
+        #[test]
+        fn kinds_map_to_dotted_names() {
+            assert_eq!(HubEventKind::ServiceRegistered.name(), "service.registered");
+            assert_eq!(HubEventKind::EscrowDisputed.name(), "escrow.disputed");
+            assert_eq!(HubEventKind::DisputeResolved.name(), "dispute.resolved");
+        }
+    }
+}
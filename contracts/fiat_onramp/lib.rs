@@ -0,0 +1,309 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::fiat_onramp::{Error, FiatAttestation, FiatOnrampRef};
+
+#[ink::contract]
+mod fiat_onramp {
+    use hub_types::HubError;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// A processor's statement that a fiat payment was received
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct FiatAttestation {
+        // Hash of the processor's payment reference
+        pub reference_id: H256,
+        pub processor: H160,
+        // Hub account the payment is bound to
+        pub payer: H160,
+        pub escrow_id: u64,
+        // Amount in the currency's minor units (e.g. cents)
+        pub amount: u128,
+        // ISO 4217 currency code
+        pub currency: [u8; 3],
+        pub attested_at: u64,
+        pub consumed: bool,
+    }
+
+    /// Events
+    /// Emitted when a processor is approved or removed
+    #[ink(event)]
+    pub struct ProcessorUpdated {
+        #[ink(topic)]
+        processor: H160,
+        approved: bool,
+    }
+    /// Emitted when a processor attests a fiat payment
+    #[ink(event)]
+    pub struct PaymentAttested {
+        #[ink(topic)]
+        reference_id: H256,
+        #[ink(topic)]
+        escrow_id: u64,
+        processor: H160,
+        payer: H160,
+        amount: u128,
+        currency: [u8; 3],
+    }
+    /// Emitted when the escrow contract consumes an attestation
+    #[ink(event)]
+    pub struct AttestationConsumed {
+        #[ink(topic)]
+        reference_id: H256,
+        #[ink(topic)]
+        escrow_id: u64,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the caller is not an approved processor
+        NotProcessor,
+        /// Emitted when the payment reference was already attested
+        DuplicateReference,
+        /// Emitted when the attestation is not found
+        AttestationNotFound,
+        /// Emitted when the attestation is bound to another payer or escrow
+        BindingMismatch,
+        /// Emitted when the attestation was already consumed
+        AlreadyConsumed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NotProcessor => HubError::Unauthorized,
+                Error::DuplicateReference => HubError::AlreadyExists,
+                Error::AttestationNotFound => HubError::NotFound,
+                Error::BindingMismatch => HubError::InvalidProof,
+                Error::AlreadyConsumed => HubError::AlreadyExists,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct FiatOnramp {
+        owner: H160,
+        // Only the escrow contract may consume attestations
+        escrow: Option<H160>,
+        processors: Mapping<H160, ()>,
+        attestations: Mapping<H256, FiatAttestation>,
+    }
+
+    impl FiatOnramp {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                escrow: None,
+                processors: Mapping::default(),
+                attestations: Mapping::default(),
+            }
+        }
+
+        /// Approve or remove a payment processor
+        #[ink(message)]
+        pub fn set_processor(&mut self, processor: H160, approved: bool) -> Result<()> {
+            self.ensure_owner()?;
+
+            if approved {
+                self.processors.insert(processor, &());
+            } else {
+                self.processors.remove(processor);
+            }
+
+            self.env().emit_event(ProcessorUpdated {
+                processor,
+                approved,
+            });
+
+            Ok(())
+        }
+
+        /// Set the escrow contract allowed to consume attestations
+        #[ink(message)]
+        pub fn set_escrow(&mut self, escrow: H160) -> Result<()> {
+            self.ensure_owner()?;
+            self.escrow = Some(escrow);
+            Ok(())
+        }
+
+        /// Attest a fiat payment made by `payer` towards `escrow_id`
+        #[ink(message)]
+        pub fn attest_payment(
+            &mut self,
+            reference_id: H256,
+            payer: H160,
+            escrow_id: u64,
+            amount: u128,
+            currency: [u8; 3],
+        ) -> Result<()> {
+            let processor = self.env().caller();
+            if !self.processors.contains(processor) {
+                return Err(Error::NotProcessor);
+            }
+            if amount == 0 {
+                return Err(Error::InvalidInput);
+            }
+            if self.attestations.contains(reference_id) {
+                return Err(Error::DuplicateReference);
+            }
+
+            let attestation = FiatAttestation {
+                reference_id,
+                processor,
+                payer,
+                escrow_id,
+                amount,
+                currency,
+                attested_at: self.env().block_timestamp(),
+                consumed: false,
+            };
+            self.attestations.insert(reference_id, &attestation);
+
+            self.env().emit_event(PaymentAttested {
+                reference_id,
+                escrow_id,
+                processor,
+                payer,
+                amount,
+                currency,
+            });
+
+            Ok(())
+        }
+
+        /// Consume an attestation for the escrow it is bound to
+        ///
+        /// Checked against the payer and escrow so a reference can fund
+        /// exactly one escrow, once.
+        #[ink(message)]
+        pub fn consume_attestation(
+            &mut self,
+            reference_id: H256,
+            escrow_id: u64,
+            payer: H160,
+        ) -> Result<FiatAttestation> {
+            if Some(self.env().caller()) != self.escrow {
+                return Err(Error::Unauthorized);
+            }
+            let mut attestation = self
+                .attestations
+                .get(reference_id)
+                .ok_or(Error::AttestationNotFound)?;
+            if attestation.escrow_id != escrow_id || attestation.payer != payer {
+                return Err(Error::BindingMismatch);
+            }
+            if attestation.consumed {
+                return Err(Error::AlreadyConsumed);
+            }
+            // A processor removed after attesting no longer vouches for it
+            if !self.processors.contains(attestation.processor) {
+                return Err(Error::NotProcessor);
+            }
+
+            attestation.consumed = true;
+            self.attestations.insert(reference_id, &attestation);
+
+            self.env().emit_event(AttestationConsumed {
+                reference_id,
+                escrow_id,
+            });
+
+            Ok(attestation)
+        }
+
+        /// Get an attestation by payment reference
+        #[ink(message)]
+        pub fn get_attestation(&self, reference_id: H256) -> Option<FiatAttestation> {
+            self.attestations.get(reference_id)
+        }
+
+        /// Check if an account is an approved processor
+        #[ink(message)]
+        pub fn is_processor(&self, account: H160) -> bool {
+            self.processors.contains(account)
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const REFERENCE: [u8; 32] = [0x42; 32];
+
+        fn setup() -> FiatOnramp {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = FiatOnramp::new();
+            contract.set_processor(accounts.django, true).unwrap();
+            contract.set_escrow(accounts.eve).unwrap();
+
+            ink::env::test::set_caller(accounts.django);
+            contract
+                .attest_payment(H256::from(REFERENCE), accounts.bob, 7, 2_500, *b"USD")
+                .unwrap();
+            ink::env::test::set_caller(accounts.eve);
+            contract
+        }
+
+        #[ink::test]
+        fn attestation_is_consumed_once() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            let attestation = contract
+                .consume_attestation(H256::from(REFERENCE), 7, accounts.bob)
+                .unwrap();
+            assert_eq!(attestation.amount, 2_500);
+            assert_eq!(
+                contract.consume_attestation(H256::from(REFERENCE), 7, accounts.bob),
+                Err(Error::AlreadyConsumed)
+            );
+        }
+
+        #[ink::test]
+        fn binding_is_enforced() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            assert_eq!(
+                contract.consume_attestation(H256::from(REFERENCE), 8, accounts.bob),
+                Err(Error::BindingMismatch)
+            );
+            assert_eq!(
+                contract.consume_attestation(H256::from(REFERENCE), 7, accounts.charlie),
+                Err(Error::BindingMismatch)
+            );
+        }
+
+        #[ink::test]
+        fn only_processors_attest() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            assert_eq!(
+                contract.attest_payment(H256::from([0x01; 32]), accounts.bob, 7, 100, *b"EUR"),
+                Err(Error::NotProcessor)
+            );
+        }
+    }
+}
@@ -0,0 +1,210 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::gateway_registry::{Error, GatewayInfo, GatewayRegistryRef};
+
+#[ink::contract]
+mod gateway_registry {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// Gateway fees are expressed in basis points of the payment
+    const MAX_BPS: u32 = 10_000;
+    /// Longest gateway name accepted, in bytes
+    const MAX_NAME_LEN: usize = 64;
+    /// Most chains or tokens one gateway may list
+    const MAX_GATEWAY_ENTRIES: usize = 16;
+
+    /// Metadata of an approved x402 gateway
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct GatewayInfo {
+        pub name: String,
+        // Chains the gateway settles on; `None` is this chain
+        pub chains: Vec<Option<u64>>,
+        pub tokens: Vec<H160>,
+        pub fee_bps: u32,
+    }
+
+    /// Events
+    /// Emitted when a gateway is approved or its metadata replaced
+    #[ink(event)]
+    pub struct GatewayRegistered {
+        #[ink(topic)]
+        gateway: H160,
+        fee_bps: u32,
+    }
+    /// Emitted when a gateway is removed
+    #[ink(event)]
+    pub struct GatewayRemoved {
+        #[ink(topic)]
+        gateway: H160,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the gateway is not approved
+        GatewayNotFound,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::GatewayNotFound => HubError::NotFound,
+            }
+        }
+    }
+
+    /// Owner-approved x402 gateways services may route payments through
+    #[ink(storage)]
+    pub struct GatewayRegistry {
+        owner: H160,
+        gateways: Mapping<H160, GatewayInfo>,
+    }
+
+    impl GatewayRegistry {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                gateways: Mapping::default(),
+            }
+        }
+
+        /// Approve a gateway, or replace its metadata
+        #[ink(message)]
+        pub fn register_gateway(&mut self, gateway: H160, info: GatewayInfo) -> Result<()> {
+            self.ensure_owner()?;
+            if info.name.is_empty()
+                || info.name.len() > MAX_NAME_LEN
+                || info.chains.is_empty()
+                || info.chains.len() > MAX_GATEWAY_ENTRIES
+                || info.tokens.is_empty()
+                || info.tokens.len() > MAX_GATEWAY_ENTRIES
+                || info.fee_bps > MAX_BPS
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            let fee_bps = info.fee_bps;
+            self.gateways.insert(gateway, &info);
+            self.env()
+                .emit_event(GatewayRegistered { gateway, fee_bps });
+
+            Ok(())
+        }
+
+        /// Remove a gateway
+        #[ink(message)]
+        pub fn remove_gateway(&mut self, gateway: H160) -> Result<()> {
+            self.ensure_owner()?;
+            if self.gateways.take(gateway).is_none() {
+                return Err(Error::GatewayNotFound);
+            }
+            self.env().emit_event(GatewayRemoved { gateway });
+            Ok(())
+        }
+
+        /// Get a gateway's metadata
+        #[ink(message)]
+        pub fn get_gateway(&self, gateway: H160) -> Option<GatewayInfo> {
+            self.gateways.get(gateway)
+        }
+
+        /// Check if a gateway is approved
+        #[ink(message)]
+        pub fn is_approved(&self, gateway: H160) -> bool {
+            self.gateways.contains(gateway)
+        }
+
+        /// Check if an approved gateway handles an x402 asset's token and chain
+        #[ink(message)]
+        pub fn supports(&self, gateway: H160, asset: PaymentAsset) -> bool {
+            let (Some(info), PaymentAsset::X402 { token, chain_id }) =
+                (self.gateways.get(gateway), asset)
+            else {
+                return false;
+            };
+            info.tokens.contains(&token) && info.chains.contains(&chain_id)
+        }
+
+        /// Hand the registry over to another account (e.g. a DAO contract)
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: H160) -> Result<()> {
+            self.ensure_owner()?;
+            self.owner = new_owner;
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn approved_gateways_support_listed_assets() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = GatewayRegistry::new();
+            let info = GatewayInfo {
+                name: String::from("gw"),
+                chains: vec![None, Some(8453)],
+                tokens: vec![accounts.django],
+                fee_bps: 30,
+            };
+
+            assert_eq!(
+                contract.register_gateway(
+                    accounts.eve,
+                    GatewayInfo {
+                        fee_bps: 10_001,
+                        ..info.clone()
+                    }
+                ),
+                Err(Error::InvalidInput)
+            );
+            contract.register_gateway(accounts.eve, info).unwrap();
+
+            let on_base = PaymentAsset::X402 {
+                token: accounts.django,
+                chain_id: Some(8453),
+            };
+            let elsewhere = PaymentAsset::X402 {
+                token: accounts.django,
+                chain_id: Some(1),
+            };
+            assert!(contract.supports(accounts.eve, on_base));
+            assert!(!contract.supports(accounts.eve, elsewhere));
+            assert!(!contract.supports(accounts.eve, PaymentAsset::Native));
+            assert!(!contract.supports(accounts.frank, on_base));
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.remove_gateway(accounts.eve),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.alice);
+            contract.remove_gateway(accounts.eve).unwrap();
+            assert!(!contract.is_approved(accounts.eve));
+        }
+    }
+}
@@ -0,0 +1,703 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod grants_pool {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::format;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use payment_escrow::PaymentEscrowRef;
+
+    /// Upper bound on milestones per proposal
+    const MAX_MILESTONES: usize = 20;
+
+    /// Different statuses of a grant proposal
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ProposalStatus {
+        Voting,
+        Approved,
+        Rejected,
+        Completed,
+        Cancelled,
+    }
+
+    /// Grant proposal submitted by an agent developer
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Proposal {
+        pub id: u64,
+        pub grantee: H160,
+        // Hash of the off-chain proposal document
+        pub details_hash: H256,
+        pub milestones: Vec<Balance>,
+        pub next_milestone: u32,
+        // Escrow currently holding the funds of `next_milestone`
+        pub active_escrow: Option<u64>,
+        pub approvals: u32,
+        pub rejections: u32,
+        pub status: ProposalStatus,
+        pub created_at: u64,
+    }
+
+    /// Events
+    /// Emitted when someone donates to the pool
+    #[ink(event)]
+    pub struct DonationReceived {
+        #[ink(topic)]
+        donor: H160,
+        amount: Balance,
+    }
+    /// Emitted when a proposal is submitted
+    #[ink(event)]
+    pub struct ProposalSubmitted {
+        #[ink(topic)]
+        proposal_id: u64,
+        #[ink(topic)]
+        grantee: H160,
+        total: Balance,
+    }
+    /// Emitted when a council member votes
+    #[ink(event)]
+    pub struct ProposalVoted {
+        #[ink(topic)]
+        proposal_id: u64,
+        #[ink(topic)]
+        voter: H160,
+        approve: bool,
+    }
+    /// Emitted when voting on a proposal concludes
+    #[ink(event)]
+    pub struct ProposalDecided {
+        #[ink(topic)]
+        proposal_id: u64,
+        status: ProposalStatus,
+    }
+    /// Emitted when a milestone is funded through the escrow contract
+    #[ink(event)]
+    pub struct MilestoneFunded {
+        #[ink(topic)]
+        proposal_id: u64,
+        milestone: u32,
+        escrow_id: u64,
+        amount: Balance,
+    }
+    /// Emitted when a milestone escrow is released to the grantee
+    #[ink(event)]
+    pub struct MilestoneReleased {
+        #[ink(topic)]
+        proposal_id: u64,
+        milestone: u32,
+        escrow_id: u64,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the amount is invalid
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the proposal is not found
+        ProposalNotFound,
+        /// Emitted when the proposal is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when a council member votes twice
+        AlreadyVoted,
+        /// Emitted when the pool can't cover the proposal
+        InsufficientFunds,
+        /// Emitted when a milestone escrow is still open
+        MilestoneInProgress,
+        /// Emitted when the escrow contract rejects a call
+        EscrowCallFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::ProposalNotFound => HubError::NotFound,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::AlreadyVoted => HubError::AlreadyExists,
+                Error::InsufficientFunds => HubError::InsufficientFunds,
+                Error::MilestoneInProgress => HubError::InvalidStatus,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct GrantsPool {
+        governance: H160,
+        escrow: H160,
+        council: Vec<H160>,
+        quorum: u32,
+        proposals: Mapping<u64, Proposal>,
+        proposal_count: u64,
+        votes: Mapping<(u64, H160), bool>,
+        // Funds promised to approved proposals but not yet released
+        allocated: Balance,
+    }
+
+    impl GrantsPool {
+        #[ink(constructor)]
+        pub fn new(escrow: H160, council: Vec<H160>, quorum: u32) -> Self {
+            assert!(
+                quorum > 0 && quorum as usize <= council.len(),
+                "quorum must be between 1 and the council size"
+            );
+            Self {
+                governance: Self::env().caller(),
+                escrow,
+                council,
+                quorum,
+                proposals: Mapping::default(),
+                proposal_count: 0,
+                votes: Mapping::default(),
+                allocated: 0,
+            }
+        }
+
+        /// Donate the transferred value to the pool
+        #[ink(message, payable)]
+        pub fn donate(&mut self) -> Result<()> {
+            let donor = self.env().caller();
+            let amount = self.transferred_balance();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.env().emit_event(DonationReceived { donor, amount });
+
+            Ok(())
+        }
+
+        /// Submit a proposal split into milestone payments
+        #[ink(message)]
+        pub fn submit_proposal(
+            &mut self,
+            details_hash: H256,
+            milestones: Vec<Balance>,
+        ) -> Result<u64> {
+            let grantee = self.env().caller();
+            if milestones.is_empty() || milestones.len() > MAX_MILESTONES || milestones.contains(&0)
+            {
+                return Err(Error::InvalidInput);
+            }
+            let total = Self::total(&milestones)?;
+
+            self.proposal_count = self.proposal_count.checked_add(1).ok_or(Error::Overflow)?;
+            let proposal_id = self.proposal_count;
+
+            let proposal = Proposal {
+                id: proposal_id,
+                grantee,
+                details_hash,
+                milestones,
+                next_milestone: 0,
+                active_escrow: None,
+                approvals: 0,
+                rejections: 0,
+                status: ProposalStatus::Voting,
+                created_at: self.env().block_timestamp(),
+            };
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(ProposalSubmitted {
+                proposal_id,
+                grantee,
+                total,
+            });
+
+            Ok(proposal_id)
+        }
+
+        /// Vote on a proposal; the allocation is reserved once quorum approves
+        #[ink(message)]
+        pub fn vote(&mut self, proposal_id: u64, approve: bool) -> Result<()> {
+            let voter = self.env().caller();
+            self.ensure_council(voter)?;
+            let mut proposal = self.get_proposal(proposal_id)?;
+
+            if proposal.status != ProposalStatus::Voting {
+                return Err(Error::InvalidStatus);
+            }
+            if self.votes.contains((proposal_id, voter)) {
+                return Err(Error::AlreadyVoted);
+            }
+            self.votes.insert((proposal_id, voter), &approve);
+
+            if approve {
+                proposal.approvals += 1;
+            } else {
+                proposal.rejections += 1;
+            }
+
+            self.env().emit_event(ProposalVoted {
+                proposal_id,
+                voter,
+                approve,
+            });
+
+            let blocking = self.council.len() as u32 - self.quorum;
+            if proposal.approvals >= self.quorum {
+                let total = Self::total(&proposal.milestones)?;
+                if self.available() < total {
+                    return Err(Error::InsufficientFunds);
+                }
+                self.allocated = self.allocated.checked_add(total).ok_or(Error::Overflow)?;
+                proposal.status = ProposalStatus::Approved;
+            } else if proposal.rejections > blocking {
+                proposal.status = ProposalStatus::Rejected;
+            }
+            self.proposals.insert(proposal_id, &proposal);
+
+            if proposal.status != ProposalStatus::Voting {
+                self.env().emit_event(ProposalDecided {
+                    proposal_id,
+                    status: proposal.status,
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Lock the next milestone's funds in an escrow payable to the grantee
+        ///
+        /// If the council stops responding the grantee can still auto-release
+        /// the escrow once it times out, so delivered work is never stranded.
+        #[ink(message)]
+        pub fn fund_milestone(&mut self, proposal_id: u64) -> Result<u64> {
+            self.ensure_council(self.env().caller())?;
+            let mut proposal = self.get_proposal(proposal_id)?;
+
+            if proposal.status != ProposalStatus::Approved {
+                return Err(Error::InvalidStatus);
+            }
+            if proposal.active_escrow.is_some() {
+                return Err(Error::MilestoneInProgress);
+            }
+
+            let milestone = proposal.next_milestone;
+            let amount = proposal.milestones[milestone as usize];
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let escrow_id = escrow
+                .call_mut()
+                .create_escrow(
+                    proposal.grantee,
+                    0,
+                    format!("grant:{}:{}", proposal_id, milestone),
+                    PaymentAsset::Native,
+                )
+                .transferred_value(amount.into())
+                .invoke()
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            proposal.active_escrow = Some(escrow_id);
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(MilestoneFunded {
+                proposal_id,
+                milestone,
+                escrow_id,
+                amount,
+            });
+
+            Ok(escrow_id)
+        }
+
+        /// Approve the delivered milestone and release its escrow
+        #[ink(message)]
+        pub fn approve_milestone(&mut self, proposal_id: u64) -> Result<()> {
+            self.ensure_council(self.env().caller())?;
+            let mut proposal = self.get_proposal(proposal_id)?;
+
+            if proposal.status != ProposalStatus::Approved {
+                return Err(Error::InvalidStatus);
+            }
+            let escrow_id = proposal.active_escrow.ok_or(Error::InvalidStatus)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .release_payment(escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            let milestone = proposal.next_milestone;
+            let amount = proposal.milestones[milestone as usize];
+            self.allocated = self.allocated.saturating_sub(amount);
+
+            proposal.active_escrow = None;
+            proposal.next_milestone += 1;
+            if proposal.next_milestone as usize == proposal.milestones.len() {
+                proposal.status = ProposalStatus::Completed;
+            }
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(MilestoneReleased {
+                proposal_id,
+                milestone,
+                escrow_id,
+            });
+
+            Ok(())
+        }
+
+        /// Cancel an approved proposal, refunding any open milestone escrow
+        #[ink(message)]
+        pub fn cancel_proposal(&mut self, proposal_id: u64) -> Result<()> {
+            if self.env().caller() != self.governance {
+                return Err(Error::Unauthorized);
+            }
+            let mut proposal = self.get_proposal(proposal_id)?;
+
+            match proposal.status {
+                ProposalStatus::Voting => {}
+                ProposalStatus::Approved => {
+                    if let Some(escrow_id) = proposal.active_escrow.take() {
+                        let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+                        escrow
+                            .refund(escrow_id)
+                            .map_err(|_| Error::EscrowCallFailed)?;
+                    }
+                    let remaining =
+                        Self::total(&proposal.milestones[proposal.next_milestone as usize..])?;
+                    self.allocated = self.allocated.saturating_sub(remaining);
+                }
+                _ => return Err(Error::InvalidStatus),
+            }
+
+            proposal.status = ProposalStatus::Cancelled;
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(ProposalDecided {
+                proposal_id,
+                status: ProposalStatus::Cancelled,
+            });
+
+            Ok(())
+        }
+
+        /// Get proposal details
+        #[ink(message)]
+        pub fn get_proposal(&self, proposal_id: u64) -> Result<Proposal> {
+            self.proposals
+                .get(proposal_id)
+                .ok_or(Error::ProposalNotFound)
+        }
+
+        /// Get total proposal count
+        #[ink(message)]
+        pub fn get_proposal_count(&self) -> u64 {
+            self.proposal_count
+        }
+
+        /// Pool balance not yet promised to approved proposals
+        #[ink(message)]
+        pub fn available(&self) -> Balance {
+            let balance: Balance = self.env().balance().try_into().unwrap_or_default();
+            balance.saturating_sub(self.allocated)
+        }
+
+        /// Funds promised to approved proposals but not yet released
+        #[ink(message)]
+        pub fn get_allocated(&self) -> Balance {
+            self.allocated
+        }
+
+        /// Get the council members
+        #[ink(message)]
+        pub fn get_council(&self) -> Vec<H160> {
+            self.council.clone()
+        }
+
+        /// Get the number of approvals needed to fund a proposal
+        #[ink(message)]
+        pub fn get_quorum(&self) -> u32 {
+            self.quorum
+        }
+
+        fn ensure_council(&self, account: H160) -> Result<()> {
+            if !self.council.contains(&account) {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        fn transferred_balance(&self) -> Balance {
+            self.env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default()
+        }
+
+        fn total(milestones: &[Balance]) -> Result<Balance> {
+            milestones
+                .iter()
+                .try_fold(0u128, |total, amount| total.checked_add(*amount))
+                .ok_or(Error::Overflow)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::deploy_at;
+        use ink::U256;
+
+        const POOL: Balance = 1_000;
+
+        /// A pool judged by bob, charlie and django, two of whom must
+        /// approve, holding `POOL` donated by alice
+        fn funded_pool() -> GrantsPool {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xC0; 20]), 0);
+            ink::env::test::set_caller(accounts.alice);
+
+            let mut pool = GrantsPool::new(
+                accounts.frank,
+                vec![accounts.bob, accounts.charlie, accounts.django],
+                2,
+            );
+            ink::env::test::transfer_in(U256::from(POOL));
+            pool.donate().unwrap();
+            ink::env::test::set_value_transferred(U256::zero());
+            pool
+        }
+
+        #[ink::test]
+        fn donations_fund_the_pool() {
+            let mut pool = funded_pool();
+            assert_eq!(pool.donate(), Err(Error::InvalidAmount));
+            assert_eq!(pool.available(), POOL);
+            assert_eq!(pool.get_allocated(), 0);
+        }
+
+        #[ink::test]
+        fn council_quorum_reserves_the_grant() {
+            let accounts = ink::env::test::default_accounts();
+            let mut pool = funded_pool();
+            let details = H256::from([1; 32]);
+
+            ink::env::test::set_caller(accounts.eve);
+            assert_eq!(
+                pool.submit_proposal(details, Vec::new()),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                pool.submit_proposal(details, vec![100, 0]),
+                Err(Error::InvalidInput)
+            );
+            let proposal_id = pool.submit_proposal(details, vec![300, 300]).unwrap();
+            assert_eq!(pool.vote(proposal_id, true), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller(accounts.bob);
+            pool.vote(proposal_id, true).unwrap();
+            assert_eq!(pool.vote(proposal_id, true), Err(Error::AlreadyVoted));
+            assert_eq!(
+                pool.get_proposal(proposal_id).unwrap().status,
+                ProposalStatus::Voting
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            pool.vote(proposal_id, true).unwrap();
+            let proposal = pool.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Approved);
+            assert_eq!(proposal.grantee, accounts.eve);
+            assert_eq!(pool.get_allocated(), 600);
+            assert_eq!(pool.available(), POOL - 600);
+            assert_eq!(pool.vote(proposal_id, true), Err(Error::InvalidStatus));
+        }
+
+        #[ink::test]
+        fn grants_cannot_exceed_what_is_unallocated() {
+            let accounts = ink::env::test::default_accounts();
+            let mut pool = funded_pool();
+
+            ink::env::test::set_caller(accounts.eve);
+            let first = pool
+                .submit_proposal(H256::from([1; 32]), vec![POOL - 100])
+                .unwrap();
+            let second = pool
+                .submit_proposal(H256::from([2; 32]), vec![200])
+                .unwrap();
+            for voter in [accounts.bob, accounts.charlie] {
+                ink::env::test::set_caller(voter);
+                pool.vote(first, true).unwrap();
+            }
+
+            ink::env::test::set_caller(accounts.bob);
+            pool.vote(second, true).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(pool.vote(second, true), Err(Error::InsufficientFunds));
+            assert_eq!(pool.get_allocated(), POOL - 100);
+        }
+
+        #[ink::test]
+        fn rejected_and_cancelled_proposals_free_their_allocation() {
+            let accounts = ink::env::test::default_accounts();
+            let mut pool = funded_pool();
+
+            ink::env::test::set_caller(accounts.eve);
+            let rejected = pool
+                .submit_proposal(H256::from([1; 32]), vec![100])
+                .unwrap();
+            let approved = pool
+                .submit_proposal(H256::from([2; 32]), vec![400])
+                .unwrap();
+
+            // One rejection can still be outvoted, the second blocks the quorum
+            for voter in [accounts.bob, accounts.charlie] {
+                ink::env::test::set_caller(voter);
+                pool.vote(rejected, false).unwrap();
+                pool.vote(approved, true).unwrap();
+            }
+            assert_eq!(
+                pool.get_proposal(rejected).unwrap().status,
+                ProposalStatus::Rejected
+            );
+            assert_eq!(pool.get_allocated(), 400);
+
+            assert_eq!(pool.cancel_proposal(approved), Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(pool.cancel_proposal(rejected), Err(Error::InvalidStatus));
+            pool.cancel_proposal(approved).unwrap();
+            assert_eq!(
+                pool.get_proposal(approved).unwrap().status,
+                ProposalStatus::Cancelled
+            );
+            assert_eq!(pool.get_allocated(), 0);
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::{EscrowStatus, PaymentEscrow};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const AMOUNT: Balance = 1_000_000_000_000;
+        const ESCROW_TIMEOUT: u64 = 86_400_000;
+
+        #[ink_e2e::test]
+        async fn approved_milestones_pay_the_grantee_through_the_escrow<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Alice);
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(ESCROW_TIMEOUT),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let pool = client
+                .instantiate(
+                    "grants_pool",
+                    &ink_e2e::alice(),
+                    &mut GrantsPoolRef::new(escrow.addr, vec![alice], 1),
+                )
+                .submit()
+                .await
+                .expect("grants pool instantiate failed");
+            let mut calls = pool.call_builder::<GrantsPool>();
+
+            client
+                .call(&ink_e2e::alice(), &calls.donate())
+                .value(2 * AMOUNT)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let proposal_id = client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.submit_proposal(H256::from([1; 32]), vec![AMOUNT]),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            client
+                .call(&ink_e2e::alice(), &calls.vote(proposal_id, true))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let escrow_id = client
+                .call(&ink_e2e::alice(), &calls.fund_milestone(proposal_id))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.payee, bob);
+            assert_eq!(details.amount, AMOUNT);
+
+            client
+                .call(&ink_e2e::alice(), &calls.approve_milestone(proposal_id))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Completed);
+
+            let proposal = client
+                .call(&ink_e2e::alice(), &calls.get_proposal(proposal_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Completed);
+            let allocated = client
+                .call(&ink_e2e::alice(), &calls.get_allocated())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(allocated, 0);
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,477 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Types shared across hub contracts
+//!
+//! Every hub contract keeps its own detailed `Error` enum and converts it
+//! into [`HubError`], so SDKs and cross-contract callers can handle a failure
+//! from any contract the same way. Listings, escrows and settlements name
+//! the asset they are denominated in with [`PaymentAsset`], services and
+//! agents name what they offer with [`ServiceCategory`], and show raw
+//! amounts to people as [`DisplayAmount`]. Accounts an owner trusts with
+//! part of a contract's administration hold a [`Role`]. Payloads users sign
+//! off-chain are hashed and their signers recovered with [`typed_data`].
+//! Contracts report the version of their public interface as an
+//! [`ApiVersion`], and the storage each account's records occupy as a
+//! [`StorageUsage`].
+//! Off-chain tests share their payment fixtures through [`testing`].
+
+use ink::primitives::H160;
+
+/// Decimals of the chain's native currency
+pub const NATIVE_DECIMALS: u8 = 12;
+/// Most decimals an asset may have while `10^decimals` fits a `u128`
+pub const MAX_DECIMALS: u8 = 38;
+
+/// Error categories shared by every hub contract
+///
+/// The encoded index of each variant is its stable numeric code. Codes are
+/// never reused or renumbered; new categories get the next free code.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum HubError {
+    /// Code 1: an argument failed validation
+    #[codec(index = 1)]
+    InvalidInput,
+    /// Code 2: the transferred or requested amount is wrong
+    #[codec(index = 2)]
+    InvalidAmount,
+    /// Code 3: an arithmetic operation overflowed
+    #[codec(index = 3)]
+    Overflow,
+    /// Code 4: the referenced item does not exist
+    #[codec(index = 4)]
+    NotFound,
+    /// Code 5: the caller is not allowed to do this
+    #[codec(index = 5)]
+    Unauthorized,
+    /// Code 6: the item is in the wrong state for this call
+    #[codec(index = 6)]
+    InvalidStatus,
+    /// Code 7: a deadline or validity period has passed
+    #[codec(index = 7)]
+    Expired,
+    /// Code 8: a waiting period has not passed yet
+    #[codec(index = 8)]
+    TooEarly,
+    /// Code 9: the action was already taken or the item already exists
+    #[codec(index = 9)]
+    AlreadyExists,
+    /// Code 10: a count, budget or rate limit would be exceeded
+    #[codec(index = 10)]
+    LimitExceeded,
+    /// Code 11: there are not enough funds to cover the call
+    #[codec(index = 11)]
+    InsufficientFunds,
+    /// Code 12: a signature, proof or attestation was rejected
+    #[codec(index = 12)]
+    InvalidProof,
+    /// Code 13: a required contract address has not been configured
+    #[codec(index = 13)]
+    NotConfigured,
+    /// Code 14: a call into another hub contract failed
+    #[codec(index = 14)]
+    CrossContractCallFailed,
+    /// Code 15: a native transfer failed
+    #[codec(index = 15)]
+    TransferFailed,
+    /// Code 16: the item is inactive or closed
+    #[codec(index = 16)]
+    Inactive,
+}
+
+impl HubError {
+    /// Stable numeric code of the error
+    pub fn code(&self) -> u8 {
+        match self {
+            HubError::InvalidInput => 1,
+            HubError::InvalidAmount => 2,
+            HubError::Overflow => 3,
+            HubError::NotFound => 4,
+            HubError::Unauthorized => 5,
+            HubError::InvalidStatus => 6,
+            HubError::Expired => 7,
+            HubError::TooEarly => 8,
+            HubError::AlreadyExists => 9,
+            HubError::LimitExceeded => 10,
+            HubError::InsufficientFunds => 11,
+            HubError::InvalidProof => 12,
+            HubError::NotConfigured => 13,
+            HubError::CrossContractCallFailed => 14,
+            HubError::TransferFailed => 15,
+            HubError::Inactive => 16,
+        }
+    }
+}
+
+/// Asset a price or escrowed amount is denominated in
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum PaymentAsset {
+    /// The chain's native currency, held by the escrow contract
+    #[default]
+    Native,
+    /// A PSP22 token contract on this chain
+    Psp22(H160),
+    /// A token paid off-chain through the x402 protocol
+    X402 {
+        token: H160,
+        // `None` when the payment settles on this chain
+        chain_id: Option<u64>,
+    },
+}
+
+impl PaymentAsset {
+    /// Whether payment happens off-chain through x402
+    pub fn is_x402(&self) -> bool {
+        matches!(self, PaymentAsset::X402 { .. })
+    }
+
+    /// Token contract of the asset, `None` for the native currency
+    pub fn token(&self) -> Option<H160> {
+        match self {
+            PaymentAsset::Native => None,
+            PaymentAsset::Psp22(token) | PaymentAsset::X402 { token, .. } => Some(*token),
+        }
+    }
+}
+
+/// Options for type of services an AI agent can offer
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum ServiceCategory {
+    TextProcessing,
+    ImageGeneration,
+    DataAnalysis,
+    Translation,
+    Computation,
+}
+
+/// Permissions a contract owner may grant to other accounts
+///
+/// The owner alone grants and revokes roles and hands over ownership;
+/// roles are never implied by ownership.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum Role {
+    // Sets parameters and other configuration
+    Admin,
+    // Vouches for off-chain facts such as x402 payments and service calls
+    Verifier,
+    // Rules on disputes and slashes provider stake
+    Arbitrator,
+    // Halts and resumes the contract
+    Pauser,
+}
+
+/// Semantic version of a contract's messages and events
+///
+/// The major version changes when a selector, argument list or event
+/// signature changes incompatibly; the minor version when messages or
+/// events are only added.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct ApiVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ApiVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Whether a caller built against `required` can use this interface
+    pub fn supports(&self, required: ApiVersion) -> bool {
+        self.major == required.major && *self >= required
+    }
+}
+
+/// Storage an account's records take up in a contract
+///
+/// The chain charges a deposit for every storage item and byte a call adds
+/// and refunds it when they are removed. Contracts count the records each
+/// account created so this otherwise hidden cost can be shown.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct StorageUsage {
+    pub items: u32,
+    pub bytes: u64,
+}
+
+impl StorageUsage {
+    /// Count a new record of `bytes` encoded bytes
+    pub fn add(&mut self, bytes: usize) {
+        self.items = self.items.saturating_add(1);
+        self.bytes = self.bytes.saturating_add(bytes as u64);
+    }
+
+    /// Stop counting a removed record of `bytes` encoded bytes
+    pub fn remove(&mut self, bytes: usize) {
+        self.items = self.items.saturating_sub(1);
+        self.bytes = self.bytes.saturating_sub(bytes as u64);
+    }
+}
+
+/// Storage deposit the chain charges, mirrored from its runtime configuration
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct StorageDepositRates {
+    pub per_item: u128,
+    pub per_byte: u128,
+}
+
+impl StorageDepositRates {
+    /// Deposit held for `usage` at these rates
+    pub fn deposit(&self, usage: &StorageUsage) -> u128 {
+        self.per_item
+            .saturating_mul(u128::from(usage.items))
+            .saturating_add(self.per_byte.saturating_mul(u128::from(usage.bytes)))
+    }
+}
+
+/// A raw amount split into whole and fractional display units
+///
+/// `whole.fraction` with `fraction` zero-padded to `decimals` digits is the
+/// amount as a wallet would show it, e.g. 1_500_000 at 6 decimals is 1.500000.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct DisplayAmount {
+    pub whole: u128,
+    pub fraction: u128,
+    pub decimals: u8,
+}
+
+impl DisplayAmount {
+    /// Split `amount` at `decimals`, or `None` if `decimals` is above `MAX_DECIMALS`
+    pub fn new(amount: u128, decimals: u8) -> Option<Self> {
+        let unit = 10u128.checked_pow(u32::from(decimals))?;
+        Some(Self {
+            whole: amount / unit,
+            fraction: amount % unit,
+            decimals,
+        })
+    }
+}
+
+/// EIP-712-style hashing of signed payloads and signer recovery
+///
+/// A payload is signed as `keccak256(0x1901 ‖ domain separator ‖ struct
+/// hash)`. The domain binds the signature to one contract and one version
+/// of its signing schemes; the struct hash names the payload's layout so
+/// two payloads that happen to encode alike can't be swapped.
+pub mod typed_data {
+    use ink::env::hash::Keccak256;
+    use ink::primitives::H160;
+    use scale::Encode;
+
+    /// Layout every domain separator is hashed with
+    pub const DOMAIN_TYPE: &str = "HubDomain(string name,string version,address verifyingContract)";
+
+    /// Contract and scheme version a signature is valid for
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct Domain {
+        pub name: &'static str,
+        pub version: &'static str,
+        pub verifying_contract: H160,
+    }
+
+    impl Domain {
+        pub fn new(name: &'static str, version: &'static str, verifying_contract: H160) -> Self {
+            Self {
+                name,
+                version,
+                verifying_contract,
+            }
+        }
+
+        /// Hash identifying this domain
+        pub fn separator(&self) -> [u8; 32] {
+            hash_encoded(&(
+                keccak(DOMAIN_TYPE.as_bytes()),
+                keccak(self.name.as_bytes()),
+                keccak(self.version.as_bytes()),
+                self.verifying_contract,
+            ))
+        }
+
+        /// Digest a signer signs for `value`, laid out as `type_name`
+        pub fn digest<T: Encode>(&self, type_name: &str, value: &T) -> [u8; 32] {
+            let mut input = [0u8; 66];
+            input[..2].copy_from_slice(&[0x19, 0x01]);
+            input[2..34].copy_from_slice(&self.separator());
+            input[34..].copy_from_slice(&struct_hash(type_name, value));
+            keccak(&input)
+        }
+    }
+
+    /// Hash of a payload: its type hash followed by its SCALE encoding
+    pub fn struct_hash<T: Encode>(type_name: &str, value: &T) -> [u8; 32] {
+        hash_encoded(&(keccak(type_name.as_bytes()), value))
+    }
+
+    /// Address that produced a recoverable ECDSA signature over `digest`
+    ///
+    /// `None` if the signature is malformed.
+    pub fn recover(digest: &[u8; 32], signature: &[u8; 65]) -> Option<H160> {
+        let mut public_key = [0u8; 33];
+        ink::env::ecdsa_recover(signature, digest, &mut public_key).ok()?;
+        let mut address = [0u8; 20];
+        ink::env::ecdsa_to_eth_address(&public_key, &mut address).ok()?;
+        Some(H160::from(address))
+    }
+
+    fn keccak(input: &[u8]) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        ink::env::hash_bytes::<Keccak256>(input, &mut output);
+        output
+    }
+
+    fn hash_encoded<T: Encode>(input: &T) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        ink::env::hash_encoded::<Keccak256, _>(input, &mut output);
+        output
+    }
+}
+
+/// Fixtures for contracts' off-chain tests
+///
+/// The off-chain engine runs every contract at alice's address unless told
+/// otherwise, so whatever a contract pays alice would come out of her own
+/// balance and never show up in it. Tests that check payouts first move the
+/// contract under test to an address of its own with [`deploy_at`].
+#[cfg(feature = "std")]
+pub mod testing {
+    use ink::env::{Balance, DefaultEnvironment};
+    use ink::primitives::H160;
+    use ink::U256;
+
+    /// Run the contract under test at `address`, holding `balance`
+    pub fn deploy_at(address: H160, balance: Balance) {
+        ink::env::test::set_callee(address);
+        ink::env::test::set_contract_balance(address, U256::from(balance));
+    }
+
+    /// Native balance of `account`
+    pub fn balance_of(account: H160) -> Balance {
+        ink::env::test::get_contract_balance::<DefaultEnvironment>(account)
+            .unwrap_or_default()
+            .try_into()
+            .unwrap()
+    }
+
+    /// Call as `caller`, moving `value` from them into the contract
+    pub fn paying<T>(caller: H160, value: Balance, call: impl FnOnce() -> T) -> T {
+        ink::env::test::set_caller(caller);
+        ink::env::test::transfer_in(U256::from(value));
+        let result = call();
+        ink::env::test::set_value_transferred(U256::zero());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::Encode;
+
+    #[test]
+    fn codes_match_encoding() {
+        for error in [
+            HubError::InvalidInput,
+            HubError::NotFound,
+            HubError::CrossContractCallFailed,
+            HubError::Inactive,
+        ] {
+            assert_eq!(error.encode(), [error.code()]);
+        }
+    }
+
+    #[test]
+    fn asset_token_and_x402() {
+        let token = H160::from([0x07; 20]);
+        let x402 = PaymentAsset::X402 {
+            token,
+            chain_id: Some(8453),
+        };
+
+        assert_eq!(PaymentAsset::Native.token(), None);
+        assert_eq!(PaymentAsset::Psp22(token).token(), Some(token));
+        assert_eq!(x402.token(), Some(token));
+        assert!(x402.is_x402());
+        assert!(!PaymentAsset::Psp22(token).is_x402());
+    }
+
+    #[test]
+    fn display_amount_splits_at_decimals() {
+        let usdc = DisplayAmount::new(1_500_000, 6).unwrap();
+        assert_eq!((usdc.whole, usdc.fraction), (1, 500_000));
+
+        let native = DisplayAmount::new(1_500_000, NATIVE_DECIMALS).unwrap();
+        assert_eq!((native.whole, native.fraction), (0, 1_500_000));
+
+        assert!(DisplayAmount::new(1, MAX_DECIMALS).is_some());
+        assert_eq!(DisplayAmount::new(1, MAX_DECIMALS + 1), None);
+    }
+
+    #[test]
+    fn api_versions_are_compatible_within_a_major() {
+        let current = ApiVersion::new(1, 2, 0);
+
+        assert!(current.supports(ApiVersion::new(1, 1, 5)));
+        assert!(current.supports(current));
+        assert!(!current.supports(ApiVersion::new(1, 3, 0)));
+        assert!(!current.supports(ApiVersion::new(0, 9, 0)));
+    }
+
+    #[test]
+    fn storage_deposit_follows_usage() {
+        let rates = StorageDepositRates {
+            per_item: 1_000,
+            per_byte: 10,
+        };
+        let mut usage = StorageUsage::default();
+        usage.add(120);
+        usage.add(80);
+        assert_eq!(rates.deposit(&usage), 2 * 1_000 + 200 * 10);
+
+        usage.remove(120);
+        usage.remove(80);
+        usage.remove(80);
+        assert_eq!(usage, StorageUsage::default());
+    }
+
+    #[test]
+    fn typed_digests_bind_domain_and_type() {
+        use typed_data::Domain;
+
+        let escrow = Domain::new("PaymentEscrow", "1", H160::from([0x07; 20]));
+        let elsewhere = Domain::new("PaymentEscrow", "1", H160::from([0x08; 20]));
+        let payload = (1u64, 100u128);
+
+        let digest = escrow.digest("Quote(uint64 id,uint128 price)", &payload);
+        assert_eq!(
+            digest,
+            escrow.digest("Quote(uint64 id,uint128 price)", &payload)
+        );
+        assert_ne!(
+            digest,
+            elsewhere.digest("Quote(uint64 id,uint128 price)", &payload)
+        );
+        assert_ne!(
+            digest,
+            escrow.digest("Proof(uint64 id,uint128 amount)", &payload)
+        );
+        assert_eq!(typed_data::recover(&digest, &[0; 65]), None);
+    }
+}
@@ -0,0 +1,558 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod job_board {
+    use hub_types::{HubError, PaymentAsset, ServiceCategory};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::format;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use payment_escrow::PaymentEscrowRef;
+    use service_registry::ServiceRegistryRef;
+
+    /// Upper bound on bids per job so listing them stays bounded
+    const MAX_BIDS: usize = 50;
+
+    /// Different statuses of a job
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum JobStatus {
+        Open,
+        // A bid was accepted and its escrow is pending
+        Assigned,
+        Completed,
+        Refunded,
+        Cancelled,
+    }
+
+    /// A task posted by a consumer for providers to bid on
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Job {
+        pub id: u64,
+        pub consumer: H160,
+        // Hash of the off-chain task description
+        pub description_hash: H256,
+        pub category: ServiceCategory,
+        // Locked when the job is posted; bids can't exceed it
+        pub budget: Balance,
+        // Bids are accepted until then
+        pub deadline: u64,
+        pub status: JobStatus,
+        pub accepted_bid: Option<u64>,
+        pub escrow_id: Option<u64>,
+        pub created_at: u64,
+    }
+
+    /// A provider's offer to do a job through one of its services
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Bid {
+        pub id: u64,
+        pub job_id: u64,
+        pub provider: H160,
+        pub service_id: u64,
+        pub amount: Balance,
+        pub submitted_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a consumer posts a job
+    #[ink(event)]
+    pub struct JobPosted {
+        #[ink(topic)]
+        job_id: u64,
+        #[ink(topic)]
+        consumer: H160,
+        category: ServiceCategory,
+        budget: Balance,
+        deadline: u64,
+    }
+    /// Emitted when a provider bids on a job
+    #[ink(event)]
+    pub struct BidSubmitted {
+        #[ink(topic)]
+        job_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        bid_id: u64,
+        service_id: u64,
+        amount: Balance,
+    }
+    /// Emitted when the consumer accepts a bid and its escrow is opened
+    #[ink(event)]
+    pub struct BidAccepted {
+        #[ink(topic)]
+        job_id: u64,
+        bid_id: u64,
+        escrow_id: u64,
+        amount: Balance,
+    }
+    /// Emitted when a job's escrow is released or refunded, or an open job cancelled
+    #[ink(event)]
+    pub struct JobClosed {
+        #[ink(topic)]
+        job_id: u64,
+        status: JobStatus,
+        refunded: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the amount is zero or exceeds the budget
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the job is not found
+        JobNotFound,
+        /// Emitted when the bid is not found or belongs to another job
+        BidNotFound,
+        /// Emitted when the job is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the job's bidding deadline has passed
+        DeadlinePassed,
+        /// Emitted when the service isn't the bidder's active listing in the job's category
+        InvalidService,
+        /// Emitted when the job already holds the maximum number of bids
+        TooManyBids,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::JobNotFound => HubError::NotFound,
+                Error::BidNotFound => HubError::NotFound,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::DeadlinePassed => HubError::Expired,
+                Error::InvalidService => HubError::InvalidInput,
+                Error::TooManyBids => HubError::LimitExceeded,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    /// Consumers post jobs, providers bid with their registered services
+    ///
+    /// This contract is the payer of every job escrow: the budget is locked
+    /// here when the job is posted, the accepted bid is paid into a new
+    /// escrow and the rest goes back to the consumer, who then releases or
+    /// refunds the escrow through this contract.
+    #[ink(storage)]
+    pub struct JobBoard {
+        registry: H160,
+        escrow: H160,
+        jobs: Mapping<u64, Job>,
+        job_count: u64,
+        bids: Mapping<u64, Bid>,
+        bid_count: u64,
+        job_bids: Mapping<u64, Vec<u64>>,
+        consumer_jobs: Mapping<H160, Vec<u64>>,
+        escrow_jobs: Mapping<u64, u64>,
+    }
+
+    impl JobBoard {
+        #[ink(constructor)]
+        pub fn new(registry: H160, escrow: H160) -> Self {
+            Self {
+                registry,
+                escrow,
+                jobs: Mapping::default(),
+                job_count: 0,
+                bids: Mapping::default(),
+                bid_count: 0,
+                job_bids: Mapping::default(),
+                consumer_jobs: Mapping::default(),
+                escrow_jobs: Mapping::default(),
+            }
+        }
+
+        /// Post a job, locking the transferred value as its budget
+        #[ink(message, payable)]
+        pub fn post_job(
+            &mut self,
+            description_hash: H256,
+            category: ServiceCategory,
+            deadline: u64,
+        ) -> Result<u64> {
+            let consumer = self.env().caller();
+            let budget: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if budget == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::InvalidInput);
+            }
+
+            self.job_count = self.job_count.checked_add(1).ok_or(Error::Overflow)?;
+            let job_id = self.job_count;
+
+            let job = Job {
+                id: job_id,
+                consumer,
+                description_hash,
+                category: category.clone(),
+                budget,
+                deadline,
+                status: JobStatus::Open,
+                accepted_bid: None,
+                escrow_id: None,
+                created_at: self.env().block_timestamp(),
+            };
+            self.jobs.insert(job_id, &job);
+
+            let mut jobs = self.consumer_jobs.get(consumer).unwrap_or_default();
+            jobs.push(job_id);
+            self.consumer_jobs.insert(consumer, &jobs);
+
+            self.env().emit_event(JobPosted {
+                job_id,
+                consumer,
+                category,
+                budget,
+                deadline,
+            });
+
+            Ok(job_id)
+        }
+
+        /// Bid on an open job through one of the caller's services
+        ///
+        /// The service must be the caller's active listing in the job's
+        /// category, and the amount within the job's budget.
+        #[ink(message)]
+        pub fn submit_bid(&mut self, job_id: u64, service_id: u64, amount: Balance) -> Result<u64> {
+            let provider = self.env().caller();
+            let job = self.get_job(job_id)?;
+            if job.status != JobStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() >= job.deadline {
+                return Err(Error::DeadlinePassed);
+            }
+            if amount == 0 || amount > job.budget {
+                return Err(Error::InvalidAmount);
+            }
+            let mut bids = self.job_bids.get(job_id).unwrap_or_default();
+            if bids.len() >= MAX_BIDS {
+                return Err(Error::TooManyBids);
+            }
+
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service = registry
+                .get_service(service_id)
+                .map_err(|_| Error::InvalidService)?;
+            if service.provider != provider
+                || !service.is_active
+                || service.category != job.category
+            {
+                return Err(Error::InvalidService);
+            }
+
+            self.bid_count = self.bid_count.checked_add(1).ok_or(Error::Overflow)?;
+            let bid_id = self.bid_count;
+
+            self.bids.insert(
+                bid_id,
+                &Bid {
+                    id: bid_id,
+                    job_id,
+                    provider,
+                    service_id,
+                    amount,
+                    submitted_at: self.env().block_timestamp(),
+                },
+            );
+            bids.push(bid_id);
+            self.job_bids.insert(job_id, &bids);
+
+            self.env().emit_event(BidSubmitted {
+                job_id,
+                provider,
+                bid_id,
+                service_id,
+                amount,
+            });
+
+            Ok(bid_id)
+        }
+
+        /// Accept a bid, paying it into a new escrow for the bidder's service
+        ///
+        /// The part of the budget the bid doesn't use goes back to the
+        /// consumer. Returns the escrow id.
+        #[ink(message)]
+        pub fn accept_bid(&mut self, job_id: u64, bid_id: u64) -> Result<u64> {
+            let mut job = self.consumer_job(job_id)?;
+            if job.status != JobStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            let bid = self
+                .bids
+                .get(bid_id)
+                .filter(|bid| bid.job_id == job_id)
+                .ok_or(Error::BidNotFound)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let escrow_id = escrow
+                .call_mut()
+                .create_escrow(
+                    bid.provider,
+                    bid.service_id,
+                    format!("job:{}:{}", job_id, bid_id),
+                    PaymentAsset::Native,
+                )
+                .transferred_value(bid.amount.into())
+                .invoke()
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            let unused = job.budget.saturating_sub(bid.amount);
+            if unused > 0 && self.env().transfer(job.consumer, unused.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+
+            job.budget = bid.amount;
+            job.status = JobStatus::Assigned;
+            job.accepted_bid = Some(bid_id);
+            job.escrow_id = Some(escrow_id);
+            self.jobs.insert(job_id, &job);
+            self.escrow_jobs.insert(escrow_id, &job_id);
+
+            self.env().emit_event(BidAccepted {
+                job_id,
+                bid_id,
+                escrow_id,
+                amount: bid.amount,
+            });
+
+            Ok(escrow_id)
+        }
+
+        /// Release an assigned job's escrow to the provider
+        #[ink(message)]
+        pub fn release_job(&mut self, job_id: u64) -> Result<()> {
+            let (mut job, escrow_id) = self.assigned_job(job_id)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .release_payment(escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            job.status = JobStatus::Completed;
+            self.jobs.insert(job_id, &job);
+
+            self.env().emit_event(JobClosed {
+                job_id,
+                status: JobStatus::Completed,
+                refunded: 0,
+            });
+
+            Ok(())
+        }
+
+        /// Refund an assigned job's escrow back to the consumer
+        #[ink(message)]
+        pub fn refund_job(&mut self, job_id: u64) -> Result<()> {
+            let (mut job, escrow_id) = self.assigned_job(job_id)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .refund(escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+            if self
+                .env()
+                .transfer(job.consumer, job.budget.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            job.status = JobStatus::Refunded;
+            self.jobs.insert(job_id, &job);
+
+            self.env().emit_event(JobClosed {
+                job_id,
+                status: JobStatus::Refunded,
+                refunded: job.budget,
+            });
+
+            Ok(())
+        }
+
+        /// Cancel an open job and return its budget
+        #[ink(message)]
+        pub fn cancel_job(&mut self, job_id: u64) -> Result<()> {
+            let mut job = self.consumer_job(job_id)?;
+            if job.status != JobStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            if self
+                .env()
+                .transfer(job.consumer, job.budget.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            job.status = JobStatus::Cancelled;
+            self.jobs.insert(job_id, &job);
+
+            self.env().emit_event(JobClosed {
+                job_id,
+                status: JobStatus::Cancelled,
+                refunded: job.budget,
+            });
+
+            Ok(())
+        }
+
+        /// Get job details
+        #[ink(message)]
+        pub fn get_job(&self, job_id: u64) -> Result<Job> {
+            self.jobs.get(job_id).ok_or(Error::JobNotFound)
+        }
+
+        /// Get bid details
+        #[ink(message)]
+        pub fn get_bid(&self, bid_id: u64) -> Result<Bid> {
+            self.bids.get(bid_id).ok_or(Error::BidNotFound)
+        }
+
+        /// Get all bids on a job, oldest first
+        #[ink(message)]
+        pub fn get_job_bids(&self, job_id: u64) -> Vec<Bid> {
+            self.job_bids
+                .get(job_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|bid_id| self.bids.get(bid_id))
+                .collect()
+        }
+
+        /// Get all jobs posted by a consumer
+        #[ink(message)]
+        pub fn get_consumer_jobs(&self, consumer: H160) -> Vec<u64> {
+            self.consumer_jobs.get(consumer).unwrap_or_default()
+        }
+
+        /// Get the job an escrow was opened for, if any
+        #[ink(message)]
+        pub fn get_escrow_job(&self, escrow_id: u64) -> Option<u64> {
+            self.escrow_jobs.get(escrow_id)
+        }
+
+        /// Get total job count
+        #[ink(message)]
+        pub fn get_job_count(&self) -> u64 {
+            self.job_count
+        }
+
+        fn consumer_job(&self, job_id: u64) -> Result<Job> {
+            let job = self.get_job(job_id)?;
+            if job.consumer != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            Ok(job)
+        }
+
+        fn assigned_job(&self, job_id: u64) -> Result<(Job, u64)> {
+            let job = self.consumer_job(job_id)?;
+            match (&job.status, job.escrow_id) {
+                (JobStatus::Assigned, Some(escrow_id)) => Ok((job, escrow_id)),
+                _ => Err(Error::InvalidStatus),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::U256;
+
+        fn post(contract: &mut JobBoard, budget: u128) -> Result<u64> {
+            ink::env::test::set_value_transferred(U256::from(budget));
+            contract.post_job(H256::from([0x01; 32]), ServiceCategory::Translation, 1_000)
+        }
+
+        #[ink::test]
+        fn post_job_locks_budget() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = JobBoard::new(accounts.django, accounts.eve);
+
+            assert_eq!(post(&mut contract, 0), Err(Error::InvalidAmount));
+            let job_id = post(&mut contract, 500).unwrap();
+
+            let job = contract.get_job(job_id).unwrap();
+            assert_eq!(job.consumer, accounts.alice);
+            assert_eq!(job.budget, 500);
+            assert_eq!(job.status, JobStatus::Open);
+            assert_eq!(contract.get_consumer_jobs(accounts.alice), vec![job_id]);
+        }
+
+        #[ink::test]
+        fn bids_must_fit_the_budget_and_deadline() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = JobBoard::new(accounts.django, accounts.eve);
+            let job_id = post(&mut contract, 500).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.submit_bid(job_id, 1, 501),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(contract.submit_bid(7, 1, 100), Err(Error::JobNotFound));
+
+            ink::env::test::set_block_timestamp(1_000);
+            assert_eq!(
+                contract.submit_bid(job_id, 1, 100),
+                Err(Error::DeadlinePassed)
+            );
+        }
+
+        #[ink::test]
+        fn only_consumer_settles_a_job() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = JobBoard::new(accounts.django, accounts.eve);
+            let job_id = post(&mut contract, 500).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.accept_bid(job_id, 1), Err(Error::Unauthorized));
+            assert_eq!(contract.cancel_job(job_id), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(contract.accept_bid(job_id, 1), Err(Error::BidNotFound));
+            assert_eq!(contract.release_job(job_id), Err(Error::InvalidStatus));
+            contract.cancel_job(job_id).unwrap();
+            assert_eq!(
+                contract.get_job(job_id).unwrap().status,
+                JobStatus::Cancelled
+            );
+        }
+    }
+}
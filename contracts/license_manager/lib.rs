@@ -0,0 +1,540 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod license_manager {
+    use dataset_marketplace::DatasetMarketplaceRef;
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use model_registry::ModelRegistryRef;
+
+    /// Kinds of hub assets a license can cover
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AssetKind {
+        Model,
+        Dataset,
+    }
+
+    /// License details
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct License {
+        pub id: u64,
+        pub licensor: H160,
+        pub licensee: H160,
+        pub asset_kind: AssetKind,
+        pub asset_id: u64,
+        // Hash of the terms agreed at sale time
+        pub terms_hash: H256,
+        pub expires_at: Option<u64>,
+        pub max_uses: Option<u32>,
+        pub uses: u32,
+        pub issued_at: u64,
+        pub revoked: bool,
+        pub revocation_reason: Option<H256>,
+    }
+
+    /// Events
+    /// Emitted when a license is issued
+    #[ink(event)]
+    pub struct LicenseIssued {
+        #[ink(topic)]
+        license_id: u64,
+        #[ink(topic)]
+        licensee: H160,
+        asset_kind: AssetKind,
+        asset_id: u64,
+        expires_at: Option<u64>,
+        max_uses: Option<u32>,
+    }
+    /// Emitted when a use is recorded against a license
+    #[ink(event)]
+    pub struct LicenseUsed {
+        #[ink(topic)]
+        license_id: u64,
+        uses: u32,
+    }
+    /// Emitted when the licensor revokes a license
+    #[ink(event)]
+    pub struct LicenseRevoked {
+        #[ink(topic)]
+        license_id: u64,
+        reason: H256,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the license is not found
+        LicenseNotFound,
+        /// Emitted when the asset is not found
+        AssetNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the license is expired, exhausted or revoked
+        LicenseInvalid,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::LicenseNotFound => HubError::NotFound,
+                Error::AssetNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::LicenseInvalid => HubError::InvalidStatus,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct LicenseManager {
+        model_registry: H160,
+        dataset_marketplace: H160,
+        licenses: Mapping<u64, License>,
+        license_count: u64,
+        user_licenses: Mapping<H160, Vec<u64>>,
+        // Latest license per (licensee, asset)
+        asset_licenses: Mapping<(H160, AssetKind, u64), u64>,
+    }
+
+    impl LicenseManager {
+        #[ink(constructor)]
+        pub fn new(model_registry: H160, dataset_marketplace: H160) -> Self {
+            Self {
+                model_registry,
+                dataset_marketplace,
+                licenses: Mapping::default(),
+                license_count: 0,
+                user_licenses: Mapping::default(),
+                asset_licenses: Mapping::default(),
+            }
+        }
+
+        /// Issue a license for an asset the caller owns
+        ///
+        /// A license can be bound by time, by number of uses, both, or
+        /// neither (perpetual until revoked).
+        #[ink(message)]
+        pub fn issue_license(
+            &mut self,
+            licensee: H160,
+            asset_kind: AssetKind,
+            asset_id: u64,
+            terms_hash: H256,
+            expires_at: Option<u64>,
+            max_uses: Option<u32>,
+        ) -> Result<u64> {
+            let licensor = self.env().caller();
+            if self.asset_owner(asset_kind, asset_id)? != licensor {
+                return Err(Error::Unauthorized);
+            }
+            if expires_at.is_some_and(|expires_at| expires_at <= self.env().block_timestamp())
+                || max_uses == Some(0)
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            self.license_count = self.license_count.checked_add(1).ok_or(Error::Overflow)?;
+            let license_id = self.license_count;
+
+            let license = License {
+                id: license_id,
+                licensor,
+                licensee,
+                asset_kind,
+                asset_id,
+                terms_hash,
+                expires_at,
+                max_uses,
+                uses: 0,
+                issued_at: self.env().block_timestamp(),
+                revoked: false,
+                revocation_reason: None,
+            };
+            self.licenses.insert(license_id, &license);
+            self.asset_licenses
+                .insert((licensee, asset_kind, asset_id), &license_id);
+
+            for user in [licensor, licensee] {
+                let mut licenses = self.user_licenses.get(user).unwrap_or_default();
+                licenses.push(license_id);
+                self.user_licenses.insert(user, &licenses);
+            }
+
+            self.env().emit_event(LicenseIssued {
+                license_id,
+                licensee,
+                asset_kind,
+                asset_id,
+                expires_at,
+                max_uses,
+            });
+
+            Ok(license_id)
+        }
+
+        /// Record one use of a usage-bound license
+        #[ink(message)]
+        pub fn record_use(&mut self, license_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut license = self.get_license(license_id)?;
+
+            if license.licensor != caller && license.licensee != caller {
+                return Err(Error::Unauthorized);
+            }
+            if !self.is_license_valid(&license) {
+                return Err(Error::LicenseInvalid);
+            }
+
+            license.uses = license.uses.checked_add(1).ok_or(Error::Overflow)?;
+            self.licenses.insert(license_id, &license);
+
+            self.env().emit_event(LicenseUsed {
+                license_id,
+                uses: license.uses,
+            });
+
+            Ok(())
+        }
+
+        /// Revoke a license after a terms violation
+        #[ink(message)]
+        pub fn revoke_license(&mut self, license_id: u64, reason: H256) -> Result<()> {
+            let caller = self.env().caller();
+            let mut license = self.get_license(license_id)?;
+
+            if license.licensor != caller {
+                return Err(Error::Unauthorized);
+            }
+            if license.revoked {
+                return Err(Error::LicenseInvalid);
+            }
+
+            license.revoked = true;
+            license.revocation_reason = Some(reason);
+            self.licenses.insert(license_id, &license);
+
+            self.env().emit_event(LicenseRevoked { license_id, reason });
+
+            Ok(())
+        }
+
+        /// Check if a license is currently valid
+        #[ink(message)]
+        pub fn is_valid(&self, license_id: u64) -> bool {
+            self.licenses
+                .get(license_id)
+                .is_some_and(|license| self.is_license_valid(&license))
+        }
+
+        /// Check if an account holds a valid license for an asset
+        #[ink(message)]
+        pub fn has_valid_license(
+            &self,
+            licensee: H160,
+            asset_kind: AssetKind,
+            asset_id: u64,
+        ) -> bool {
+            self.asset_licenses
+                .get((licensee, asset_kind, asset_id))
+                .is_some_and(|license_id| self.is_valid(license_id))
+        }
+
+        /// Get license details
+        #[ink(message)]
+        pub fn get_license(&self, license_id: u64) -> Result<License> {
+            self.licenses.get(license_id).ok_or(Error::LicenseNotFound)
+        }
+
+        /// Get all licenses a user issued or holds
+        #[ink(message)]
+        pub fn get_user_licenses(&self, user: H160) -> Vec<u64> {
+            self.user_licenses.get(user).unwrap_or_default()
+        }
+
+        fn is_license_valid(&self, license: &License) -> bool {
+            let not_expired = license
+                .expires_at
+                .is_none_or(|expires_at| self.env().block_timestamp() < expires_at);
+            let uses_left = license
+                .max_uses
+                .is_none_or(|max_uses| license.uses < max_uses);
+            !license.revoked && not_expired && uses_left
+        }
+
+        fn asset_owner(&self, asset_kind: AssetKind, asset_id: u64) -> Result<H160> {
+            match asset_kind {
+                AssetKind::Model => {
+                    let registry: ModelRegistryRef = FromAddr::from_addr(self.model_registry);
+                    registry
+                        .get_model(asset_id)
+                        .map(|model| model.owner)
+                        .map_err(|_| Error::AssetNotFound)
+                }
+                AssetKind::Dataset => {
+                    let marketplace: DatasetMarketplaceRef =
+                        FromAddr::from_addr(self.dataset_marketplace);
+                    marketplace
+                        .get_dataset(asset_id)
+                        .map(|dataset| dataset.provider)
+                        .map_err(|_| Error::AssetNotFound)
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::prelude::string::String;
+        use ink::{ToAddr, U256};
+
+        const EXPIRY: u64 = 1_000;
+
+        /// A manager over a registry in which alice owns a model and a
+        /// marketplace in which bob lists a dataset
+        fn manager() -> (LicenseManager, u64, u64) {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, ModelRegistryRef>();
+            let mut registry = ModelRegistryRef::new()
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            let model_id = registry
+                .register_model(
+                    String::from("hub-llm"),
+                    String::from("1.0"),
+                    H256::from([1; 32]),
+                    String::from("transformer/decoder-only/7B"),
+                    H256::from([0xAA; 32]),
+                    None,
+                )
+                .unwrap();
+
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, DatasetMarketplaceRef>(
+                );
+            let mut marketplace = DatasetMarketplaceRef::new(EXPIRY)
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            ink::env::test::set_caller(accounts.bob);
+            let dataset_id = marketplace
+                .register_dataset(
+                    String::from("Support tickets"),
+                    H256::from([2; 32]),
+                    H256::from([0xBB; 32]),
+                    100,
+                    String::from("ipfs://sample"),
+                )
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            let manager = LicenseManager::new(registry.to_addr(), marketplace.to_addr());
+            (manager, model_id, dataset_id)
+        }
+
+        #[ink::test]
+        fn owners_issue_licenses_for_their_assets() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut manager, model_id, dataset_id) = manager();
+            let terms = H256::from([0xCC; 32]);
+
+            let license_id = manager
+                .issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id,
+                    terms,
+                    Some(EXPIRY),
+                    None,
+                )
+                .unwrap();
+            let license = manager.get_license(license_id).unwrap();
+            assert_eq!(license.licensor, accounts.alice);
+            assert_eq!(license.licensee, accounts.charlie);
+            assert_eq!(license.terms_hash, terms);
+            assert!(manager.has_valid_license(accounts.charlie, AssetKind::Model, model_id));
+            assert!(!manager.has_valid_license(accounts.charlie, AssetKind::Dataset, model_id));
+
+            // Only the provider of the dataset may license it
+            assert_eq!(
+                manager.issue_license(
+                    accounts.charlie,
+                    AssetKind::Dataset,
+                    dataset_id,
+                    terms,
+                    None,
+                    None,
+                ),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            let dataset_license = manager
+                .issue_license(
+                    accounts.charlie,
+                    AssetKind::Dataset,
+                    dataset_id,
+                    terms,
+                    None,
+                    Some(3),
+                )
+                .unwrap();
+            assert_eq!(
+                manager.get_user_licenses(accounts.charlie),
+                vec![license_id, dataset_license]
+            );
+            assert_eq!(
+                manager.get_user_licenses(accounts.bob),
+                vec![dataset_license]
+            );
+        }
+
+        #[ink::test]
+        fn issue_license_rejects_unknown_assets_and_dead_terms() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut manager, model_id, _) = manager();
+            let terms = H256::from([0xCC; 32]);
+
+            assert_eq!(
+                manager.issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id + 1,
+                    terms,
+                    None,
+                    None,
+                ),
+                Err(Error::AssetNotFound)
+            );
+            ink::env::test::set_block_timestamp(EXPIRY);
+            assert_eq!(
+                manager.issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id,
+                    terms,
+                    Some(EXPIRY),
+                    None,
+                ),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                manager.issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id,
+                    terms,
+                    None,
+                    Some(0),
+                ),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(manager.get_license(1), Err(Error::LicenseNotFound));
+        }
+
+        #[ink::test]
+        fn uses_are_recorded_until_the_license_runs_out() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut manager, model_id, _) = manager();
+            let license_id = manager
+                .issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id,
+                    H256::from([0xCC; 32]),
+                    Some(EXPIRY),
+                    Some(2),
+                )
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.django);
+            assert_eq!(manager.record_use(license_id), Err(Error::Unauthorized));
+            assert_eq!(
+                manager.record_use(license_id + 1),
+                Err(Error::LicenseNotFound)
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            manager.record_use(license_id).unwrap();
+            ink::env::test::set_caller(accounts.alice);
+            manager.record_use(license_id).unwrap();
+            assert_eq!(manager.get_license(license_id).unwrap().uses, 2);
+            assert!(!manager.is_valid(license_id));
+            assert_eq!(manager.record_use(license_id), Err(Error::LicenseInvalid));
+
+            let timed = manager
+                .issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id,
+                    H256::from([0xCC; 32]),
+                    Some(EXPIRY),
+                    None,
+                )
+                .unwrap();
+            assert!(manager.is_valid(timed));
+            ink::env::test::set_block_timestamp(EXPIRY);
+            assert!(!manager.is_valid(timed));
+            assert_eq!(manager.record_use(timed), Err(Error::LicenseInvalid));
+        }
+
+        #[ink::test]
+        fn only_the_licensor_revokes_a_license() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut manager, model_id, _) = manager();
+            let license_id = manager
+                .issue_license(
+                    accounts.charlie,
+                    AssetKind::Model,
+                    model_id,
+                    H256::from([0xCC; 32]),
+                    None,
+                    None,
+                )
+                .unwrap();
+            let reason = H256::from([0xDD; 32]);
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                manager.revoke_license(license_id, reason),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                manager.revoke_license(license_id + 1, reason),
+                Err(Error::LicenseNotFound)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            manager.revoke_license(license_id, reason).unwrap();
+            let license = manager.get_license(license_id).unwrap();
+            assert!(license.revoked);
+            assert_eq!(license.revocation_reason, Some(reason));
+            assert!(!manager.has_valid_license(accounts.charlie, AssetKind::Model, model_id));
+            assert_eq!(
+                manager.revoke_license(license_id, reason),
+                Err(Error::LicenseInvalid)
+            );
+        }
+    }
+}
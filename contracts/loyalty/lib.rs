@@ -0,0 +1,279 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::loyalty::{Error, LoyaltyRef};
+
+#[ink::contract]
+mod loyalty {
+    use hub_types::HubError;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// Discounts are expressed in basis points of the listed price
+    const MAX_DISCOUNT_BPS: u32 = 10_000;
+    /// Upper bound on tiers per provider to keep lookups cheap
+    const MAX_TIERS: usize = 10;
+
+    /// A volume discount tier configured by a provider
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DiscountTier {
+        pub min_spend: Balance,
+        pub discount_bps: u32,
+    }
+
+    /// Events
+    /// Emitted when a provider changes its discount tiers
+    #[ink(event)]
+    pub struct TiersUpdated {
+        #[ink(topic)]
+        provider: H160,
+        tiers: Vec<DiscountTier>,
+    }
+    /// Emitted when the escrow contract records spend for a consumer
+    #[ink(event)]
+    pub struct SpendRecorded {
+        #[ink(topic)]
+        consumer: H160,
+        #[ink(topic)]
+        provider: H160,
+        amount: Balance,
+        total_spend: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::Unauthorized => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Loyalty {
+        owner: H160,
+        // Only the escrow contract may record spend
+        escrow: Option<H160>,
+        tiers: Mapping<H160, Vec<DiscountTier>>,
+        spend: Mapping<(H160, H160), Balance>,
+    }
+
+    impl Loyalty {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                escrow: None,
+                tiers: Mapping::default(),
+                spend: Mapping::default(),
+            }
+        }
+
+        /// Set the escrow contract allowed to record spend
+        #[ink(message)]
+        pub fn set_escrow(&mut self, escrow: H160) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            self.escrow = Some(escrow);
+            Ok(())
+        }
+
+        /// Replace the caller's discount tiers
+        ///
+        /// Tiers must be ordered by strictly increasing `min_spend` and
+        /// non-decreasing discount. An empty list removes all discounts.
+        #[ink(message)]
+        pub fn set_discount_tiers(&mut self, tiers: Vec<DiscountTier>) -> Result<()> {
+            let provider = self.env().caller();
+            if tiers.len() > MAX_TIERS {
+                return Err(Error::InvalidInput);
+            }
+            for (i, tier) in tiers.iter().enumerate() {
+                if tier.discount_bps > MAX_DISCOUNT_BPS {
+                    return Err(Error::InvalidInput);
+                }
+                if i > 0 {
+                    let previous = &tiers[i - 1];
+                    if tier.min_spend <= previous.min_spend
+                        || tier.discount_bps < previous.discount_bps
+                    {
+                        return Err(Error::InvalidInput);
+                    }
+                }
+            }
+
+            if tiers.is_empty() {
+                self.tiers.remove(provider);
+            } else {
+                self.tiers.insert(provider, &tiers);
+            }
+
+            self.env().emit_event(TiersUpdated { provider, tiers });
+
+            Ok(())
+        }
+
+        /// Record spend by a consumer with a provider
+        #[ink(message)]
+        pub fn record_spend(
+            &mut self,
+            consumer: H160,
+            provider: H160,
+            amount: Balance,
+        ) -> Result<()> {
+            if Some(self.env().caller()) != self.escrow {
+                return Err(Error::Unauthorized);
+            }
+
+            let total_spend = self
+                .spend
+                .get((consumer, provider))
+                .unwrap_or(0)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.spend.insert((consumer, provider), &total_spend);
+
+            self.env().emit_event(SpendRecorded {
+                consumer,
+                provider,
+                amount,
+                total_spend,
+            });
+
+            Ok(())
+        }
+
+        /// Discount in basis points the consumer currently earns with the provider
+        #[ink(message)]
+        pub fn get_discount_bps(&self, consumer: H160, provider: H160) -> u32 {
+            let spend = self.get_spend(consumer, provider);
+            self.tiers
+                .get(provider)
+                .unwrap_or_default()
+                .iter()
+                .rev()
+                .find(|tier| spend >= tier.min_spend)
+                .map(|tier| tier.discount_bps)
+                .unwrap_or(0)
+        }
+
+        /// Price the consumer pays for a listing at `price` after discounts
+        #[ink(message)]
+        pub fn quote_price(&self, consumer: H160, provider: H160, price: Balance) -> Balance {
+            let discount_bps = self.get_discount_bps(consumer, provider);
+            let discount =
+                price.saturating_mul(Balance::from(discount_bps)) / Balance::from(MAX_DISCOUNT_BPS);
+            price.saturating_sub(discount)
+        }
+
+        /// Total spend recorded for a consumer with a provider
+        #[ink(message)]
+        pub fn get_spend(&self, consumer: H160, provider: H160) -> Balance {
+            self.spend.get((consumer, provider)).unwrap_or(0)
+        }
+
+        /// Get a provider's discount tiers
+        #[ink(message)]
+        pub fn get_discount_tiers(&self, provider: H160) -> Vec<DiscountTier> {
+            self.tiers.get(provider).unwrap_or_default()
+        }
+
+        /// Get the escrow contract allowed to record spend
+        #[ink(message)]
+        pub fn get_escrow(&self) -> Option<H160> {
+            self.escrow
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn tiers() -> Vec<DiscountTier> {
+            vec![
+                DiscountTier {
+                    min_spend: 1_000,
+                    discount_bps: 500,
+                },
+                DiscountTier {
+                    min_spend: 10_000,
+                    discount_bps: 1_000,
+                },
+            ]
+        }
+
+        #[ink::test]
+        fn discount_follows_recorded_spend() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Loyalty::new();
+            contract.set_escrow(accounts.eve).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.set_discount_tiers(tiers()).unwrap();
+            assert_eq!(
+                contract.quote_price(accounts.charlie, accounts.bob, 200),
+                200
+            );
+
+            ink::env::test::set_caller(accounts.eve);
+            contract
+                .record_spend(accounts.charlie, accounts.bob, 1_000)
+                .unwrap();
+            assert_eq!(
+                contract.get_discount_bps(accounts.charlie, accounts.bob),
+                500
+            );
+            assert_eq!(
+                contract.quote_price(accounts.charlie, accounts.bob, 200),
+                190
+            );
+
+            contract
+                .record_spend(accounts.charlie, accounts.bob, 9_000)
+                .unwrap();
+            assert_eq!(
+                contract.quote_price(accounts.charlie, accounts.bob, 200),
+                180
+            );
+        }
+
+        #[ink::test]
+        fn only_escrow_can_record_spend() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Loyalty::new();
+            contract.set_escrow(accounts.eve).unwrap();
+
+            assert_eq!(
+                contract.record_spend(accounts.charlie, accounts.bob, 1_000),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn unordered_tiers_are_rejected() {
+            let mut contract = Loyalty::new();
+            let mut tiers = tiers();
+            tiers.reverse();
+
+            assert_eq!(contract.set_discount_tiers(tiers), Err(Error::InvalidInput));
+        }
+    }
+}
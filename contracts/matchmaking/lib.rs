@@ -0,0 +1,345 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod matchmaking {
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use service_registry::{ServiceCategory, ServiceRegistryRef, ServiceSummary};
+
+    /// Success rates are expressed in basis points
+    const MAX_SUCCESS_RATE: u32 = 10_000;
+
+    /// Requirement profile posted by a consumer looking for a service
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RequirementProfile {
+        pub id: u64,
+        pub consumer: H160,
+        // The registry category stands in for the capability being requested
+        pub category: ServiceCategory,
+        pub max_price: Balance,
+        pub min_reputation: u32,
+        pub min_success_rate: u32,
+        pub requires_x402: bool,
+        pub is_open: bool,
+        pub created_at: u64,
+    }
+
+    /// A service that satisfies a requirement profile
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct Candidate {
+        pub service_id: u64,
+        pub provider: H160,
+        pub price: Balance,
+        pub reputation: u32,
+        pub success_rate: u32,
+    }
+
+    /// Events
+    /// Emitted when a consumer posts a requirement profile
+    #[ink(event)]
+    pub struct ProfilePosted {
+        #[ink(topic)]
+        profile_id: u64,
+        #[ink(topic)]
+        consumer: H160,
+        category: ServiceCategory,
+        max_price: Balance,
+    }
+    /// Emitted when a requirement profile is closed
+    #[ink(event)]
+    pub struct ProfileClosed {
+        #[ink(topic)]
+        profile_id: u64,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the profile is not found
+        ProfileNotFound,
+        /// Emitted when the profile has already been closed
+        ProfileClosed,
+        /// Emitted when the caller is not the consumer who posted the profile
+        Unauthorized,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::ProfileNotFound => HubError::NotFound,
+                Error::ProfileClosed => HubError::Inactive,
+                Error::Unauthorized => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Matchmaking {
+        registry: H160,
+        profiles: Mapping<u64, RequirementProfile>,
+        consumer_profiles: Mapping<H160, Vec<u64>>,
+        profile_count: u64,
+    }
+
+    impl Matchmaking {
+        #[ink(constructor)]
+        pub fn new(registry: H160) -> Self {
+            Self {
+                registry,
+                profiles: Mapping::default(),
+                consumer_profiles: Mapping::default(),
+                profile_count: 0,
+            }
+        }
+
+        /// Post a requirement profile
+        #[ink(message)]
+        pub fn post_profile(
+            &mut self,
+            category: ServiceCategory,
+            max_price: Balance,
+            min_reputation: u32,
+            min_success_rate: u32,
+            requires_x402: bool,
+        ) -> Result<u64> {
+            let caller = self.env().caller();
+            if max_price == 0 || min_success_rate > MAX_SUCCESS_RATE {
+                return Err(Error::InvalidInput);
+            }
+
+            self.profile_count = self.profile_count.checked_add(1).ok_or(Error::Overflow)?;
+            let profile_id = self.profile_count;
+
+            let profile = RequirementProfile {
+                id: profile_id,
+                consumer: caller,
+                category: category.clone(),
+                max_price,
+                min_reputation,
+                min_success_rate,
+                requires_x402,
+                is_open: true,
+                created_at: self.env().block_timestamp(),
+            };
+            self.profiles.insert(profile_id, &profile);
+
+            let mut consumer_profiles = self.consumer_profiles.get(caller).unwrap_or_default();
+            consumer_profiles.push(profile_id);
+            self.consumer_profiles.insert(caller, &consumer_profiles);
+
+            self.env().emit_event(ProfilePosted {
+                profile_id,
+                consumer: caller,
+                category,
+                max_price,
+            });
+
+            Ok(profile_id)
+        }
+
+        /// Close a requirement profile once the consumer has picked a service
+        #[ink(message)]
+        pub fn close_profile(&mut self, profile_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut profile = self
+                .profiles
+                .get(profile_id)
+                .ok_or(Error::ProfileNotFound)?;
+
+            if profile.consumer != caller {
+                return Err(Error::Unauthorized);
+            }
+            if !profile.is_open {
+                return Err(Error::ProfileClosed);
+            }
+
+            profile.is_open = false;
+            self.profiles.insert(profile_id, &profile);
+
+            self.env().emit_event(ProfileClosed { profile_id });
+
+            Ok(())
+        }
+
+        /// Return up to `limit` ranked candidate services for an open profile
+        ///
+        /// Candidates are read live from the registry, so the result always
+        /// reflects current prices, reputation and availability.
+        #[ink(message)]
+        pub fn find_matches(&self, profile_id: u64, limit: u32) -> Result<Vec<Candidate>> {
+            let profile = self
+                .profiles
+                .get(profile_id)
+                .ok_or(Error::ProfileNotFound)?;
+            if !profile.is_open {
+                return Err(Error::ProfileClosed);
+            }
+
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let services = registry.get_active_services(registry.get_service_count());
+
+            let mut candidates = Vec::new();
+            for service in services {
+                if !matches_profile(&profile, &service) {
+                    continue;
+                }
+                if service.reputation < profile.min_reputation
+                    || service.success_rate < profile.min_success_rate
+                {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    service_id: service.id,
+                    provider: service.provider,
+                    price: service.price,
+                    reputation: service.reputation,
+                    success_rate: service.success_rate,
+                });
+            }
+
+            rank(&mut candidates);
+            candidates.truncate(limit as usize);
+
+            Ok(candidates)
+        }
+
+        /// Get a requirement profile
+        #[ink(message)]
+        pub fn get_profile(&self, profile_id: u64) -> Result<RequirementProfile> {
+            self.profiles.get(profile_id).ok_or(Error::ProfileNotFound)
+        }
+
+        /// Get all profiles posted by a consumer
+        #[ink(message)]
+        pub fn get_consumer_profiles(&self, consumer: H160) -> Vec<u64> {
+            self.consumer_profiles.get(consumer).unwrap_or_default()
+        }
+
+        /// Get the registry this contract matches against
+        #[ink(message)]
+        pub fn get_registry(&self) -> H160 {
+            self.registry
+        }
+    }
+
+    /// Static checks on the listing itself; the registry only lists active services
+    fn matches_profile(profile: &RequirementProfile, service: &ServiceSummary) -> bool {
+        service.category == profile.category
+            && service.price <= profile.max_price
+            && (!profile.requires_x402 || service.payment_asset.is_x402())
+    }
+
+    /// Sort candidates best first: higher reputation, then higher success
+    /// rate, then lower price. Remaining ties go to the older listing.
+    fn rank(candidates: &mut [Candidate]) {
+        candidates.sort_unstable_by(|a, b| {
+            b.reputation
+                .cmp(&a.reputation)
+                .then(b.success_rate.cmp(&a.success_rate))
+                .then(a.price.cmp(&b.price))
+                .then(a.service_id.cmp(&b.service_id))
+        });
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn candidate(
+            service_id: u64,
+            price: Balance,
+            reputation: u32,
+            success_rate: u32,
+        ) -> Candidate {
+            Candidate {
+                service_id,
+                provider: H160::from([service_id as u8; 20]),
+                price,
+                reputation,
+                success_rate,
+            }
+        }
+
+        #[ink::test]
+        fn post_profile_works() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Matchmaking::new(accounts.django);
+
+            let profile_id = contract
+                .post_profile(ServiceCategory::Translation, 1000, 50, 9000, false)
+                .unwrap();
+
+            let profile = contract.get_profile(profile_id).unwrap();
+            assert_eq!(profile.consumer, accounts.alice);
+            assert!(profile.is_open);
+            assert_eq!(
+                contract.get_consumer_profiles(accounts.alice),
+                vec![profile_id]
+            );
+        }
+
+        #[ink::test]
+        fn invalid_profile_fails() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Matchmaking::new(accounts.django);
+
+            assert_eq!(
+                contract.post_profile(ServiceCategory::Translation, 0, 0, 0, false),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                contract.post_profile(ServiceCategory::Translation, 10, 0, 10_001, false),
+                Err(Error::InvalidInput)
+            );
+        }
+
+        #[ink::test]
+        fn only_consumer_can_close_profile() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = Matchmaking::new(accounts.django);
+            let profile_id = contract
+                .post_profile(ServiceCategory::Computation, 500, 0, 0, true)
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.close_profile(profile_id), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller(accounts.alice);
+            assert!(contract.close_profile(profile_id).is_ok());
+            assert_eq!(
+                contract.find_matches(profile_id, 10),
+                Err(Error::ProfileClosed)
+            );
+        }
+
+        #[ink::test]
+        fn rank_orders_by_reputation_then_success_then_price() {
+            let mut candidates = vec![
+                candidate(1, 300, 80, 9000),
+                candidate(2, 100, 90, 5000),
+                candidate(3, 200, 80, 9500),
+                candidate(4, 100, 80, 9500),
+            ];
+
+            rank(&mut candidates);
+
+            let ids: Vec<u64> = candidates.iter().map(|c| c.service_id).collect();
+            assert_eq!(ids, vec![2, 4, 3, 1]);
+        }
+    }
+}
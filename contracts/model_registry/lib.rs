@@ -0,0 +1,343 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::model_registry::{Error, Model, ModelRegistryRef};
+
+#[ink::contract]
+mod model_registry {
+    use hub_types::HubError;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+
+    /// Upper bound on ancestors returned by `get_model_lineage`
+    const MAX_LINEAGE_DEPTH: usize = 32;
+
+    /// Model structure
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Model {
+        pub id: u64,
+        pub owner: H160,
+        pub name: String,
+        pub version: String,
+        pub weights_hash: H256,
+        // e.g. "transformer/decoder-only/7B"
+        pub architecture: String,
+        pub license_hash: H256,
+        // The model this one was fine-tuned from or supersedes
+        pub parent_id: Option<u64>,
+        pub is_deprecated: bool,
+        pub created_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a new model is registered
+    #[ink(event)]
+    pub struct ModelRegistered {
+        #[ink(topic)]
+        model_id: u64,
+        #[ink(topic)]
+        owner: H160,
+        weights_hash: H256,
+        parent_id: Option<u64>,
+    }
+    /// Emitted when a model is deprecated
+    #[ink(event)]
+    pub struct ModelDeprecated {
+        #[ink(topic)]
+        model_id: u64,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the model is not found
+        ModelNotFound,
+        /// Emitted when the caller does not own the model
+        Unauthorized,
+        /// Emitted when the same weights are registered twice
+        DuplicateWeights,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::ModelNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::DuplicateWeights => HubError::AlreadyExists,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct ModelRegistry {
+        models: Mapping<u64, Model>,
+        model_count: u64,
+        owner_models: Mapping<H160, Vec<u64>>,
+        // parent id -> direct descendants
+        model_versions: Mapping<u64, Vec<u64>>,
+        weights_index: Mapping<H256, u64>,
+    }
+
+    impl ModelRegistry {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                models: Mapping::default(),
+                model_count: 0,
+                owner_models: Mapping::default(),
+                model_versions: Mapping::default(),
+                weights_index: Mapping::default(),
+            }
+        }
+
+        /// Register a new model, optionally as a version of an existing one
+        #[ink(message)]
+        pub fn register_model(
+            &mut self,
+            name: String,
+            version: String,
+            weights_hash: H256,
+            architecture: String,
+            license_hash: H256,
+            parent_id: Option<u64>,
+        ) -> Result<u64> {
+            let caller = self.env().caller();
+            if name.is_empty() || version.is_empty() || weights_hash == H256::zero() {
+                return Err(Error::InvalidInput);
+            }
+            if self.weights_index.contains(weights_hash) {
+                return Err(Error::DuplicateWeights);
+            }
+            if let Some(parent_id) = parent_id {
+                if !self.models.contains(parent_id) {
+                    return Err(Error::ModelNotFound);
+                }
+            }
+
+            self.model_count = self.model_count.checked_add(1).ok_or(Error::Overflow)?;
+            let model_id = self.model_count;
+
+            let model = Model {
+                id: model_id,
+                owner: caller,
+                name,
+                version,
+                weights_hash,
+                architecture,
+                license_hash,
+                parent_id,
+                is_deprecated: false,
+                created_at: self.env().block_timestamp(),
+            };
+            self.models.insert(model_id, &model);
+            self.weights_index.insert(weights_hash, &model_id);
+
+            let mut owner_models = self.owner_models.get(caller).unwrap_or_default();
+            owner_models.push(model_id);
+            self.owner_models.insert(caller, &owner_models);
+
+            if let Some(parent_id) = parent_id {
+                let mut versions = self.model_versions.get(parent_id).unwrap_or_default();
+                versions.push(model_id);
+                self.model_versions.insert(parent_id, &versions);
+            }
+
+            self.env().emit_event(ModelRegistered {
+                model_id,
+                owner: caller,
+                weights_hash,
+                parent_id,
+            });
+
+            Ok(model_id)
+        }
+
+        /// Mark a model as deprecated; it stays resolvable for provenance
+        #[ink(message)]
+        pub fn deprecate_model(&mut self, model_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut model = self.models.get(model_id).ok_or(Error::ModelNotFound)?;
+
+            if model.owner != caller {
+                return Err(Error::Unauthorized);
+            }
+
+            model.is_deprecated = true;
+            self.models.insert(model_id, &model);
+
+            self.env().emit_event(ModelDeprecated { model_id });
+
+            Ok(())
+        }
+
+        /// Get model details
+        #[ink(message)]
+        pub fn get_model(&self, model_id: u64) -> Result<Model> {
+            self.models.get(model_id).ok_or(Error::ModelNotFound)
+        }
+
+        /// Find the model registered with the given weights
+        #[ink(message)]
+        pub fn get_model_by_weights(&self, weights_hash: H256) -> Option<u64> {
+            self.weights_index.get(weights_hash)
+        }
+
+        /// Ancestors of a model, nearest first
+        #[ink(message)]
+        pub fn get_model_lineage(&self, model_id: u64) -> Result<Vec<u64>> {
+            let mut model = self.models.get(model_id).ok_or(Error::ModelNotFound)?;
+            let mut lineage = Vec::new();
+
+            while let Some(parent_id) = model.parent_id {
+                if lineage.len() == MAX_LINEAGE_DEPTH {
+                    break;
+                }
+                lineage.push(parent_id);
+                model = self.models.get(parent_id).ok_or(Error::ModelNotFound)?;
+            }
+
+            Ok(lineage)
+        }
+
+        /// Direct descendants (new versions, fine-tunes) of a model
+        #[ink(message)]
+        pub fn get_model_versions(&self, model_id: u64) -> Vec<u64> {
+            self.model_versions.get(model_id).unwrap_or_default()
+        }
+
+        /// Get all models registered by an owner
+        #[ink(message)]
+        pub fn get_owner_models(&self, owner: H160) -> Vec<u64> {
+            self.owner_models.get(owner).unwrap_or_default()
+        }
+
+        /// Get total model count
+        #[ink(message)]
+        pub fn get_model_count(&self) -> u64 {
+            self.model_count
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn register(registry: &mut ModelRegistry, seed: u8, parent_id: Option<u64>) -> Result<u64> {
+            registry.register_model(
+                String::from("hub-llm"),
+                String::from("1.0"),
+                H256::from([seed; 32]),
+                String::from("transformer/decoder-only/7B"),
+                H256::from([0xAA; 32]),
+                parent_id,
+            )
+        }
+
+        #[ink::test]
+        fn register_model_indexes_weights_and_versions() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut registry = ModelRegistry::new();
+
+            let base = register(&mut registry, 1, None).unwrap();
+            let tuned = register(&mut registry, 2, Some(base)).unwrap();
+
+            let model = registry.get_model(tuned).unwrap();
+            assert_eq!(model.owner, accounts.alice);
+            assert_eq!(model.parent_id, Some(base));
+            assert!(!model.is_deprecated);
+            assert_eq!(
+                registry.get_model_by_weights(H256::from([2; 32])),
+                Some(tuned)
+            );
+            assert_eq!(registry.get_model_by_weights(H256::from([3; 32])), None);
+            assert_eq!(registry.get_model_versions(base), vec![tuned]);
+            assert_eq!(registry.get_owner_models(accounts.alice), vec![base, tuned]);
+            assert_eq!(registry.get_model_count(), 2);
+        }
+
+        #[ink::test]
+        fn register_model_rejects_invalid_and_duplicate_models() {
+            ink::env::test::set_caller(ink::env::test::default_accounts().alice);
+            let mut registry = ModelRegistry::new();
+            let base = register(&mut registry, 1, None).unwrap();
+
+            assert_eq!(register(&mut registry, 0, None), Err(Error::InvalidInput));
+            assert_eq!(
+                registry.register_model(
+                    String::new(),
+                    String::from("1.0"),
+                    H256::from([2; 32]),
+                    String::new(),
+                    H256::zero(),
+                    None,
+                ),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                register(&mut registry, 1, None),
+                Err(Error::DuplicateWeights)
+            );
+            assert_eq!(
+                register(&mut registry, 2, Some(base + 1)),
+                Err(Error::ModelNotFound)
+            );
+            assert_eq!(registry.get_model(base + 1), Err(Error::ModelNotFound));
+            assert_eq!(registry.get_model_count(), 1);
+        }
+
+        #[ink::test]
+        fn only_the_owner_deprecates_a_model() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut registry = ModelRegistry::new();
+            let model_id = register(&mut registry, 1, None).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(registry.deprecate_model(model_id), Err(Error::Unauthorized));
+            assert_eq!(
+                registry.deprecate_model(model_id + 1),
+                Err(Error::ModelNotFound)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            registry.deprecate_model(model_id).unwrap();
+            let model = registry.get_model(model_id).unwrap();
+            assert!(model.is_deprecated);
+            // Deprecated models stay resolvable for provenance
+            assert_eq!(
+                registry.get_model_by_weights(model.weights_hash),
+                Some(model_id)
+            );
+        }
+
+        #[ink::test]
+        fn lineage_lists_ancestors_nearest_first() {
+            ink::env::test::set_caller(ink::env::test::default_accounts().alice);
+            let mut registry = ModelRegistry::new();
+            let base = register(&mut registry, 1, None).unwrap();
+            let tuned = register(&mut registry, 2, Some(base)).unwrap();
+            let distilled = register(&mut registry, 3, Some(tuned)).unwrap();
+
+            assert_eq!(registry.get_model_lineage(distilled), Ok(vec![tuned, base]));
+            assert_eq!(registry.get_model_lineage(base), Ok(vec![]));
+            assert_eq!(
+                registry.get_model_lineage(distilled + 1),
+                Err(Error::ModelNotFound)
+            );
+        }
+    }
+}
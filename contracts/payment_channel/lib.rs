@@ -0,0 +1,436 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod payment_channel {
+    use hub_types::typed_data::{self, Domain};
+    use hub_types::HubError;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// Layout of a signed balance proof
+    const BALANCE_PROOF_TYPE: &str = "BalanceProof(uint64 channelId,uint128 amount)";
+
+    /// Different statuses of a payment channel
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ChannelStatus {
+        Open,
+        // The payer asked to close; the payee may still submit a balance proof
+        Closing,
+        Closed,
+    }
+
+    /// Unidirectional channel from a consumer (payer) to a provider (payee)
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Channel {
+        pub id: u64,
+        pub payer: H160,
+        pub payee: H160,
+        pub deposit: Balance,
+        // Amount paid out to the payee when the channel closed
+        pub paid_out: Balance,
+        pub status: ChannelStatus,
+        pub opened_at: u64,
+        pub challenge_ends_at: Option<u64>,
+    }
+
+    /// Events
+    /// Emitted when a channel is opened
+    #[ink(event)]
+    pub struct ChannelOpened {
+        #[ink(topic)]
+        channel_id: u64,
+        #[ink(topic)]
+        payer: H160,
+        #[ink(topic)]
+        payee: H160,
+        deposit: Balance,
+    }
+    /// Emitted when the payer adds funds to a channel
+    #[ink(event)]
+    pub struct ChannelToppedUp {
+        #[ink(topic)]
+        channel_id: u64,
+        amount: Balance,
+        deposit: Balance,
+    }
+    /// Emitted when the payer starts a unilateral close
+    #[ink(event)]
+    pub struct ChannelClosing {
+        #[ink(topic)]
+        channel_id: u64,
+        challenge_ends_at: u64,
+    }
+    /// Emitted when the channel is closed and funds are paid out
+    #[ink(event)]
+    pub struct ChannelClosed {
+        #[ink(topic)]
+        channel_id: u64,
+        payee_amount: Balance,
+        payer_refund: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when the channel is not found
+        ChannelNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the amount is invalid
+        InvalidAmount,
+        /// Emitted when the status is invalid
+        InvalidStatus,
+        /// Emitted when the balance proof is not signed by the payer
+        InvalidSignature,
+        /// Emitted when the challenge period has not ended yet
+        ChallengePeriodActive,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::ChannelNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::InvalidSignature => HubError::InvalidProof,
+                Error::ChallengePeriodActive => HubError::TooEarly,
+                Error::Overflow => HubError::Overflow,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct PaymentChannel {
+        channels: Mapping<u64, Channel>,
+        channel_count: u64,
+        user_channels: Mapping<H160, Vec<u64>>,
+        // Time the payee has to answer a unilateral close, in milliseconds
+        challenge_period: u64,
+    }
+
+    impl PaymentChannel {
+        #[ink(constructor)]
+        pub fn new(challenge_period: u64) -> Self {
+            Self {
+                channels: Mapping::default(),
+                channel_count: 0,
+                user_channels: Mapping::default(),
+                challenge_period,
+            }
+        }
+
+        /// Open a channel to `payee` funded with the transferred value
+        #[ink(message, payable)]
+        pub fn open_channel(&mut self, payee: H160) -> Result<u64> {
+            let payer = self.env().caller();
+            let deposit = self.transferred_balance();
+            if deposit == 0 || payee == payer {
+                return Err(Error::InvalidAmount);
+            }
+
+            self.channel_count = self.channel_count.checked_add(1).ok_or(Error::Overflow)?;
+            let channel_id = self.channel_count;
+
+            let channel = Channel {
+                id: channel_id,
+                payer,
+                payee,
+                deposit,
+                paid_out: 0,
+                status: ChannelStatus::Open,
+                opened_at: self.env().block_timestamp(),
+                challenge_ends_at: None,
+            };
+            self.channels.insert(channel_id, &channel);
+
+            for user in [payer, payee] {
+                let mut channels = self.user_channels.get(user).unwrap_or_default();
+                channels.push(channel_id);
+                self.user_channels.insert(user, &channels);
+            }
+
+            self.env().emit_event(ChannelOpened {
+                channel_id,
+                payer,
+                payee,
+                deposit,
+            });
+
+            Ok(channel_id)
+        }
+
+        /// Add the transferred value to an open channel
+        #[ink(message, payable)]
+        pub fn top_up(&mut self, channel_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let amount = self.transferred_balance();
+            let mut channel = self
+                .channels
+                .get(channel_id)
+                .ok_or(Error::ChannelNotFound)?;
+
+            if channel.payer != caller {
+                return Err(Error::Unauthorized);
+            }
+            if channel.status != ChannelStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            channel.deposit = channel.deposit.checked_add(amount).ok_or(Error::Overflow)?;
+            self.channels.insert(channel_id, &channel);
+
+            self.env().emit_event(ChannelToppedUp {
+                channel_id,
+                amount,
+                deposit: channel.deposit,
+            });
+
+            Ok(())
+        }
+
+        /// Close the channel with the latest balance proof signed by the payer
+        ///
+        /// `amount` is the cumulative total owed to the payee. The payee can
+        /// call this at any time while the channel is open, and must call it
+        /// during the challenge period after the payer requests a close.
+        #[ink(message)]
+        pub fn close_channel(
+            &mut self,
+            channel_id: u64,
+            amount: Balance,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            let channel = self
+                .channels
+                .get(channel_id)
+                .ok_or(Error::ChannelNotFound)?;
+
+            if channel.payee != caller {
+                return Err(Error::Unauthorized);
+            }
+            if channel.status == ChannelStatus::Closed {
+                return Err(Error::InvalidStatus);
+            }
+            if amount > channel.deposit {
+                return Err(Error::InvalidAmount);
+            }
+
+            let message_hash = self.balance_proof_hash(channel_id, amount);
+            if typed_data::recover(&message_hash, &signature) != Some(channel.payer) {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.payout(channel, amount)
+        }
+
+        /// Start a unilateral close as the payer
+        #[ink(message)]
+        pub fn request_close(&mut self, channel_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut channel = self
+                .channels
+                .get(channel_id)
+                .ok_or(Error::ChannelNotFound)?;
+
+            if channel.payer != caller {
+                return Err(Error::Unauthorized);
+            }
+            if channel.status != ChannelStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+
+            let challenge_ends_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.challenge_period);
+            channel.status = ChannelStatus::Closing;
+            channel.challenge_ends_at = Some(challenge_ends_at);
+            self.channels.insert(channel_id, &channel);
+
+            self.env().emit_event(ChannelClosing {
+                channel_id,
+                challenge_ends_at,
+            });
+
+            Ok(())
+        }
+
+        /// Refund the payer once the challenge period passed unanswered
+        #[ink(message)]
+        pub fn settle(&mut self, channel_id: u64) -> Result<()> {
+            let channel = self
+                .channels
+                .get(channel_id)
+                .ok_or(Error::ChannelNotFound)?;
+
+            if channel.status != ChannelStatus::Closing {
+                return Err(Error::InvalidStatus);
+            }
+            let challenge_ends_at = channel.challenge_ends_at.unwrap_or_default();
+            if self.env().block_timestamp() <= challenge_ends_at {
+                return Err(Error::ChallengePeriodActive);
+            }
+
+            self.payout(channel, 0)
+        }
+
+        /// Get channel details
+        #[ink(message)]
+        pub fn get_channel(&self, channel_id: u64) -> Result<Channel> {
+            self.channels.get(channel_id).ok_or(Error::ChannelNotFound)
+        }
+
+        /// Get all channels a user is party to
+        #[ink(message)]
+        pub fn get_user_channels(&self, user: H160) -> Vec<u64> {
+            self.user_channels.get(user).unwrap_or_default()
+        }
+
+        /// Get the hash a payer signs to authorize a cumulative `amount`
+        #[ink(message)]
+        pub fn get_balance_proof_hash(&self, channel_id: u64, amount: Balance) -> [u8; 32] {
+            self.balance_proof_hash(channel_id, amount)
+        }
+
+        /// Get the challenge period in milliseconds
+        #[ink(message)]
+        pub fn get_challenge_period(&self) -> u64 {
+            self.challenge_period
+        }
+
+        fn transferred_balance(&self) -> Balance {
+            self.env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default()
+        }
+
+        /// Binds the proof to this contract and channel so it can't be replayed
+        fn balance_proof_hash(&self, channel_id: u64, amount: Balance) -> [u8; 32] {
+            Domain::new("PaymentChannel", "1", self.env().address())
+                .digest(BALANCE_PROOF_TYPE, &(channel_id, amount))
+        }
+
+        /// Pays `payee_amount` to the payee, refunds the rest and closes the channel
+        fn payout(&mut self, mut channel: Channel, payee_amount: Balance) -> Result<()> {
+            let payer_refund = channel.deposit.saturating_sub(payee_amount);
+
+            if payee_amount > 0
+                && self
+                    .env()
+                    .transfer(channel.payee, payee_amount.into())
+                    .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+            if payer_refund > 0
+                && self
+                    .env()
+                    .transfer(channel.payer, payer_refund.into())
+                    .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            channel.paid_out = payee_amount;
+            channel.status = ChannelStatus::Closed;
+            self.channels.insert(channel.id, &channel);
+
+            self.env().emit_event(ChannelClosed {
+                channel_id: channel.id,
+                payee_amount,
+                payer_refund,
+            });
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::U256;
+
+        fn open(contract: &mut PaymentChannel, payee: H160, deposit: u128) -> u64 {
+            ink::env::test::set_value_transferred(U256::from(deposit));
+            contract.open_channel(payee).unwrap()
+        }
+
+        #[ink::test]
+        fn open_channel_works() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = PaymentChannel::new(1_000);
+
+            let channel_id = open(&mut contract, accounts.bob, 500);
+
+            let channel = contract.get_channel(channel_id).unwrap();
+            assert_eq!(channel.payer, accounts.alice);
+            assert_eq!(channel.deposit, 500);
+            assert_eq!(channel.status, ChannelStatus::Open);
+            assert_eq!(contract.get_user_channels(accounts.bob), vec![channel_id]);
+        }
+
+        #[ink::test]
+        fn empty_channel_fails() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = PaymentChannel::new(1_000);
+
+            ink::env::test::set_value_transferred(U256::zero());
+            assert_eq!(
+                contract.open_channel(accounts.bob),
+                Err(Error::InvalidAmount)
+            );
+        }
+
+        #[ink::test]
+        fn settle_waits_for_challenge_period() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = PaymentChannel::new(1_000);
+            let channel_id = open(&mut contract, accounts.bob, 500);
+
+            assert_eq!(contract.settle(channel_id), Err(Error::InvalidStatus));
+            contract.request_close(channel_id).unwrap();
+            assert_eq!(
+                contract.settle(channel_id),
+                Err(Error::ChallengePeriodActive)
+            );
+        }
+
+        #[ink::test]
+        fn only_payee_can_close_with_proof() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = PaymentChannel::new(1_000);
+            let channel_id = open(&mut contract, accounts.bob, 500);
+
+            assert_eq!(
+                contract.close_channel(channel_id, 100, [0; 65]),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.close_channel(channel_id, 600, [0; 65]),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(contract.request_close(channel_id), Err(Error::Unauthorized));
+        }
+    }
+}
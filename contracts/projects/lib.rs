@@ -0,0 +1,676 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod projects {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use payment_escrow::{EscrowStatus, PaymentEscrowRef};
+
+    /// Upper bound on escrows per project so bulk operations stay bounded
+    const MAX_JOBS: usize = 100;
+
+    /// A group of related escrows sharing one budget
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Project {
+        pub id: u64,
+        pub owner: H160,
+        pub name: String,
+        pub budget_cap: Balance,
+        // Funds locked in escrows that haven't been refunded
+        pub committed: Balance,
+        pub escrow_ids: Vec<u64>,
+        pub created_at: u64,
+        pub closed: bool,
+    }
+
+    /// Aggregate status of the escrows in a project
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ProjectSummary {
+        pub pending_count: u32,
+        pub completed_count: u32,
+        pub refunded_count: u32,
+        pub rejected_count: u32,
+        pub disputed_count: u32,
+        pub pending_amount: Balance,
+        pub released_amount: Balance,
+        pub budget_remaining: Balance,
+    }
+
+    /// Events
+    /// Emitted when a project is created
+    #[ink(event)]
+    pub struct ProjectCreated {
+        #[ink(topic)]
+        project_id: u64,
+        #[ink(topic)]
+        owner: H160,
+        budget_cap: Balance,
+    }
+    /// Emitted when an escrow is opened under a project
+    #[ink(event)]
+    pub struct JobAdded {
+        #[ink(topic)]
+        project_id: u64,
+        #[ink(topic)]
+        escrow_id: u64,
+        payee: H160,
+        amount: Balance,
+    }
+    /// Emitted when a bulk release or refund finishes
+    #[ink(event)]
+    pub struct BulkSettled {
+        #[ink(topic)]
+        project_id: u64,
+        released: bool,
+        count: u32,
+        amount: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the transferred amount is invalid
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the project is not found
+        ProjectNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the project is closed
+        ProjectClosed,
+        /// Emitted when the escrow would exceed the project budget
+        BudgetExceeded,
+        /// Emitted when the project already holds the maximum number of jobs
+        TooManyJobs,
+        /// Emitted when the escrow does not belong to the project
+        JobNotInProject,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::ProjectNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::ProjectClosed => HubError::Inactive,
+                Error::BudgetExceeded => HubError::LimitExceeded,
+                Error::TooManyJobs => HubError::LimitExceeded,
+                Error::JobNotInProject => HubError::InvalidStatus,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Projects {
+        // This contract is the payer of every project escrow
+        escrow: H160,
+        projects: Mapping<u64, Project>,
+        project_count: u64,
+        owner_projects: Mapping<H160, Vec<u64>>,
+        escrow_projects: Mapping<u64, u64>,
+    }
+
+    impl Projects {
+        #[ink(constructor)]
+        pub fn new(escrow: H160) -> Self {
+            Self {
+                escrow,
+                projects: Mapping::default(),
+                project_count: 0,
+                owner_projects: Mapping::default(),
+                escrow_projects: Mapping::default(),
+            }
+        }
+
+        /// Create a project with a cap on the total locked in its escrows
+        #[ink(message)]
+        pub fn create_project(&mut self, name: String, budget_cap: Balance) -> Result<u64> {
+            let owner = self.env().caller();
+            if name.is_empty() || budget_cap == 0 {
+                return Err(Error::InvalidInput);
+            }
+
+            self.project_count = self.project_count.checked_add(1).ok_or(Error::Overflow)?;
+            let project_id = self.project_count;
+
+            let project = Project {
+                id: project_id,
+                owner,
+                name,
+                budget_cap,
+                committed: 0,
+                escrow_ids: Vec::new(),
+                created_at: self.env().block_timestamp(),
+                closed: false,
+            };
+            self.projects.insert(project_id, &project);
+
+            let mut owned = self.owner_projects.get(owner).unwrap_or_default();
+            owned.push(project_id);
+            self.owner_projects.insert(owner, &owned);
+
+            self.env().emit_event(ProjectCreated {
+                project_id,
+                owner,
+                budget_cap,
+            });
+
+            Ok(project_id)
+        }
+
+        /// Open an escrow under a project, funded with the transferred value
+        #[ink(message, payable)]
+        pub fn add_job(
+            &mut self,
+            project_id: u64,
+            payee: H160,
+            service_id: u64,
+            payment_code: String,
+        ) -> Result<u64> {
+            let mut project = self.owned_project(project_id)?;
+            if project.closed {
+                return Err(Error::ProjectClosed);
+            }
+            if project.escrow_ids.len() >= MAX_JOBS {
+                return Err(Error::TooManyJobs);
+            }
+            let amount: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let committed = project
+                .committed
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            if committed > project.budget_cap {
+                return Err(Error::BudgetExceeded);
+            }
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let escrow_id = escrow
+                .call_mut()
+                .create_escrow(payee, service_id, payment_code, PaymentAsset::Native)
+                .transferred_value(amount.into())
+                .invoke()
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            project.committed = committed;
+            project.escrow_ids.push(escrow_id);
+            self.projects.insert(project_id, &project);
+            self.escrow_projects.insert(escrow_id, &project_id);
+
+            self.env().emit_event(JobAdded {
+                project_id,
+                escrow_id,
+                payee,
+                amount,
+            });
+
+            Ok(escrow_id)
+        }
+
+        /// Release several pending project escrows to their payees
+        ///
+        /// An empty list releases every pending escrow in the project.
+        /// Escrows that are no longer pending are skipped.
+        #[ink(message)]
+        pub fn release_jobs(&mut self, project_id: u64, escrow_ids: Vec<u64>) -> Result<u32> {
+            let project = self.owned_project(project_id)?;
+            let targets = self.pending_targets(&project, escrow_ids)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let mut count: u32 = 0;
+            let mut amount: Balance = 0;
+            for (escrow_id, escrow_amount) in targets {
+                escrow
+                    .release_payment(escrow_id)
+                    .map_err(|_| Error::EscrowCallFailed)?;
+                count = count.saturating_add(1);
+                amount = amount.saturating_add(escrow_amount);
+            }
+
+            self.env().emit_event(BulkSettled {
+                project_id,
+                released: true,
+                count,
+                amount,
+            });
+
+            Ok(count)
+        }
+
+        /// Refund several pending project escrows back to the project owner
+        ///
+        /// An empty list refunds every pending escrow in the project. The
+        /// refunded amounts free up budget for new jobs.
+        #[ink(message)]
+        pub fn refund_jobs(&mut self, project_id: u64, escrow_ids: Vec<u64>) -> Result<u32> {
+            let mut project = self.owned_project(project_id)?;
+            let targets = self.pending_targets(&project, escrow_ids)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let mut count: u32 = 0;
+            let mut amount: Balance = 0;
+            for (escrow_id, escrow_amount) in targets {
+                escrow
+                    .refund(escrow_id)
+                    .map_err(|_| Error::EscrowCallFailed)?;
+                count = count.saturating_add(1);
+                amount = amount.checked_add(escrow_amount).ok_or(Error::Overflow)?;
+            }
+
+            if amount > 0 && self.env().transfer(project.owner, amount.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            project.committed = project.committed.saturating_sub(amount);
+            self.projects.insert(project_id, &project);
+
+            self.env().emit_event(BulkSettled {
+                project_id,
+                released: false,
+                count,
+                amount,
+            });
+
+            Ok(count)
+        }
+
+        /// Stop new jobs from being added to a project
+        #[ink(message)]
+        pub fn close_project(&mut self, project_id: u64) -> Result<()> {
+            let mut project = self.owned_project(project_id)?;
+            project.closed = true;
+            self.projects.insert(project_id, &project);
+            Ok(())
+        }
+
+        /// Raise or lower a project's budget cap
+        ///
+        /// The cap can't go below what is already committed.
+        #[ink(message)]
+        pub fn set_budget_cap(&mut self, project_id: u64, budget_cap: Balance) -> Result<()> {
+            let mut project = self.owned_project(project_id)?;
+            if budget_cap < project.committed {
+                return Err(Error::InvalidInput);
+            }
+            project.budget_cap = budget_cap;
+            self.projects.insert(project_id, &project);
+            Ok(())
+        }
+
+        /// Aggregate status of every escrow in a project
+        #[ink(message)]
+        pub fn get_project_summary(&self, project_id: u64) -> Result<ProjectSummary> {
+            let project = self.get_project(project_id)?;
+            let escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+
+            let mut summary = ProjectSummary {
+                budget_remaining: project.budget_cap.saturating_sub(project.committed),
+                ..Default::default()
+            };
+            for escrow_id in project.escrow_ids {
+                let Ok(details) = escrow.get_escrow(escrow_id) else {
+                    continue;
+                };
+                match details.status {
+                    EscrowStatus::Pending => {
+                        summary.pending_count = summary.pending_count.saturating_add(1);
+                        summary.pending_amount =
+                            summary.pending_amount.saturating_add(details.amount);
+                    }
+                    EscrowStatus::Completed => {
+                        summary.completed_count = summary.completed_count.saturating_add(1);
+                        summary.released_amount =
+                            summary.released_amount.saturating_add(details.amount);
+                    }
+                    EscrowStatus::Refunded | EscrowStatus::Cancelled => {
+                        summary.refunded_count = summary.refunded_count.saturating_add(1);
+                    }
+                    EscrowStatus::Rejected => {
+                        summary.rejected_count = summary.rejected_count.saturating_add(1);
+                    }
+                    EscrowStatus::Disputed => {
+                        summary.disputed_count = summary.disputed_count.saturating_add(1);
+                    }
+                }
+            }
+
+            Ok(summary)
+        }
+
+        /// Get project details
+        #[ink(message)]
+        pub fn get_project(&self, project_id: u64) -> Result<Project> {
+            self.projects.get(project_id).ok_or(Error::ProjectNotFound)
+        }
+
+        /// Get the project an escrow was opened under, if any
+        #[ink(message)]
+        pub fn get_escrow_project(&self, escrow_id: u64) -> Option<u64> {
+            self.escrow_projects.get(escrow_id)
+        }
+
+        /// Get all projects owned by an account
+        #[ink(message)]
+        pub fn get_owner_projects(&self, owner: H160) -> Vec<u64> {
+            self.owner_projects.get(owner).unwrap_or_default()
+        }
+
+        /// Get total project count
+        #[ink(message)]
+        pub fn get_project_count(&self) -> u64 {
+            self.project_count
+        }
+
+        fn owned_project(&self, project_id: u64) -> Result<Project> {
+            let project = self.get_project(project_id)?;
+            if project.owner != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            Ok(project)
+        }
+
+        /// Resolves the escrows a bulk operation applies to, with their
+        /// amounts, keeping only those still pending
+        fn pending_targets(
+            &self,
+            project: &Project,
+            escrow_ids: Vec<u64>,
+        ) -> Result<Vec<(u64, Balance)>> {
+            let ids = if escrow_ids.is_empty() {
+                project.escrow_ids.clone()
+            } else {
+                if escrow_ids.len() > MAX_JOBS {
+                    return Err(Error::InvalidInput);
+                }
+                if escrow_ids
+                    .iter()
+                    .any(|id| self.escrow_projects.get(id) != Some(project.id))
+                {
+                    return Err(Error::JobNotInProject);
+                }
+                escrow_ids
+            };
+
+            let escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let mut targets = Vec::new();
+            for escrow_id in ids {
+                if let Ok(details) = escrow.get_escrow(escrow_id) {
+                    if details.status == EscrowStatus::Pending {
+                        targets.push((escrow_id, details.amount));
+                    }
+                }
+            }
+            Ok(targets)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::U256;
+
+        const CAP: Balance = 1_000;
+
+        /// Projects over an escrow the tests never reach, where alice owns one project
+        fn projects() -> (Projects, u64) {
+            ink::env::test::set_caller(ink::env::test::default_accounts().alice);
+            let mut projects = Projects::new(H160::from([0xE5; 20]));
+            let project_id = projects
+                .create_project(String::from("Launch"), CAP)
+                .unwrap();
+            (projects, project_id)
+        }
+
+        fn add_job(projects: &mut Projects, project_id: u64, value: Balance) -> Result<u64> {
+            let payee = ink::env::test::default_accounts().bob;
+            ink::env::test::set_value_transferred(U256::from(value));
+            let result = projects.add_job(project_id, payee, 1, String::from("design"));
+            ink::env::test::set_value_transferred(U256::zero());
+            result
+        }
+
+        #[ink::test]
+        fn projects_are_created_with_a_budget() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut projects, project_id) = projects();
+
+            let project = projects.get_project(project_id).unwrap();
+            assert_eq!(project.owner, accounts.alice);
+            assert_eq!(project.budget_cap, CAP);
+            assert_eq!(project.committed, 0);
+            assert_eq!(
+                projects.get_owner_projects(accounts.alice),
+                vec![project_id]
+            );
+            assert_eq!(
+                projects
+                    .get_project_summary(project_id)
+                    .unwrap()
+                    .budget_remaining,
+                CAP
+            );
+
+            assert_eq!(
+                projects.create_project(String::new(), CAP),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                projects.create_project(String::from("Free"), 0),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                projects.get_project(project_id + 1),
+                Err(Error::ProjectNotFound)
+            );
+            assert_eq!(projects.get_project_count(), 1);
+        }
+
+        #[ink::test]
+        fn jobs_are_funded_within_the_budget_by_the_owner() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut projects, project_id) = projects();
+
+            assert_eq!(
+                add_job(&mut projects, project_id, 0),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                add_job(&mut projects, project_id, CAP + 1),
+                Err(Error::BudgetExceeded)
+            );
+            assert_eq!(
+                add_job(&mut projects, project_id + 1, CAP),
+                Err(Error::ProjectNotFound)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                add_job(&mut projects, project_id, CAP),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                projects.set_budget_cap(project_id, 2 * CAP),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(projects.close_project(project_id), Err(Error::Unauthorized));
+            assert_eq!(
+                projects.release_jobs(project_id, Vec::new()),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                projects.refund_jobs(project_id, Vec::new()),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            projects.set_budget_cap(project_id, 2 * CAP).unwrap();
+            assert_eq!(
+                projects.get_project(project_id).unwrap().budget_cap,
+                2 * CAP
+            );
+            assert_eq!(
+                projects.release_jobs(project_id, vec![1]),
+                Err(Error::JobNotInProject)
+            );
+            assert_eq!(projects.release_jobs(project_id, Vec::new()), Ok(0));
+
+            projects.close_project(project_id).unwrap();
+            assert_eq!(
+                add_job(&mut projects, project_id, CAP),
+                Err(Error::ProjectClosed)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::PaymentEscrow;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const AMOUNT: Balance = 1_000_000_000_000;
+
+        #[ink_e2e::test]
+        async fn jobs_are_paid_out_or_cancelled_in_bulk<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+            let charlie = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Charlie);
+
+            // Jobs are escrows the project pays for, so cancelling one is a
+            // payer refund; a one millisecond timeout allows it a block later
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(1),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let contract = client
+                .instantiate(
+                    "projects",
+                    &ink_e2e::alice(),
+                    &mut ProjectsRef::new(escrow.addr),
+                )
+                .submit()
+                .await
+                .expect("projects instantiate failed");
+            let mut calls = contract.call_builder::<Projects>();
+
+            let project_id = client
+                .call(
+                    &ink_e2e::alice(),
+                    &calls.create_project(String::from("Launch"), 3 * AMOUNT),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let mut jobs = Vec::new();
+            for payee in [bob, charlie] {
+                let escrow_id = client
+                    .call(
+                        &ink_e2e::alice(),
+                        &calls.add_job(project_id, payee, 1, String::from("job")),
+                    )
+                    .value(AMOUNT)
+                    .submit()
+                    .await
+                    .expect("call failed")
+                    .return_value()
+                    .unwrap();
+                jobs.push(escrow_id);
+            }
+            let over_budget = client
+                .call(
+                    &ink_e2e::alice(),
+                    &calls.add_job(project_id, bob, 1, String::from("job")),
+                )
+                .value(2 * AMOUNT)
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(over_budget, Err(Error::BudgetExceeded));
+
+            let released = client
+                .call(
+                    &ink_e2e::alice(),
+                    &calls.release_jobs(project_id, vec![jobs[0]]),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            assert_eq!(released, 1);
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(jobs[0]))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.payee, bob);
+            assert_eq!(details.status, EscrowStatus::Completed);
+
+            // Cancelling the rest frees its share of the budget
+            let refunded = client
+                .call(
+                    &ink_e2e::alice(),
+                    &calls.refund_jobs(project_id, Vec::new()),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            assert_eq!(refunded, 1);
+            let summary = client
+                .call(&ink_e2e::alice(), &calls.get_project_summary(project_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(summary.completed_count, 1);
+            assert_eq!(summary.refunded_count, 1);
+            assert_eq!(summary.pending_count, 0);
+            assert_eq!(summary.released_amount, AMOUNT);
+            assert_eq!(summary.budget_remaining, 2 * AMOUNT);
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Interface for PSP22 fungible tokens
+//!
+//! The subset of the PSP22 standard hub contracts call: token escrows pull
+//! the payer's approved tokens with `transfer_from` and pay them out with
+//! `transfer`.
+
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+use ink::primitives::H160;
+use ink::U256;
+
+/// Errors defined by the PSP22 standard
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum PSP22Error {
+    /// Custom error type for implementation-specific errors
+    Custom(String),
+    /// Returned when the account doesn't hold enough tokens
+    InsufficientBalance,
+    /// Returned when the spender's allowance is too low
+    InsufficientAllowance,
+    /// Returned when the recipient is the zero address
+    ZeroRecipientAddress,
+    /// Returned when the sender is the zero address
+    ZeroSenderAddress,
+    /// Returned when a safe transfer check fails
+    SafeTransferCheckFailed(String),
+}
+
+#[ink::trait_definition]
+pub trait PSP22 {
+    /// Tokens held by `owner`
+    #[ink(message)]
+    fn balance_of(&self, owner: H160) -> U256;
+
+    /// Tokens `spender` may still transfer on behalf of `owner`
+    #[ink(message)]
+    fn allowance(&self, owner: H160, spender: H160) -> U256;
+
+    /// Transfer `value` tokens from the caller to `to`
+    #[ink(message)]
+    fn transfer(&mut self, to: H160, value: U256, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+    /// Transfer `value` tokens from `from` to `to` out of the caller's allowance
+    #[ink(message)]
+    fn transfer_from(
+        &mut self,
+        from: H160,
+        to: H160,
+        value: U256,
+        data: Vec<u8>,
+    ) -> Result<(), PSP22Error>;
+}
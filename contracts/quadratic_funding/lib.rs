@@ -0,0 +1,493 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod quadratic_funding {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::format;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use payment_escrow::PaymentEscrowRef;
+
+    /// Upper bound on projects per round so finalization stays bounded
+    const MAX_PROJECTS: usize = 50;
+
+    /// A matching round funded by the treasury
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Round {
+        pub id: u64,
+        pub matching_pool: Balance,
+        pub starts_at: u64,
+        pub ends_at: u64,
+        pub projects: Vec<u64>,
+        pub finalized: bool,
+    }
+
+    /// An agent project taking part in a round
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Project {
+        pub id: u64,
+        pub round_id: u64,
+        pub recipient: H160,
+        pub details_hash: H256,
+        pub contributions: Balance,
+        // Sum over contributors of the square root of their total
+        pub sqrt_sum: Balance,
+        pub contributor_count: u32,
+        pub matched: Balance,
+        pub escrow_id: Option<u64>,
+    }
+
+    /// Events
+    /// Emitted when the treasury opens a matching round
+    #[ink(event)]
+    pub struct RoundCreated {
+        #[ink(topic)]
+        round_id: u64,
+        matching_pool: Balance,
+        starts_at: u64,
+        ends_at: u64,
+    }
+    /// Emitted when a project joins a round
+    #[ink(event)]
+    pub struct ProjectRegistered {
+        #[ink(topic)]
+        round_id: u64,
+        #[ink(topic)]
+        project_id: u64,
+        recipient: H160,
+    }
+    /// Emitted when someone donates to a project
+    #[ink(event)]
+    pub struct ContributionMade {
+        #[ink(topic)]
+        project_id: u64,
+        #[ink(topic)]
+        contributor: H160,
+        amount: Balance,
+    }
+    /// Emitted when a project's donations and match are paid into escrow
+    #[ink(event)]
+    pub struct ProjectPaidOut {
+        #[ink(topic)]
+        round_id: u64,
+        #[ink(topic)]
+        project_id: u64,
+        escrow_id: u64,
+        contributions: Balance,
+        matched: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the amount is zero
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the round is not found
+        RoundNotFound,
+        /// Emitted when the project is not found
+        ProjectNotFound,
+        /// Emitted when the caller is not the treasury
+        Unauthorized,
+        /// Emitted when the round is not accepting projects or donations
+        RoundClosed,
+        /// Emitted when the round has not ended yet
+        RoundNotEnded,
+        /// Emitted when the round was already finalized
+        AlreadyFinalized,
+        /// Emitted when the round already has the maximum number of projects
+        TooManyProjects,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::RoundNotFound => HubError::NotFound,
+                Error::ProjectNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::RoundClosed => HubError::Expired,
+                Error::RoundNotEnded => HubError::TooEarly,
+                Error::AlreadyFinalized => HubError::AlreadyExists,
+                Error::TooManyProjects => HubError::LimitExceeded,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct QuadraticFunding {
+        treasury: H160,
+        escrow: H160,
+        rounds: Mapping<u64, Round>,
+        round_count: u64,
+        projects: Mapping<u64, Project>,
+        project_count: u64,
+        contributions: Mapping<(u64, H160), Balance>,
+    }
+
+    impl QuadraticFunding {
+        #[ink(constructor)]
+        pub fn new(escrow: H160) -> Self {
+            Self {
+                treasury: Self::env().caller(),
+                escrow,
+                rounds: Mapping::default(),
+                round_count: 0,
+                projects: Mapping::default(),
+                project_count: 0,
+                contributions: Mapping::default(),
+            }
+        }
+
+        /// Open a round; the transferred value is the matching pool
+        #[ink(message, payable)]
+        pub fn create_round(&mut self, starts_at: u64, ends_at: u64) -> Result<u64> {
+            self.ensure_treasury()?;
+            let matching_pool: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if matching_pool == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if ends_at <= starts_at || ends_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidInput);
+            }
+
+            self.round_count = self.round_count.checked_add(1).ok_or(Error::Overflow)?;
+            let round_id = self.round_count;
+
+            let round = Round {
+                id: round_id,
+                matching_pool,
+                starts_at,
+                ends_at,
+                projects: Vec::new(),
+                finalized: false,
+            };
+            self.rounds.insert(round_id, &round);
+
+            self.env().emit_event(RoundCreated {
+                round_id,
+                matching_pool,
+                starts_at,
+                ends_at,
+            });
+
+            Ok(round_id)
+        }
+
+        /// Register an agent project in a round before it ends
+        #[ink(message)]
+        pub fn register_project(&mut self, round_id: u64, details_hash: H256) -> Result<u64> {
+            let recipient = self.env().caller();
+            let mut round = self.get_round(round_id)?;
+            if round.finalized || self.env().block_timestamp() >= round.ends_at {
+                return Err(Error::RoundClosed);
+            }
+            if round.projects.len() >= MAX_PROJECTS {
+                return Err(Error::TooManyProjects);
+            }
+
+            self.project_count = self.project_count.checked_add(1).ok_or(Error::Overflow)?;
+            let project_id = self.project_count;
+
+            let project = Project {
+                id: project_id,
+                round_id,
+                recipient,
+                details_hash,
+                contributions: 0,
+                sqrt_sum: 0,
+                contributor_count: 0,
+                matched: 0,
+                escrow_id: None,
+            };
+            self.projects.insert(project_id, &project);
+
+            round.projects.push(project_id);
+            self.rounds.insert(round_id, &round);
+
+            self.env().emit_event(ProjectRegistered {
+                round_id,
+                project_id,
+                recipient,
+            });
+
+            Ok(project_id)
+        }
+
+        /// Donate the transferred value to a project while its round is open
+        #[ink(message, payable)]
+        pub fn contribute(&mut self, project_id: u64) -> Result<()> {
+            let contributor = self.env().caller();
+            let amount: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+
+            let mut project = self.get_project(project_id)?;
+            let round = self.get_round(project.round_id)?;
+            let now = self.env().block_timestamp();
+            if round.finalized || now < round.starts_at || now >= round.ends_at {
+                return Err(Error::RoundClosed);
+            }
+
+            // Replace the contributor's previous square root with the new one
+            let previous = self
+                .contributions
+                .get((project_id, contributor))
+                .unwrap_or(0);
+            let total = previous.checked_add(amount).ok_or(Error::Overflow)?;
+            if previous == 0 {
+                project.contributor_count = project
+                    .contributor_count
+                    .checked_add(1)
+                    .ok_or(Error::Overflow)?;
+            }
+            project.sqrt_sum = project
+                .sqrt_sum
+                .saturating_sub(isqrt(previous))
+                .checked_add(isqrt(total))
+                .ok_or(Error::Overflow)?;
+            project.contributions = project
+                .contributions
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+
+            self.contributions.insert((project_id, contributor), &total);
+            self.projects.insert(project_id, &project);
+
+            self.env().emit_event(ContributionMade {
+                project_id,
+                contributor,
+                amount,
+            });
+
+            Ok(())
+        }
+
+        /// Compute the quadratic match for every project and pay donations
+        /// plus match into an escrow for each recipient
+        ///
+        /// A project's score is `(Σ √cᵢ)² - Σ cᵢ`; the pool is shared in
+        /// proportion to scores. Anyone may finalize once the round ends.
+        #[ink(message)]
+        pub fn finalize_round(&mut self, round_id: u64) -> Result<()> {
+            let mut round = self.get_round(round_id)?;
+            if round.finalized {
+                return Err(Error::AlreadyFinalized);
+            }
+            if self.env().block_timestamp() < round.ends_at {
+                return Err(Error::RoundNotEnded);
+            }
+
+            let mut projects = Vec::with_capacity(round.projects.len());
+            for project_id in &round.projects {
+                projects.push(self.get_project(*project_id)?);
+            }
+            let scores: Vec<Balance> = projects
+                .iter()
+                .map(|project| qf_score(project.sqrt_sum, project.contributions))
+                .collect();
+            let matches = share_pool(round.matching_pool, &scores);
+
+            round.finalized = true;
+            self.rounds.insert(round_id, &round);
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            for (mut project, matched) in projects.into_iter().zip(matches) {
+                let payout = project.contributions.saturating_add(matched);
+                project.matched = matched;
+                if payout > 0 {
+                    let escrow_id = escrow
+                        .call_mut()
+                        .create_escrow(
+                            project.recipient,
+                            0,
+                            format!("qf:{}:{}", round_id, project.id),
+                            PaymentAsset::Native,
+                        )
+                        .transferred_value(payout.into())
+                        .invoke()
+                        .map_err(|_| Error::EscrowCallFailed)?;
+                    project.escrow_id = Some(escrow_id);
+
+                    self.env().emit_event(ProjectPaidOut {
+                        round_id,
+                        project_id: project.id,
+                        escrow_id,
+                        contributions: project.contributions,
+                        matched,
+                    });
+                }
+                self.projects.insert(project.id, &project);
+            }
+
+            Ok(())
+        }
+
+        /// Release a project's payout escrow to its recipient
+        #[ink(message)]
+        pub fn release_payout(&mut self, project_id: u64) -> Result<()> {
+            self.ensure_treasury()?;
+            let project = self.get_project(project_id)?;
+            let escrow_id = project.escrow_id.ok_or(Error::InvalidInput)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .release_payment(escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)
+        }
+
+        /// Current estimated match for a project if the round ended now
+        #[ink(message)]
+        pub fn estimate_match(&self, project_id: u64) -> Result<Balance> {
+            let project = self.get_project(project_id)?;
+            let round = self.get_round(project.round_id)?;
+            if round.finalized {
+                return Ok(project.matched);
+            }
+
+            let mut scores = Vec::with_capacity(round.projects.len());
+            let mut index = 0;
+            for (i, id) in round.projects.iter().enumerate() {
+                let other = self.get_project(*id)?;
+                if *id == project_id {
+                    index = i;
+                }
+                scores.push(qf_score(other.sqrt_sum, other.contributions));
+            }
+            Ok(share_pool(round.matching_pool, &scores)[index])
+        }
+
+        /// Get round details
+        #[ink(message)]
+        pub fn get_round(&self, round_id: u64) -> Result<Round> {
+            self.rounds.get(round_id).ok_or(Error::RoundNotFound)
+        }
+
+        /// Get project details
+        #[ink(message)]
+        pub fn get_project(&self, project_id: u64) -> Result<Project> {
+            self.projects.get(project_id).ok_or(Error::ProjectNotFound)
+        }
+
+        /// Total a contributor has donated to a project
+        #[ink(message)]
+        pub fn get_contribution(&self, project_id: u64, contributor: H160) -> Balance {
+            self.contributions
+                .get((project_id, contributor))
+                .unwrap_or(0)
+        }
+
+        /// Get total round count
+        #[ink(message)]
+        pub fn get_round_count(&self) -> u64 {
+            self.round_count
+        }
+
+        fn ensure_treasury(&self) -> Result<()> {
+            if self.env().caller() != self.treasury {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+    }
+
+    /// Integer square root, rounded down
+    fn isqrt(value: Balance) -> Balance {
+        if value < 2 {
+            return value;
+        }
+        let mut x = value;
+        let mut y = (x + 1) / 2;
+        while y < x {
+            x = y;
+            y = (x + value / x) / 2;
+        }
+        x
+    }
+
+    /// Quadratic funding score: square of the root sum minus direct donations
+    fn qf_score(sqrt_sum: Balance, contributions: Balance) -> Balance {
+        sqrt_sum
+            .saturating_mul(sqrt_sum)
+            .saturating_sub(contributions)
+    }
+
+    /// Split the pool in proportion to scores, rounding down
+    fn share_pool(pool: Balance, scores: &[Balance]) -> Vec<Balance> {
+        let total = scores
+            .iter()
+            .fold(0 as Balance, |acc, score| acc.saturating_add(*score));
+        if total == 0 {
+            return scores.iter().map(|_| 0).collect();
+        }
+        scores
+            .iter()
+            .map(|score| {
+                // Scale down first if the product would overflow
+                match pool.checked_mul(*score) {
+                    Some(product) => product / total,
+                    None => pool / total * score,
+                }
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn isqrt_rounds_down() {
+            assert_eq!(isqrt(0), 0);
+            assert_eq!(isqrt(1), 1);
+            assert_eq!(isqrt(15), 3);
+            assert_eq!(isqrt(16), 4);
+            assert_eq!(isqrt(1_000_000), 1_000);
+        }
+
+        #[test]
+        fn broad_support_beats_single_whale() {
+            // Ten donors of 100 each versus one donor of 1_000
+            let broad = qf_score(10 * isqrt(100), 1_000);
+            let whale = qf_score(isqrt(1_000), 1_000);
+            assert_eq!(broad, 9_000);
+            assert_eq!(whale, 0);
+
+            assert_eq!(share_pool(5_000, &[broad, whale]), vec![5_000, 0]);
+        }
+
+        #[test]
+        fn pool_is_shared_by_score() {
+            assert_eq!(share_pool(900, &[100, 200]), vec![300, 600]);
+            assert_eq!(share_pool(900, &[0, 0]), vec![0, 0]);
+        }
+    }
+}
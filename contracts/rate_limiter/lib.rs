@@ -0,0 +1,576 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod rate_limiter {
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use service_registry::ServiceRegistryRef;
+
+    /// Request budget a provider sold to a consumer
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Quota {
+        pub requests_per_epoch: u32,
+        // Epoch length in milliseconds
+        pub epoch_length: u64,
+        pub expires_at: Option<u64>,
+    }
+
+    /// Requests consumed in the current epoch
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Usage {
+        pub epoch: u64,
+        pub used: u32,
+    }
+
+    /// Events
+    /// Emitted when a provider grants or changes a consumer's quota
+    #[ink(event)]
+    pub struct QuotaGranted {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        consumer: H160,
+        requests_per_epoch: u32,
+        epoch_length: u64,
+        expires_at: Option<u64>,
+    }
+    /// Emitted when a quota is revoked
+    #[ink(event)]
+    pub struct QuotaRevoked {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        consumer: H160,
+    }
+    /// Emitted when a request is counted against a quota
+    #[ink(event)]
+    pub struct RequestMetered {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        consumer: H160,
+        epoch: u64,
+        used: u32,
+        success: bool,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the service is not found
+        ServiceNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the consumer has no quota for the service
+        NoQuota,
+        /// Emitted when the quota has expired
+        QuotaExpired,
+        /// Emitted when the consumer used up the current epoch's budget
+        RateLimited,
+        /// Emitted when the registry rejects the request report
+        RegistryCallFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::ServiceNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NoQuota => HubError::NotFound,
+                Error::QuotaExpired => HubError::Expired,
+                Error::RateLimited => HubError::LimitExceeded,
+                Error::RegistryCallFailed => HubError::CrossContractCallFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct RateLimiter {
+        registry: H160,
+        quotas: Mapping<(u64, H160), Quota>,
+        usage: Mapping<(u64, H160), Usage>,
+        // Accounts besides the provider allowed to report requests
+        reporters: Mapping<(u64, H160), ()>,
+    }
+
+    impl RateLimiter {
+        #[ink(constructor)]
+        pub fn new(registry: H160) -> Self {
+            Self {
+                registry,
+                quotas: Mapping::default(),
+                usage: Mapping::default(),
+                reporters: Mapping::default(),
+            }
+        }
+
+        /// Grant a consumer a request budget on one of the caller's services
+        #[ink(message)]
+        pub fn grant_quota(
+            &mut self,
+            service_id: u64,
+            consumer: H160,
+            requests_per_epoch: u32,
+            epoch_length: u64,
+            expires_at: Option<u64>,
+        ) -> Result<()> {
+            self.ensure_provider(service_id)?;
+            if requests_per_epoch == 0 || epoch_length == 0 {
+                return Err(Error::InvalidInput);
+            }
+
+            let quota = Quota {
+                requests_per_epoch,
+                epoch_length,
+                expires_at,
+            };
+            self.quotas.insert((service_id, consumer), &quota);
+
+            self.env().emit_event(QuotaGranted {
+                service_id,
+                consumer,
+                requests_per_epoch,
+                epoch_length,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Revoke a consumer's quota
+        #[ink(message)]
+        pub fn revoke_quota(&mut self, service_id: u64, consumer: H160) -> Result<()> {
+            self.ensure_provider(service_id)?;
+
+            self.quotas.remove((service_id, consumer));
+            self.usage.remove((service_id, consumer));
+
+            self.env().emit_event(QuotaRevoked {
+                service_id,
+                consumer,
+            });
+
+            Ok(())
+        }
+
+        /// Allow or disallow an account to report requests for a service
+        #[ink(message)]
+        pub fn set_reporter(
+            &mut self,
+            service_id: u64,
+            reporter: H160,
+            allowed: bool,
+        ) -> Result<()> {
+            self.ensure_provider(service_id)?;
+
+            if allowed {
+                self.reporters.insert((service_id, reporter), &());
+            } else {
+                self.reporters.remove((service_id, reporter));
+            }
+
+            Ok(())
+        }
+
+        /// Meter a served request against the consumer's budget and record it
+        /// in the registry
+        ///
+        /// This is the reporter path for rate-limited plans: a request over
+        /// budget is rejected and never reaches the registry's stats.
+        #[ink(message)]
+        pub fn report_request(
+            &mut self,
+            service_id: u64,
+            consumer: H160,
+            success: bool,
+        ) -> Result<u32> {
+            let caller = self.env().caller();
+            let mut registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service = registry
+                .get_service(service_id)
+                .map_err(|_| Error::ServiceNotFound)?;
+            if service.provider != caller && !self.reporters.contains((service_id, caller)) {
+                return Err(Error::Unauthorized);
+            }
+
+            let quota = self
+                .quotas
+                .get((service_id, consumer))
+                .ok_or(Error::NoQuota)?;
+            let now = self.env().block_timestamp();
+            if quota.expires_at.is_some_and(|expires_at| now >= expires_at) {
+                return Err(Error::QuotaExpired);
+            }
+
+            let epoch = now / quota.epoch_length;
+            let mut usage = self.usage.get((service_id, consumer)).unwrap_or_default();
+            if usage.epoch != epoch {
+                usage = Usage { epoch, used: 0 };
+            }
+            if usage.used >= quota.requests_per_epoch {
+                return Err(Error::RateLimited);
+            }
+            usage.used += 1;
+            self.usage.insert((service_id, consumer), &usage);
+
+            registry
+                .record_service_request(service_id, success)
+                .map_err(|_| Error::RegistryCallFailed)?;
+
+            self.env().emit_event(RequestMetered {
+                service_id,
+                consumer,
+                epoch,
+                used: usage.used,
+                success,
+            });
+
+            Ok(quota.requests_per_epoch - usage.used)
+        }
+
+        /// Requests the consumer has left in the current epoch
+        #[ink(message)]
+        pub fn get_remaining(&self, service_id: u64, consumer: H160) -> u32 {
+            let Some(quota) = self.quotas.get((service_id, consumer)) else {
+                return 0;
+            };
+            let now = self.env().block_timestamp();
+            if quota.expires_at.is_some_and(|expires_at| now >= expires_at) {
+                return 0;
+            }
+
+            let epoch = now / quota.epoch_length;
+            let used = match self.usage.get((service_id, consumer)) {
+                Some(usage) if usage.epoch == epoch => usage.used,
+                _ => 0,
+            };
+            quota.requests_per_epoch.saturating_sub(used)
+        }
+
+        /// Get a consumer's quota for a service
+        #[ink(message)]
+        pub fn get_quota(&self, service_id: u64, consumer: H160) -> Option<Quota> {
+            self.quotas.get((service_id, consumer))
+        }
+
+        /// Check if an account may report requests for a service
+        #[ink(message)]
+        pub fn is_reporter(&self, service_id: u64, account: H160) -> bool {
+            self.reporters.contains((service_id, account))
+        }
+
+        fn ensure_provider(&self, service_id: u64) -> Result<()> {
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service = registry
+                .get_service(service_id)
+                .map_err(|_| Error::ServiceNotFound)?;
+            if service.provider != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::prelude::string::String;
+        use ink::{ToAddr, U256};
+        use service_registry::{PaymentAsset, Role, ServiceCategory};
+
+        const EPOCH: u64 = 100;
+
+        /// A limiter over a registry in which bob provides one service
+        ///
+        /// Off-chain, the registry sees the reporting account rather than the
+        /// limiter as its caller, so bob and charlie hold the verifier role.
+        fn limiter() -> (RateLimiter, ServiceRegistryRef, u64) {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, ServiceRegistryRef>();
+            let mut registry = ServiceRegistryRef::new()
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            registry.grant_role(Role::Verifier, accounts.bob).unwrap();
+            registry
+                .grant_role(Role::Verifier, accounts.charlie)
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            let service_id = registry
+                .register_service(
+                    String::from("Summarizer"),
+                    String::from("Summarizes documents"),
+                    ServiceCategory::TextProcessing,
+                    10,
+                    String::from("https://summarizer.example"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            let limiter = RateLimiter::new(registry.to_addr());
+            (limiter, registry, service_id)
+        }
+
+        #[ink::test]
+        fn providers_grant_and_revoke_quotas() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut limiter, _, service_id) = limiter();
+
+            limiter
+                .grant_quota(service_id, accounts.django, 5, EPOCH, None)
+                .unwrap();
+            assert_eq!(
+                limiter.get_quota(service_id, accounts.django),
+                Some(Quota {
+                    requests_per_epoch: 5,
+                    epoch_length: EPOCH,
+                    expires_at: None,
+                })
+            );
+            assert_eq!(limiter.get_remaining(service_id, accounts.django), 5);
+            assert_eq!(
+                limiter.grant_quota(service_id, accounts.django, 0, EPOCH, None),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                limiter.grant_quota(service_id, accounts.django, 5, 0, None),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                limiter.grant_quota(service_id + 1, accounts.django, 5, EPOCH, None),
+                Err(Error::ServiceNotFound)
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                limiter.grant_quota(service_id, accounts.charlie, 5, EPOCH, None),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                limiter.revoke_quota(service_id, accounts.django),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            limiter.revoke_quota(service_id, accounts.django).unwrap();
+            assert_eq!(limiter.get_quota(service_id, accounts.django), None);
+            assert_eq!(limiter.get_remaining(service_id, accounts.django), 0);
+        }
+
+        #[ink::test]
+        fn providers_choose_who_reports() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut limiter, _, service_id) = limiter();
+
+            limiter
+                .set_reporter(service_id, accounts.charlie, true)
+                .unwrap();
+            assert!(limiter.is_reporter(service_id, accounts.charlie));
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                limiter.set_reporter(service_id, accounts.django, true),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                limiter.set_reporter(service_id + 1, accounts.django, true),
+                Err(Error::ServiceNotFound)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            limiter
+                .set_reporter(service_id, accounts.charlie, false)
+                .unwrap();
+            assert!(!limiter.is_reporter(service_id, accounts.charlie));
+        }
+
+        #[ink::test]
+        fn requests_are_metered_per_epoch() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut limiter, registry, service_id) = limiter();
+            limiter
+                .grant_quota(service_id, accounts.django, 2, EPOCH, None)
+                .unwrap();
+            limiter
+                .set_reporter(service_id, accounts.charlie, true)
+                .unwrap();
+
+            assert_eq!(
+                limiter.report_request(service_id, accounts.eve, true),
+                Err(Error::NoQuota)
+            );
+            assert_eq!(
+                limiter.report_request(service_id, accounts.django, true),
+                Ok(1)
+            );
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                limiter.report_request(service_id, accounts.django, false),
+                Ok(0)
+            );
+            assert_eq!(
+                limiter.report_request(service_id, accounts.django, true),
+                Err(Error::RateLimited)
+            );
+            ink::env::test::set_caller(accounts.eve);
+            assert_eq!(
+                limiter.report_request(service_id, accounts.django, true),
+                Err(Error::Unauthorized)
+            );
+
+            // A new epoch restores the full budget
+            ink::env::test::set_block_timestamp(EPOCH);
+            assert_eq!(limiter.get_remaining(service_id, accounts.django), 2);
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                limiter.report_request(service_id, accounts.django, true),
+                Ok(1)
+            );
+
+            // Rejected requests never reach the registry's stats
+            let service = registry.get_service(service_id).unwrap();
+            assert_eq!(service.total_requests, 3);
+            assert_eq!(service.successful_requests, 2);
+        }
+
+        #[ink::test]
+        fn expired_quotas_stop_metering() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut limiter, _, service_id) = limiter();
+            limiter
+                .grant_quota(service_id, accounts.django, 2, EPOCH, Some(EPOCH / 2))
+                .unwrap();
+
+            ink::env::test::set_block_timestamp(EPOCH / 2);
+            assert_eq!(limiter.get_remaining(service_id, accounts.django), 0);
+            assert_eq!(
+                limiter.report_request(service_id, accounts.django, true),
+                Err(Error::QuotaExpired)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink::prelude::string::String;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use service_registry::{PaymentAsset, Role, ServiceCategory, ServiceRegistry};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn metered_requests_reach_the_registry<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let charlie = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Charlie);
+
+            let registry = client
+                .instantiate(
+                    "service_registry",
+                    &ink_e2e::alice(),
+                    &mut ServiceRegistryRef::new(),
+                )
+                .submit()
+                .await
+                .expect("registry instantiate failed");
+            let mut registry_calls = registry.call_builder::<ServiceRegistry>();
+            let limiter = client
+                .instantiate(
+                    "rate_limiter",
+                    &ink_e2e::alice(),
+                    &mut RateLimiterRef::new(registry.addr),
+                )
+                .submit()
+                .await
+                .expect("rate limiter instantiate failed");
+            let mut calls = limiter.call_builder::<RateLimiter>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &registry_calls.grant_role(Role::Verifier, limiter.addr),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let service_id = client
+                .call(
+                    &ink_e2e::bob(),
+                    &registry_calls.register_service(
+                        String::from("Summarizer"),
+                        String::from("Summarizes documents"),
+                        ServiceCategory::TextProcessing,
+                        10,
+                        String::from("https://summarizer.example"),
+                        PaymentAsset::Native,
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.grant_quota(service_id, charlie, 1, 86_400_000, None),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let remaining = client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.report_request(service_id, charlie, true),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value();
+            assert_eq!(remaining, Ok(0));
+            let over_budget = client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.report_request(service_id, charlie, true),
+                )
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(over_budget, Err(Error::RateLimited));
+
+            let service = client
+                .call(&ink_e2e::alice(), &registry_calls.get_service(service_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(service.total_requests, 1);
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,414 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod reputation_bridge {
+    use hub_types::typed_data::{self, Domain};
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use payment_escrow::PaymentEscrowRef;
+    use service_registry::ServiceRegistryRef;
+
+    /// Layout of a signed reputation snapshot
+    const SNAPSHOT_TYPE: &str = "ReputationSnapshot(address provider,uint32 reputation,uint32 serviceCount,uint64 totalRequests,uint64 successfulRequests,uint32 settledCount,uint128 settledVolume,uint32 refundedCount,uint32 disputedCount,uint64 takenAt,uint32 blockNumber)";
+
+    /// Point-in-time record of a provider's standing on the hub
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ReputationSnapshot {
+        pub provider: H160,
+        pub reputation: u32,
+        pub service_count: u32,
+        pub total_requests: u64,
+        pub successful_requests: u64,
+        pub settled_count: u32,
+        pub settled_volume: Balance,
+        pub refunded_count: u32,
+        pub disputed_count: u32,
+        pub taken_at: u64,
+        pub block_number: u32,
+    }
+
+    /// An exported snapshot and the attestor's signature over its digest
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Export {
+        pub id: u64,
+        pub snapshot: ReputationSnapshot,
+        pub digest: H256,
+        pub signature: Option<[u8; 65]>,
+    }
+
+    /// Events
+    /// Emitted when a snapshot is taken for export
+    #[ink(event)]
+    pub struct ReputationExported {
+        #[ink(topic)]
+        export_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        digest: H256,
+    }
+    /// Emitted when the attestor signs an exported snapshot
+    #[ink(event)]
+    pub struct ExportAttested {
+        #[ink(topic)]
+        export_id: u64,
+        #[ink(topic)]
+        provider: H160,
+        signature: [u8; 65],
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the export is not found
+        ExportNotFound,
+        /// Emitted when the export already carries a signature
+        AlreadyAttested,
+        /// Emitted when the signature was not made by the attestor
+        InvalidSignature,
+        /// Emitted when the caller is not the owner
+        Unauthorized,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::Overflow => HubError::Overflow,
+                Error::ExportNotFound => HubError::NotFound,
+                Error::AlreadyAttested => HubError::AlreadyExists,
+                Error::InvalidSignature => HubError::InvalidProof,
+                Error::Unauthorized => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct ReputationBridge {
+        owner: H160,
+        // Key whose signatures other chains trust
+        attestor: H160,
+        registry: H160,
+        escrow: H160,
+        exports: Mapping<u64, Export>,
+        export_count: u64,
+        latest_exports: Mapping<H160, u64>,
+    }
+
+    impl ReputationBridge {
+        #[ink(constructor)]
+        pub fn new(registry: H160, escrow: H160, attestor: H160) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                attestor,
+                registry,
+                escrow,
+                exports: Mapping::default(),
+                export_count: 0,
+                latest_exports: Mapping::default(),
+            }
+        }
+
+        /// Take a snapshot of a provider's reputation and settlement history
+        ///
+        /// The snapshot and its digest are stored so the attestor can sign
+        /// exactly what was recorded. Anyone may export any provider.
+        #[ink(message)]
+        pub fn export_reputation(&mut self, provider: H160) -> Result<u64> {
+            let snapshot = self.take_snapshot(provider);
+            let digest = self.snapshot_digest(&snapshot);
+
+            self.export_count = self.export_count.checked_add(1).ok_or(Error::Overflow)?;
+            let export_id = self.export_count;
+
+            let export = Export {
+                id: export_id,
+                snapshot,
+                digest,
+                signature: None,
+            };
+            self.exports.insert(export_id, &export);
+            self.latest_exports.insert(provider, &export_id);
+
+            self.env().emit_event(ReputationExported {
+                export_id,
+                provider,
+                digest,
+            });
+
+            Ok(export_id)
+        }
+
+        /// Attach the attestor's signature over an export's digest
+        ///
+        /// Anyone may submit the signature; it is checked against the
+        /// attestor address so consumers elsewhere only need that key.
+        #[ink(message)]
+        pub fn attest(&mut self, export_id: u64, signature: [u8; 65]) -> Result<()> {
+            let mut export = self.get_export(export_id)?;
+            if export.signature.is_some() {
+                return Err(Error::AlreadyAttested);
+            }
+            if typed_data::recover(&export.digest.0, &signature) != Some(self.attestor) {
+                return Err(Error::InvalidSignature);
+            }
+
+            export.signature = Some(signature);
+            self.exports.insert(export_id, &export);
+
+            self.env().emit_event(ExportAttested {
+                export_id,
+                provider: export.snapshot.provider,
+                signature,
+            });
+
+            Ok(())
+        }
+
+        /// Check that a snapshot was signed by the attestor
+        #[ink(message)]
+        pub fn verify_snapshot(&self, snapshot: ReputationSnapshot, signature: [u8; 65]) -> bool {
+            let digest = self.snapshot_digest(&snapshot);
+            typed_data::recover(&digest.0, &signature) == Some(self.attestor)
+        }
+
+        /// Current snapshot of a provider, without recording it
+        #[ink(message)]
+        pub fn get_snapshot(&self, provider: H160) -> ReputationSnapshot {
+            self.take_snapshot(provider)
+        }
+
+        /// Digest the attestor signs for a snapshot
+        #[ink(message)]
+        pub fn get_snapshot_digest(&self, snapshot: ReputationSnapshot) -> H256 {
+            self.snapshot_digest(&snapshot)
+        }
+
+        /// Rotate the attestor key
+        #[ink(message)]
+        pub fn set_attestor(&mut self, attestor: H160) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            self.attestor = attestor;
+            Ok(())
+        }
+
+        /// Get export details
+        #[ink(message)]
+        pub fn get_export(&self, export_id: u64) -> Result<Export> {
+            self.exports.get(export_id).ok_or(Error::ExportNotFound)
+        }
+
+        /// Get the most recent export of a provider
+        #[ink(message)]
+        pub fn get_latest_export(&self, provider: H160) -> Option<u64> {
+            self.latest_exports.get(provider)
+        }
+
+        /// Get the attestor address
+        #[ink(message)]
+        pub fn get_attestor(&self) -> H160 {
+            self.attestor
+        }
+
+        fn take_snapshot(&self, provider: H160) -> ReputationSnapshot {
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service_ids: Vec<u64> = registry.get_provider_services(provider);
+
+            let mut total_requests = 0u64;
+            let mut successful_requests = 0u64;
+            for service_id in &service_ids {
+                if let Ok(service) = registry.get_service(*service_id) {
+                    total_requests =
+                        total_requests.saturating_add(u64::from(service.total_requests));
+                    successful_requests =
+                        successful_requests.saturating_add(u64::from(service.successful_requests));
+                }
+            }
+
+            let escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let settlements = escrow.get_settlement_summary(provider);
+
+            ReputationSnapshot {
+                provider,
+                reputation: registry.get_reputation(provider),
+                service_count: service_ids.len() as u32,
+                total_requests,
+                successful_requests,
+                settled_count: settlements.completed_count,
+                settled_volume: settlements.completed_volume,
+                refunded_count: settlements.refunded_count,
+                disputed_count: settlements.disputed_count,
+                taken_at: self.env().block_timestamp(),
+                block_number: self.env().block_number(),
+            }
+        }
+
+        /// Typed digest bound to this bridge's address
+        fn snapshot_digest(&self, snapshot: &ReputationSnapshot) -> H256 {
+            H256::from(
+                Domain::new("ReputationBridge", "1", self.env().address())
+                    .digest(SNAPSHOT_TYPE, snapshot),
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::prelude::string::String;
+        use ink::{ToAddr, U256};
+        use secp256k1::{ecdsa::RecoveryId, Message, PublicKey, SecretKey, SECP256K1};
+        use service_registry::{PaymentAsset, ServiceCategory};
+
+        const ATTESTOR_KEY: [u8; 32] = [7; 32];
+
+        fn address_of(secret: [u8; 32]) -> H160 {
+            let secret = SecretKey::from_byte_array(&secret).unwrap();
+            let public_key = PublicKey::from_secret_key(SECP256K1, &secret).serialize();
+            let mut address = [0u8; 20];
+            ink::env::ecdsa_to_eth_address(&public_key, &mut address).unwrap();
+            H160::from(address)
+        }
+
+        fn sign(secret: [u8; 32], digest: H256) -> [u8; 65] {
+            let secret = SecretKey::from_byte_array(&secret).unwrap();
+            let (recovery_id, compact) = SECP256K1
+                .sign_ecdsa_recoverable(&Message::from_digest(digest.0), &secret)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = i32::from(recovery_id) as u8;
+            signature
+        }
+
+        /// A bridge deployed by alice over a registry in which bob provides
+        /// one service and an escrow with no settlements yet
+        fn bridge() -> ReputationBridge {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, ServiceRegistryRef>();
+            let mut registry = ServiceRegistryRef::new()
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, PaymentEscrowRef>();
+            let escrow = PaymentEscrowRef::new(1_000)
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+
+            ink::env::test::set_caller(accounts.bob);
+            registry
+                .register_service(
+                    String::from("Translator"),
+                    String::from("Translates documents"),
+                    ServiceCategory::TextProcessing,
+                    10,
+                    String::from("https://translator.example"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            ReputationBridge::new(
+                registry.to_addr(),
+                escrow.to_addr(),
+                address_of(ATTESTOR_KEY),
+            )
+        }
+
+        #[ink::test]
+        fn exports_record_the_providers_standing() {
+            let accounts = ink::env::test::default_accounts();
+            let mut bridge = bridge();
+            ink::env::test::set_block_timestamp(42);
+
+            // Anyone may export any provider
+            ink::env::test::set_caller(accounts.charlie);
+            let export_id = bridge.export_reputation(accounts.bob).unwrap();
+            let export = bridge.get_export(export_id).unwrap();
+            assert_eq!(export.snapshot, bridge.get_snapshot(accounts.bob));
+            assert_eq!(export.snapshot.service_count, 1);
+            assert_eq!(export.snapshot.settled_count, 0);
+            assert_eq!(export.snapshot.taken_at, 42);
+            assert_eq!(export.digest, bridge.get_snapshot_digest(export.snapshot));
+            assert_eq!(export.signature, None);
+            assert_eq!(bridge.get_latest_export(accounts.bob), Some(export_id));
+            assert_eq!(bridge.get_latest_export(accounts.charlie), None);
+            assert_eq!(bridge.get_export(export_id + 1), Err(Error::ExportNotFound));
+        }
+
+        #[ink::test]
+        fn only_attestor_signatures_are_accepted() {
+            let accounts = ink::env::test::default_accounts();
+            let mut bridge = bridge();
+            let export_id = bridge.export_reputation(accounts.bob).unwrap();
+            let export = bridge.get_export(export_id).unwrap();
+
+            assert_eq!(
+                bridge.attest(export_id, sign([8; 32], export.digest)),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(
+                bridge.attest(export_id + 1, sign(ATTESTOR_KEY, export.digest)),
+                Err(Error::ExportNotFound)
+            );
+
+            let signature = sign(ATTESTOR_KEY, export.digest);
+            bridge.attest(export_id, signature).unwrap();
+            assert_eq!(
+                bridge.get_export(export_id).unwrap().signature,
+                Some(signature)
+            );
+            assert_eq!(
+                bridge.attest(export_id, signature),
+                Err(Error::AlreadyAttested)
+            );
+
+            assert!(bridge.verify_snapshot(export.snapshot.clone(), signature));
+            let inflated = ReputationSnapshot {
+                reputation: export.snapshot.reputation + 1,
+                ..export.snapshot
+            };
+            assert!(!bridge.verify_snapshot(inflated, signature));
+        }
+
+        #[ink::test]
+        fn only_the_owner_rotates_the_attestor() {
+            let accounts = ink::env::test::default_accounts();
+            let mut bridge = bridge();
+            let export_id = bridge.export_reputation(accounts.bob).unwrap();
+            let digest = bridge.get_export(export_id).unwrap().digest;
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(bridge.set_attestor(accounts.bob), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller(accounts.alice);
+            bridge.set_attestor(address_of([8; 32])).unwrap();
+            assert_eq!(bridge.get_attestor(), address_of([8; 32]));
+            assert_eq!(
+                bridge.attest(export_id, sign(ATTESTOR_KEY, digest)),
+                Err(Error::InvalidSignature)
+            );
+            bridge.attest(export_id, sign([8; 32], digest)).unwrap();
+        }
+    }
+}
@@ -0,0 +1,392 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::reputation_oracle::{Error, ReputationOracleRef, SignalKind};
+
+#[ink::contract]
+mod reputation_oracle {
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use service_registry::ServiceRegistryRef;
+
+    /// Signals use the same 0 - 100 scale as registry reputation scores
+    const MAX_SIGNAL: u32 = 100;
+
+    /// Kinds of external reputation signals an oracle can post
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum SignalKind {
+        Benchmark,
+        Uptime,
+        Reviews,
+    }
+
+    /// Aggregate of the latest value each oracle posted for one signal kind
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SignalAggregate {
+        pub value_sum: u64,
+        pub reporters: u32,
+        pub updated_at: u64,
+    }
+
+    /// Governance weights for the combined reputation formula
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Weights {
+        pub on_chain: u32,
+        pub benchmark: u32,
+        pub uptime: u32,
+        pub reviews: u32,
+    }
+
+    /// Events
+    /// Emitted when an oracle is approved or removed
+    #[ink(event)]
+    pub struct OracleUpdated {
+        #[ink(topic)]
+        oracle: H160,
+        approved: bool,
+    }
+    /// Emitted when an oracle posts a signal
+    #[ink(event)]
+    pub struct SignalPosted {
+        #[ink(topic)]
+        provider: H160,
+        #[ink(topic)]
+        oracle: H160,
+        kind: SignalKind,
+        value: u32,
+    }
+    /// Emitted when governance changes the formula weights
+    #[ink(event)]
+    pub struct WeightsUpdated {
+        weights: Weights,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the caller is not the governance account
+        Unauthorized,
+        /// Emitted when the caller is not an approved oracle
+        NotOracle,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NotOracle => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct ReputationOracle {
+        governance: H160,
+        registry: H160,
+        oracles: Mapping<H160, ()>,
+        signals: Mapping<(H160, SignalKind), SignalAggregate>,
+        oracle_values: Mapping<(H160, SignalKind, H160), u32>,
+        weights: Weights,
+    }
+
+    impl ReputationOracle {
+        #[ink(constructor)]
+        pub fn new(registry: H160) -> Self {
+            Self {
+                governance: Self::env().caller(),
+                registry,
+                oracles: Mapping::default(),
+                signals: Mapping::default(),
+                oracle_values: Mapping::default(),
+                weights: Weights {
+                    on_chain: 1,
+                    benchmark: 1,
+                    uptime: 1,
+                    reviews: 1,
+                },
+            }
+        }
+
+        /// Approve or remove an oracle
+        #[ink(message)]
+        pub fn set_oracle(&mut self, oracle: H160, approved: bool) -> Result<()> {
+            self.ensure_governance()?;
+
+            if approved {
+                self.oracles.insert(oracle, &());
+            } else {
+                self.oracles.remove(oracle);
+            }
+
+            self.env().emit_event(OracleUpdated { oracle, approved });
+
+            Ok(())
+        }
+
+        /// Set the weights used by `get_combined_reputation`
+        #[ink(message)]
+        pub fn set_weights(&mut self, weights: Weights) -> Result<()> {
+            self.ensure_governance()?;
+
+            let total = u64::from(weights.on_chain)
+                + u64::from(weights.benchmark)
+                + u64::from(weights.uptime)
+                + u64::from(weights.reviews);
+            if total == 0 {
+                return Err(Error::InvalidInput);
+            }
+
+            self.weights = weights.clone();
+            self.env().emit_event(WeightsUpdated { weights });
+
+            Ok(())
+        }
+
+        /// Hand governance over to another account (e.g. a DAO contract)
+        #[ink(message)]
+        pub fn transfer_governance(&mut self, new_governance: H160) -> Result<()> {
+            self.ensure_governance()?;
+            self.governance = new_governance;
+            Ok(())
+        }
+
+        /// Post a signal for a provider, replacing this oracle's previous value
+        #[ink(message)]
+        pub fn post_signal(&mut self, provider: H160, kind: SignalKind, value: u32) -> Result<()> {
+            let oracle = self.env().caller();
+            if !self.oracles.contains(oracle) {
+                return Err(Error::NotOracle);
+            }
+            if value > MAX_SIGNAL {
+                return Err(Error::InvalidInput);
+            }
+
+            let mut aggregate = self.signals.get((provider, kind)).unwrap_or_default();
+            match self.oracle_values.get((provider, kind, oracle)) {
+                Some(previous) => {
+                    aggregate.value_sum = aggregate.value_sum.saturating_sub(u64::from(previous));
+                }
+                None => {
+                    aggregate.reporters =
+                        aggregate.reporters.checked_add(1).ok_or(Error::Overflow)?;
+                }
+            }
+            aggregate.value_sum = aggregate
+                .value_sum
+                .checked_add(u64::from(value))
+                .ok_or(Error::Overflow)?;
+            aggregate.updated_at = self.env().block_timestamp();
+
+            self.signals.insert((provider, kind), &aggregate);
+            self.oracle_values.insert((provider, kind, oracle), &value);
+
+            self.env().emit_event(SignalPosted {
+                provider,
+                oracle,
+                kind,
+                value,
+            });
+
+            Ok(())
+        }
+
+        /// Average value posted by all oracles for a signal, if any
+        #[ink(message)]
+        pub fn get_signal(&self, provider: H160, kind: SignalKind) -> Option<u32> {
+            let aggregate = self.signals.get((provider, kind))?;
+            if aggregate.reporters == 0 {
+                return None;
+            }
+            Some((aggregate.value_sum / u64::from(aggregate.reporters)) as u32)
+        }
+
+        /// Combined reputation on the registry's 0 - 100 scale
+        ///
+        /// Weighted average of the registry score and every external signal
+        /// that has at least one report. Missing signals don't drag the
+        /// score down, they are simply left out of the average.
+        #[ink(message)]
+        pub fn get_combined_reputation(&self, provider: H160) -> u32 {
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let on_chain = registry.get_reputation(provider).min(MAX_SIGNAL);
+
+            let mut weighted_sum = u64::from(self.weights.on_chain) * u64::from(on_chain);
+            let mut total_weight = u64::from(self.weights.on_chain);
+
+            let external = [
+                (SignalKind::Benchmark, self.weights.benchmark),
+                (SignalKind::Uptime, self.weights.uptime),
+                (SignalKind::Reviews, self.weights.reviews),
+            ];
+            for (kind, weight) in external {
+                if let Some(value) = self.get_signal(provider, kind) {
+                    weighted_sum += u64::from(weight) * u64::from(value);
+                    total_weight += u64::from(weight);
+                }
+            }
+
+            if total_weight == 0 {
+                return 0;
+            }
+            (weighted_sum / total_weight) as u32
+        }
+
+        /// Check if an account is an approved oracle
+        #[ink(message)]
+        pub fn is_oracle(&self, account: H160) -> bool {
+            self.oracles.contains(account)
+        }
+
+        /// Get the current formula weights
+        #[ink(message)]
+        pub fn get_weights(&self) -> Weights {
+            self.weights.clone()
+        }
+
+        /// Get the governance account
+        #[ink(message)]
+        pub fn get_governance(&self) -> H160 {
+            self.governance
+        }
+
+        fn ensure_governance(&self) -> Result<()> {
+            if self.env().caller() != self.governance {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::{ToAddr, U256};
+
+        fn registry() -> H160 {
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, ServiceRegistryRef>();
+            ServiceRegistryRef::new()
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate()
+                .to_addr()
+        }
+
+        #[ink::test]
+        fn governance_approves_oracles_and_weights() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut contract = ReputationOracle::new(accounts.frank);
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_oracle(accounts.bob, true),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_oracle(accounts.bob, true).unwrap();
+            assert!(contract.is_oracle(accounts.bob));
+            contract.set_oracle(accounts.bob, false).unwrap();
+            assert!(!contract.is_oracle(accounts.bob));
+
+            let zero = Weights {
+                on_chain: 0,
+                benchmark: 0,
+                uptime: 0,
+                reviews: 0,
+            };
+            assert_eq!(contract.set_weights(zero), Err(Error::InvalidInput));
+            let weights = Weights {
+                on_chain: 2,
+                benchmark: 1,
+                uptime: 0,
+                reviews: 0,
+            };
+            contract.set_weights(weights.clone()).unwrap();
+            assert_eq!(contract.get_weights(), weights);
+
+            contract.transfer_governance(accounts.bob).unwrap();
+            assert_eq!(contract.get_governance(), accounts.bob);
+            assert_eq!(contract.set_weights(weights), Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn oracles_replace_their_own_signals() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut contract = ReputationOracle::new(accounts.frank);
+            contract.set_oracle(accounts.bob, true).unwrap();
+            contract.set_oracle(accounts.charlie, true).unwrap();
+
+            ink::env::test::set_caller(accounts.django);
+            assert_eq!(
+                contract.post_signal(accounts.eve, SignalKind::Uptime, 50),
+                Err(Error::NotOracle)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.post_signal(accounts.eve, SignalKind::Uptime, MAX_SIGNAL + 1),
+                Err(Error::InvalidInput)
+            );
+            contract
+                .post_signal(accounts.eve, SignalKind::Uptime, 80)
+                .unwrap();
+            contract
+                .post_signal(accounts.eve, SignalKind::Uptime, 60)
+                .unwrap();
+            assert_eq!(
+                contract.get_signal(accounts.eve, SignalKind::Uptime),
+                Some(60)
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            contract
+                .post_signal(accounts.eve, SignalKind::Uptime, 100)
+                .unwrap();
+            assert_eq!(
+                contract.get_signal(accounts.eve, SignalKind::Uptime),
+                Some(80)
+            );
+            assert_eq!(contract.get_signal(accounts.eve, SignalKind::Reviews), None);
+        }
+
+        #[ink::test]
+        fn missing_signals_are_left_out_of_the_combined_score() {
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.alice);
+            let mut contract = ReputationOracle::new(registry());
+            contract.set_oracle(accounts.bob, true).unwrap();
+
+            // No settlements yet, so the registry scores the provider 0
+            assert_eq!(contract.get_combined_reputation(accounts.eve), 0);
+
+            ink::env::test::set_caller(accounts.bob);
+            contract
+                .post_signal(accounts.eve, SignalKind::Benchmark, 90)
+                .unwrap();
+            contract
+                .post_signal(accounts.eve, SignalKind::Uptime, 60)
+                .unwrap();
+            assert_eq!(contract.get_combined_reputation(accounts.eve), 50);
+        }
+    }
+}
@@ -0,0 +1,780 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod retainer {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::format;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use ink::H256;
+    use payment_escrow::PaymentEscrowRef;
+
+    /// Different statuses of a retainer
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum RetainerStatus {
+        Active,
+        Terminated,
+    }
+
+    /// Different statuses of a deliverable
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum DeliverableStatus {
+        Submitted,
+        Confirmed,
+        Rejected,
+    }
+
+    /// An ongoing engagement between a client and a provider
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Retainer {
+        pub id: u64,
+        pub client: H160,
+        pub provider: H160,
+        // Locked funds not yet committed to a deliverable
+        pub budget: Balance,
+        // Maximum the provider may draw in one period
+        pub period_allowance: Balance,
+        // Period length in milliseconds (e.g. 30 days)
+        pub period_length: u64,
+        pub started_at: u64,
+        pub current_period: u64,
+        pub drawn_this_period: Balance,
+        pub status: RetainerStatus,
+    }
+
+    /// A unit of work billed against a retainer
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Deliverable {
+        pub id: u64,
+        pub retainer_id: u64,
+        pub amount: Balance,
+        pub deliverable_hash: H256,
+        pub period: u64,
+        pub escrow_id: u64,
+        pub status: DeliverableStatus,
+        pub submitted_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a client opens a retainer
+    #[ink(event)]
+    pub struct RetainerOpened {
+        #[ink(topic)]
+        retainer_id: u64,
+        #[ink(topic)]
+        client: H160,
+        #[ink(topic)]
+        provider: H160,
+        budget: Balance,
+        period_allowance: Balance,
+    }
+    /// Emitted when the client adds to the budget
+    #[ink(event)]
+    pub struct RetainerToppedUp {
+        #[ink(topic)]
+        retainer_id: u64,
+        amount: Balance,
+        budget: Balance,
+    }
+    /// Emitted when the provider bills a deliverable into escrow
+    #[ink(event)]
+    pub struct DeliverableSubmitted {
+        #[ink(topic)]
+        retainer_id: u64,
+        #[ink(topic)]
+        deliverable_id: u64,
+        escrow_id: u64,
+        amount: Balance,
+    }
+    /// Emitted when the client confirms or rejects a deliverable
+    #[ink(event)]
+    pub struct DeliverableResolved {
+        #[ink(topic)]
+        retainer_id: u64,
+        #[ink(topic)]
+        deliverable_id: u64,
+        status: DeliverableStatus,
+    }
+    /// Emitted when a retainer is terminated and the unused budget returned
+    #[ink(event)]
+    pub struct RetainerTerminated {
+        #[ink(topic)]
+        retainer_id: u64,
+        #[ink(topic)]
+        terminated_by: H160,
+        refunded: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the amount is zero or exceeds the budget
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the retainer is not found
+        RetainerNotFound,
+        /// Emitted when the deliverable is not found
+        DeliverableNotFound,
+        /// Emitted when the retainer or deliverable is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the deliverable exceeds this period's allowance
+        AllowanceExceeded,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::RetainerNotFound => HubError::NotFound,
+                Error::DeliverableNotFound => HubError::NotFound,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::AllowanceExceeded => HubError::LimitExceeded,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct RetainerBook {
+        escrow: H160,
+        retainers: Mapping<u64, Retainer>,
+        retainer_count: u64,
+        deliverables: Mapping<u64, Deliverable>,
+        deliverable_count: u64,
+        retainer_deliverables: Mapping<u64, Vec<u64>>,
+        user_retainers: Mapping<H160, Vec<u64>>,
+    }
+
+    impl RetainerBook {
+        #[ink(constructor)]
+        pub fn new(escrow: H160) -> Self {
+            Self {
+                escrow,
+                retainers: Mapping::default(),
+                retainer_count: 0,
+                deliverables: Mapping::default(),
+                deliverable_count: 0,
+                retainer_deliverables: Mapping::default(),
+                user_retainers: Mapping::default(),
+            }
+        }
+
+        /// Open a retainer with a provider, locking the transferred value as budget
+        #[ink(message, payable)]
+        pub fn open_retainer(
+            &mut self,
+            provider: H160,
+            period_allowance: Balance,
+            period_length: u64,
+        ) -> Result<u64> {
+            let client = self.env().caller();
+            let budget: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if budget == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            if provider == client || period_allowance == 0 || period_length == 0 {
+                return Err(Error::InvalidInput);
+            }
+
+            self.retainer_count = self.retainer_count.checked_add(1).ok_or(Error::Overflow)?;
+            let retainer_id = self.retainer_count;
+
+            let retainer = Retainer {
+                id: retainer_id,
+                client,
+                provider,
+                budget,
+                period_allowance,
+                period_length,
+                started_at: self.env().block_timestamp(),
+                current_period: 0,
+                drawn_this_period: 0,
+                status: RetainerStatus::Active,
+            };
+            self.retainers.insert(retainer_id, &retainer);
+
+            for user in [client, provider] {
+                let mut retainers = self.user_retainers.get(user).unwrap_or_default();
+                retainers.push(retainer_id);
+                self.user_retainers.insert(user, &retainers);
+            }
+
+            self.env().emit_event(RetainerOpened {
+                retainer_id,
+                client,
+                provider,
+                budget,
+                period_allowance,
+            });
+
+            Ok(retainer_id)
+        }
+
+        /// Add the transferred value to a retainer's budget
+        #[ink(message, payable)]
+        pub fn top_up(&mut self, retainer_id: u64) -> Result<()> {
+            let mut retainer = self.get_retainer(retainer_id)?;
+            if retainer.client != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if retainer.status != RetainerStatus::Active {
+                return Err(Error::InvalidStatus);
+            }
+
+            let amount: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if amount == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            retainer.budget = retainer.budget.checked_add(amount).ok_or(Error::Overflow)?;
+            self.retainers.insert(retainer_id, &retainer);
+
+            self.env().emit_event(RetainerToppedUp {
+                retainer_id,
+                amount,
+                budget: retainer.budget,
+            });
+
+            Ok(())
+        }
+
+        /// Bill a deliverable against the current period's allowance
+        ///
+        /// The amount moves from the budget into an escrow paying the
+        /// provider, which the client then confirms or rejects.
+        #[ink(message)]
+        pub fn submit_deliverable(
+            &mut self,
+            retainer_id: u64,
+            amount: Balance,
+            deliverable_hash: H256,
+        ) -> Result<u64> {
+            let mut retainer = self.get_retainer(retainer_id)?;
+            if retainer.provider != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if retainer.status != RetainerStatus::Active {
+                return Err(Error::InvalidStatus);
+            }
+            if amount == 0 || amount > retainer.budget {
+                return Err(Error::InvalidAmount);
+            }
+
+            let period = self.period_of(&retainer);
+            if period != retainer.current_period {
+                retainer.current_period = period;
+                retainer.drawn_this_period = 0;
+            }
+            let drawn = retainer
+                .drawn_this_period
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            if drawn > retainer.period_allowance {
+                return Err(Error::AllowanceExceeded);
+            }
+
+            self.deliverable_count = self
+                .deliverable_count
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            let deliverable_id = self.deliverable_count;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let escrow_id = escrow
+                .call_mut()
+                .create_escrow(
+                    retainer.provider,
+                    0,
+                    format!("retainer:{}:{}", retainer_id, deliverable_id),
+                    PaymentAsset::Native,
+                )
+                .transferred_value(amount.into())
+                .invoke()
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            retainer.budget -= amount;
+            retainer.drawn_this_period = drawn;
+            self.retainers.insert(retainer_id, &retainer);
+
+            let deliverable = Deliverable {
+                id: deliverable_id,
+                retainer_id,
+                amount,
+                deliverable_hash,
+                period,
+                escrow_id,
+                status: DeliverableStatus::Submitted,
+                submitted_at: self.env().block_timestamp(),
+            };
+            self.deliverables.insert(deliverable_id, &deliverable);
+
+            let mut deliverables = self
+                .retainer_deliverables
+                .get(retainer_id)
+                .unwrap_or_default();
+            deliverables.push(deliverable_id);
+            self.retainer_deliverables
+                .insert(retainer_id, &deliverables);
+
+            self.env().emit_event(DeliverableSubmitted {
+                retainer_id,
+                deliverable_id,
+                escrow_id,
+                amount,
+            });
+
+            Ok(deliverable_id)
+        }
+
+        /// Confirm a deliverable and release its escrow to the provider
+        #[ink(message)]
+        pub fn confirm_deliverable(&mut self, deliverable_id: u64) -> Result<()> {
+            let (retainer, mut deliverable) = self.client_deliverable(deliverable_id)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .release_payment(deliverable.escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            deliverable.status = DeliverableStatus::Confirmed;
+            self.deliverables.insert(deliverable_id, &deliverable);
+
+            self.env().emit_event(DeliverableResolved {
+                retainer_id: retainer.id,
+                deliverable_id,
+                status: DeliverableStatus::Confirmed,
+            });
+
+            Ok(())
+        }
+
+        /// Reject a deliverable, returning its amount to the budget
+        ///
+        /// If the deliverable was billed in the current period its amount no
+        /// longer counts against the allowance.
+        #[ink(message)]
+        pub fn reject_deliverable(&mut self, deliverable_id: u64) -> Result<()> {
+            let (mut retainer, mut deliverable) = self.client_deliverable(deliverable_id)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .refund(deliverable.escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            if retainer.status == RetainerStatus::Active {
+                retainer.budget = retainer
+                    .budget
+                    .checked_add(deliverable.amount)
+                    .ok_or(Error::Overflow)?;
+                if deliverable.period == retainer.current_period {
+                    retainer.drawn_this_period = retainer
+                        .drawn_this_period
+                        .saturating_sub(deliverable.amount);
+                }
+                self.retainers.insert(retainer.id, &retainer);
+            } else if self
+                .env()
+                .transfer(retainer.client, deliverable.amount.into())
+                .is_err()
+            {
+                // Terminated retainers have no budget left to return to
+                return Err(Error::TransferFailed);
+            }
+
+            deliverable.status = DeliverableStatus::Rejected;
+            self.deliverables.insert(deliverable_id, &deliverable);
+
+            self.env().emit_event(DeliverableResolved {
+                retainer_id: retainer.id,
+                deliverable_id,
+                status: DeliverableStatus::Rejected,
+            });
+
+            Ok(())
+        }
+
+        /// End a retainer and return the uncommitted budget to the client
+        ///
+        /// Either party may terminate. Deliverables already in escrow can
+        /// still be confirmed or rejected afterwards.
+        #[ink(message)]
+        pub fn terminate(&mut self, retainer_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut retainer = self.get_retainer(retainer_id)?;
+            if caller != retainer.client && caller != retainer.provider {
+                return Err(Error::Unauthorized);
+            }
+            if retainer.status != RetainerStatus::Active {
+                return Err(Error::InvalidStatus);
+            }
+
+            let refunded = retainer.budget;
+            if refunded > 0
+                && self
+                    .env()
+                    .transfer(retainer.client, refunded.into())
+                    .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            retainer.budget = 0;
+            retainer.status = RetainerStatus::Terminated;
+            self.retainers.insert(retainer_id, &retainer);
+
+            self.env().emit_event(RetainerTerminated {
+                retainer_id,
+                terminated_by: caller,
+                refunded,
+            });
+
+            Ok(())
+        }
+
+        /// Amount the provider may still bill in the current period
+        #[ink(message)]
+        pub fn get_available_allowance(&self, retainer_id: u64) -> Result<Balance> {
+            let retainer = self.get_retainer(retainer_id)?;
+            if retainer.status != RetainerStatus::Active {
+                return Ok(0);
+            }
+            let drawn = if self.period_of(&retainer) == retainer.current_period {
+                retainer.drawn_this_period
+            } else {
+                0
+            };
+            Ok(retainer
+                .period_allowance
+                .saturating_sub(drawn)
+                .min(retainer.budget))
+        }
+
+        /// Get retainer details
+        #[ink(message)]
+        pub fn get_retainer(&self, retainer_id: u64) -> Result<Retainer> {
+            self.retainers
+                .get(retainer_id)
+                .ok_or(Error::RetainerNotFound)
+        }
+
+        /// Get deliverable details
+        #[ink(message)]
+        pub fn get_deliverable(&self, deliverable_id: u64) -> Result<Deliverable> {
+            self.deliverables
+                .get(deliverable_id)
+                .ok_or(Error::DeliverableNotFound)
+        }
+
+        /// Get all deliverables billed against a retainer
+        #[ink(message)]
+        pub fn get_retainer_deliverables(&self, retainer_id: u64) -> Vec<u64> {
+            self.retainer_deliverables
+                .get(retainer_id)
+                .unwrap_or_default()
+        }
+
+        /// Get all retainers a user is party to
+        #[ink(message)]
+        pub fn get_user_retainers(&self, user: H160) -> Vec<u64> {
+            self.user_retainers.get(user).unwrap_or_default()
+        }
+
+        /// Get total retainer count
+        #[ink(message)]
+        pub fn get_retainer_count(&self) -> u64 {
+            self.retainer_count
+        }
+
+        fn period_of(&self, retainer: &Retainer) -> u64 {
+            self.env()
+                .block_timestamp()
+                .saturating_sub(retainer.started_at)
+                / retainer.period_length
+        }
+
+        fn client_deliverable(&self, deliverable_id: u64) -> Result<(Retainer, Deliverable)> {
+            let deliverable = self.get_deliverable(deliverable_id)?;
+            let retainer = self.get_retainer(deliverable.retainer_id)?;
+            if retainer.client != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if deliverable.status != DeliverableStatus::Submitted {
+                return Err(Error::InvalidStatus);
+            }
+            Ok((retainer, deliverable))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::{balance_of, deploy_at};
+        use ink::U256;
+
+        const BUDGET: Balance = 300;
+        const ALLOWANCE: Balance = 100;
+        const PERIOD: u64 = 1_000;
+
+        /// A book in which alice has opened a retainer with bob
+        fn book_with_retainer() -> (RetainerBook, u64) {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xE0; 20]), 0);
+
+            ink::env::test::set_caller(accounts.alice);
+            let mut book = RetainerBook::new(H160::from([0xE5; 20]));
+            ink::env::test::transfer_in(U256::from(BUDGET));
+            let retainer_id = book.open_retainer(accounts.bob, ALLOWANCE, PERIOD).unwrap();
+            ink::env::test::set_value_transferred(U256::zero());
+            (book, retainer_id)
+        }
+
+        #[ink::test]
+        fn opening_a_retainer_locks_the_budget() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut book, retainer_id) = book_with_retainer();
+
+            let retainer = book.get_retainer(retainer_id).unwrap();
+            assert_eq!(retainer.client, accounts.alice);
+            assert_eq!(retainer.budget, BUDGET);
+            assert_eq!(retainer.status, RetainerStatus::Active);
+            assert_eq!(book.get_available_allowance(retainer_id), Ok(ALLOWANCE));
+            assert_eq!(book.get_user_retainers(accounts.bob), vec![retainer_id]);
+
+            assert_eq!(
+                book.open_retainer(accounts.bob, ALLOWANCE, PERIOD),
+                Err(Error::InvalidAmount)
+            );
+            ink::env::test::set_value_transferred(U256::from(BUDGET));
+            assert_eq!(
+                book.open_retainer(accounts.alice, ALLOWANCE, PERIOD),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                book.open_retainer(accounts.bob, 0, PERIOD),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                book.open_retainer(accounts.bob, ALLOWANCE, 0),
+                Err(Error::InvalidInput)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(book.top_up(retainer_id), Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.alice);
+            book.top_up(retainer_id).unwrap();
+            ink::env::test::set_value_transferred(U256::zero());
+            assert_eq!(book.top_up(retainer_id), Err(Error::InvalidAmount));
+            assert_eq!(book.get_retainer(retainer_id).unwrap().budget, 2 * BUDGET);
+            assert_eq!(book.get_retainer_count(), 1);
+        }
+
+        #[ink::test]
+        fn deliverables_cannot_overdraw_the_budget_or_allowance() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut book, retainer_id) = book_with_retainer();
+            let hash = H256::from([1; 32]);
+
+            assert_eq!(
+                book.submit_deliverable(retainer_id, ALLOWANCE, hash),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                book.submit_deliverable(retainer_id + 1, ALLOWANCE, hash),
+                Err(Error::RetainerNotFound)
+            );
+            assert_eq!(
+                book.submit_deliverable(retainer_id, 0, hash),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                book.submit_deliverable(retainer_id, BUDGET + 1, hash),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                book.submit_deliverable(retainer_id, ALLOWANCE + 1, hash),
+                Err(Error::AllowanceExceeded)
+            );
+            assert_eq!(book.get_retainer_deliverables(retainer_id), vec![]);
+            assert_eq!(book.get_retainer(retainer_id).unwrap().budget, BUDGET);
+        }
+
+        #[ink::test]
+        fn terminating_early_refunds_the_unused_budget() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut book, retainer_id) = book_with_retainer();
+
+            ink::env::test::set_caller(accounts.eve);
+            assert_eq!(book.terminate(retainer_id), Err(Error::Unauthorized));
+
+            // Either party may end the engagement
+            let before = balance_of(accounts.alice);
+            ink::env::test::set_caller(accounts.bob);
+            book.terminate(retainer_id).unwrap();
+            assert_eq!(balance_of(accounts.alice), before + BUDGET);
+
+            let retainer = book.get_retainer(retainer_id).unwrap();
+            assert_eq!(retainer.status, RetainerStatus::Terminated);
+            assert_eq!(retainer.budget, 0);
+            assert_eq!(book.get_available_allowance(retainer_id), Ok(0));
+            assert_eq!(book.terminate(retainer_id), Err(Error::InvalidStatus));
+            assert_eq!(
+                book.submit_deliverable(retainer_id, 1, H256::from([1; 32])),
+                Err(Error::InvalidStatus)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::{EscrowStatus, PaymentEscrow};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const AMOUNT: Balance = 1_000_000_000_000;
+        const ESCROW_TIMEOUT: u64 = 86_400_000;
+
+        #[ink_e2e::test]
+        async fn each_period_draws_a_fresh_allowance<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(ESCROW_TIMEOUT),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let book = client
+                .instantiate(
+                    "retainer",
+                    &ink_e2e::alice(),
+                    &mut RetainerBookRef::new(escrow.addr),
+                )
+                .submit()
+                .await
+                .expect("retainer instantiate failed");
+            let mut calls = book.call_builder::<RetainerBook>();
+
+            // A one millisecond period makes every block a new period
+            let retainer_id = client
+                .call(&ink_e2e::alice(), &calls.open_retainer(bob, AMOUNT, 1))
+                .value(2 * AMOUNT)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let first = client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.submit_deliverable(retainer_id, AMOUNT, H256::from([1; 32])),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.submit_deliverable(retainer_id, AMOUNT, H256::from([2; 32])),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let overdraw = client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.submit_deliverable(retainer_id, 1, H256::from([3; 32])),
+                )
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(overdraw, Err(Error::InvalidAmount));
+
+            let deliverable = client
+                .call(&ink_e2e::alice(), &calls.get_deliverable(first))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            let details = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.get_escrow(deliverable.escrow_id),
+                )
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.payee, bob);
+            assert_eq!(details.amount, AMOUNT);
+
+            client
+                .call(&ink_e2e::alice(), &calls.confirm_deliverable(first))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let details = client
+                .call(
+                    &ink_e2e::alice(),
+                    &escrow_calls.get_escrow(deliverable.escrow_id),
+                )
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Completed);
+
+            Ok(())
+        }
+    }
+}
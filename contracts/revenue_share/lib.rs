@@ -0,0 +1,562 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod revenue_share {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::env::call::FromAddr;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use payment_escrow::{EscrowStatus, PaymentEscrowRef};
+    use service_registry::{ServiceCategory, ServiceRegistryRef};
+
+    /// Shares are expressed in basis points and must add up to this
+    const TOTAL_BPS: u32 = 10_000;
+    /// Upper bound on members per agreement
+    const MAX_MEMBERS: usize = 20;
+
+    /// Different statuses of an agreement
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum AgreementStatus {
+        // Waiting for every member to accept
+        Proposed,
+        Active,
+    }
+
+    /// A provider taking part in an agreement
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Member {
+        pub account: H160,
+        pub share_bps: u32,
+        pub accepted: bool,
+        // When the member's exit takes effect, once requested
+        pub exit_at: Option<u64>,
+    }
+
+    /// A standing revenue split for one or more composite services
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Agreement {
+        pub id: u64,
+        pub members: Vec<Member>,
+        pub service_ids: Vec<u64>,
+        // Time between an exit request and it taking effect, in milliseconds
+        pub notice_period: u64,
+        pub status: AgreementStatus,
+        pub total_collected: Balance,
+        pub created_at: u64,
+    }
+
+    /// Events
+    /// Emitted when an agreement is proposed
+    #[ink(event)]
+    pub struct AgreementProposed {
+        #[ink(topic)]
+        agreement_id: u64,
+        proposer: H160,
+    }
+    /// Emitted when every member has accepted an agreement
+    #[ink(event)]
+    pub struct AgreementActivated {
+        #[ink(topic)]
+        agreement_id: u64,
+    }
+    /// Emitted when a composite service is listed for an agreement
+    #[ink(event)]
+    pub struct CompositeServiceListed {
+        #[ink(topic)]
+        agreement_id: u64,
+        #[ink(topic)]
+        service_id: u64,
+    }
+    /// Emitted when a released escrow is split between members
+    #[ink(event)]
+    pub struct RevenueDistributed {
+        #[ink(topic)]
+        agreement_id: u64,
+        #[ink(topic)]
+        escrow_id: u64,
+        amount: Balance,
+    }
+    /// Emitted when a member gives notice to leave
+    #[ink(event)]
+    pub struct ExitRequested {
+        #[ink(topic)]
+        agreement_id: u64,
+        #[ink(topic)]
+        member: H160,
+        exit_at: u64,
+    }
+    /// Emitted when a member withdraws their balance
+    #[ink(event)]
+    pub struct Withdrawn {
+        #[ink(topic)]
+        member: H160,
+        amount: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the agreement is not found
+        AgreementNotFound,
+        /// Emitted when the escrow is not found
+        EscrowNotFound,
+        /// Emitted when the agreement is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not a member of the agreement
+        NotMember,
+        /// Emitted when the escrow does not pay one of the agreement's services
+        NotCompositeEscrow,
+        /// Emitted when the escrow has not been released yet
+        EscrowNotReleased,
+        /// Emitted when the escrow was already distributed
+        AlreadyCollected,
+        /// Emitted when the last remaining member tries to leave
+        LastMember,
+        /// Emitted when there is nothing to withdraw
+        NothingToWithdraw,
+        /// Emitted when a call into the registry fails
+        RegistryCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::AgreementNotFound => HubError::NotFound,
+                Error::EscrowNotFound => HubError::NotFound,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::NotMember => HubError::Unauthorized,
+                Error::NotCompositeEscrow => HubError::InvalidStatus,
+                Error::EscrowNotReleased => HubError::InvalidStatus,
+                Error::AlreadyCollected => HubError::AlreadyExists,
+                Error::LastMember => HubError::InvalidStatus,
+                Error::NothingToWithdraw => HubError::InsufficientFunds,
+                Error::RegistryCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct RevenueShare {
+        registry: H160,
+        escrow: H160,
+        agreements: Mapping<u64, Agreement>,
+        agreement_count: u64,
+        service_agreements: Mapping<u64, u64>,
+        collected: Mapping<u64, ()>,
+        // Withdrawable balance per member, across agreements
+        balances: Mapping<H160, Balance>,
+        // Lifetime earnings per member per agreement
+        earned: Mapping<(u64, H160), Balance>,
+    }
+
+    impl RevenueShare {
+        #[ink(constructor)]
+        pub fn new(registry: H160, escrow: H160) -> Self {
+            Self {
+                registry,
+                escrow,
+                agreements: Mapping::default(),
+                agreement_count: 0,
+                service_agreements: Mapping::default(),
+                collected: Mapping::default(),
+                balances: Mapping::default(),
+                earned: Mapping::default(),
+            }
+        }
+
+        /// Propose a split between providers; the proposer must be a member
+        /// and shares must add up to 100%
+        #[ink(message)]
+        pub fn propose_agreement(
+            &mut self,
+            shares: Vec<(H160, u32)>,
+            notice_period: u64,
+        ) -> Result<u64> {
+            let proposer = self.env().caller();
+            if shares.is_empty() || shares.len() > MAX_MEMBERS {
+                return Err(Error::InvalidInput);
+            }
+
+            let mut total = 0u32;
+            let mut members: Vec<Member> = Vec::with_capacity(shares.len());
+            for (account, share_bps) in shares {
+                if share_bps == 0 || members.iter().any(|m| m.account == account) {
+                    return Err(Error::InvalidInput);
+                }
+                total = total.checked_add(share_bps).ok_or(Error::Overflow)?;
+                members.push(Member {
+                    account,
+                    share_bps,
+                    accepted: account == proposer,
+                    exit_at: None,
+                });
+            }
+            if total != TOTAL_BPS || !members.iter().any(|m| m.account == proposer) {
+                return Err(Error::InvalidInput);
+            }
+
+            self.agreement_count = self.agreement_count.checked_add(1).ok_or(Error::Overflow)?;
+            let agreement_id = self.agreement_count;
+
+            let status = if members.iter().all(|m| m.accepted) {
+                AgreementStatus::Active
+            } else {
+                AgreementStatus::Proposed
+            };
+            let agreement = Agreement {
+                id: agreement_id,
+                members,
+                service_ids: Vec::new(),
+                notice_period,
+                status,
+                total_collected: 0,
+                created_at: self.env().block_timestamp(),
+            };
+            self.agreements.insert(agreement_id, &agreement);
+
+            self.env().emit_event(AgreementProposed {
+                agreement_id,
+                proposer,
+            });
+
+            Ok(agreement_id)
+        }
+
+        /// Accept a proposed agreement; it activates once everyone accepted
+        #[ink(message)]
+        pub fn accept_agreement(&mut self, agreement_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut agreement = self.get_agreement(agreement_id)?;
+            if agreement.status != AgreementStatus::Proposed {
+                return Err(Error::InvalidStatus);
+            }
+            let member = agreement
+                .members
+                .iter_mut()
+                .find(|m| m.account == caller)
+                .ok_or(Error::NotMember)?;
+            member.accepted = true;
+
+            if agreement.members.iter().all(|m| m.accepted) {
+                agreement.status = AgreementStatus::Active;
+                self.env().emit_event(AgreementActivated { agreement_id });
+            }
+            self.agreements.insert(agreement_id, &agreement);
+
+            Ok(())
+        }
+
+        /// List a composite service in the registry on behalf of an agreement
+        ///
+        /// This contract becomes the service's provider, so escrows for it
+        /// pay out here and can be split with `collect`.
+        #[ink(message)]
+        pub fn list_composite_service(
+            &mut self,
+            agreement_id: u64,
+            name: String,
+            description: String,
+            category: ServiceCategory,
+            price: Balance,
+            endpoint: String,
+        ) -> Result<u64> {
+            let mut agreement = self.get_active_membership(agreement_id)?;
+
+            let mut registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service_id = registry
+                .register_service(
+                    name,
+                    description,
+                    category,
+                    price,
+                    endpoint,
+                    PaymentAsset::Native,
+                    None,
+                )
+                .map_err(|_| Error::RegistryCallFailed)?;
+
+            agreement.service_ids.push(service_id);
+            self.agreements.insert(agreement_id, &agreement);
+            self.service_agreements.insert(service_id, &agreement_id);
+
+            self.env().emit_event(CompositeServiceListed {
+                agreement_id,
+                service_id,
+            });
+
+            Ok(service_id)
+        }
+
+        /// Pause or resume one of the agreement's composite services
+        #[ink(message)]
+        pub fn set_composite_service_status(
+            &mut self,
+            agreement_id: u64,
+            service_id: u64,
+            is_active: bool,
+        ) -> Result<()> {
+            let agreement = self.get_active_membership(agreement_id)?;
+            if !agreement.service_ids.contains(&service_id) {
+                return Err(Error::InvalidInput);
+            }
+
+            let mut registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            registry
+                .update_service_status(service_id, is_active)
+                .map_err(|_| Error::RegistryCallFailed)
+        }
+
+        /// Split a released escrow for a composite service between members
+        ///
+        /// Anyone may call this. Members whose exit has taken effect are left
+        /// out and the remaining shares are scaled up to cover them.
+        #[ink(message)]
+        pub fn collect(&mut self, escrow_id: u64) -> Result<()> {
+            if self.collected.contains(escrow_id) {
+                return Err(Error::AlreadyCollected);
+            }
+            let escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let details = escrow
+                .get_escrow(escrow_id)
+                .map_err(|_| Error::EscrowNotFound)?;
+            if details.payee != self.env().address() {
+                return Err(Error::NotCompositeEscrow);
+            }
+            if details.status != EscrowStatus::Completed {
+                return Err(Error::EscrowNotReleased);
+            }
+            let agreement_id = self
+                .service_agreements
+                .get(details.service_id)
+                .ok_or(Error::NotCompositeEscrow)?;
+            let mut agreement = self.get_agreement(agreement_id)?;
+
+            let now = self.env().block_timestamp();
+            for (account, amount) in split(details.amount, &agreement.members, now) {
+                let balance = self
+                    .balances
+                    .get(account)
+                    .unwrap_or(0)
+                    .checked_add(amount)
+                    .ok_or(Error::Overflow)?;
+                self.balances.insert(account, &balance);
+
+                let earned = self
+                    .earned
+                    .get((agreement_id, account))
+                    .unwrap_or(0)
+                    .checked_add(amount)
+                    .ok_or(Error::Overflow)?;
+                self.earned.insert((agreement_id, account), &earned);
+            }
+
+            agreement.total_collected = agreement
+                .total_collected
+                .checked_add(details.amount)
+                .ok_or(Error::Overflow)?;
+            self.agreements.insert(agreement_id, &agreement);
+            self.collected.insert(escrow_id, &());
+
+            self.env().emit_event(RevenueDistributed {
+                agreement_id,
+                escrow_id,
+                amount: details.amount,
+            });
+
+            Ok(())
+        }
+
+        /// Give notice to leave an agreement
+        ///
+        /// The member keeps earning until the notice period ends and can
+        /// always withdraw what they already earned.
+        #[ink(message)]
+        pub fn request_exit(&mut self, agreement_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut agreement = self.get_agreement(agreement_id)?;
+            let remaining = agreement
+                .members
+                .iter()
+                .filter(|m| m.exit_at.is_none())
+                .count();
+
+            let exit_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(agreement.notice_period);
+            let member = agreement
+                .members
+                .iter_mut()
+                .find(|m| m.account == caller)
+                .ok_or(Error::NotMember)?;
+            if member.exit_at.is_some() {
+                return Err(Error::InvalidStatus);
+            }
+            if remaining <= 1 {
+                return Err(Error::LastMember);
+            }
+            member.exit_at = Some(exit_at);
+            self.agreements.insert(agreement_id, &agreement);
+
+            self.env().emit_event(ExitRequested {
+                agreement_id,
+                member: caller,
+                exit_at,
+            });
+
+            Ok(())
+        }
+
+        /// Withdraw the caller's accumulated revenue
+        #[ink(message)]
+        pub fn withdraw(&mut self) -> Result<Balance> {
+            let member = self.env().caller();
+            let amount = self.balances.get(member).unwrap_or(0);
+            if amount == 0 {
+                return Err(Error::NothingToWithdraw);
+            }
+
+            self.balances.remove(member);
+            if self.env().transfer(member, amount.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+
+            self.env().emit_event(Withdrawn { member, amount });
+
+            Ok(amount)
+        }
+
+        /// Get agreement details
+        #[ink(message)]
+        pub fn get_agreement(&self, agreement_id: u64) -> Result<Agreement> {
+            self.agreements
+                .get(agreement_id)
+                .ok_or(Error::AgreementNotFound)
+        }
+
+        /// Get the agreement a composite service belongs to
+        #[ink(message)]
+        pub fn get_service_agreement(&self, service_id: u64) -> Option<u64> {
+            self.service_agreements.get(service_id)
+        }
+
+        /// Withdrawable balance of a member
+        #[ink(message)]
+        pub fn get_balance(&self, member: H160) -> Balance {
+            self.balances.get(member).unwrap_or(0)
+        }
+
+        /// Lifetime earnings of a member under an agreement
+        #[ink(message)]
+        pub fn get_earned(&self, agreement_id: u64, member: H160) -> Balance {
+            self.earned.get((agreement_id, member)).unwrap_or(0)
+        }
+
+        /// Check if an escrow was already distributed
+        #[ink(message)]
+        pub fn is_collected(&self, escrow_id: u64) -> bool {
+            self.collected.contains(escrow_id)
+        }
+
+        fn get_active_membership(&self, agreement_id: u64) -> Result<Agreement> {
+            let agreement = self.get_agreement(agreement_id)?;
+            if agreement.status != AgreementStatus::Active {
+                return Err(Error::InvalidStatus);
+            }
+            let caller = self.env().caller();
+            if !agreement.members.iter().any(|m| m.account == caller) {
+                return Err(Error::NotMember);
+            }
+            Ok(agreement)
+        }
+    }
+
+    /// Split an amount between members still in the agreement at `now`
+    ///
+    /// Rounding dust goes to the first remaining member.
+    fn split(amount: Balance, members: &[Member], now: u64) -> Vec<(H160, Balance)> {
+        let remaining: Vec<&Member> = members
+            .iter()
+            .filter(|m| m.exit_at.is_none_or(|exit_at| now < exit_at))
+            .collect();
+        let total_bps: Balance = remaining.iter().map(|m| Balance::from(m.share_bps)).sum();
+        if total_bps == 0 {
+            return Vec::new();
+        }
+
+        let mut shares: Vec<(H160, Balance)> = remaining
+            .iter()
+            .map(|m| {
+                (
+                    m.account,
+                    amount.saturating_mul(Balance::from(m.share_bps)) / total_bps,
+                )
+            })
+            .collect();
+        let distributed: Balance = shares.iter().map(|(_, share)| *share).sum();
+        if let Some((_, first)) = shares.first_mut() {
+            *first += amount - distributed;
+        }
+        shares
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn member(account: H160, share_bps: u32, exit_at: Option<u64>) -> Member {
+            Member {
+                account,
+                share_bps,
+                accepted: true,
+                exit_at,
+            }
+        }
+
+        #[test]
+        fn split_follows_shares() {
+            let a = H160::from([0x01; 20]);
+            let b = H160::from([0x02; 20]);
+            let members = [member(a, 7_000, None), member(b, 3_000, None)];
+
+            assert_eq!(split(1_000, &members, 0), vec![(a, 700), (b, 300)]);
+            assert_eq!(split(1_001, &members, 0), vec![(a, 701), (b, 300)]);
+        }
+
+        #[test]
+        fn exited_members_are_left_out() {
+            let a = H160::from([0x01; 20]);
+            let b = H160::from([0x02; 20]);
+            let c = H160::from([0x03; 20]);
+            let members = [
+                member(a, 5_000, None),
+                member(b, 2_500, Some(100)),
+                member(c, 2_500, None),
+            ];
+
+            // Still within the notice period
+            assert_eq!(split(1_000, &members, 99).len(), 3);
+            assert_eq!(split(1_000, &members, 100), vec![(a, 667), (c, 333)]);
+        }
+    }
+}
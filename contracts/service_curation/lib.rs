@@ -0,0 +1,619 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::service_curation::{Error, ServiceCurationRef};
+
+#[ink::contract]
+mod service_curation {
+    use hub_types::HubError;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// A service applying to, or on, the verified list
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Listing {
+        pub service_id: u64,
+        pub applicant: H160,
+        pub deposit: Balance,
+        pub application_ends: u64,
+        pub listed: bool,
+        pub challenge_id: Option<u64>,
+    }
+
+    /// A challenge against a listing, decided by staked votes
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Challenge {
+        pub id: u64,
+        pub service_id: u64,
+        pub challenger: H160,
+        pub deposit: Balance,
+        pub voting_ends: u64,
+        pub votes_keep: Balance,
+        pub votes_remove: Balance,
+        // Outcome once resolved: `Some(true)` when the listing was kept
+        pub listing_kept: Option<bool>,
+    }
+
+    /// Events
+    /// Emitted when a service applies for the verified list
+    #[ink(event)]
+    pub struct Applied {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        applicant: H160,
+        deposit: Balance,
+        application_ends: u64,
+    }
+    /// Emitted when a listing is challenged
+    #[ink(event)]
+    pub struct Challenged {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        challenge_id: u64,
+        challenger: H160,
+        voting_ends: u64,
+    }
+    /// Emitted when a staked vote is cast
+    #[ink(event)]
+    pub struct Voted {
+        #[ink(topic)]
+        challenge_id: u64,
+        #[ink(topic)]
+        voter: H160,
+        keep: bool,
+        stake: Balance,
+    }
+    /// Emitted when a challenge is resolved
+    #[ink(event)]
+    pub struct ChallengeResolved {
+        #[ink(topic)]
+        challenge_id: u64,
+        #[ink(topic)]
+        service_id: u64,
+        listing_kept: bool,
+    }
+    /// Emitted when a service is added to or removed from the verified list
+    #[ink(event)]
+    pub struct ListingUpdated {
+        #[ink(topic)]
+        service_id: u64,
+        listed: bool,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when the deposit is below the minimum
+        InsufficientDeposit,
+        /// Emitted when the stake is zero
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the listing is not found
+        ListingNotFound,
+        /// Emitted when the challenge is not found
+        ChallengeNotFound,
+        /// Emitted when the service already has a listing
+        AlreadyListed,
+        /// Emitted when the listing is already being challenged
+        AlreadyChallenged,
+        /// Emitted when the listing or challenge is in the wrong state
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the relevant period has not ended
+        PeriodNotOver,
+        /// Emitted when the voting period has ended
+        VotingClosed,
+        /// Emitted when the voter already voted on the challenge
+        AlreadyVoted,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientDeposit => HubError::InvalidAmount,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::ListingNotFound => HubError::NotFound,
+                Error::ChallengeNotFound => HubError::NotFound,
+                Error::AlreadyListed => HubError::AlreadyExists,
+                Error::AlreadyChallenged => HubError::AlreadyExists,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::PeriodNotOver => HubError::TooEarly,
+                Error::VotingClosed => HubError::Expired,
+                Error::AlreadyVoted => HubError::AlreadyExists,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct ServiceCuration {
+        min_deposit: Balance,
+        // Unchallenged time before an application is listed, in milliseconds
+        application_period: u64,
+        voting_period: u64,
+        listings: Mapping<u64, Listing>,
+        challenges: Mapping<u64, Challenge>,
+        challenge_count: u64,
+        votes: Mapping<(u64, H160), (bool, Balance)>,
+    }
+
+    impl ServiceCuration {
+        #[ink(constructor)]
+        pub fn new(min_deposit: Balance, application_period: u64, voting_period: u64) -> Self {
+            Self {
+                min_deposit,
+                application_period,
+                voting_period,
+                listings: Mapping::default(),
+                challenges: Mapping::default(),
+                challenge_count: 0,
+                votes: Mapping::default(),
+            }
+        }
+
+        /// Apply for a service to join the verified list, staking a deposit
+        #[ink(message, payable)]
+        pub fn apply(&mut self, service_id: u64) -> Result<()> {
+            let applicant = self.env().caller();
+            let deposit = self.transferred();
+            if deposit < self.min_deposit {
+                return Err(Error::InsufficientDeposit);
+            }
+            if self.listings.contains(service_id) {
+                return Err(Error::AlreadyListed);
+            }
+
+            let application_ends = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.application_period);
+            let listing = Listing {
+                service_id,
+                applicant,
+                deposit,
+                application_ends,
+                listed: false,
+                challenge_id: None,
+            };
+            self.listings.insert(service_id, &listing);
+
+            self.env().emit_event(Applied {
+                service_id,
+                applicant,
+                deposit,
+                application_ends,
+            });
+
+            Ok(())
+        }
+
+        /// List an application that went unchallenged through its period
+        #[ink(message)]
+        pub fn finalize_application(&mut self, service_id: u64) -> Result<()> {
+            let mut listing = self.get_listing(service_id)?;
+            if listing.listed || listing.challenge_id.is_some() {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() < listing.application_ends {
+                return Err(Error::PeriodNotOver);
+            }
+
+            listing.listed = true;
+            self.listings.insert(service_id, &listing);
+
+            self.env().emit_event(ListingUpdated {
+                service_id,
+                listed: true,
+            });
+
+            Ok(())
+        }
+
+        /// Challenge an application or listing, matching its deposit
+        #[ink(message, payable)]
+        pub fn challenge(&mut self, service_id: u64) -> Result<u64> {
+            let challenger = self.env().caller();
+            let deposit = self.transferred();
+            let mut listing = self.get_listing(service_id)?;
+            if listing.challenge_id.is_some() {
+                return Err(Error::AlreadyChallenged);
+            }
+            if deposit < listing.deposit {
+                return Err(Error::InsufficientDeposit);
+            }
+
+            self.challenge_count = self.challenge_count.checked_add(1).ok_or(Error::Overflow)?;
+            let challenge_id = self.challenge_count;
+
+            let voting_ends = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.voting_period);
+            let challenge = Challenge {
+                id: challenge_id,
+                service_id,
+                challenger,
+                deposit,
+                voting_ends,
+                votes_keep: 0,
+                votes_remove: 0,
+                listing_kept: None,
+            };
+            self.challenges.insert(challenge_id, &challenge);
+
+            listing.challenge_id = Some(challenge_id);
+            self.listings.insert(service_id, &listing);
+
+            self.env().emit_event(Challenged {
+                service_id,
+                challenge_id,
+                challenger,
+                voting_ends,
+            });
+
+            Ok(challenge_id)
+        }
+
+        /// Vote on a challenge, staking the transferred value as weight
+        ///
+        /// Stakes are locked until the challenge resolves and then returned
+        /// with `withdraw_vote`.
+        #[ink(message, payable)]
+        pub fn vote(&mut self, challenge_id: u64, keep: bool) -> Result<()> {
+            let voter = self.env().caller();
+            let stake = self.transferred();
+            if stake == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            let mut challenge = self.get_challenge(challenge_id)?;
+            if self.env().block_timestamp() >= challenge.voting_ends {
+                return Err(Error::VotingClosed);
+            }
+            if self.votes.contains((challenge_id, voter)) {
+                return Err(Error::AlreadyVoted);
+            }
+
+            if keep {
+                challenge.votes_keep = challenge
+                    .votes_keep
+                    .checked_add(stake)
+                    .ok_or(Error::Overflow)?;
+            } else {
+                challenge.votes_remove = challenge
+                    .votes_remove
+                    .checked_add(stake)
+                    .ok_or(Error::Overflow)?;
+            }
+            self.challenges.insert(challenge_id, &challenge);
+            self.votes.insert((challenge_id, voter), &(keep, stake));
+
+            self.env().emit_event(Voted {
+                challenge_id,
+                voter,
+                keep,
+                stake,
+            });
+
+            Ok(())
+        }
+
+        /// Resolve a challenge once voting ends
+        ///
+        /// Ties keep the listing. The losing side's deposit goes to the
+        /// winner; a removed service leaves the list entirely.
+        #[ink(message)]
+        pub fn resolve_challenge(&mut self, challenge_id: u64) -> Result<()> {
+            let mut challenge = self.get_challenge(challenge_id)?;
+            if challenge.listing_kept.is_some() {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() < challenge.voting_ends {
+                return Err(Error::PeriodNotOver);
+            }
+            let mut listing = self.get_listing(challenge.service_id)?;
+
+            let listing_kept = challenge.votes_keep >= challenge.votes_remove;
+            let pot = listing
+                .deposit
+                .checked_add(challenge.deposit)
+                .ok_or(Error::Overflow)?;
+            if listing_kept {
+                // The applicant's stake stays on the listing, plus the
+                // challenger's deposit as reward
+                if self
+                    .env()
+                    .transfer(listing.applicant, challenge.deposit.into())
+                    .is_err()
+                {
+                    return Err(Error::TransferFailed);
+                }
+                listing.listed = true;
+                listing.challenge_id = None;
+                self.listings.insert(challenge.service_id, &listing);
+            } else {
+                if self
+                    .env()
+                    .transfer(challenge.challenger, pot.into())
+                    .is_err()
+                {
+                    return Err(Error::TransferFailed);
+                }
+                self.listings.remove(challenge.service_id);
+            }
+
+            challenge.listing_kept = Some(listing_kept);
+            self.challenges.insert(challenge_id, &challenge);
+
+            self.env().emit_event(ChallengeResolved {
+                challenge_id,
+                service_id: challenge.service_id,
+                listing_kept,
+            });
+            self.env().emit_event(ListingUpdated {
+                service_id: challenge.service_id,
+                listed: listing_kept,
+            });
+
+            Ok(())
+        }
+
+        /// Return a voter's stake once the challenge is resolved
+        #[ink(message)]
+        pub fn withdraw_vote(&mut self, challenge_id: u64) -> Result<Balance> {
+            let voter = self.env().caller();
+            let challenge = self.get_challenge(challenge_id)?;
+            if challenge.listing_kept.is_none() {
+                return Err(Error::PeriodNotOver);
+            }
+            let (_, stake) = self
+                .votes
+                .get((challenge_id, voter))
+                .ok_or(Error::Unauthorized)?;
+
+            self.votes.remove((challenge_id, voter));
+            if self.env().transfer(voter, stake.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+
+            Ok(stake)
+        }
+
+        /// Leave the list and take the deposit back
+        #[ink(message)]
+        pub fn exit(&mut self, service_id: u64) -> Result<()> {
+            let listing = self.get_listing(service_id)?;
+            if listing.applicant != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if listing.challenge_id.is_some() {
+                return Err(Error::AlreadyChallenged);
+            }
+
+            self.listings.remove(service_id);
+            if self
+                .env()
+                .transfer(listing.applicant, listing.deposit.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            self.env().emit_event(ListingUpdated {
+                service_id,
+                listed: false,
+            });
+
+            Ok(())
+        }
+
+        /// Check if a service is on the verified list
+        #[ink(message)]
+        pub fn is_verified(&self, service_id: u64) -> bool {
+            self.listings
+                .get(service_id)
+                .is_some_and(|listing| listing.listed)
+        }
+
+        /// Get listing details
+        #[ink(message)]
+        pub fn get_listing(&self, service_id: u64) -> Result<Listing> {
+            self.listings.get(service_id).ok_or(Error::ListingNotFound)
+        }
+
+        /// Get challenge details
+        #[ink(message)]
+        pub fn get_challenge(&self, challenge_id: u64) -> Result<Challenge> {
+            self.challenges
+                .get(challenge_id)
+                .ok_or(Error::ChallengeNotFound)
+        }
+
+        /// Get the minimum application deposit
+        #[ink(message)]
+        pub fn get_min_deposit(&self) -> Balance {
+            self.min_deposit
+        }
+
+        fn transferred(&self) -> Balance {
+            self.env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::{balance_of, deploy_at, paying};
+
+        const DEPOSIT: Balance = 100;
+        const APPLICATION_PERIOD: u64 = 1_000;
+        const VOTING_PERIOD: u64 = 500;
+        const SERVICE: u64 = 7;
+
+        /// An empty curation list in which bob applied for `SERVICE`
+        fn curation_with_application() -> ServiceCuration {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xC5; 20]), 0);
+
+            ink::env::test::set_caller(accounts.alice);
+            let mut curation = ServiceCuration::new(DEPOSIT, APPLICATION_PERIOD, VOTING_PERIOD);
+            paying(accounts.bob, DEPOSIT, || curation.apply(SERVICE)).unwrap();
+            curation
+        }
+
+        #[ink::test]
+        fn unchallenged_applications_are_listed_and_can_exit() {
+            let accounts = ink::env::test::default_accounts();
+            let mut curation = curation_with_application();
+
+            assert_eq!(
+                paying(accounts.charlie, DEPOSIT - 1, || curation
+                    .apply(SERVICE + 1)),
+                Err(Error::InsufficientDeposit)
+            );
+            assert_eq!(
+                paying(accounts.charlie, DEPOSIT, || curation.apply(SERVICE)),
+                Err(Error::AlreadyListed)
+            );
+
+            assert_eq!(
+                curation.finalize_application(SERVICE),
+                Err(Error::PeriodNotOver)
+            );
+            ink::env::test::set_block_timestamp(APPLICATION_PERIOD);
+            curation.finalize_application(SERVICE).unwrap();
+            assert!(curation.is_verified(SERVICE));
+            assert_eq!(
+                curation.finalize_application(SERVICE),
+                Err(Error::InvalidStatus)
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(curation.exit(SERVICE), Err(Error::Unauthorized));
+            let before = balance_of(accounts.bob);
+            ink::env::test::set_caller(accounts.bob);
+            curation.exit(SERVICE).unwrap();
+            assert_eq!(balance_of(accounts.bob), before + DEPOSIT);
+            assert!(!curation.is_verified(SERVICE));
+            assert_eq!(curation.get_listing(SERVICE), Err(Error::ListingNotFound));
+        }
+
+        #[ink::test]
+        fn losing_a_challenge_slashes_the_applicants_deposit() {
+            let accounts = ink::env::test::default_accounts();
+            let mut curation = curation_with_application();
+
+            assert_eq!(
+                paying(accounts.charlie, DEPOSIT - 1, || curation
+                    .challenge(SERVICE)),
+                Err(Error::InsufficientDeposit)
+            );
+            let challenge_id =
+                paying(accounts.charlie, DEPOSIT, || curation.challenge(SERVICE)).unwrap();
+            assert_eq!(
+                paying(accounts.alice, DEPOSIT, || curation.challenge(SERVICE)),
+                Err(Error::AlreadyChallenged)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(curation.exit(SERVICE), Err(Error::AlreadyChallenged));
+
+            paying(accounts.alice, 300, || curation.vote(challenge_id, false)).unwrap();
+            paying(accounts.bob, 100, || curation.vote(challenge_id, true)).unwrap();
+            assert_eq!(
+                paying(accounts.alice, 1, || curation.vote(challenge_id, true)),
+                Err(Error::AlreadyVoted)
+            );
+            assert_eq!(
+                paying(accounts.charlie, 0, || curation.vote(challenge_id, false)),
+                Err(Error::InvalidAmount)
+            );
+            assert_eq!(
+                curation.resolve_challenge(challenge_id),
+                Err(Error::PeriodNotOver)
+            );
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                curation.withdraw_vote(challenge_id),
+                Err(Error::PeriodNotOver)
+            );
+
+            ink::env::test::set_block_timestamp(VOTING_PERIOD);
+            assert_eq!(
+                paying(accounts.charlie, 1, || curation.vote(challenge_id, false)),
+                Err(Error::VotingClosed)
+            );
+            let before = balance_of(accounts.charlie);
+            curation.resolve_challenge(challenge_id).unwrap();
+            assert_eq!(balance_of(accounts.charlie), before + 2 * DEPOSIT);
+            assert_eq!(
+                curation.get_challenge(challenge_id).unwrap().listing_kept,
+                Some(false)
+            );
+            assert_eq!(curation.get_listing(SERVICE), Err(Error::ListingNotFound));
+            assert_eq!(
+                curation.resolve_challenge(challenge_id),
+                Err(Error::InvalidStatus)
+            );
+
+            // Voters on both sides get their stakes back, once
+            let before = balance_of(accounts.alice);
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(curation.withdraw_vote(challenge_id), Ok(300));
+            assert_eq!(balance_of(accounts.alice), before + 300);
+            assert_eq!(
+                curation.withdraw_vote(challenge_id),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(curation.withdraw_vote(challenge_id), Ok(100));
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                curation.withdraw_vote(challenge_id),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn surviving_a_challenge_rewards_the_applicant() {
+            let accounts = ink::env::test::default_accounts();
+            let mut curation = curation_with_application();
+            let challenge_id =
+                paying(accounts.charlie, DEPOSIT, || curation.challenge(SERVICE)).unwrap();
+
+            // Ties keep the listing
+            paying(accounts.alice, 200, || curation.vote(challenge_id, false)).unwrap();
+            paying(accounts.bob, 200, || curation.vote(challenge_id, true)).unwrap();
+
+            ink::env::test::set_block_timestamp(VOTING_PERIOD);
+            let before = balance_of(accounts.bob);
+            curation.resolve_challenge(challenge_id).unwrap();
+            assert_eq!(balance_of(accounts.bob), before + DEPOSIT);
+
+            let listing = curation.get_listing(SERVICE).unwrap();
+            assert!(listing.listed);
+            assert_eq!(listing.challenge_id, None);
+            assert_eq!(listing.deposit, DEPOSIT);
+            assert!(curation.is_verified(SERVICE));
+            assert_eq!(
+                curation.get_challenge(challenge_id + 1),
+                Err(Error::ChallengeNotFound)
+            );
+        }
+    }
+}
@@ -0,0 +1,448 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod service_index {
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use service_registry::{Service, ServiceCategory, ServiceIndexHook, ServiceRegistryRef};
+
+    /// Upper bound on results returned by one query
+    const MAX_PAGE_SIZE: u32 = 50;
+    /// Upper bound on tags per service
+    const MAX_TAGS: usize = 8;
+    /// Upper bound on the length of a single tag
+    const MAX_TAG_LEN: usize = 32;
+    /// Width of a reputation bucket on the registry's 0 - 100 scale
+    const REPUTATION_BUCKET_WIDTH: u32 = 10;
+
+    /// Where a service currently sits in the indexes
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct IndexEntry {
+        pub category: ServiceCategory,
+        pub price: Balance,
+        // Provider reputation when the service was last indexed
+        pub reputation: u32,
+        pub price_bucket: u8,
+        pub reputation_bucket: u8,
+        pub is_active: bool,
+    }
+
+    /// Events
+    /// Emitted when a service is (re-)indexed
+    #[ink(event)]
+    pub struct ServiceIndexed {
+        #[ink(topic)]
+        service_id: u64,
+        entry: IndexEntry,
+    }
+    /// Emitted when a provider replaces a service's tags
+    #[ink(event)]
+    pub struct TagsUpdated {
+        #[ink(topic)]
+        service_id: u64,
+        tags: Vec<String>,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the service is not found in the registry
+        ServiceNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::ServiceNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct ServiceIndex {
+        // Only the registry may push listing changes
+        registry: H160,
+        entries: Mapping<u64, IndexEntry>,
+        // Indexes only hold active services
+        by_category: Mapping<ServiceCategory, Vec<u64>>,
+        by_price_bucket: Mapping<u8, Vec<u64>>,
+        by_reputation_bucket: Mapping<u8, Vec<u64>>,
+        by_tag: Mapping<String, Vec<u64>>,
+        service_tags: Mapping<u64, Vec<String>>,
+    }
+
+    impl ServiceIndex {
+        #[ink(constructor)]
+        pub fn new(registry: H160) -> Self {
+            Self {
+                registry,
+                entries: Mapping::default(),
+                by_category: Mapping::default(),
+                by_price_bucket: Mapping::default(),
+                by_reputation_bucket: Mapping::default(),
+                by_tag: Mapping::default(),
+                service_tags: Mapping::default(),
+            }
+        }
+
+        /// Replace the tags of one of the caller's services
+        #[ink(message)]
+        pub fn set_service_tags(&mut self, service_id: u64, tags: Vec<String>) -> Result<()> {
+            if tags.len() > MAX_TAGS
+                || tags
+                    .iter()
+                    .any(|tag| tag.is_empty() || tag.len() > MAX_TAG_LEN)
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service = registry
+                .get_service(service_id)
+                .map_err(|_| Error::ServiceNotFound)?;
+            if service.provider != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+
+            let is_active = self
+                .entries
+                .get(service_id)
+                .is_some_and(|entry| entry.is_active);
+            for tag in self.service_tags.get(service_id).unwrap_or_default() {
+                remove_from(&mut self.by_tag, tag, service_id);
+            }
+            if is_active {
+                for tag in tags.iter() {
+                    add_to(&mut self.by_tag, tag.clone(), service_id);
+                }
+            }
+
+            if tags.is_empty() {
+                self.service_tags.remove(service_id);
+            } else {
+                self.service_tags.insert(service_id, &tags);
+            }
+
+            self.env().emit_event(TagsUpdated { service_id, tags });
+
+            Ok(())
+        }
+
+        /// Active services in a category
+        #[ink(message)]
+        pub fn get_by_category(
+            &self,
+            category: ServiceCategory,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<u64> {
+            page(
+                self.by_category.get(category).unwrap_or_default(),
+                offset,
+                limit,
+            )
+        }
+
+        /// Active services in a category priced at most `max_price` whose
+        /// provider has at least `min_reputation`
+        ///
+        /// `offset` and `limit` count matching services, in the order they
+        /// entered the category.
+        #[ink(message)]
+        pub fn search_services(
+            &self,
+            category: ServiceCategory,
+            max_price: Balance,
+            min_reputation: u32,
+            offset: u32,
+            limit: u32,
+        ) -> Vec<u64> {
+            self.by_category
+                .get(category)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|service_id| {
+                    self.entries.get(service_id).is_some_and(|entry| {
+                        entry.price <= max_price && entry.reputation >= min_reputation
+                    })
+                })
+                .skip(offset as usize)
+                .take(limit.min(MAX_PAGE_SIZE) as usize)
+                .collect()
+        }
+
+        /// Active services carrying a tag
+        #[ink(message)]
+        pub fn get_by_tag(&self, tag: String, offset: u32, limit: u32) -> Vec<u64> {
+            page(self.by_tag.get(tag).unwrap_or_default(), offset, limit)
+        }
+
+        /// Active services in a price bucket (see `price_bucket`)
+        #[ink(message)]
+        pub fn get_by_price_bucket(&self, bucket: u8, offset: u32, limit: u32) -> Vec<u64> {
+            page(
+                self.by_price_bucket.get(bucket).unwrap_or_default(),
+                offset,
+                limit,
+            )
+        }
+
+        /// Active services whose provider is in a reputation bucket
+        #[ink(message)]
+        pub fn get_by_reputation_bucket(&self, bucket: u8, offset: u32, limit: u32) -> Vec<u64> {
+            page(
+                self.by_reputation_bucket.get(bucket).unwrap_or_default(),
+                offset,
+                limit,
+            )
+        }
+
+        /// Price bucket a price falls into
+        #[ink(message)]
+        pub fn get_price_bucket(&self, price: Balance) -> u8 {
+            price_bucket(price)
+        }
+
+        /// Get where a service currently sits in the indexes
+        #[ink(message)]
+        pub fn get_entry(&self, service_id: u64) -> Option<IndexEntry> {
+            self.entries.get(service_id)
+        }
+
+        /// Get a service's tags
+        #[ink(message)]
+        pub fn get_service_tags(&self, service_id: u64) -> Vec<String> {
+            self.service_tags.get(service_id).unwrap_or_default()
+        }
+
+        /// Get the registry allowed to push listing changes
+        #[ink(message)]
+        pub fn get_registry(&self) -> H160 {
+            self.registry
+        }
+
+        /// Takes a service out of every index it is in
+        fn unindex(&mut self, service_id: u64, entry: &IndexEntry) {
+            if !entry.is_active {
+                return;
+            }
+            remove_from(&mut self.by_category, entry.category.clone(), service_id);
+            remove_from(&mut self.by_price_bucket, entry.price_bucket, service_id);
+            remove_from(
+                &mut self.by_reputation_bucket,
+                entry.reputation_bucket,
+                service_id,
+            );
+            for tag in self.service_tags.get(service_id).unwrap_or_default() {
+                remove_from(&mut self.by_tag, tag, service_id);
+            }
+        }
+
+        /// Adds a service to every index its entry places it in
+        fn reindex(&mut self, service_id: u64, entry: &IndexEntry) {
+            if !entry.is_active {
+                return;
+            }
+            add_to(&mut self.by_category, entry.category.clone(), service_id);
+            add_to(&mut self.by_price_bucket, entry.price_bucket, service_id);
+            add_to(
+                &mut self.by_reputation_bucket,
+                entry.reputation_bucket,
+                service_id,
+            );
+            for tag in self.service_tags.get(service_id).unwrap_or_default() {
+                add_to(&mut self.by_tag, tag, service_id);
+            }
+        }
+    }
+
+    impl ServiceIndexHook for ServiceIndex {
+        /// Move a service to the buckets matching its current state
+        ///
+        /// Calls from anyone but the registry are ignored.
+        #[ink(message)]
+        fn on_service_changed(&mut self, service: Service, reputation: u32) -> bool {
+            if self.env().caller() != self.registry {
+                return false;
+            }
+
+            let entry = IndexEntry {
+                category: service.category,
+                price: service.price,
+                reputation,
+                price_bucket: price_bucket(service.price),
+                reputation_bucket: reputation_bucket(reputation),
+                is_active: service.is_active,
+            };
+            if let Some(previous) = self.entries.get(service.id) {
+                if previous == entry {
+                    return true;
+                }
+                self.unindex(service.id, &previous);
+            }
+            self.reindex(service.id, &entry);
+            self.entries.insert(service.id, &entry);
+
+            self.env().emit_event(ServiceIndexed {
+                service_id: service.id,
+                entry,
+            });
+
+            true
+        }
+    }
+
+    /// Order-of-magnitude bucket of a price: the number of bits needed to
+    /// represent it, so bucket `n` holds prices in `[2^(n-1), 2^n)`
+    fn price_bucket(price: Balance) -> u8 {
+        (Balance::BITS - price.leading_zeros()) as u8
+    }
+
+    /// Decile of a 0 - 100 reputation score, with 100 sharing the top bucket
+    fn reputation_bucket(reputation: u32) -> u8 {
+        (reputation.min(99) / REPUTATION_BUCKET_WIDTH) as u8
+    }
+
+    fn add_to<K: ink::scale::Encode>(index: &mut Mapping<K, Vec<u64>>, key: K, service_id: u64) {
+        let mut ids = index.get(&key).unwrap_or_default();
+        if !ids.contains(&service_id) {
+            ids.push(service_id);
+            index.insert(&key, &ids);
+        }
+    }
+
+    fn remove_from<K: ink::scale::Encode>(
+        index: &mut Mapping<K, Vec<u64>>,
+        key: K,
+        service_id: u64,
+    ) {
+        let mut ids = index.get(&key).unwrap_or_default();
+        ids.retain(|id| *id != service_id);
+        if ids.is_empty() {
+            index.remove(&key);
+        } else {
+            index.insert(&key, &ids);
+        }
+    }
+
+    fn page(ids: Vec<u64>, offset: u32, limit: u32) -> Vec<u64> {
+        ids.into_iter()
+            .skip(offset as usize)
+            .take(limit.min(MAX_PAGE_SIZE) as usize)
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use service_registry::PaymentAsset;
+
+        fn service(id: u64, category: ServiceCategory, price: Balance, is_active: bool) -> Service {
+            Service {
+                id,
+                provider: H160::from([0x01; 20]),
+                name: String::from("Translator"),
+                description: String::from("Translates text"),
+                category,
+                price,
+                payment_asset: PaymentAsset::Native,
+                endpoint: String::from("https://example.com"),
+                is_active,
+                total_requests: 0,
+                successful_requests: 0,
+                created_at: 0,
+                x402_gateway_address: None,
+            }
+        }
+
+        #[test]
+        fn buckets_follow_magnitude() {
+            assert_eq!(price_bucket(0), 0);
+            assert_eq!(price_bucket(1), 1);
+            assert_eq!(price_bucket(1_000), 10);
+            assert_eq!(price_bucket(1_024), 11);
+            assert_eq!(reputation_bucket(0), 0);
+            assert_eq!(reputation_bucket(55), 5);
+            assert_eq!(reputation_bucket(100), 9);
+        }
+
+        #[ink::test]
+        fn changes_move_service_between_buckets() {
+            let accounts = ink::env::test::default_accounts();
+            let mut index = ServiceIndex::new(accounts.alice);
+
+            assert!(
+                index.on_service_changed(service(1, ServiceCategory::Translation, 1_000, true), 40)
+            );
+            assert_eq!(
+                index.get_by_category(ServiceCategory::Translation, 0, 10),
+                vec![1]
+            );
+            assert_eq!(index.get_by_price_bucket(10, 0, 10), vec![1]);
+            assert_eq!(index.get_by_reputation_bucket(4, 0, 10), vec![1]);
+
+            assert!(
+                index.on_service_changed(service(1, ServiceCategory::Translation, 5_000, true), 70)
+            );
+            assert!(index.get_by_price_bucket(10, 0, 10).is_empty());
+            assert_eq!(index.get_by_price_bucket(13, 0, 10), vec![1]);
+            assert!(index.get_by_reputation_bucket(4, 0, 10).is_empty());
+            assert_eq!(index.get_by_reputation_bucket(7, 0, 10), vec![1]);
+
+            assert!(index
+                .on_service_changed(service(1, ServiceCategory::Translation, 5_000, false), 70));
+            assert!(index
+                .get_by_category(ServiceCategory::Translation, 0, 10)
+                .is_empty());
+        }
+
+        #[ink::test]
+        fn search_filters_a_category_by_price_and_reputation() {
+            let accounts = ink::env::test::default_accounts();
+            let mut index = ServiceIndex::new(accounts.alice);
+
+            index.on_service_changed(service(1, ServiceCategory::Translation, 1_000, true), 90);
+            index.on_service_changed(service(2, ServiceCategory::Translation, 300, true), 40);
+            index.on_service_changed(service(3, ServiceCategory::Translation, 200, true), 80);
+            index.on_service_changed(service(4, ServiceCategory::Computation, 100, true), 95);
+
+            assert_eq!(
+                index.search_services(ServiceCategory::Translation, 500, 50, 0, 10),
+                vec![3]
+            );
+            assert_eq!(
+                index.search_services(ServiceCategory::Translation, Balance::MAX, 0, 1, 10),
+                vec![2, 3]
+            );
+            assert!(index
+                .search_services(ServiceCategory::Translation, 100, 0, 0, 10)
+                .is_empty());
+        }
+
+        #[ink::test]
+        fn only_registry_can_push_changes() {
+            let accounts = ink::env::test::default_accounts();
+            let mut index = ServiceIndex::new(accounts.alice);
+
+            ink::env::test::set_caller(accounts.bob);
+            assert!(!index
+                .on_service_changed(service(1, ServiceCategory::Translation, 1_000, true), 40));
+            assert_eq!(index.get_entry(1), None);
+        }
+    }
+}
@@ -1,26 +1,59 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::service_registry::parameters::{Parameter, Parameters};
+pub use self::service_registry::{
+    AcceptancePolicy, AwayNotice, DeactivationPolicy, Error, PriceChange, ProviderActivity,
+    Service, ServiceRegistry, ServiceRegistryRef, ServiceReputation, ServiceSla, ServiceSummary,
+    SlaWindow, StringLimits, TrendingService, Unbonding,
+};
+pub use hub_types::{PaymentAsset, Role, ServiceCategory};
+
+/// Hook the registry calls on every listing mutation so a companion index
+/// contract can keep its discovery indexes in sync
+#[ink::trait_definition]
+pub trait ServiceIndexHook {
+    /// Re-index a service after it was registered or changed
+    #[ink(message)]
+    fn on_service_changed(&mut self, service: Service, reputation: u32) -> bool;
+}
+
 #[ink::contract]
 mod service_registry {
+    use account_links::AccountLinksRef;
+    use agent_registry::AgentRegistryRef;
+    use certification::{Certification, CertificationsRef};
+    use event_hub::{EventHubRef, HubEventKind};
+    use gateway_registry::GatewayRegistryRef;
+    use hub_types::{
+        ApiVersion, DisplayAmount, HubError, PaymentAsset, Role, ServiceCategory,
+        StorageDepositRates, StorageUsage, NATIVE_DECIMALS,
+    };
+    use ink::env::call::FromAddr;
     use ink::prelude::string::String;
     use ink::prelude::vec::Vec;
     use ink::primitives::H160;
     use ink::storage::Mapping;
     use ink::H256;
+    use service_curation::ServiceCurationRef;
+    use token_registry::TokenRegistryRef;
 
-    /// Options for type of services an AI agent can offer
-
-    #[derive(Debug, PartialEq, Eq, Clone)]
-    #[ink::scale_derive(Encode, Decode, TypeInfo)]
-    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    use crate::ServiceIndexHook;
+    use parameters::{Parameter, Parameters};
 
-    pub enum ServiceCategory {
-        TextProcessing,
-        ImageGeneration,
-        DataAnalysis,
-        Translation,
-        Computation,
-    }
+    /// Version of the messages and events below, checked by the `abi` tests
+    const API_VERSION: ApiVersion = ApiVersion::new(2, 0, 0);
+    /// Layout version of this contract's storage, raised by every migration
+    const STORAGE_VERSION: u32 = 1;
+    /// Default price changes kept per service; older entries are dropped
+    const MAX_PRICE_HISTORY: u32 = 32;
+    /// Services tracked as trending candidates at once
+    const MAX_TRENDING_CANDIDATES: usize = 50;
+    /// Default most recent outcomes kept per service for the deactivation policy
+    const MAX_PERFORMANCE_SAMPLE: u32 = 100;
+    /// Most payers one acceptance policy may deny
+    const MAX_DENIED_PAYERS: usize = 32;
+    /// Weight one settlement adds to a provider's activity before decay
+    const SETTLEMENT_WEIGHT: u64 = 1_000;
 
     /// Service structure
     #[derive(Debug, PartialEq, Eq, Clone)]
@@ -33,17 +66,229 @@ mod service_registry {
         pub description: String,
         pub category: ServiceCategory,
         pub price: Balance,
+        // Asset the price is denominated in
+        pub payment_asset: PaymentAsset,
         pub endpoint: String,
         pub is_active: bool,
         pub total_requests: u32,
         pub successful_requests: u32,
         pub created_at: u64,
         // I need some x402 integration
-        pub supports_x402: bool,
-        pub x402_payment_token: Option<H160>,
-        pub x402_payment_amount: Option<Balance>,
         pub x402_gateway_address: Option<H160>,
-        pub x402_chain_id: Option<u64>,
+    }
+
+    impl Service {
+        /// Successful share of the service's requests in basis points
+        pub fn success_rate(&self) -> u32 {
+            if self.total_requests == 0 {
+                return 0;
+            }
+            (u64::from(self.successful_requests) * 10_000 / u64::from(self.total_requests)) as u32
+        }
+    }
+
+    /// Compact view of a service returned by listing queries
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ServiceSummary {
+        pub id: u64,
+        pub provider: H160,
+        pub name: String,
+        pub category: ServiceCategory,
+        pub price: Balance,
+        pub payment_asset: PaymentAsset,
+        // Successful share of the service's requests in basis points
+        pub success_rate: u32,
+        pub reputation: u32,
+        pub service_reputation: u32,
+        // Replacement listing, if the provider deprecated this one
+        pub successor: Option<u64>,
+        // Provider is away and takes no new escrows for now
+        pub away: bool,
+    }
+
+    /// Absence a provider announced, pausing new escrows for its services
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AwayNotice {
+        pub until: u64,
+        // Hash of an off-chain message shown to consumers meanwhile
+        pub message_hash: H256,
+    }
+
+    /// Stake a provider withdrew, claimable once the unbonding period passed
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Unbonding {
+        pub amount: Balance,
+        pub unlocks_at: u64,
+    }
+
+    /// Payers a service accepts escrows from, enforced by the escrow
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct AcceptancePolicy {
+        // Lowest average counterparty rating (0 - 100) a payer needs; unrated payers count as 0
+        pub min_payer_rating: u32,
+        pub denied_payers: Vec<H160>,
+        // Payer must be a key of an agent in the agent registry
+        pub require_identity: bool,
+    }
+
+    /// Settlement outcomes and reviews recorded for a single service
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ServiceReputation {
+        pub settled: u32,
+        pub successful: u32,
+        pub review_count: u32,
+        pub rating_sum: u64,
+    }
+
+    /// Recency-weighted settlements behind a provider's reputation
+    ///
+    /// Each settlement adds `SETTLEMENT_WEIGHT`; both totals decay
+    /// hyperbolically, halving after one reputation half-life without activity.
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ProviderActivity {
+        pub settled: u64,
+        pub successful: u64,
+        // Time the totals were last decayed
+        pub updated_at: u64,
+    }
+
+    impl ProviderActivity {
+        /// The totals as of `now`
+        pub fn decayed(&self, now: u64, half_life: u64) -> Self {
+            let elapsed = now.saturating_sub(self.updated_at);
+            let decay = |weight: u64| {
+                (u128::from(weight) * u128::from(half_life)
+                    / (u128::from(half_life) + u128::from(elapsed))) as u64
+            };
+            Self {
+                settled: decay(self.settled),
+                successful: decay(self.successful),
+                updated_at: now,
+            }
+        }
+
+        /// Score on a 0 - 100 scale
+        ///
+        /// The success rate, scaled down until the decayed activity reaches
+        /// `volume` settlements, so a handful of fresh escrows can't buy a
+        /// top score.
+        pub fn score(&self, volume: u32) -> u32 {
+            if self.settled == 0 {
+                return 0;
+            }
+            let full = u128::from(volume).saturating_mul(u128::from(SETTLEMENT_WEIGHT));
+            let settled = u128::from(self.settled);
+            let rate = u128::from(self.successful) * 100 / settled;
+            (rate * settled.min(full) / full.max(1)) as u32
+        }
+    }
+
+    /// A recorded change of a service's price
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PriceChange {
+        pub timestamp: u64,
+        pub old_price: Balance,
+        pub new_price: Balance,
+    }
+
+    /// Completions of a service in the current and previous trending window
+    #[derive(Debug, PartialEq, Eq, Clone, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TrendCounter {
+        pub window: u64,
+        pub current: u32,
+        pub previous: u32,
+    }
+
+    /// A service listed by `get_trending_services`
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TrendingService {
+        pub summary: ServiceSummary,
+        // Completions over the last window, older ones weighted down linearly
+        pub recent_completions: u32,
+    }
+
+    /// Maximum byte lengths of a listing's strings
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct StringLimits {
+        pub max_name_len: u32,
+        pub max_description_len: u32,
+        pub max_endpoint_len: u32,
+    }
+
+    impl Default for StringLimits {
+        fn default() -> Self {
+            Self {
+                max_name_len: 64,
+                max_description_len: 1024,
+                max_endpoint_len: 256,
+            }
+        }
+    }
+
+    /// Outcome of a request counted towards the deactivation policy
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum Outcome {
+        Success,
+        Failure,
+        Disputed,
+    }
+
+    /// Thresholds below which a service is deactivated automatically
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DeactivationPolicy {
+        // Number of most recent outcomes the rates are computed over
+        pub sample_size: u32,
+        // Successful share of settled requests, in basis points
+        pub min_success_bps: u32,
+        // Disputed share of all outcomes, in basis points
+        pub max_dispute_bps: u32,
+    }
+
+    /// Service level a provider commits a service to
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ServiceSla {
+        // Highest average response time tolerated, in milliseconds
+        pub max_response_ms: u64,
+        // Successful share of reported requests, in basis points
+        pub min_success_bps: u32,
+    }
+
+    /// Requests reported against a service's SLA in the current window
+    #[derive(Debug, Default, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SlaWindow {
+        // Index of the window, `block_timestamp / sla_window`
+        pub window: u64,
+        pub requests: u32,
+        pub successes: u32,
+        pub total_latency_ms: u64,
     }
 
     /// Events
@@ -73,6 +318,155 @@ mod service_registry {
         service_id: u64,
         is_active: bool,
     }
+    /// Emitted when a provider deprecates a service in favour of another
+    #[ink(event)]
+    pub struct ServiceDeprecated {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        successor_id: u64,
+    }
+    /// Emitted when a provider deregisters a service
+    #[ink(event)]
+    pub struct ServiceDeregistered {
+        #[ink(topic)]
+        service_id: u64,
+        deposit_unlocks_at: u64,
+    }
+    /// Emitted when a service's deposit is returned or forfeited
+    #[ink(event)]
+    pub struct DepositSettled {
+        #[ink(topic)]
+        service_id: u64,
+        amount: Balance,
+        forfeited: bool,
+    }
+    /// Emitted when a provider adds to its stake
+    #[ink(event)]
+    pub struct Staked {
+        #[ink(topic)]
+        provider: H160,
+        amount: Balance,
+        total: Balance,
+    }
+    /// Emitted when a provider starts unbonding part of its stake
+    #[ink(event)]
+    pub struct UnstakeStarted {
+        #[ink(topic)]
+        provider: H160,
+        amount: Balance,
+        unlocks_at: u64,
+    }
+    /// Emitted when a provider withdraws its unbonded stake
+    #[ink(event)]
+    pub struct StakeWithdrawn {
+        #[ink(topic)]
+        provider: H160,
+        amount: Balance,
+    }
+    /// Emitted when the arbitrator slashes a provider's stake
+    #[ink(event)]
+    pub struct ProviderSlashed {
+        #[ink(topic)]
+        provider: H160,
+        #[ink(topic)]
+        arbitrator: H160,
+        #[ink(topic)]
+        recipient: H160,
+        amount: Balance,
+    }
+    /// Emitted when a service declares the model backing it
+    #[ink(event)]
+    pub struct ServiceModelUpdated {
+        #[ink(topic)]
+        service_id: u64,
+        model_id: Option<u64>,
+    }
+    /// Emitted when governance changes a tunable setting
+    #[ink(event)]
+    pub struct ParameterUpdated {
+        parameter: Parameter,
+    }
+    /// Emitted when the owner grants an account a role
+    #[ink(event)]
+    pub struct RoleGranted {
+        role: Role,
+        #[ink(topic)]
+        account: H160,
+    }
+    /// Emitted when the owner revokes an account's role
+    #[ink(event)]
+    pub struct RoleRevoked {
+        role: Role,
+        #[ink(topic)]
+        account: H160,
+    }
+    /// Emitted when the owner replaces the contract's code
+    #[ink(event)]
+    pub struct Upgraded {
+        code_hash: H256,
+    }
+    /// Emitted when stored data is brought up to a newer layout
+    #[ink(event)]
+    pub struct Migrated {
+        from_version: u32,
+        to_version: u32,
+    }
+    /// Emitted when the owner hands the registry over
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: H160,
+        #[ink(topic)]
+        new_owner: H160,
+    }
+    /// Emitted when a provider goes away or comes back early
+    #[ink(event)]
+    pub struct AwayUpdated {
+        #[ink(topic)]
+        provider: H160,
+        notice: Option<AwayNotice>,
+    }
+    /// Emitted when a service attaches or removes its escrow hook
+    #[ink(event)]
+    pub struct ServiceHookUpdated {
+        #[ink(topic)]
+        service_id: u64,
+        hook: Option<H160>,
+    }
+    /// Emitted when a service sets or clears its acceptance policy
+    #[ink(event)]
+    pub struct AcceptancePolicyUpdated {
+        #[ink(topic)]
+        service_id: u64,
+        policy: Option<AcceptancePolicy>,
+    }
+    /// Emitted when a service is linked to an agent identity
+    #[ink(event)]
+    pub struct ServiceAgentLinked {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        agent_id: u64,
+    }
+    /// Emitted when a service's payout address moves to another agent key
+    #[ink(event)]
+    pub struct ServiceProviderChanged {
+        #[ink(topic)]
+        service_id: u64,
+        previous_provider: H160,
+        new_provider: H160,
+    }
+    /// Emitted when the escrow contract reports a settled escrow
+    #[ink(event)]
+    pub struct SettlementRecorded {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        payer: H160,
+        amount: Balance,
+        success: bool,
+    }
     /// Emitted when the reputation is updated
     #[ink(event)]
     pub struct ReputationUpdated {
@@ -80,6 +474,58 @@ mod service_registry {
         provider: H160,
         score: u32,
     }
+    /// Emitted when a service's own reputation changes
+    #[ink(event)]
+    pub struct ServiceReputationUpdated {
+        #[ink(topic)]
+        service_id: u64,
+        score: u32,
+    }
+    /// Emitted when a service is deactivated by the performance policy
+    #[ink(event)]
+    pub struct ServiceAutoDeactivated {
+        #[ink(topic)]
+        service_id: u64,
+        success_bps: u32,
+        dispute_bps: u32,
+    }
+    /// Emitted when a service is deactivated for missing its SLA
+    #[ink(event)]
+    pub struct SlaBreached {
+        #[ink(topic)]
+        service_id: u64,
+        success_bps: u32,
+        average_latency_ms: u64,
+    }
+    /// Emitted when a provider appeals an automatic deactivation
+    #[ink(event)]
+    pub struct DeactivationAppealed {
+        #[ink(topic)]
+        service_id: u64,
+        provider: H160,
+    }
+    /// Emitted when governance rules on an appeal
+    #[ink(event)]
+    pub struct AppealResolved {
+        #[ink(topic)]
+        service_id: u64,
+        reinstated: bool,
+    }
+    /// Emitted when a payer reviews a service
+    #[ink(event)]
+    pub struct ServiceReviewed {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        reviewer: H160,
+        rating: u32,
+    }
+    /// Emitted when the owner sets or clears a category's platform fee
+    #[ink(event)]
+    pub struct CategoryFeeUpdated {
+        category: ServiceCategory,
+        fee_bps: Option<u32>,
+    }
 
     /// Errors
     #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -94,6 +540,154 @@ mod service_registry {
 
         /// Emitted when the caller is not authorized to update the service status
         Unauthorized,
+        /// Emitted when the agent registry address has not been configured
+        AgentRegistryNotSet,
+        /// Emitted when the account is not a key of the agent
+        NotAgentKey,
+        /// Emitted when a string is longer than the configured limit
+        InputTooLong,
+        /// Emitted when the caller has no settled escrow left to review
+        NotReviewEligible,
+        /// Emitted when reactivating a service the performance policy deactivated
+        ServiceSuspended,
+        /// Emitted when the service was not deactivated by the performance policy
+        NotSuspended,
+        /// Emitted when the service already has an appeal awaiting a ruling
+        AppealPending,
+        /// Emitted when the payment token is not in the token registry
+        UnknownToken,
+        /// Emitted when the x402 gateway is not approved for the payment asset
+        UnknownGateway,
+        /// Emitted when the transferred value doesn't match the registration deposit
+        InvalidDeposit,
+        /// Emitted when the service was deregistered
+        ServiceDeregistered,
+        /// Emitted when the service has not been deregistered
+        NotDeregistered,
+        /// Emitted when the deposit is still held for late disputes
+        DepositLocked,
+        /// Emitted when the service holds no deposit
+        NoDeposit,
+        /// Emitted when the transfer fails
+        TransferFailed,
+        /// Emitted when the provider has no away notice to clear
+        NotAway,
+        /// Emitted when the agent was deactivated
+        AgentInactive,
+        /// Emitted when the provider's stake is below the minimum to list
+        InsufficientStake,
+        /// Emitted when the provider has no unbonded stake to withdraw
+        NoStake,
+        /// Emitted when the new code hash isn't uploaded
+        UpgradeFailed,
+        /// Emitted when storage is already at the current layout version
+        NothingToMigrate,
+    }
+
+    /// Settings governance may tune without an upgrade
+    pub mod parameters {
+        use super::{
+            Balance, Error, Result, StringLimits, MAX_PERFORMANCE_SAMPLE, MAX_PRICE_HISTORY,
+        };
+        use hub_types::StorageDepositRates;
+
+        /// Current value of every tunable setting
+        #[derive(Debug, PartialEq, Eq, Clone)]
+        #[ink::scale_derive(Encode, Decode, TypeInfo)]
+        #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+        pub struct Parameters {
+            pub string_limits: StringLimits,
+            // Refundable deposit new listings must lock; 0 disables deposits
+            pub registration_deposit: Balance,
+            // Time a deregistered service's deposit, or withdrawn stake, stays locked
+            pub unbonding_period: u64,
+            // Stake a provider must hold to list a service; 0 disables staking
+            pub min_provider_stake: Balance,
+            // Length of the rolling window trending services are ranked over
+            pub trending_window: u64,
+            pub max_price_history: u32,
+            // Most recent outcomes kept per service for the deactivation policy
+            pub max_performance_sample: u32,
+            pub storage_deposit_rates: StorageDepositRates,
+            // Settlements below this amount don't move reputation or earn a rating
+            pub min_rated_amount: Balance,
+            // Time after which a provider's past settlements count half
+            pub reputation_half_life: u64,
+            // Settlements a provider needs before its score reaches its success rate
+            pub reputation_volume: u32,
+            // Length of the window SLA compliance is measured over
+            pub sla_window: u64,
+            // Reports a window needs before a service can breach its SLA
+            pub min_sla_requests: u32,
+        }
+
+        /// One setting and the value to give it
+        #[derive(Debug, PartialEq, Eq, Clone)]
+        #[ink::scale_derive(Encode, Decode, TypeInfo)]
+        pub enum Parameter {
+            StringLimits(StringLimits),
+            RegistrationDeposit(Balance),
+            UnbondingPeriod(u64),
+            MinProviderStake(Balance),
+            TrendingWindow(u64),
+            MaxPriceHistory(u32),
+            MaxPerformanceSample(u32),
+            StorageDepositRates(StorageDepositRates),
+            MinRatedAmount(Balance),
+            ReputationHalfLife(u64),
+            ReputationVolume(u32),
+            SlaWindow(u64),
+            MinSlaRequests(u32),
+        }
+
+        impl Default for Parameters {
+            fn default() -> Self {
+                Self {
+                    string_limits: StringLimits::default(),
+                    registration_deposit: 0,
+                    unbonding_period: 604_800_000,
+                    min_provider_stake: 0,
+                    trending_window: 86_400_000,
+                    max_price_history: MAX_PRICE_HISTORY,
+                    max_performance_sample: MAX_PERFORMANCE_SAMPLE,
+                    storage_deposit_rates: StorageDepositRates::default(),
+                    min_rated_amount: 0,
+                    reputation_half_life: 2_592_000_000,
+                    reputation_volume: 10,
+                    sla_window: 86_400_000,
+                    min_sla_requests: 20,
+                }
+            }
+        }
+
+        impl Parameters {
+            /// Apply `parameter`, rejecting values that would break an invariant
+            pub fn set(&mut self, parameter: Parameter) -> Result<()> {
+                match parameter {
+                    Parameter::TrendingWindow(0)
+                    | Parameter::MaxPriceHistory(0)
+                    | Parameter::MaxPerformanceSample(0)
+                    | Parameter::ReputationHalfLife(0)
+                    | Parameter::ReputationVolume(0)
+                    | Parameter::SlaWindow(0)
+                    | Parameter::MinSlaRequests(0) => return Err(Error::InvalidInput),
+                    Parameter::StringLimits(limits) => self.string_limits = limits,
+                    Parameter::RegistrationDeposit(deposit) => self.registration_deposit = deposit,
+                    Parameter::UnbondingPeriod(period) => self.unbonding_period = period,
+                    Parameter::MinProviderStake(stake) => self.min_provider_stake = stake,
+                    Parameter::TrendingWindow(window) => self.trending_window = window,
+                    Parameter::MaxPriceHistory(max) => self.max_price_history = max,
+                    Parameter::MaxPerformanceSample(max) => self.max_performance_sample = max,
+                    Parameter::StorageDepositRates(rates) => self.storage_deposit_rates = rates,
+                    Parameter::MinRatedAmount(amount) => self.min_rated_amount = amount,
+                    Parameter::ReputationHalfLife(period) => self.reputation_half_life = period,
+                    Parameter::ReputationVolume(volume) => self.reputation_volume = volume,
+                    Parameter::SlaWindow(window) => self.sla_window = window,
+                    Parameter::MinSlaRequests(min) => self.min_sla_requests = min,
+                }
+                Ok(())
+            }
+        }
     }
 
     #[ink(storage)]
@@ -101,11 +695,116 @@ mod service_registry {
         services: Mapping<u64, Service>,
         provider_services: Mapping<H160, Vec<u64>>,
         service_count: u64,
-        reputation_scores: Mapping<H160, u32>,
+        // Recency-weighted settlements each provider's reputation derives from
+        provider_activity: Mapping<H160, ProviderActivity>,
+        // Model registry id of the model backing each service
+        service_models: Mapping<u64, u64>,
+        owner: H160,
+        // Accounts the owner granted each role
+        roles: Mapping<(Role, H160), ()>,
+        // Layout the stored data was written in, see `migrate`
+        storage_version: u32,
+        agent_registry: Option<H160>,
+        service_agents: Mapping<u64, u64>,
+        agent_services: Mapping<u64, Vec<u64>>,
+        // Token-curated list backing the verified discovery filter
+        curation: Option<H160>,
+        // Index contract notified on every listing mutation
+        index: Option<H160>,
+        // Optional hub lifecycle events are re-emitted through
+        event_hub: Option<H160>,
+        // Benchmark certifications surfaced alongside listings
+        certification: Option<H160>,
+        // Native Substrate accounts linked to the H160 addresses they act for
+        account_links: Option<H160>,
+        // Token metadata used to validate and display token prices
+        token_registry: Option<H160>,
+        // Approved x402 gateways listings may route payments through
+        gateway_registry: Option<H160>,
+        // Decimals of each token-priced listing, as registered when it was priced
+        price_decimals: Mapping<u64, u8>,
+        // Escrow contract allowed to report settlements
+        escrow: Option<H160>,
+        provider_earnings: Mapping<H160, Balance>,
+        // Settled escrows per (service, payer), making the payer eligible to review
+        review_eligibility: Mapping<(u64, H160), u32>,
+        // Completed escrows whose payer may still rate the service, with both
+        rateable_escrows: Mapping<u64, (u64, H160)>,
+        // Cap on simultaneously pending escrows per service, enforced by the escrow
+        max_pending_escrows: Mapping<u64, u32>,
+        // Services accepting one pending escrow per payer at a time, enforced by the escrow
+        single_escrow_services: Mapping<u64, ()>,
+        // Contract the escrow notifies on each escrow of a service, best-effort
+        service_hooks: Mapping<u64, H160>,
+        // Providers taking no new escrows until the notice's date
+        away_notices: Mapping<H160, AwayNotice>,
+        // Payers each service accepts, enforced by the escrow
+        acceptance_policies: Mapping<u64, AcceptancePolicy>,
+        // Listing records each provider created, at their registered size
+        storage_usage: Mapping<H160, StorageUsage>,
+        // Limits, periods and deposits governance may adjust
+        params: Parameters,
+        // Most recent price changes per service, oldest first
+        price_history: Mapping<u64, Vec<PriceChange>>,
+        trend_counters: Mapping<u64, TrendCounter>,
+        trending_candidates: Vec<u64>,
+        service_reputation: Mapping<u64, ServiceReputation>,
+        deactivation_policy: Option<DeactivationPolicy>,
+        recent_outcomes: Mapping<u64, Vec<Outcome>>,
+        // Services deactivated by the policy, and whether an appeal is pending
+        suspended: Mapping<u64, bool>,
+        // Service levels providers committed to, and the current window measured against them
+        service_slas: Mapping<u64, ServiceSla>,
+        sla_windows: Mapping<u64, SlaWindow>,
+        // Platform fee overrides in basis points, read by the escrow on settlement
+        category_fees: Mapping<ServiceCategory, u32>,
+        // Replacement listing of each deprecated service
+        successors: Mapping<u64, u64>,
+        service_deposits: Mapping<u64, Balance>,
+        // Disputes each service lost, which forfeit its deposit
+        lost_disputes: Mapping<u64, u32>,
+        deregistered_at: Mapping<u64, u64>,
+        // Forfeited deposits not yet withdrawn by the owner
+        forfeited_deposits: Balance,
+        // Collateral each provider bonded, slashable by Arbitrators
+        provider_stakes: Mapping<H160, Balance>,
+        unbonding_stakes: Mapping<H160, Unbonding>,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::ServiceNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::AgentRegistryNotSet => HubError::NotConfigured,
+                Error::NotAgentKey => HubError::Unauthorized,
+                Error::InputTooLong => HubError::InvalidInput,
+                Error::NotReviewEligible => HubError::Unauthorized,
+                Error::ServiceSuspended => HubError::Inactive,
+                Error::NotSuspended => HubError::InvalidStatus,
+                Error::AppealPending => HubError::AlreadyExists,
+                Error::UnknownToken => HubError::NotFound,
+                Error::UnknownGateway => HubError::NotFound,
+                Error::InvalidDeposit => HubError::InvalidAmount,
+                Error::ServiceDeregistered => HubError::Inactive,
+                Error::NotDeregistered => HubError::InvalidStatus,
+                Error::DepositLocked => HubError::TooEarly,
+                Error::NoDeposit => HubError::NotFound,
+                Error::TransferFailed => HubError::TransferFailed,
+                Error::NotAway => HubError::NotFound,
+                Error::AgentInactive => HubError::Inactive,
+                Error::InsufficientStake => HubError::InsufficientFunds,
+                Error::NoStake => HubError::NotFound,
+                Error::UpgradeFailed => HubError::InvalidInput,
+                Error::NothingToMigrate => HubError::InvalidStatus,
+            }
+        }
+    }
+
     impl ServiceRegistry {
         #[ink(constructor)]
         pub fn new() -> Self {
@@ -113,12 +812,84 @@ mod service_registry {
                 services: Mapping::default(),
                 provider_services: Mapping::default(),
                 service_count: 0,
-                reputation_scores: Mapping::default(),
+                provider_activity: Mapping::default(),
+                service_models: Mapping::default(),
+                owner: Self::env().caller(),
+                roles: Mapping::default(),
+                storage_version: STORAGE_VERSION,
+                agent_registry: None,
+                service_agents: Mapping::default(),
+                agent_services: Mapping::default(),
+                curation: None,
+                index: None,
+                event_hub: None,
+                certification: None,
+                account_links: None,
+                token_registry: None,
+                gateway_registry: None,
+                price_decimals: Mapping::default(),
+                escrow: None,
+                provider_earnings: Mapping::default(),
+                review_eligibility: Mapping::default(),
+                rateable_escrows: Mapping::default(),
+                max_pending_escrows: Mapping::default(),
+                single_escrow_services: Mapping::default(),
+                service_hooks: Mapping::default(),
+                away_notices: Mapping::default(),
+                acceptance_policies: Mapping::default(),
+                storage_usage: Mapping::default(),
+                params: Parameters::default(),
+                price_history: Mapping::default(),
+                trend_counters: Mapping::default(),
+                trending_candidates: Vec::new(),
+                service_reputation: Mapping::default(),
+                deactivation_policy: None,
+                recent_outcomes: Mapping::default(),
+                suspended: Mapping::default(),
+                service_slas: Mapping::default(),
+                sla_windows: Mapping::default(),
+                category_fees: Mapping::default(),
+                successors: Mapping::default(),
+                service_deposits: Mapping::default(),
+                lost_disputes: Mapping::default(),
+                deregistered_at: Mapping::default(),
+                forfeited_deposits: 0,
+                provider_stakes: Mapping::default(),
+                unbonding_stakes: Mapping::default(),
             }
         }
 
-        /// Register a new service
+        /// Check whether `register_service` would accept a listing
+        ///
+        /// Returns the error registering it would fail with, without
+        /// spending gas on a failed transaction.
         #[ink(message)]
+        pub fn can_register(
+            &self,
+            name: String,
+            description: String,
+            price: Balance,
+            endpoint: String,
+            payment_asset: PaymentAsset,
+            x402_gateway_address: Option<H160>,
+        ) -> Result<()> {
+            self.validate_listing(
+                &name,
+                &description,
+                price,
+                &endpoint,
+                payment_asset,
+                x402_gateway_address,
+            )
+            .map(|_| ())
+        }
+
+        /// Register a new service
+        ///
+        /// While a registration deposit is set, the transferred value must
+        /// equal it. The deposit is returned after a clean deregistration.
+        /// The provider must have staked at least the minimum provider stake.
+        #[ink(message, payable)]
         pub fn register_service(
             &mut self,
             name: String,
@@ -126,21 +897,28 @@ mod service_registry {
             category: ServiceCategory,
             price: Balance,
             endpoint: String,
-            supports_x402: bool,
-            x402_payment_token: Option<H160>,
-            x402_payment_amount: Option<Balance>,
+            payment_asset: PaymentAsset,
             x402_gateway_address: Option<H160>,
-            x402_chain_id: Option<u64>,
         ) -> Result<u64> {
             let caller = self.env().caller();
-            if name.is_empty() || description.is_empty() || endpoint.is_empty() || price == 0 {
-                return Err(Error::InvalidInput);
+            let decimals = self.validate_listing(
+                &name,
+                &description,
+                price,
+                &endpoint,
+                payment_asset,
+                x402_gateway_address,
+            )?;
+            let deposit: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default();
+            if deposit != self.params.registration_deposit {
+                return Err(Error::InvalidDeposit);
             }
-            // Validate x402 parameters if x402 is enabled
-            if supports_x402 {
-                if x402_payment_token.is_none() || x402_payment_amount.is_none() {
-                    return Err(Error::InvalidInput);
-                }
+            if self.get_stake(caller) < self.params.min_provider_stake {
+                return Err(Error::InsufficientStake);
             }
 
             self.service_count = self.service_count.checked_add(1).ok_or(Error::Overflow)?;
@@ -153,19 +931,26 @@ mod service_registry {
                 description,
                 category,
                 price,
+                payment_asset,
                 endpoint,
                 is_active: true,
                 total_requests: 0,
                 successful_requests: 0,
                 created_at: self.env().block_timestamp(),
-                supports_x402,
-                x402_payment_token,
-                x402_payment_amount,
                 x402_gateway_address,
-                x402_chain_id,
             };
 
             self.services.insert(service_id, &service);
+            self.notify_index(&service);
+            let mut usage = self.get_storage_usage(caller);
+            usage.add(ink::scale::Encode::encoded_size(&service));
+            self.storage_usage.insert(caller, &usage);
+            if deposit > 0 {
+                self.service_deposits.insert(service_id, &deposit);
+            }
+            if let Some(decimals) = decimals {
+                self.price_decimals.insert(service_id, &decimals);
+            }
 
             let mut provider_services = self.provider_services.get(caller).unwrap_or_default();
             provider_services.push(service_id);
@@ -177,6 +962,7 @@ mod service_registry {
                 name,
                 price,
             });
+            self.publish(HubEventKind::ServiceRegistered, service_id, caller, price);
 
             Ok(service_id)
         }
@@ -184,31 +970,143 @@ mod service_registry {
         pub fn get_service(&self, service_id: u64) -> Result<Service> {
             self.services.get(service_id).ok_or(Error::ServiceNotFound)
         }
-        #[ink(message)]
-        pub fn update_service_status(&mut self, service_id: u64, is_active: bool) -> Result<()> {
-            let caller = self.env().caller();
-            let mut service = self
-                .services
-                .get(service_id)
-                .ok_or(Error::ServiceNotFound)?;
 
-            // Check authorization
-            if service.provider != caller {
-                return Err(Error::Unauthorized);
-            }
+        /// Get the version of this contract's public interface
+        #[ink(message)]
+        pub fn api_version(&self) -> ApiVersion {
+            API_VERSION
+        }
+
+        /// Grant an account a role
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: Role, account: H160) -> Result<()> {
+            self.ensure_owner()?;
+            self.roles.insert((role, account), &());
+            self.env().emit_event(RoleGranted { role, account });
+            Ok(())
+        }
+
+        /// Revoke an account's role
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: H160) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.roles.contains((role, account)) {
+                return Err(Error::InvalidInput);
+            }
+            self.roles.remove((role, account));
+            self.env().emit_event(RoleRevoked { role, account });
+            Ok(())
+        }
+
+        /// Check if an account holds a role
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: H160) -> bool {
+            self.roles.contains((role, account))
+        }
+
+        /// Hand the registry over to another account (e.g. a DAO contract)
+        ///
+        /// Roles stay with the accounts they were granted to.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: H160) -> Result<()> {
+            self.ensure_owner()?;
+            let previous_owner = self.owner;
+            self.owner = new_owner;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner,
+            });
+            Ok(())
+        }
+
+        /// Get the registry owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> H160 {
+            self.owner
+        }
+
+        /// Replace this contract's code, keeping its storage and address
+        ///
+        /// The new code must already be uploaded. If it changes the storage
+        /// layout, call `migrate` right after.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: H256) -> Result<()> {
+            self.ensure_owner()?;
+            if self.env().set_code_hash(&code_hash).is_err() {
+                return Err(Error::UpgradeFailed);
+            }
+            self.env().emit_event(Upgraded { code_hash });
+            Ok(())
+        }
+
+        /// Bring storage written by older code up to `STORAGE_VERSION`
+        ///
+        /// Each layout change adds a step here keyed on the version it
+        /// upgrades from. New fields go in `Mapping`s or `Lazy` cells, so
+        /// the root struct still decodes before its step has run.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            let from_version = self.storage_version;
+            if from_version >= STORAGE_VERSION {
+                return Err(Error::NothingToMigrate);
+            }
+            // Steps go here, e.g. `if from_version < 2 { ... }`
+            self.storage_version = STORAGE_VERSION;
+            self.env().emit_event(Migrated {
+                from_version,
+                to_version: STORAGE_VERSION,
+            });
+            Ok(())
+        }
+
+        /// Get the layout version of the stored data
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.storage_version
+        }
+        #[ink(message)]
+        pub fn update_service_status(&mut self, service_id: u64, is_active: bool) -> Result<()> {
+            let caller = self.env().caller();
+            let mut service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+
+            // Check authorization
+            self.ensure_service_controller(&service, caller)?;
+            if is_active && self.suspended.contains(service_id) {
+                return Err(Error::ServiceSuspended);
+            }
+            if is_active && self.deregistered_at.contains(service_id) {
+                return Err(Error::ServiceDeregistered);
+            }
 
             service.is_active = is_active;
             self.services.insert(service_id, &service);
+            self.notify_index(&service);
 
             self.env().emit_event(ServiceUpdated {
                 service_id,
                 is_active,
             });
+            self.publish(
+                HubEventKind::ServiceUpdated,
+                service_id,
+                caller,
+                service.price,
+            );
 
             Ok(())
         }
+        /// Record a service call observed off-chain
+        ///
+        /// Only accounts holding the Verifier role may report calls.
         #[ink(message)]
         pub fn record_service_request(&mut self, service_id: u64, success: bool) -> Result<()> {
+            if !self.has_role(Role::Verifier, self.env().caller()) {
+                return Err(Error::Unauthorized);
+            }
             let mut service = self
                 .services
                 .get(service_id)
@@ -217,26 +1115,325 @@ mod service_registry {
             service.total_requests += 1;
             if success {
                 service.successful_requests += 1;
+                self.record_completion(service_id);
+            }
+
+            self.services.insert(service_id, &service);
+            Ok(())
+        }
+
+        /// Record a service call observed off-chain along with its response time
+        ///
+        /// Counts the call like `record_service_request` and measures it
+        /// against the service's SLA, if the provider committed to one.
+        #[ink(message)]
+        pub fn report_request(
+            &mut self,
+            service_id: u64,
+            success: bool,
+            latency_ms: u64,
+        ) -> Result<()> {
+            self.record_service_request(service_id, success)?;
+            self.record_sla_sample(service_id, success, latency_ms);
+            Ok(())
+        }
+
+        /// Record a settled escrow for a service
+        ///
+        /// Called by the escrow contract when an escrow is released
+        /// (`success`) or refunded. Counts the request, credits the provider's
+        /// earnings, weighs the escrow into its reputation and makes the payer
+        /// eligible to review the service. Escrows below `min_rated_amount`
+        /// only count in the request stats and earnings. Escrows that didn't
+        /// pay the service's provider are rejected.
+        #[ink(message)]
+        pub fn record_settlement(
+            &mut self,
+            escrow_id: u64,
+            service_id: u64,
+            payer: H160,
+            payee: H160,
+            amount: Balance,
+            success: bool,
+        ) -> Result<()> {
+            if Some(self.env().caller()) != self.escrow {
+                return Err(Error::Unauthorized);
+            }
+            let mut service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            if service.provider != payee {
+                return Err(Error::InvalidInput);
             }
 
+            service.total_requests = service
+                .total_requests
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            if success {
+                service.successful_requests = service
+                    .successful_requests
+                    .checked_add(1)
+                    .ok_or(Error::Overflow)?;
+                let earnings = self
+                    .get_provider_earnings(payee)
+                    .checked_add(amount)
+                    .ok_or(Error::Overflow)?;
+                self.provider_earnings.insert(payee, &earnings);
+                self.record_completion(service_id);
+            }
             self.services.insert(service_id, &service);
+
+            self.env().emit_event(SettlementRecorded {
+                service_id,
+                payer,
+                amount,
+                success,
+            });
+
+            // Dust escrows between sybil accounts must not build reputation
+            // or earn reviews
+            if amount < self.params.min_rated_amount {
+                return Ok(());
+            }
+
+            let settled = self
+                .review_eligibility
+                .get((service_id, payer))
+                .unwrap_or(0)
+                .saturating_add(1);
+            self.review_eligibility
+                .insert((service_id, payer), &settled);
+
+            let mut reputation = self.service_reputation.get(service_id).unwrap_or_default();
+            reputation.settled = reputation.settled.saturating_add(1);
+            if success {
+                reputation.successful = reputation.successful.saturating_add(1);
+            }
+            self.update_service_reputation(service_id, &reputation);
+            self.record_outcome(
+                service_id,
+                if success {
+                    Outcome::Success
+                } else {
+                    Outcome::Failure
+                },
+            );
+
+            if success {
+                self.rateable_escrows
+                    .insert(escrow_id, &(service_id, payer));
+            }
+            let mut activity = self.decayed_activity(payee);
+            activity.settled = activity.settled.saturating_add(SETTLEMENT_WEIGHT);
+            if success {
+                activity.successful = activity.successful.saturating_add(SETTLEMENT_WEIGHT);
+            }
+            self.provider_activity.insert(payee, &activity);
+            self.notify_index(&service);
+            self.env().emit_event(ReputationUpdated {
+                provider: payee,
+                score: activity.score(self.params.reputation_volume),
+            });
+
+            Ok(())
+        }
+
+        /// Record that an escrow for a service was disputed
+        ///
+        /// Called by the escrow contract; counts towards the dispute rate of
+        /// the deactivation policy.
+        #[ink(message)]
+        pub fn record_dispute(&mut self, service_id: u64) -> Result<()> {
+            if Some(self.env().caller()) != self.escrow {
+                return Err(Error::Unauthorized);
+            }
+            if !self.services.contains(service_id) {
+                return Err(Error::ServiceNotFound);
+            }
+            self.record_outcome(service_id, Outcome::Disputed);
+            Ok(())
+        }
+
+        /// Appeal an automatic deactivation to governance
+        #[ink(message)]
+        pub fn appeal_deactivation(&mut self, service_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, caller)?;
+
+            match self.suspended.get(service_id) {
+                None => return Err(Error::NotSuspended),
+                Some(true) => return Err(Error::AppealPending),
+                Some(false) => {}
+            }
+            self.suspended.insert(service_id, &true);
+
+            self.env().emit_event(DeactivationAppealed {
+                service_id,
+                provider: caller,
+            });
+
             Ok(())
         }
 
-        /// Update provider reputation
+        /// Rule on a pending appeal
+        ///
+        /// Reinstating reactivates the service with a clean outcome history.
+        /// A rejected appeal leaves it deactivated; the provider may appeal again.
         #[ink(message)]
-        pub fn update_reputation(&mut self, provider: H160, score: u32) -> Result<()> {
-            self.reputation_scores.insert(provider, &score);
+        pub fn resolve_appeal(&mut self, service_id: u64, reinstate: bool) -> Result<()> {
+            self.ensure_admin()?;
+            if self.suspended.get(service_id) != Some(true) {
+                return Err(Error::NotSuspended);
+            }
+
+            if reinstate {
+                let mut service = self
+                    .services
+                    .get(service_id)
+                    .ok_or(Error::ServiceNotFound)?;
+                self.suspended.remove(service_id);
+                self.recent_outcomes.remove(service_id);
+                service.is_active = true;
+                self.services.insert(service_id, &service);
+                self.notify_index(&service);
+                self.env().emit_event(ServiceUpdated {
+                    service_id,
+                    is_active: true,
+                });
+            } else {
+                self.suspended.insert(service_id, &false);
+            }
 
-            self.env().emit_event(ReputationUpdated { provider, score });
+            self.env().emit_event(AppealResolved {
+                service_id,
+                reinstated: reinstate,
+            });
 
             Ok(())
         }
 
-        /// Get provider reputation
+        /// Whether the service was deactivated by the performance policy
+        #[ink(message)]
+        pub fn is_suspended(&self, service_id: u64) -> bool {
+            self.suspended.contains(service_id)
+        }
+
+        /// Total paid out to a provider through settled escrows
+        #[ink(message)]
+        pub fn get_provider_earnings(&self, provider: H160) -> Balance {
+            self.provider_earnings.get(provider).unwrap_or(0)
+        }
+
+        /// Whether the payer has settled an escrow for the service and may review it
+        #[ink(message)]
+        pub fn is_review_eligible(&self, service_id: u64, payer: H160) -> bool {
+            self.review_eligibility.contains((service_id, payer))
+        }
+
+        /// Rate a service on a 0 - 100 scale
+        ///
+        /// Each settled escrow entitles its payer to one review of the service.
+        #[ink(message)]
+        pub fn submit_review(&mut self, service_id: u64, rating: u32) -> Result<()> {
+            let reviewer = self.env().caller();
+            if rating > 100 {
+                return Err(Error::InvalidInput);
+            }
+            let remaining = self
+                .review_eligibility
+                .get((service_id, reviewer))
+                .ok_or(Error::NotReviewEligible)?
+                .saturating_sub(1);
+            if remaining == 0 {
+                self.review_eligibility.remove((service_id, reviewer));
+            } else {
+                self.review_eligibility
+                    .insert((service_id, reviewer), &remaining);
+            }
+
+            self.add_review(service_id, reviewer, rating)
+        }
+
+        /// Rate a service on a 0 - 100 scale for one completed escrow
+        ///
+        /// Only the payer of an escrow that released to the service may rate
+        /// it, once; the rating uses up the review the settlement entitled
+        /// the payer to.
+        #[ink(message)]
+        pub fn rate_service(&mut self, service_id: u64, escrow_id: u64, score: u32) -> Result<()> {
+            let reviewer = self.env().caller();
+            if score > 100 {
+                return Err(Error::InvalidInput);
+            }
+            if self.rateable_escrows.get(escrow_id) != Some((service_id, reviewer)) {
+                return Err(Error::NotReviewEligible);
+            }
+            let remaining = self
+                .review_eligibility
+                .get((service_id, reviewer))
+                .ok_or(Error::NotReviewEligible)?
+                .saturating_sub(1);
+            self.rateable_escrows.remove(escrow_id);
+            if remaining == 0 {
+                self.review_eligibility.remove((service_id, reviewer));
+            } else {
+                self.review_eligibility
+                    .insert((service_id, reviewer), &remaining);
+            }
+
+            self.add_review(service_id, reviewer, score)
+        }
+
+        /// Reputation of a single service on a 0 - 100 scale
+        ///
+        /// Average of the service's settlement success rate and its mean
+        /// review rating, leaving out whichever has no data yet.
+        #[ink(message)]
+        pub fn get_service_reputation(&self, service_id: u64) -> u32 {
+            let reputation = self.service_reputation.get(service_id).unwrap_or_default();
+            let mut sum: u64 = 0;
+            let mut parts: u64 = 0;
+            if reputation.settled > 0 {
+                sum += u64::from(reputation.successful) * 100 / u64::from(reputation.settled);
+                parts += 1;
+            }
+            if reputation.review_count > 0 {
+                sum += reputation.rating_sum / u64::from(reputation.review_count);
+                parts += 1;
+            }
+            if parts == 0 {
+                return 0;
+            }
+            (sum / parts) as u32
+        }
+
+        /// Raw settlement and review counts behind `get_service_reputation`
+        #[ink(message)]
+        pub fn get_service_reputation_details(&self, service_id: u64) -> ServiceReputation {
+            self.service_reputation.get(service_id).unwrap_or_default()
+        }
+
+        /// Get provider reputation on a 0 - 100 scale
+        ///
+        /// Derived from the escrows the provider settled: its success rate,
+        /// discounted until it has settled enough of them, with older
+        /// settlements counting less. Providers can't set it themselves.
         #[ink(message)]
         pub fn get_reputation(&self, provider: H160) -> u32 {
-            self.reputation_scores.get(provider).unwrap_or(0)
+            self.decayed_activity(provider)
+                .score(self.params.reputation_volume)
+        }
+
+        /// Recency-weighted settlements behind a provider's reputation, as of now
+        #[ink(message)]
+        pub fn get_provider_activity(&self, provider: H160) -> ProviderActivity {
+            self.decayed_activity(provider)
         }
 
         /// Get all services by provider
@@ -255,7 +1452,7 @@ mod service_registry {
         /// For this MVP I have simplified it to returns first N active services
         ///
         #[ink(message)]
-        pub fn get_active_services(&self, limit: u64) -> Vec<Service> {
+        pub fn get_active_services(&self, limit: u64) -> Vec<ServiceSummary> {
             let mut active_services = Vec::new();
             let max = if limit > self.service_count {
                 self.service_count
@@ -266,7 +1463,7 @@ mod service_registry {
             for i in 1..=max {
                 if let Some(service) = self.services.get(i) {
                     if service.is_active {
-                        active_services.push(service);
+                        active_services.push(self.summarize(service));
                     }
                 }
             }
@@ -274,6 +1471,43 @@ mod service_registry {
             active_services
         }
 
+        /// Edit the name, description and endpoint of a service
+        #[ink(message)]
+        pub fn update_service_details(
+            &mut self,
+            service_id: u64,
+            name: String,
+            description: String,
+            endpoint: String,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            let mut service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+
+            self.ensure_service_controller(&service, caller)?;
+
+            if name.is_empty() || description.is_empty() || endpoint.is_empty() {
+                return Err(Error::InvalidInput);
+            }
+            self.ensure_within_limits(&name, &description, &endpoint)?;
+
+            service.name = name;
+            service.description = description;
+            service.endpoint = endpoint;
+            self.services.insert(service_id, &service);
+            self.notify_index(&service);
+            self.publish(
+                HubEventKind::ServiceUpdated,
+                service_id,
+                caller,
+                service.price,
+            );
+
+            Ok(())
+        }
+
         /// Update service price
         #[ink(message)]
         pub fn update_service_price(&mut self, service_id: u64, new_price: Balance) -> Result<()> {
@@ -283,25 +1517,24 @@ mod service_registry {
                 .get(service_id)
                 .ok_or(Error::ServiceNotFound)?;
 
-            if service.provider != caller {
-                return Err(Error::Unauthorized);
-            }
+            self.ensure_service_controller(&service, caller)?;
 
+            self.record_price_change(service_id, service.price, new_price);
             service.price = new_price;
             self.services.insert(service_id, &service);
+            self.notify_index(&service);
+            self.publish(HubEventKind::ServiceUpdated, service_id, caller, new_price);
 
             Ok(())
         }
-        /// Update x402 payment parameters for a service
+        /// Change the asset a service is priced in, with its price in that asset
         #[ink(message)]
-        pub fn update_x402_params(
+        pub fn update_payment_asset(
             &mut self,
             service_id: u64,
-            supports_x402: bool,
-            x402_payment_token: Option<H160>,
-            x402_payment_amount: Option<Balance>,
+            payment_asset: PaymentAsset,
+            price: Balance,
             x402_gateway_address: Option<H160>,
-            x402_chain_id: Option<u64>,
         ) -> Result<()> {
             let caller = self.env().caller();
             let mut service = self
@@ -309,31 +1542,52 @@ mod service_registry {
                 .get(service_id)
                 .ok_or(Error::ServiceNotFound)?;
 
-            if service.provider != caller {
-                return Err(Error::Unauthorized);
-            }
+            self.ensure_service_controller(&service, caller)?;
 
-            // Validate x402 parameters if x402 is enabled
-            if supports_x402 {
-                if x402_payment_token.is_none() || x402_payment_amount.is_none() {
-                    return Err(Error::InvalidInput);
-                }
+            if price == 0 {
+                return Err(Error::InvalidInput);
             }
+            self.ensure_gateway_supports(payment_asset, x402_gateway_address)?;
+            match self.token_decimals(payment_asset)? {
+                Some(decimals) => self.price_decimals.insert(service_id, &decimals),
+                None => self.price_decimals.remove(service_id),
+            };
 
-            service.supports_x402 = supports_x402;
-            service.x402_payment_token = x402_payment_token;
-            service.x402_payment_amount = x402_payment_amount;
+            self.record_price_change(service_id, service.price, price);
+            service.payment_asset = payment_asset;
+            service.price = price;
             service.x402_gateway_address = x402_gateway_address;
-            service.x402_chain_id = x402_chain_id;
 
             self.services.insert(service_id, &service);
+            self.notify_index(&service);
 
             Ok(())
         }
 
+        /// Decimals of a listing's price, if known
+        ///
+        /// Native prices use the chain's decimals. Token prices use the
+        /// decimals the token registry held when the listing was priced, and
+        /// are unknown if no token registry was configured then.
+        #[ink(message)]
+        pub fn get_price_decimals(&self, service_id: u64) -> Option<u8> {
+            let service = self.services.get(service_id)?;
+            match service.payment_asset.token() {
+                None => Some(NATIVE_DECIMALS),
+                Some(_) => self.price_decimals.get(service_id),
+            }
+        }
+
+        /// A listing's price in whole and fractional display units, if its decimals are known
+        #[ink(message)]
+        pub fn get_display_price(&self, service_id: u64) -> Option<DisplayAmount> {
+            let service = self.services.get(service_id)?;
+            DisplayAmount::new(service.price, self.get_price_decimals(service_id)?)
+        }
+
         /// Get services that support x402 payments
         #[ink(message)]
-        pub fn get_x402_services(&self, limit: u64) -> Vec<Service> {
+        pub fn get_x402_services(&self, limit: u64) -> Vec<ServiceSummary> {
             let mut x402_services = Vec::new();
             let max = if limit > self.service_count {
                 self.service_count
@@ -343,8 +1597,8 @@ mod service_registry {
 
             for i in 1..=max {
                 if let Some(service) = self.services.get(i) {
-                    if service.is_active && service.supports_x402 {
-                        x402_services.push(service);
+                    if service.is_active && service.payment_asset.is_x402() {
+                        x402_services.push(self.summarize(service));
                     }
                 }
             }
@@ -352,122 +1606,2874 @@ mod service_registry {
             x402_services
         }
 
-        /// Record x402 payment for a service request
+        /// Get active services on the curated verified list
         #[ink(message)]
-        pub fn record_x402_payment(
-            &mut self,
-            service_id: u64,
-            payment_hash: H256,
-            success: bool,
-        ) -> Result<()> {
-            let mut service = self
+        pub fn get_verified_services(&self, limit: u64) -> Vec<ServiceSummary> {
+            let mut verified_services = Vec::new();
+            let Some(curation) = self.curation else {
+                return verified_services;
+            };
+            let curation: ServiceCurationRef = FromAddr::from_addr(curation);
+            let max = if limit > self.service_count {
+                self.service_count
+            } else {
+                limit
+            };
+
+            for i in 1..=max {
+                if let Some(service) = self.services.get(i) {
+                    if service.is_active && curation.is_verified(i) {
+                        verified_services.push(self.summarize(service));
+                    }
+                }
+            }
+
+            verified_services
+        }
+
+        /// Valid benchmark certifications held by a service's provider
+        #[ink(message)]
+        pub fn get_service_certifications(&self, service_id: u64) -> Result<Vec<Certification>> {
+            let service = self
                 .services
                 .get(service_id)
                 .ok_or(Error::ServiceNotFound)?;
+            let Some(certification) = self.certification else {
+                return Ok(Vec::new());
+            };
+            let certification: CertificationsRef = FromAddr::from_addr(certification);
+            Ok(certification.get_valid_certifications(service.provider))
+        }
 
-            if !service.supports_x402 {
-                return Err(Error::InvalidInput);
-            }
+        /// Declare which model registry entry backs a service
+        #[ink(message)]
+        pub fn set_service_model(&mut self, service_id: u64, model_id: Option<u64>) -> Result<()> {
+            let caller = self.env().caller();
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
 
-            service.total_requests += 1;
-            if success {
-                service.successful_requests += 1;
+            self.ensure_service_controller(&service, caller)?;
+
+            if let Some(model_id) = model_id {
+                self.service_models.insert(service_id, &model_id);
+            } else {
+                self.service_models.remove(service_id);
             }
 
-            self.services.insert(service_id, &service);
+            self.env().emit_event(ServiceModelUpdated {
+                service_id,
+                model_id,
+            });
+
+            Ok(())
+        }
+
+        /// Services with the most completed requests over the recent window
+        ///
+        /// Completions from the previous window are weighted down linearly as
+        /// the current one progresses, so the ranking moves smoothly. Only
+        /// escrow settlements and Verifier-reported service requests count.
+        #[ink(message)]
+        pub fn get_trending_services(&self, limit: u32) -> Vec<TrendingService> {
+            let mut trending: Vec<TrendingService> = self
+                .trending_candidates
+                .iter()
+                .filter_map(|service_id| {
+                    let recent_completions = self.trend_score(*service_id);
+                    let service = self.services.get(service_id)?;
+                    (recent_completions > 0 && service.is_active).then(|| TrendingService {
+                        summary: self.summarize(service),
+                        recent_completions,
+                    })
+                })
+                .collect();
+            trending.sort_by(|a, b| b.recent_completions.cmp(&a.recent_completions));
+            trending.truncate(limit as usize);
+            trending
+        }
+
+        /// Most recent price changes of a service, newest first
+        ///
+        /// Only the last `max_price_history` changes are kept. A service with
+        /// no entries has had its registration price throughout.
+        #[ink(message)]
+        pub fn get_price_history(&self, service_id: u64, limit: u32) -> Vec<PriceChange> {
+            self.price_history
+                .get(service_id)
+                .unwrap_or_default()
+                .into_iter()
+                .rev()
+                .take(limit as usize)
+                .collect()
+        }
+
+        /// Get the model registry id backing a service, if declared
+        #[ink(message)]
+        pub fn get_service_model(&self, service_id: u64) -> Option<u64> {
+            self.service_models.get(service_id)
+        }
+
+        /// Cap how many escrows for a service may be pending at once
+        ///
+        /// The provider (or its agent's keys), the registry owner and Admins
+        /// may set the cap; `None` removes it.
+        #[ink(message)]
+        pub fn set_max_pending_escrows(
+            &mut self,
+            service_id: u64,
+            limit: Option<u32>,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+
+            if !self.is_admin(caller) {
+                self.ensure_service_controller(&service, caller)?;
+            }
+
+            if limit == Some(0) {
+                return Err(Error::InvalidInput);
+            }
+            if let Some(limit) = limit {
+                self.max_pending_escrows.insert(service_id, &limit);
+            } else {
+                self.max_pending_escrows.remove(service_id);
+            }
+
+            Ok(())
+        }
+
+        /// Get the cap on simultaneously pending escrows for a service, if any
+        #[ink(message)]
+        pub fn get_max_pending_escrows(&self, service_id: u64) -> Option<u32> {
+            self.max_pending_escrows.get(service_id)
+        }
+
+        /// Commit a service to (or withdraw it from) a service level
+        ///
+        /// Once reporters have measured `min_sla_requests` calls in a window,
+        /// a service averaging slower than `max_response_ms` or succeeding
+        /// less often than `min_success_bps` is deactivated pending appeal,
+        /// like the deactivation policy does.
+        #[ink(message)]
+        pub fn set_service_sla(&mut self, service_id: u64, sla: Option<ServiceSla>) -> Result<()> {
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, self.env().caller())?;
+
+            self.sla_windows.remove(service_id);
+            let Some(sla) = sla else {
+                self.service_slas.remove(service_id);
+                return Ok(());
+            };
+            if sla.max_response_ms == 0 || sla.min_success_bps > 10_000 {
+                return Err(Error::InvalidInput);
+            }
+            self.service_slas.insert(service_id, &sla);
+
+            Ok(())
+        }
+
+        /// Get a service's SLA, if any, and the calls measured in the current window
+        #[ink(message)]
+        pub fn get_service_sla(&self, service_id: u64) -> Option<(ServiceSla, SlaWindow)> {
+            let sla = self.service_slas.get(service_id)?;
+            let window = self.env().block_timestamp() / self.params.sla_window;
+            let current = self
+                .sla_windows
+                .get(service_id)
+                .filter(|stats| stats.window == window)
+                .unwrap_or(SlaWindow {
+                    window,
+                    ..Default::default()
+                });
+            Some((sla, current))
+        }
+
+        /// Accept only one pending escrow per payer for a service
+        ///
+        /// Lets providers match requests to payers one-to-one and stops a
+        /// buggy agent from ordering twice. Only the provider (or its
+        /// agent's keys) may toggle it.
+        #[ink(message)]
+        pub fn set_single_active_escrow(&mut self, service_id: u64, enabled: bool) -> Result<()> {
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, self.env().caller())?;
+
+            if enabled {
+                self.single_escrow_services.insert(service_id, &());
+            } else {
+                self.single_escrow_services.remove(service_id);
+            }
+
+            Ok(())
+        }
+
+        /// Check if a service accepts only one pending escrow per payer
+        #[ink(message)]
+        pub fn is_single_active_escrow(&self, service_id: u64) -> bool {
+            self.single_escrow_services.contains(service_id)
+        }
+
+        /// Attach a contract the escrow notifies when escrows for a service
+        /// are created and settled
+        ///
+        /// The hook must implement `escrow_hook::EscrowHook`. Calls are
+        /// gas-capped and their failures ignored, so a broken hook never
+        /// blocks payments. Only the provider (or its agent's keys) may set it.
+        #[ink(message)]
+        pub fn set_service_hook(&mut self, service_id: u64, hook: Option<H160>) -> Result<()> {
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, self.env().caller())?;
+
+            if let Some(hook) = hook {
+                self.service_hooks.insert(service_id, &hook);
+            } else {
+                self.service_hooks.remove(service_id);
+            }
+
+            self.env()
+                .emit_event(ServiceHookUpdated { service_id, hook });
+
+            Ok(())
+        }
+
+        /// Get the escrow hook of a service, if any
+        #[ink(message)]
+        pub fn get_service_hook(&self, service_id: u64) -> Option<H160> {
+            self.service_hooks.get(service_id)
+        }
+
+        /// Set which payers a service accepts escrows from, or clear the policy
+        ///
+        /// The escrow checks the policy when an escrow is opened for the
+        /// service, before any funds move. Only the provider (or its agent's
+        /// keys) may set it.
+        #[ink(message)]
+        pub fn set_acceptance_policy(
+            &mut self,
+            service_id: u64,
+            policy: Option<AcceptancePolicy>,
+        ) -> Result<()> {
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, self.env().caller())?;
+
+            if let Some(policy) = &policy {
+                if policy.min_payer_rating > 100 || policy.denied_payers.len() > MAX_DENIED_PAYERS {
+                    return Err(Error::InvalidInput);
+                }
+                self.acceptance_policies.insert(service_id, policy);
+            } else {
+                self.acceptance_policies.remove(service_id);
+            }
+
+            self.env()
+                .emit_event(AcceptancePolicyUpdated { service_id, policy });
+
+            Ok(())
+        }
+
+        /// Get the acceptance policy of a service, if any
+        #[ink(message)]
+        pub fn get_acceptance_policy(&self, service_id: u64) -> Option<AcceptancePolicy> {
+            self.acceptance_policies.get(service_id)
+        }
+
+        /// Check if an account is a key of an active agent in the agent registry
+        ///
+        /// Always false while no agent registry is set.
+        #[ink(message)]
+        pub fn has_agent_identity(&self, account: H160) -> bool {
+            let Some(agent_registry) = self.agent_registry else {
+                return false;
+            };
+            let agent_registry: AgentRegistryRef = FromAddr::from_addr(agent_registry);
+            agent_registry
+                .get_agent_by_key(account)
+                .is_some_and(|agent_id| agent_registry.is_active(agent_id))
+        }
+
+        /// Pause new escrows for all of the caller's services until `until`
+        ///
+        /// Listings stay visible, flagged as away, so a short absence doesn't
+        /// need deactivating and reactivating every service. Setting it again
+        /// replaces the notice.
+        #[ink(message)]
+        pub fn set_away(&mut self, until: u64, message_hash: H256) -> Result<()> {
+            let provider = self.env().caller();
+            if until <= self.env().block_timestamp() {
+                return Err(Error::InvalidInput);
+            }
+
+            let notice = AwayNotice {
+                until,
+                message_hash,
+            };
+            self.away_notices.insert(provider, &notice);
+            self.env().emit_event(AwayUpdated {
+                provider,
+                notice: Some(notice),
+            });
+
+            Ok(())
+        }
+
+        /// Take new escrows again before the away notice ends
+        #[ink(message)]
+        pub fn clear_away(&mut self) -> Result<()> {
+            let provider = self.env().caller();
+            if self.away_notices.take(provider).is_none() {
+                return Err(Error::NotAway);
+            }
+            self.env().emit_event(AwayUpdated {
+                provider,
+                notice: None,
+            });
+            Ok(())
+        }
+
+        /// Get a provider's away notice while it is in effect
+        #[ink(message)]
+        pub fn get_away(&self, provider: H160) -> Option<AwayNotice> {
+            self.away_notices
+                .get(provider)
+                .filter(|notice| notice.until > self.env().block_timestamp())
+        }
+
+        /// Check if a provider is away and takes no new escrows
+        #[ink(message)]
+        pub fn is_away(&self, provider: H160) -> bool {
+            self.get_away(provider).is_some()
+        }
+
+        /// Record x402 payment for a service request
+        ///
+        /// Only accounts holding the Verifier role may report payments.
+        #[ink(message)]
+        pub fn record_x402_payment(
+            &mut self,
+            service_id: u64,
+            payment_hash: H256,
+            success: bool,
+        ) -> Result<()> {
+            if !self.has_role(Role::Verifier, self.env().caller()) {
+                return Err(Error::Unauthorized);
+            }
+            let mut service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+
+            if !service.payment_asset.is_x402() {
+                return Err(Error::InvalidInput);
+            }
+
+            // Settled off-chain, so it counts in the stats but not towards trending
+            service.total_requests += 1;
+            if success {
+                service.successful_requests += 1;
+            }
+
+            self.services.insert(service_id, &service);
+            Ok(())
+        }
+
+        /// Link one of the caller's services to an agent identity
+        ///
+        /// Once linked, any current key of the agent can manage the service,
+        /// so listings survive key rotation.
+        #[ink(message)]
+        pub fn link_service_to_agent(&mut self, service_id: u64, agent_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+
+            if service.provider != caller {
+                return Err(Error::Unauthorized);
+            }
+            if !self.is_agent_key(agent_id, caller)? {
+                return Err(Error::NotAgentKey);
+            }
+            if !self.is_agent_active(agent_id)? {
+                return Err(Error::AgentInactive);
+            }
+
+            if let Some(previous) = self.service_agents.get(service_id) {
+                let mut services = self.agent_services.get(previous).unwrap_or_default();
+                services.retain(|id| *id != service_id);
+                self.agent_services.insert(previous, &services);
+            }
+            self.service_agents.insert(service_id, &agent_id);
+            let mut services = self.agent_services.get(agent_id).unwrap_or_default();
+            services.push(service_id);
+            self.agent_services.insert(agent_id, &services);
+
+            self.env().emit_event(ServiceAgentLinked {
+                service_id,
+                agent_id,
+            });
+
+            Ok(())
+        }
+
+        /// Move a linked service's payout address to another key of its agent
+        #[ink(message)]
+        pub fn set_service_provider(&mut self, service_id: u64, new_provider: H160) -> Result<()> {
+            let caller = self.env().caller();
+            let mut service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            let agent_id = self
+                .service_agents
+                .get(service_id)
+                .ok_or(Error::Unauthorized)?;
+
+            self.ensure_service_controller(&service, caller)?;
+            if !self.is_agent_key(agent_id, new_provider)? {
+                return Err(Error::NotAgentKey);
+            }
+
+            let previous_provider = service.provider;
+            let mut previous_services = self
+                .provider_services
+                .get(previous_provider)
+                .unwrap_or_default();
+            previous_services.retain(|id| *id != service_id);
+            self.provider_services
+                .insert(previous_provider, &previous_services);
+
+            let mut new_services = self.provider_services.get(new_provider).unwrap_or_default();
+            new_services.push(service_id);
+            self.provider_services.insert(new_provider, &new_services);
+
+            service.provider = new_provider;
+            self.services.insert(service_id, &service);
+            self.notify_index(&service);
+
+            self.env().emit_event(ServiceProviderChanged {
+                service_id,
+                previous_provider,
+                new_provider,
+            });
+
+            Ok(())
+        }
+
+        /// Get the agent a service is linked to, if any
+        #[ink(message)]
+        pub fn get_service_agent(&self, service_id: u64) -> Option<u64> {
+            self.service_agents.get(service_id)
+        }
+
+        /// Get all services linked to an agent
+        #[ink(message)]
+        pub fn get_agent_services(&self, agent_id: u64) -> Vec<u64> {
+            self.agent_services.get(agent_id).unwrap_or_default()
+        }
+
+        /// Agent-level reputation on a 0 - 100 scale
+        ///
+        /// Success rate over every request served by the agent's services,
+        /// independent of which key each service currently pays out to.
+        #[ink(message)]
+        pub fn get_agent_reputation(&self, agent_id: u64) -> u32 {
+            let mut total: u64 = 0;
+            let mut successful: u64 = 0;
+            for service_id in self.get_agent_services(agent_id) {
+                if let Some(service) = self.services.get(service_id) {
+                    total += u64::from(service.total_requests);
+                    successful += u64::from(service.successful_requests);
+                }
+            }
+            if total == 0 {
+                return 0;
+            }
+            (successful * 100 / total) as u32
+        }
+
+        /// Set the agent registry used to resolve agent keys
+        #[ink(message)]
+        pub fn set_agent_registry(&mut self, agent_registry: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.agent_registry = Some(agent_registry);
+            Ok(())
+        }
+
+        /// Set the curated list used by `get_verified_services`
+        #[ink(message)]
+        pub fn set_curation(&mut self, curation: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.curation = Some(curation);
+            Ok(())
+        }
+
+        /// Set (or clear) the index contract notified on listing changes
+        #[ink(message)]
+        pub fn set_index(&mut self, index: Option<H160>) -> Result<()> {
+            self.ensure_admin()?;
+            self.index = index;
+            Ok(())
+        }
+
+        /// Set the escrow contract allowed to report settlements
+        #[ink(message)]
+        pub fn set_escrow(&mut self, escrow: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.escrow = Some(escrow);
+            Ok(())
+        }
+
+        /// Set the length in milliseconds of the trending window
+        #[ink(message)]
+        pub fn set_trending_window(&mut self, trending_window: u64) -> Result<()> {
+            self.set_parameter(Parameter::TrendingWindow(trending_window))
+        }
+
+        /// Set the maximum lengths of listing strings
+        #[ink(message)]
+        pub fn set_string_limits(&mut self, string_limits: StringLimits) -> Result<()> {
+            self.set_parameter(Parameter::StringLimits(string_limits))
+        }
+
+        /// Set (or clear) the automatic deactivation policy
+        #[ink(message)]
+        pub fn set_deactivation_policy(
+            &mut self,
+            policy: Option<DeactivationPolicy>,
+        ) -> Result<()> {
+            self.ensure_admin()?;
+            if let Some(policy) = &policy {
+                if policy.sample_size == 0
+                    || policy.sample_size > self.params.max_performance_sample
+                    || policy.min_success_bps > 10_000
+                    || policy.max_dispute_bps > 10_000
+                {
+                    return Err(Error::InvalidInput);
+                }
+            }
+            self.deactivation_policy = policy;
             Ok(())
         }
-    }
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+
+        /// Get the automatic deactivation policy, if any
+        #[ink(message)]
+        pub fn get_deactivation_policy(&self) -> Option<DeactivationPolicy> {
+            self.deactivation_policy.clone()
+        }
+
+        /// Get every setting governance may tune
+        #[ink(message)]
+        pub fn get_parameters(&self) -> Parameters {
+            self.params.clone()
+        }
+
+        /// Change one tunable setting
+        ///
+        /// Only the owner or an Admin may call it; hand ownership to a
+        /// governance or timelock contract, and grant no Admins, to put
+        /// changes behind a vote or delay. Every
+        /// change emits `ParameterUpdated`.
+        #[ink(message)]
+        pub fn set_parameter(&mut self, parameter: Parameter) -> Result<()> {
+            self.ensure_admin()?;
+            if let Parameter::MaxPerformanceSample(max) = parameter {
+                if self
+                    .deactivation_policy
+                    .as_ref()
+                    .is_some_and(|policy| policy.sample_size > max)
+                {
+                    return Err(Error::InvalidInput);
+                }
+            }
+            self.params.set(parameter.clone())?;
+            self.env().emit_event(ParameterUpdated { parameter });
+            Ok(())
+        }
+
+        /// Get the maximum lengths of listing strings
+        #[ink(message)]
+        pub fn get_string_limits(&self) -> StringLimits {
+            self.params.string_limits.clone()
+        }
+
+        /// Set the account links contract consulted in authorization checks
+        #[ink(message)]
+        pub fn set_account_links(&mut self, account_links: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.account_links = Some(account_links);
+            Ok(())
+        }
+
+        /// Set the token registry token prices are validated against
+        #[ink(message)]
+        pub fn set_token_registry(&mut self, token_registry: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.token_registry = Some(token_registry);
+            Ok(())
+        }
+
+        /// Set the gateway registry x402 gateways of listings are validated against
+        #[ink(message)]
+        pub fn set_gateway_registry(&mut self, gateway_registry: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.gateway_registry = Some(gateway_registry);
+            Ok(())
+        }
+
+        /// Get the gateway registry, if set
+        #[ink(message)]
+        pub fn get_gateway_registry(&self) -> Option<H160> {
+            self.gateway_registry
+        }
+
+        /// Set the certification contract surfaced in service queries
+        #[ink(message)]
+        pub fn set_certification(&mut self, certification: H160) -> Result<()> {
+            self.ensure_admin()?;
+            self.certification = Some(certification);
+            Ok(())
+        }
+
+        /// Deprecate a service in favour of a replacement listing
+        ///
+        /// The service stays usable so existing integrations keep working,
+        /// but discovery reports the successor and escrows created against
+        /// it flag the deprecation. The successor must be another listing
+        /// the caller controls that is not itself deprecated.
+        #[ink(message)]
+        pub fn deprecate_service(&mut self, service_id: u64, successor_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, caller)?;
+            let successor = self
+                .services
+                .get(successor_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&successor, caller)?;
+            if successor_id == service_id || self.successors.contains(successor_id) {
+                return Err(Error::InvalidInput);
+            }
+
+            self.successors.insert(service_id, &successor_id);
+            self.env().emit_event(ServiceDeprecated {
+                service_id,
+                successor_id,
+            });
+
+            Ok(())
+        }
+
+        /// Deregister a service for good, starting the unbonding of its deposit
+        ///
+        /// The service is deactivated and can't be reactivated. Its deposit
+        /// can be withdrawn once the unbonding period has passed.
+        #[ink(message)]
+        pub fn deregister_service(&mut self, service_id: u64) -> Result<()> {
+            let caller = self.env().caller();
+            let mut service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, caller)?;
+            if self.deregistered_at.contains(service_id) {
+                return Err(Error::ServiceDeregistered);
+            }
+
+            let now = self.env().block_timestamp();
+            self.deregistered_at.insert(service_id, &now);
+            service.is_active = false;
+            self.services.insert(service_id, &service);
+            self.notify_index(&service);
+            // Settings only a live listing needs; freeing them returns their deposit
+            self.price_history.remove(service_id);
+            self.max_pending_escrows.remove(service_id);
+            self.single_escrow_services.remove(service_id);
+            self.service_hooks.remove(service_id);
+            self.acceptance_policies.remove(service_id);
+
+            self.env().emit_event(ServiceDeregistered {
+                service_id,
+                deposit_unlocks_at: now.saturating_add(self.params.unbonding_period),
+            });
+
+            Ok(())
+        }
+
+        /// Return a deregistered service's deposit to its provider
+        ///
+        /// A service that lost a dispute, or is suspended by the performance
+        /// policy, forfeits its deposit instead. Returns the amount returned.
+        #[ink(message)]
+        pub fn withdraw_deposit(&mut self, service_id: u64) -> Result<Balance> {
+            let service = self
+                .services
+                .get(service_id)
+                .ok_or(Error::ServiceNotFound)?;
+            self.ensure_service_controller(&service, self.env().caller())?;
+            let deregistered_at = self
+                .deregistered_at
+                .get(service_id)
+                .ok_or(Error::NotDeregistered)?;
+            if self.env().block_timestamp()
+                < deregistered_at.saturating_add(self.params.unbonding_period)
+            {
+                return Err(Error::DepositLocked);
+            }
+            let deposit = self
+                .service_deposits
+                .get(service_id)
+                .ok_or(Error::NoDeposit)?;
+
+            let forfeited =
+                self.get_lost_disputes(service_id) > 0 || self.suspended.contains(service_id);
+            if forfeited {
+                self.forfeited_deposits = self.forfeited_deposits.saturating_add(deposit);
+            } else if self
+                .env()
+                .transfer(service.provider, deposit.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+            self.service_deposits.remove(service_id);
+
+            self.env().emit_event(DepositSettled {
+                service_id,
+                amount: deposit,
+                forfeited,
+            });
+
+            Ok(if forfeited { 0 } else { deposit })
+        }
+
+        /// Record a dispute the service lost, reported by the escrow contract
+        #[ink(message)]
+        pub fn record_lost_dispute(&mut self, service_id: u64) -> Result<()> {
+            if Some(self.env().caller()) != self.escrow {
+                return Err(Error::Unauthorized);
+            }
+            if !self.services.contains(service_id) {
+                return Err(Error::ServiceNotFound);
+            }
+            let lost = self.get_lost_disputes(service_id).saturating_add(1);
+            self.lost_disputes.insert(service_id, &lost);
+            Ok(())
+        }
+
+        /// Get how many disputes a service lost
+        #[ink(message)]
+        pub fn get_lost_disputes(&self, service_id: u64) -> u32 {
+            self.lost_disputes.get(service_id).unwrap_or(0)
+        }
+
+        /// Get the deposit a service holds, if any
+        #[ink(message)]
+        pub fn get_service_deposit(&self, service_id: u64) -> Balance {
+            self.service_deposits.get(service_id).unwrap_or(0)
+        }
+
+        /// Get the deposit new listings must lock
+        #[ink(message)]
+        pub fn get_registration_deposit(&self) -> Balance {
+            self.params.registration_deposit
+        }
+
+        /// Set the deposit new listings must lock, 0 to disable deposits
+        #[ink(message)]
+        pub fn set_registration_deposit(&mut self, registration_deposit: Balance) -> Result<()> {
+            self.set_parameter(Parameter::RegistrationDeposit(registration_deposit))
+        }
+
+        /// Listing records a provider created
+        #[ink(message)]
+        pub fn get_storage_usage(&self, provider: H160) -> StorageUsage {
+            self.storage_usage.get(provider).unwrap_or_default()
+        }
+
+        /// Storage deposit held for a provider's listing records
+        ///
+        /// An estimate at the configured rates, counting each listing at the
+        /// size it was registered with.
+        #[ink(message)]
+        pub fn get_storage_deposit(&self, provider: H160) -> Balance {
+            self.params
+                .storage_deposit_rates
+                .deposit(&self.get_storage_usage(provider))
+        }
+
+        /// Mirror the chain's storage deposit rates
+        #[ink(message)]
+        pub fn set_storage_deposit_rates(&mut self, rates: StorageDepositRates) -> Result<()> {
+            self.set_parameter(Parameter::StorageDepositRates(rates))
+        }
+
+        /// Set how long deposits stay locked after deregistration
+        #[ink(message)]
+        pub fn set_unbonding_period(&mut self, unbonding_period: u64) -> Result<()> {
+            self.set_parameter(Parameter::UnbondingPeriod(unbonding_period))
+        }
+
+        /// Withdraw the forfeited deposits to `to`
+        #[ink(message)]
+        pub fn withdraw_forfeited_deposits(&mut self, to: H160) -> Result<Balance> {
+            self.ensure_owner()?;
+            let amount = self.forfeited_deposits;
+            if amount == 0 {
+                return Err(Error::NoDeposit);
+            }
+            if self.env().transfer(to, amount.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            self.forfeited_deposits = 0;
+            Ok(amount)
+        }
+
+        /// Bond the transferred value as the caller's provider stake
+        #[ink(message, payable)]
+        pub fn stake(&mut self) -> Result<Balance> {
+            let provider = self.env().caller();
+            let amount: Balance = self
+                .env()
+                .transferred_value()
+                .try_into()
+                .map_err(|_| Error::Overflow)?;
+            if amount == 0 {
+                return Err(Error::InvalidDeposit);
+            }
+
+            let total = self
+                .get_stake(provider)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.provider_stakes.insert(provider, &total);
+
+            self.env().emit_event(Staked {
+                provider,
+                amount,
+                total,
+            });
+
+            Ok(total)
+        }
+
+        /// Start unbonding `amount` of the caller's stake
+        ///
+        /// The amount stays slashable until the unbonding period has passed,
+        /// and adding to a pending unbonding restarts its period. While the
+        /// caller has live listings, its stake can't drop below the minimum.
+        #[ink(message)]
+        pub fn unstake(&mut self, amount: Balance) -> Result<()> {
+            let provider = self.env().caller();
+            let staked = self.get_stake(provider);
+            if amount == 0 || amount > staked {
+                return Err(Error::InvalidInput);
+            }
+            let remaining = staked.saturating_sub(amount);
+            if remaining < self.params.min_provider_stake && self.has_live_listing(provider) {
+                return Err(Error::InsufficientStake);
+            }
+
+            let unlocks_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.params.unbonding_period);
+            let unbonding = Unbonding {
+                amount: self
+                    .unbonding_stakes
+                    .get(provider)
+                    .map_or(0, |unbonding| unbonding.amount)
+                    .saturating_add(amount),
+                unlocks_at,
+            };
+            self.provider_stakes.insert(provider, &remaining);
+            self.unbonding_stakes.insert(provider, &unbonding);
+
+            self.env().emit_event(UnstakeStarted {
+                provider,
+                amount,
+                unlocks_at,
+            });
+
+            Ok(())
+        }
+
+        /// Withdraw the caller's unbonded stake, returning the amount
+        #[ink(message)]
+        pub fn withdraw_stake(&mut self) -> Result<Balance> {
+            let provider = self.env().caller();
+            let unbonding = self.unbonding_stakes.get(provider).ok_or(Error::NoStake)?;
+            if self.env().block_timestamp() < unbonding.unlocks_at {
+                return Err(Error::DepositLocked);
+            }
+            if self
+                .env()
+                .transfer(provider, unbonding.amount.into())
+                .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+            self.unbonding_stakes.remove(provider);
+
+            self.env().emit_event(StakeWithdrawn {
+                provider,
+                amount: unbonding.amount,
+            });
+
+            Ok(unbonding.amount)
+        }
+
+        /// Slash up to `amount` of a provider's stake, compensating `recipient`
+        ///
+        /// Only accounts holding the Arbitrator role may slash. The stake goes
+        /// to the account the provider harmed, usually the payer of the
+        /// disputed escrow, never to the arbitrator, so slashing earns an
+        /// arbitrator nothing. Bonded stake is slashed first, then stake still
+        /// unbonding, so unstaking doesn't escape a pending ruling. Returns the
+        /// amount slashed.
+        #[ink(message)]
+        pub fn slash(
+            &mut self,
+            provider: H160,
+            amount: Balance,
+            recipient: H160,
+        ) -> Result<Balance> {
+            let arbitrator = self.env().caller();
+            if !self.has_role(Role::Arbitrator, arbitrator) {
+                return Err(Error::Unauthorized);
+            }
+            if recipient == arbitrator || recipient == provider {
+                return Err(Error::InvalidInput);
+            }
+
+            let staked = self.get_stake(provider);
+            let from_stake = amount.min(staked);
+            let mut unbonding = self.unbonding_stakes.get(provider);
+            let from_unbonding = unbonding
+                .as_ref()
+                .map_or(0, |unbonding| unbonding.amount)
+                .min(amount.saturating_sub(from_stake));
+            let slashed = from_stake.saturating_add(from_unbonding);
+            if slashed == 0 {
+                return Err(Error::NoStake);
+            }
+            if self.env().transfer(recipient, slashed.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+
+            self.provider_stakes
+                .insert(provider, &staked.saturating_sub(from_stake));
+            if let Some(unbonding) = unbonding.as_mut() {
+                unbonding.amount = unbonding.amount.saturating_sub(from_unbonding);
+                if unbonding.amount == 0 {
+                    self.unbonding_stakes.remove(provider);
+                } else {
+                    self.unbonding_stakes.insert(provider, unbonding);
+                }
+            }
+
+            self.env().emit_event(ProviderSlashed {
+                provider,
+                arbitrator,
+                recipient,
+                amount: slashed,
+            });
+
+            Ok(slashed)
+        }
+
+        /// Get a provider's bonded stake
+        #[ink(message)]
+        pub fn get_stake(&self, provider: H160) -> Balance {
+            self.provider_stakes.get(provider).unwrap_or(0)
+        }
+
+        /// Get a provider's stake still unbonding, if any
+        #[ink(message)]
+        pub fn get_unbonding(&self, provider: H160) -> Option<Unbonding> {
+            self.unbonding_stakes.get(provider)
+        }
+
+        /// Get the stake a provider must hold to list a service
+        #[ink(message)]
+        pub fn get_min_provider_stake(&self) -> Balance {
+            self.params.min_provider_stake
+        }
+
+        /// Set the stake a provider must hold to list a service, 0 to disable
+        #[ink(message)]
+        pub fn set_min_provider_stake(&mut self, min_provider_stake: Balance) -> Result<()> {
+            self.set_parameter(Parameter::MinProviderStake(min_provider_stake))
+        }
+
+        /// Get the replacement listing of a deprecated service
+        #[ink(message)]
+        pub fn get_successor(&self, service_id: u64) -> Option<u64> {
+            self.successors.get(service_id)
+        }
+
+        /// Set (or clear) the platform fee charged on a category's settlements
+        ///
+        /// Categories without an override pay the escrow's default fee.
+        #[ink(message)]
+        pub fn set_category_fee(
+            &mut self,
+            category: ServiceCategory,
+            fee_bps: Option<u32>,
+        ) -> Result<()> {
+            self.ensure_admin()?;
+            match fee_bps {
+                Some(fee_bps) if fee_bps > 10_000 => return Err(Error::InvalidInput),
+                Some(fee_bps) => self.category_fees.insert(category.clone(), &fee_bps),
+                None => self.category_fees.remove(category.clone()),
+            };
+            self.env()
+                .emit_event(CategoryFeeUpdated { category, fee_bps });
+            Ok(())
+        }
+
+        /// Get a category's platform fee override, if any
+        #[ink(message)]
+        pub fn get_category_fee(&self, category: ServiceCategory) -> Option<u32> {
+            self.category_fees.get(category)
+        }
+
+        /// Platform fee override for a service's category, if any
+        #[ink(message)]
+        pub fn get_service_fee_bps(&self, service_id: u64) -> Option<u32> {
+            let service = self.services.get(service_id)?;
+            self.category_fees.get(service.category)
+        }
+
+        /// Set (or clear) the event hub lifecycle events are re-emitted through
+        #[ink(message)]
+        pub fn set_event_hub(&mut self, event_hub: Option<H160>) -> Result<()> {
+            self.ensure_admin()?;
+            self.event_hub = event_hub;
+            Ok(())
+        }
+
+        /// Get the index contract notified on listing changes
+        #[ink(message)]
+        pub fn get_index(&self) -> Option<H160> {
+            self.index
+        }
+
+        /// Pushes the current state of a service to the index, if one is set
+        fn notify_index(&self, service: &Service) {
+            let Some(index) = self.index else {
+                return;
+            };
+            let mut index: ink::contract_ref!(ServiceIndexHook) = FromAddr::from_addr(index);
+            index.on_service_changed(service.clone(), self.get_reputation(service.provider));
+        }
+
+        fn update_service_reputation(&mut self, service_id: u64, reputation: &ServiceReputation) {
+            self.service_reputation.insert(service_id, reputation);
+            let score = self.get_service_reputation(service_id);
+            self.env()
+                .emit_event(ServiceReputationUpdated { service_id, score });
+        }
+
+        /// Keeps the service's recent outcomes and applies the deactivation policy
+        fn record_outcome(&mut self, service_id: u64, outcome: Outcome) {
+            let mut outcomes = self.recent_outcomes.get(service_id).unwrap_or_default();
+            outcomes.push(outcome);
+            while outcomes.len() > self.params.max_performance_sample as usize {
+                outcomes.remove(0);
+            }
+            self.recent_outcomes.insert(service_id, &outcomes);
+
+            let Some(policy) = &self.deactivation_policy else {
+                return;
+            };
+            let sample_size = policy.sample_size as usize;
+            if outcomes.len() < sample_size || self.suspended.contains(service_id) {
+                return;
+            }
+
+            let sample = &outcomes[outcomes.len() - sample_size..];
+            let count = |kind| sample.iter().filter(|o| **o == kind).count() as u64;
+            let (successes, failures, disputes) = (
+                count(Outcome::Success),
+                count(Outcome::Failure),
+                count(Outcome::Disputed),
+            );
+            let success_bps = if successes + failures == 0 {
+                10_000
+            } else {
+                (successes * 10_000 / (successes + failures)) as u32
+            };
+            let dispute_bps = (disputes * 10_000 / sample_size as u64) as u32;
+            if success_bps >= policy.min_success_bps && dispute_bps <= policy.max_dispute_bps {
+                return;
+            }
+
+            if self.suspend_service(service_id) {
+                self.env().emit_event(ServiceAutoDeactivated {
+                    service_id,
+                    success_bps,
+                    dispute_bps,
+                });
+            }
+        }
+
+        /// Counts a reported call towards the service's SLA window and
+        /// deactivates the service once the window misses the SLA
+        fn record_sla_sample(&mut self, service_id: u64, success: bool, latency_ms: u64) {
+            let Some(sla) = self.service_slas.get(service_id) else {
+                return;
+            };
+            let window = self.env().block_timestamp() / self.params.sla_window;
+            let mut stats = self
+                .sla_windows
+                .get(service_id)
+                .filter(|stats| stats.window == window)
+                .unwrap_or(SlaWindow {
+                    window,
+                    ..Default::default()
+                });
+            stats.requests = stats.requests.saturating_add(1);
+            if success {
+                stats.successes = stats.successes.saturating_add(1);
+            }
+            stats.total_latency_ms = stats.total_latency_ms.saturating_add(latency_ms);
+
+            if stats.requests < self.params.min_sla_requests || self.suspended.contains(service_id)
+            {
+                self.sla_windows.insert(service_id, &stats);
+                return;
+            }
+
+            let requests = stats.requests as u64;
+            let success_bps = (stats.successes as u64 * 10_000 / requests) as u32;
+            let average_latency_ms = stats.total_latency_ms / requests;
+            if success_bps >= sla.min_success_bps && average_latency_ms <= sla.max_response_ms {
+                self.sla_windows.insert(service_id, &stats);
+                return;
+            }
+
+            // A reinstated service starts measuring from a clean window
+            self.sla_windows.remove(service_id);
+            if self.suspend_service(service_id) {
+                self.env().emit_event(SlaBreached {
+                    service_id,
+                    success_bps,
+                    average_latency_ms,
+                });
+            }
+        }
+
+        /// Deactivates a service pending appeal, returning whether it exists
+        fn suspend_service(&mut self, service_id: u64) -> bool {
+            let Some(mut service) = self.services.get(service_id) else {
+                return false;
+            };
+            service.is_active = false;
+            self.services.insert(service_id, &service);
+            self.suspended.insert(service_id, &false);
+            self.notify_index(&service);
+            self.publish(
+                HubEventKind::ServiceUpdated,
+                service_id,
+                service.provider,
+                service.price,
+            );
+            true
+        }
+
+        /// Counts a completed request towards the service's trending score
+        fn record_completion(&mut self, service_id: u64) {
+            let window = self.env().block_timestamp() / self.params.trending_window;
+            let mut counter = self.trend_counters.get(service_id).unwrap_or_default();
+            if counter.window != window {
+                counter.previous = if counter.window + 1 == window {
+                    counter.current
+                } else {
+                    0
+                };
+                counter.current = 0;
+                counter.window = window;
+            }
+            counter.current = counter.current.saturating_add(1);
+            self.trend_counters.insert(service_id, &counter);
+
+            if self.trending_candidates.contains(&service_id) {
+                return;
+            }
+            if self.trending_candidates.len() >= MAX_TRENDING_CANDIDATES {
+                // Make room by dropping the coldest candidate
+                let coldest = self
+                    .trending_candidates
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, id)| self.trend_score(**id))
+                    .map(|(i, _)| i);
+                if let Some(i) = coldest {
+                    self.trending_candidates.swap_remove(i);
+                }
+            }
+            self.trending_candidates.push(service_id);
+        }
+
+        /// Completions over the trailing window ending now
+        fn trend_score(&self, service_id: u64) -> u32 {
+            let Some(counter) = self.trend_counters.get(service_id) else {
+                return 0;
+            };
+            let now = self.env().block_timestamp();
+            let window = now / self.params.trending_window;
+            let elapsed = now % self.params.trending_window;
+            let (current, previous) = if counter.window == window {
+                (counter.current, counter.previous)
+            } else if counter.window + 1 == window {
+                (0, counter.current)
+            } else {
+                return 0;
+            };
+            let carried = u64::from(previous) * (self.params.trending_window - elapsed)
+                / self.params.trending_window;
+            current.saturating_add(carried as u32)
+        }
+
+        fn record_price_change(&mut self, service_id: u64, old_price: Balance, new_price: Balance) {
+            if old_price == new_price {
+                return;
+            }
+            let mut history = self.price_history.get(service_id).unwrap_or_default();
+            while history.len() >= self.params.max_price_history as usize {
+                history.remove(0);
+            }
+            history.push(PriceChange {
+                timestamp: self.env().block_timestamp(),
+                old_price,
+                new_price,
+            });
+            self.price_history.insert(service_id, &history);
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Configuration is open to the owner and any Admin
+        fn ensure_admin(&self) -> Result<()> {
+            if !self.is_admin(self.env().caller()) {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        fn is_admin(&self, account: H160) -> bool {
+            account == self.owner || self.has_role(Role::Admin, account)
+        }
+
+        fn ensure_within_limits(
+            &self,
+            name: &str,
+            description: &str,
+            endpoint: &str,
+        ) -> Result<()> {
+            let limits = &self.params.string_limits;
+            if name.len() > limits.max_name_len as usize
+                || description.len() > limits.max_description_len as usize
+                || endpoint.len() > limits.max_endpoint_len as usize
+            {
+                return Err(Error::InputTooLong);
+            }
+            Ok(())
+        }
+
+        /// Drops the long strings and attaches the provider's reputation
+        fn summarize(&self, service: Service) -> ServiceSummary {
+            let success_rate = service.success_rate();
+            ServiceSummary {
+                id: service.id,
+                provider: service.provider,
+                reputation: self.get_reputation(service.provider),
+                service_reputation: self.get_service_reputation(service.id),
+                name: service.name,
+                category: service.category,
+                price: service.price,
+                payment_asset: service.payment_asset,
+                success_rate,
+                successor: self.successors.get(service.id),
+                away: self.is_away(service.provider),
+            }
+        }
+
+        fn decayed_activity(&self, provider: H160) -> ProviderActivity {
+            self.provider_activity
+                .get(provider)
+                .unwrap_or_default()
+                .decayed(
+                    self.env().block_timestamp(),
+                    self.params.reputation_half_life,
+                )
+        }
+
+        fn add_review(&mut self, service_id: u64, reviewer: H160, rating: u32) -> Result<()> {
+            let mut reputation = self.service_reputation.get(service_id).unwrap_or_default();
+            reputation.review_count = reputation
+                .review_count
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+            reputation.rating_sum = reputation
+                .rating_sum
+                .checked_add(u64::from(rating))
+                .ok_or(Error::Overflow)?;
+            self.update_service_reputation(service_id, &reputation);
+
+            self.env().emit_event(ServiceReviewed {
+                service_id,
+                reviewer,
+                rating,
+            });
+
+            Ok(())
+        }
+
+        /// Re-emits a lifecycle event through the event hub, if one is set
+        fn publish(&self, kind: HubEventKind, subject_id: u64, actor: H160, amount: Balance) {
+            let Some(event_hub) = self.event_hub else {
+                return;
+            };
+            let mut event_hub: EventHubRef = FromAddr::from_addr(event_hub);
+            // A hub that hasn't approved this contract must not block the action
+            let _ = event_hub.publish(kind, subject_id, actor, amount);
+        }
+
+        /// The provider (or a native account linked to it), or any current
+        /// key of the service's agent, may manage a service
+        fn ensure_service_controller(&self, service: &Service, caller: H160) -> Result<()> {
+            if self.acts_for(caller, service.provider) {
+                return Ok(());
+            }
+            match self.service_agents.get(service.id) {
+                Some(agent_id) if self.is_agent_key(agent_id, caller)? => Ok(()),
+                _ => Err(Error::Unauthorized),
+            }
+        }
+
+        /// Whether `caller` is `account` or a native account linked to it
+        fn acts_for(&self, caller: H160, account: H160) -> bool {
+            if caller == account {
+                return true;
+            }
+            let Some(account_links) = self.account_links else {
+                return false;
+            };
+            let account_links: AccountLinksRef = FromAddr::from_addr(account_links);
+            account_links.acts_for(caller, account)
+        }
+
+        /// Decimals of a token asset from the token registry
+        ///
+        /// `None` for the native currency, or when no token registry is set.
+        /// Checks a new listing, returning the decimals of its price token
+        fn validate_listing(
+            &self,
+            name: &str,
+            description: &str,
+            price: Balance,
+            endpoint: &str,
+            payment_asset: PaymentAsset,
+            x402_gateway_address: Option<H160>,
+        ) -> Result<Option<u8>> {
+            if name.is_empty() || description.is_empty() || endpoint.is_empty() || price == 0 {
+                return Err(Error::InvalidInput);
+            }
+            self.ensure_within_limits(name, description, endpoint)?;
+            self.ensure_gateway_supports(payment_asset, x402_gateway_address)?;
+            self.token_decimals(payment_asset)
+        }
+
+        /// Checks a listing's x402 gateway against the gateway registry, if one is set
+        fn ensure_gateway_supports(
+            &self,
+            asset: PaymentAsset,
+            x402_gateway_address: Option<H160>,
+        ) -> Result<()> {
+            let (Some(gateway), Some(gateway_registry)) =
+                (x402_gateway_address, self.gateway_registry)
+            else {
+                return Ok(());
+            };
+            let gateway_registry: GatewayRegistryRef = FromAddr::from_addr(gateway_registry);
+            if !gateway_registry.supports(gateway, asset) {
+                return Err(Error::UnknownGateway);
+            }
+            Ok(())
+        }
+
+        fn token_decimals(&self, asset: PaymentAsset) -> Result<Option<u8>> {
+            let (Some(token), Some(token_registry)) = (asset.token(), self.token_registry) else {
+                return Ok(None);
+            };
+            let token_registry: TokenRegistryRef = FromAddr::from_addr(token_registry);
+            token_registry
+                .get_decimals(token)
+                .map(Some)
+                .ok_or(Error::UnknownToken)
+        }
+
+        fn is_agent_key(&self, agent_id: u64, account: H160) -> Result<bool> {
+            let agent_registry: AgentRegistryRef =
+                FromAddr::from_addr(self.agent_registry.ok_or(Error::AgentRegistryNotSet)?);
+            Ok(agent_registry.is_agent_key(agent_id, account))
+        }
+
+        /// Whether a provider has a listing it hasn't deregistered
+        fn has_live_listing(&self, provider: H160) -> bool {
+            self.provider_services
+                .get(provider)
+                .unwrap_or_default()
+                .iter()
+                .any(|service_id| !self.deregistered_at.contains(*service_id))
+        }
+
+        fn is_agent_active(&self, agent_id: u64) -> Result<bool> {
+            let agent_registry: AgentRegistryRef =
+                FromAddr::from_addr(self.agent_registry.ok_or(Error::AgentRegistryNotSet)?);
+            Ok(agent_registry.is_active(agent_id))
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::U256;
+
+        #[ink::test]
+        fn register_service_works() {
+            let mut contract = ServiceRegistry::new();
+            ink::env::test::default_accounts();
+
+            let result = contract.register_service(
+                String::from("Text Summarizer"),
+                String::from("AI-powered text summarization"),
+                ServiceCategory::TextProcessing,
+                1000,
+                String::from("https://api.example.com/summarize"),
+                PaymentAsset::Native,
+                None,
+            );
+
+            assert!(result.is_ok());
+            assert_eq!(result.unwrap(), 1);
+            assert_eq!(contract.get_service_count(), 1);
+        }
+
+        #[ink::test]
+        fn get_service_works() {
+            let mut contract = ServiceRegistry::new();
+
+            let service_id = contract
+                .register_service(
+                    String::from("Test Service"),
+                    String::from("Description"),
+                    ServiceCategory::Computation,
+                    500,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            let service = contract.get_service(service_id).unwrap();
+            assert_eq!(service.name, String::from("Test Service"));
+            assert_eq!(service.price, 500);
+        }
+
+        #[ink::test]
+        fn update_status_works() {
+            let mut contract = ServiceRegistry::new();
+
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::DataAnalysis,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            assert!(contract.update_service_status(service_id, false).is_ok());
+
+            let service = contract.get_service(service_id).unwrap();
+            assert_eq!(service.is_active, false);
+        }
+
+        #[ink::test]
+        fn unauthorized_update_fails() {
+            let mut contract = ServiceRegistry::new();
+            let accounts = ink::env::test::default_accounts();
+
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::Translation,
+                    200,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            // Change caller
+            ink::env::test::set_caller(accounts.bob);
+
+            let result = contract.update_service_status(service_id, false);
+            assert_eq!(result, Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn reputation_weighs_volume_and_decays() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract.set_escrow(accounts.eve).unwrap();
+            contract
+                .set_parameter(Parameter::MinRatedAmount(50))
+                .unwrap();
+            contract
+                .set_parameter(Parameter::ReputationVolume(4))
+                .unwrap();
+            contract
+                .set_parameter(Parameter::ReputationHalfLife(1_000))
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.eve);
+            contract
+                .record_settlement(1, service_id, accounts.bob, accounts.alice, 10, true)
+                .unwrap();
+            assert_eq!(contract.get_reputation(accounts.alice), 0);
+            assert_eq!(contract.get_service_reputation(service_id), 0);
+            assert!(!contract.is_review_eligible(service_id, accounts.bob));
+            assert_eq!(
+                contract
+                    .get_service(service_id)
+                    .unwrap()
+                    .successful_requests,
+                1
+            );
+            for escrow_id in 2..4 {
+                contract
+                    .record_settlement(
+                        escrow_id,
+                        service_id,
+                        accounts.bob,
+                        accounts.alice,
+                        100,
+                        true,
+                    )
+                    .unwrap();
+            }
+            // Two of the four settlements needed for full weight
+            assert_eq!(contract.get_reputation(accounts.alice), 50);
+            for escrow_id in 4..6 {
+                contract
+                    .record_settlement(
+                        escrow_id,
+                        service_id,
+                        accounts.bob,
+                        accounts.alice,
+                        100,
+                        true,
+                    )
+                    .unwrap();
+            }
+            assert_eq!(contract.get_reputation(accounts.alice), 100);
+
+            ink::env::test::set_block_timestamp(1_000);
+            assert_eq!(contract.get_reputation(accounts.alice), 50);
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.rate_service(service_id, 2, 80),
+                Err(Error::NotReviewEligible)
+            );
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.rate_service(service_id, 1, 80),
+                Err(Error::NotReviewEligible)
+            );
+            contract.rate_service(service_id, 2, 80).unwrap();
+            assert_eq!(
+                contract.rate_service(service_id, 2, 80),
+                Err(Error::NotReviewEligible)
+            );
+            assert_eq!(
+                contract
+                    .get_service_reputation_details(service_id)
+                    .rating_sum,
+                80
+            );
+        }
+
+        #[ink::test]
+        fn service_model_works() {
+            let mut contract = ServiceRegistry::new();
+            let accounts = ink::env::test::default_accounts();
+
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            contract.set_service_model(service_id, Some(7)).unwrap();
+            assert_eq!(contract.get_service_model(service_id), Some(7));
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_service_model(service_id, None),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn x402_listing_follows_payment_asset() {
+            let mut contract = ServiceRegistry::new();
+            let token = H160::from([0x07; 20]);
+
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            assert!(contract.get_x402_services(10).is_empty());
+
+            let x402 = PaymentAsset::X402 {
+                token,
+                chain_id: Some(8453),
+            };
+            contract
+                .update_payment_asset(service_id, x402, 250, None)
+                .unwrap();
+
+            let services = contract.get_x402_services(10);
+            assert_eq!(services.len(), 1);
+            assert_eq!(services[0].payment_asset.token(), Some(token));
+            assert_eq!(services[0].price, 250);
+            assert_eq!(services[0].name, String::from("Test"));
+            assert_eq!(
+                contract.update_payment_asset(service_id, PaymentAsset::Native, 0, None),
+                Err(Error::InvalidInput)
+            );
+        }
+
+        #[ink::test]
+        fn pending_escrow_cap_is_set_by_provider_or_owner() {
+            let mut contract = ServiceRegistry::new();
+            let accounts = ink::env::test::default_accounts();
+
+            ink::env::test::set_caller(accounts.bob);
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract
+                .set_max_pending_escrows(service_id, Some(3))
+                .unwrap();
+            assert_eq!(contract.get_max_pending_escrows(service_id), Some(3));
+            assert_eq!(
+                contract.set_max_pending_escrows(service_id, Some(0)),
+                Err(Error::InvalidInput)
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.set_max_pending_escrows(service_id, None),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_max_pending_escrows(service_id, None).unwrap();
+            assert_eq!(contract.get_max_pending_escrows(service_id), None);
+        }
+
+        #[ink::test]
+        fn long_strings_are_rejected() {
+            let mut contract = ServiceRegistry::new();
+            contract
+                .set_string_limits(StringLimits {
+                    max_name_len: 8,
+                    max_description_len: 16,
+                    max_endpoint_len: 32,
+                })
+                .unwrap();
+
+            let register = |contract: &mut ServiceRegistry, name: &str| {
+                contract.register_service(
+                    String::from(name),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+            };
+            assert_eq!(
+                register(&mut contract, "Much too long"),
+                Err(Error::InputTooLong)
+            );
+            let service_id = register(&mut contract, "Test").unwrap();
+
+            assert_eq!(
+                contract.update_service_details(
+                    service_id,
+                    String::from("Test"),
+                    "x".repeat(17),
+                    String::from("https://test.com"),
+                ),
+                Err(Error::InputTooLong)
+            );
+            contract
+                .update_service_details(
+                    service_id,
+                    String::from("Renamed"),
+                    String::from("New desc"),
+                    String::from("https://new.com"),
+                )
+                .unwrap();
+            assert_eq!(
+                contract.get_service(service_id).unwrap().name,
+                String::from("Renamed")
+            );
+        }
+
+        #[ink::test]
+        fn native_prices_are_shown_in_display_units() {
+            let mut contract = ServiceRegistry::new();
+            let accounts = ink::env::test::default_accounts();
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    2_500_000_000_000,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            let price = contract.get_display_price(service_id).unwrap();
+            assert_eq!((price.whole, price.fraction), (2, 500_000_000_000));
+            assert_eq!(price.decimals, NATIVE_DECIMALS);
+
+            // Without a token registry the token's decimals are unknown
+            contract
+                .update_payment_asset(
+                    service_id,
+                    PaymentAsset::X402 {
+                        token: accounts.django,
+                        chain_id: None,
+                    },
+                    1_000_000,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(contract.get_price_decimals(service_id), None);
+            assert_eq!(contract.get_display_price(service_id), None);
+        }
+
+        #[ink::test]
+        fn category_fees_override_per_service() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::Computation,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(contract.get_service_fee_bps(service_id), None);
+
+            assert_eq!(
+                contract.set_category_fee(ServiceCategory::Computation, Some(10_001)),
+                Err(Error::InvalidInput)
+            );
+            contract
+                .set_category_fee(ServiceCategory::Computation, Some(50))
+                .unwrap();
+            assert_eq!(contract.get_service_fee_bps(service_id), Some(50));
+            assert_eq!(
+                contract.get_category_fee(ServiceCategory::Translation),
+                None
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_category_fee(ServiceCategory::Computation, None),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn can_register_reports_the_registration_error() {
+            let mut contract = ServiceRegistry::new();
+            assert_eq!(
+                contract.can_register(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    0,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                ),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                contract.can_register(
+                    String::from("Test"),
+                    "x".repeat(10_000),
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                ),
+                Err(Error::InputTooLong)
+            );
+            assert_eq!(
+                contract.can_register(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.get_service_count(), 0);
+        }
+
+        #[ink::test]
+        fn deprecated_services_point_to_their_successor() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            let register = |contract: &mut ServiceRegistry| {
+                contract
+                    .register_service(
+                        String::from("Test"),
+                        String::from("Desc"),
+                        ServiceCategory::TextProcessing,
+                        100,
+                        String::from("https://test.com"),
+                        PaymentAsset::Native,
+                        None,
+                    )
+                    .unwrap()
+            };
+            let old = register(&mut contract);
+            let new = register(&mut contract);
+
+            assert_eq!(
+                contract.deprecate_service(old, old),
+                Err(Error::InvalidInput)
+            );
+            contract.deprecate_service(old, new).unwrap();
+            assert_eq!(contract.get_successor(old), Some(new));
+            assert_eq!(
+                contract.deprecate_service(new, old),
+                Err(Error::InvalidInput)
+            );
+
+            let summaries = contract.get_active_services(10);
+            assert_eq!(summaries[0].successor, Some(new));
+            assert_eq!(summaries[1].successor, None);
+
+            ink::env::test::set_caller(accounts.bob);
+            let foreign = register(&mut contract);
+            assert_eq!(
+                contract.deprecate_service(foreign, new),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn clean_deregistration_returns_the_deposit() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            contract.set_registration_deposit(500).unwrap();
+            contract.set_unbonding_period(100).unwrap();
+            contract.set_escrow(accounts.eve).unwrap();
+            let register = |contract: &mut ServiceRegistry| {
+                contract.register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+            };
+
+            assert_eq!(register(&mut contract), Err(Error::InvalidDeposit));
+            ink::env::test::set_value_transferred(U256::from(500));
+            let clean = register(&mut contract).unwrap();
+            let disputed = register(&mut contract).unwrap();
+            assert_eq!(contract.get_service_deposit(clean), 500);
+
+            ink::env::test::set_caller(accounts.eve);
+            contract.record_lost_dispute(disputed).unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.withdraw_deposit(clean),
+                Err(Error::NotDeregistered)
+            );
+            contract.deregister_service(clean).unwrap();
+            contract.deregister_service(disputed).unwrap();
+            assert!(contract.get_price_history(clean, 5).is_empty());
+            assert_eq!(
+                contract.update_service_status(clean, true),
+                Err(Error::ServiceDeregistered)
+            );
+            assert_eq!(contract.withdraw_deposit(clean), Err(Error::DepositLocked));
+
+            ink::env::test::set_block_timestamp(100);
+            assert_eq!(contract.withdraw_deposit(clean), Ok(500));
+            assert_eq!(contract.withdraw_deposit(disputed), Ok(0));
+            assert_eq!(contract.withdraw_deposit(clean), Err(Error::NoDeposit));
+            assert_eq!(
+                contract.withdraw_forfeited_deposits(accounts.frank),
+                Ok(500)
+            );
+        }
+
+        #[ink::test]
+        fn providers_stake_to_list_and_arbitrator_slashes() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            contract.set_min_provider_stake(1_000).unwrap();
+            contract.set_unbonding_period(100).unwrap();
+            contract.grant_role(Role::Arbitrator, accounts.eve).unwrap();
+            let register = |contract: &mut ServiceRegistry| {
+                contract.register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+            };
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(register(&mut contract), Err(Error::InsufficientStake));
+            ink::env::test::set_value_transferred(U256::from(1_500));
+            assert_eq!(contract.stake(), Ok(1_500));
+            ink::env::test::set_value_transferred(U256::from(0));
+            let service_id = register(&mut contract).unwrap();
+
+            assert_eq!(contract.unstake(600), Err(Error::InsufficientStake));
+            contract.unstake(400).unwrap();
+            assert_eq!(contract.get_stake(accounts.bob), 1_100);
+            assert_eq!(contract.withdraw_stake(), Err(Error::DepositLocked));
+
+            assert_eq!(
+                contract.slash(accounts.bob, 100, accounts.charlie),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.eve);
+            assert_eq!(
+                contract.slash(accounts.bob, 100, accounts.eve),
+                Err(Error::InvalidInput)
+            );
+            let balance_of = |account| {
+                ink::env::test::get_contract_balance::<ink::env::DefaultEnvironment>(account)
+                    .unwrap_or_default()
+            };
+            let compensated = balance_of(accounts.charlie);
+            assert_eq!(
+                contract.slash(accounts.bob, 1_300, accounts.charlie),
+                Ok(1_300)
+            );
+            assert_eq!(
+                balance_of(accounts.charlie),
+                compensated + U256::from(1_300)
+            );
+            assert_eq!(contract.get_stake(accounts.bob), 0);
+            assert_eq!(
+                contract.get_unbonding(accounts.bob),
+                Some(Unbonding {
+                    amount: 200,
+                    unlocks_at: 100,
+                })
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.deregister_service(service_id).unwrap();
+            ink::env::test::set_block_timestamp(100);
+            assert_eq!(contract.withdraw_stake(), Ok(200));
+            assert_eq!(contract.withdraw_stake(), Err(Error::NoStake));
+        }
+
+        #[ink::test]
+        fn providers_limit_payers_to_one_active_escrow() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+
+            ink::env::test::set_caller(accounts.bob);
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract.set_single_active_escrow(service_id, true).unwrap();
+            assert!(contract.is_single_active_escrow(service_id));
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.set_single_active_escrow(service_id, false),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            contract
+                .set_single_active_escrow(service_id, false)
+                .unwrap();
+            assert!(!contract.is_single_active_escrow(service_id));
+        }
+
+        #[ink::test]
+        fn providers_attach_escrow_hooks() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+
+            ink::env::test::set_caller(accounts.bob);
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract
+                .set_service_hook(service_id, Some(accounts.django))
+                .unwrap();
+            assert_eq!(contract.get_service_hook(service_id), Some(accounts.django));
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.set_service_hook(service_id, Some(accounts.charlie)),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.deregister_service(service_id).unwrap();
+            assert_eq!(contract.get_service_hook(service_id), None);
+        }
 
         #[ink::test]
-        fn register_service_works() {
+        fn storage_deposit_counts_registered_listings() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = ServiceRegistry::new();
-            ink::env::test::default_accounts();
+            contract
+                .set_storage_deposit_rates(StorageDepositRates {
+                    per_item: 1_000,
+                    per_byte: 0,
+                })
+                .unwrap();
 
-            let result = contract.register_service(
-                String::from("Text Summarizer"),
-                String::from("AI-powered text summarization"),
-                ServiceCategory::TextProcessing,
-                1000,
-                String::from("https://api.example.com/summarize"),
+            for _ in 0..2 {
+                contract
+                    .register_service(
+                        String::from("Test"),
+                        String::from("Desc"),
+                        ServiceCategory::TextProcessing,
+                        100,
+                        String::from("https://test.com"),
+                        PaymentAsset::Native,
+                        None,
+                    )
+                    .unwrap();
+            }
+
+            assert_eq!(contract.get_storage_usage(accounts.alice).items, 2);
+            assert_eq!(contract.get_storage_deposit(accounts.alice), 2_000);
+            assert_eq!(contract.get_storage_deposit(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn governance_tunes_parameters() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            assert_eq!(contract.get_parameters(), Parameters::default());
+
+            contract
+                .set_deactivation_policy(Some(DeactivationPolicy {
+                    sample_size: 20,
+                    min_success_bps: 0,
+                    max_dispute_bps: 10_000,
+                }))
+                .unwrap();
+            assert_eq!(
+                contract.set_parameter(Parameter::MaxPerformanceSample(10)),
+                Err(Error::InvalidInput)
+            );
+            assert_eq!(
+                contract.set_parameter(Parameter::TrendingWindow(0)),
+                Err(Error::InvalidInput)
             );
+            contract
+                .set_parameter(Parameter::MaxPriceHistory(1))
+                .unwrap();
+            contract.set_unbonding_period(100).unwrap();
+            assert_eq!(contract.get_parameters().unbonding_period, 100);
 
-            assert!(result.is_ok());
-            assert_eq!(result.unwrap(), 1);
-            assert_eq!(contract.get_service_count(), 1);
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_parameter(Parameter::RegistrationDeposit(1)),
+                Err(Error::Unauthorized)
+            );
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract.update_service_price(service_id, 200).unwrap();
+            contract.update_service_price(service_id, 300).unwrap();
+            assert_eq!(contract.get_price_history(service_id, 10).len(), 1);
         }
 
         #[ink::test]
-        fn get_service_works() {
+        fn away_providers_stay_listed() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+
+            ink::env::test::set_caller(accounts.bob);
+            contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(contract.set_away(0, H256::zero()), Err(Error::InvalidInput));
+            contract.set_away(100, H256::from([7; 32])).unwrap();
+            assert!(contract.is_away(accounts.bob));
+            let listed = contract.get_active_services(10);
+            assert_eq!(listed.len(), 1);
+            assert!(listed[0].away);
+
+            ink::env::test::set_block_timestamp(100);
+            assert!(!contract.is_away(accounts.bob));
+            assert!(!contract.get_active_services(10)[0].away);
+
+            contract.set_away(200, H256::zero()).unwrap();
+            contract.clear_away().unwrap();
+            assert!(!contract.is_away(accounts.bob));
+            assert_eq!(contract.clear_away(), Err(Error::NotAway));
+        }
+
+        #[ink::test]
+        fn providers_set_acceptance_policies() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = ServiceRegistry::new();
 
+            ink::env::test::set_caller(accounts.bob);
             let service_id = contract
                 .register_service(
-                    String::from("Test Service"),
-                    String::from("Description"),
-                    ServiceCategory::Computation,
-                    500,
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
                     String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
                 )
                 .unwrap();
+            let policy = AcceptancePolicy {
+                min_payer_rating: 60,
+                denied_payers: vec![accounts.eve],
+                require_identity: true,
+            };
+            assert_eq!(
+                contract.set_acceptance_policy(
+                    service_id,
+                    Some(AcceptancePolicy {
+                        min_payer_rating: 101,
+                        ..policy.clone()
+                    })
+                ),
+                Err(Error::InvalidInput)
+            );
+            contract
+                .set_acceptance_policy(service_id, Some(policy.clone()))
+                .unwrap();
+            assert_eq!(contract.get_acceptance_policy(service_id), Some(policy));
+            assert!(!contract.has_agent_identity(accounts.charlie));
 
-            let service = contract.get_service(service_id).unwrap();
-            assert_eq!(service.name, String::from("Test Service"));
-            assert_eq!(service.price, 500);
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.set_acceptance_policy(service_id, None),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.deregister_service(service_id).unwrap();
+            assert_eq!(contract.get_acceptance_policy(service_id), None);
         }
 
         #[ink::test]
-        fn update_status_works() {
+        fn owner_manages_roles_and_admins_configure() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(
+                contract.record_service_request(service_id, true),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                contract.record_x402_payment(service_id, H256::from([1; 32]), true),
+                Err(Error::Unauthorized)
+            );
+
+            contract.grant_role(Role::Admin, accounts.bob).unwrap();
+            ink::env::test::set_caller(accounts.bob);
+            contract.set_trending_window(1_000).unwrap();
+            assert_eq!(
+                contract.grant_role(Role::Verifier, accounts.bob),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.revoke_role(Role::Admin, accounts.bob).unwrap();
+            assert!(!contract.has_role(Role::Admin, accounts.bob));
+            assert_eq!(
+                contract.revoke_role(Role::Admin, accounts.bob),
+                Err(Error::InvalidInput)
+            );
+
+            contract.transfer_ownership(accounts.charlie).unwrap();
+            assert_eq!(contract.get_owner(), accounts.charlie);
+            assert_eq!(
+                contract.set_trending_window(2_000),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn owner_upgrades_code_and_migrates_storage() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = ServiceRegistry::new();
+            let code_hash = H256::from([0x42; 32]);
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.upgrade(code_hash), Err(Error::Unauthorized));
+            assert_eq!(contract.migrate(), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.upgrade(code_hash).unwrap();
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate(), Err(Error::NothingToMigrate));
+        }
 
+        #[ink::test]
+        fn services_missing_their_sla_are_deactivated() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
             let service_id = contract
                 .register_service(
                     String::from("Test"),
                     String::from("Desc"),
-                    ServiceCategory::DataAnalysis,
+                    ServiceCategory::TextProcessing,
                     100,
                     String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
                 )
                 .unwrap();
+            let sla = ServiceSla {
+                max_response_ms: 500,
+                min_success_bps: 9_000,
+            };
+            contract
+                .set_parameter(Parameter::MinSlaRequests(2))
+                .unwrap();
+            contract
+                .grant_role(Role::Verifier, accounts.charlie)
+                .unwrap();
 
-            assert!(contract.update_service_status(service_id, false).is_ok());
+            assert_eq!(
+                contract.set_service_sla(
+                    service_id,
+                    Some(ServiceSla {
+                        max_response_ms: 0,
+                        ..sla.clone()
+                    })
+                ),
+                Err(Error::InvalidInput)
+            );
+            contract
+                .set_service_sla(service_id, Some(sla.clone()))
+                .unwrap();
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_service_sla(service_id, None),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                contract.report_request(service_id, true, 100),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.charlie);
+            contract.report_request(service_id, true, 100).unwrap();
+            contract.report_request(service_id, true, 300).unwrap();
+            let (committed, window) = contract.get_service_sla(service_id).unwrap();
+            assert_eq!(committed, sla);
+            assert_eq!(window.requests, 2);
+            assert_eq!(window.total_latency_ms, 400);
+            assert!(contract.get_service(service_id).unwrap().is_active);
+
+            // A new window is measured on its own
+            ink::env::test::set_block_timestamp(86_400_000);
+            contract.report_request(service_id, true, 100).unwrap();
+            assert_eq!(contract.get_service_sla(service_id).unwrap().1.requests, 1);
+            contract.report_request(service_id, true, 2_000).unwrap();
 
             let service = contract.get_service(service_id).unwrap();
-            assert_eq!(service.is_active, false);
+            assert!(!service.is_active);
+            assert_eq!(service.total_requests, 4);
+            assert_eq!(contract.get_service_sla(service_id).unwrap().1.requests, 0);
+
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.update_service_status(service_id, true),
+                Err(Error::ServiceSuspended)
+            );
+            contract.appeal_deactivation(service_id).unwrap();
         }
 
         #[ink::test]
-        fn unauthorized_update_fails() {
+        fn price_changes_are_recorded() {
             let mut contract = ServiceRegistry::new();
-            let accounts = ink::env::test::default_accounts();
 
             let service_id = contract
                 .register_service(
                     String::from("Test"),
                     String::from("Desc"),
-                    ServiceCategory::Translation,
-                    200,
+                    ServiceCategory::TextProcessing,
+                    100,
                     String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
                 )
                 .unwrap();
+            ink::env::test::set_block_timestamp(10);
+            contract.update_service_price(service_id, 150).unwrap();
+            ink::env::test::set_block_timestamp(20);
+            contract.update_service_price(service_id, 120).unwrap();
+            contract.update_service_price(service_id, 120).unwrap();
+
+            assert_eq!(
+                contract.get_price_history(service_id, 5),
+                vec![
+                    PriceChange {
+                        timestamp: 20,
+                        old_price: 150,
+                        new_price: 120,
+                    },
+                    PriceChange {
+                        timestamp: 10,
+                        old_price: 100,
+                        new_price: 150,
+                    },
+                ]
+            );
+            assert_eq!(contract.get_price_history(service_id, 1).len(), 1);
+        }
+
+        #[ink::test]
+        fn trending_follows_recent_completions() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = ServiceRegistry::new();
+            contract.set_trending_window(1_000).unwrap();
+            contract.grant_role(Role::Verifier, accounts.alice).unwrap();
+
+            let mut ids = Vec::new();
+            for _ in 0..2 {
+                ids.push(
+                    contract
+                        .register_service(
+                            String::from("Test"),
+                            String::from("Desc"),
+                            ServiceCategory::TextProcessing,
+                            100,
+                            String::from("https://test.com"),
+                            PaymentAsset::Native,
+                            None,
+                        )
+                        .unwrap(),
+                );
+            }
+
+            for _ in 0..4 {
+                contract.record_service_request(ids[0], true).unwrap();
+            }
+            contract.record_service_request(ids[1], false).unwrap();
+            assert_eq!(contract.get_trending_services(10).len(), 1);
+
+            // Half way into the next window the old completions count half
+            ink::env::test::set_block_timestamp(1_500);
+            for _ in 0..3 {
+                contract.record_service_request(ids[1], true).unwrap();
+            }
+            let trending = contract.get_trending_services(10);
+            assert_eq!(trending[0].summary.id, ids[1]);
+            assert_eq!(trending[0].recent_completions, 3);
+            assert_eq!(trending[1].recent_completions, 2);
+
+            ink::env::test::set_block_timestamp(3_000);
+            assert!(contract.get_trending_services(10).is_empty());
+
+            // Reported x402 payments don't move the ranking
+            contract
+                .update_payment_asset(
+                    ids[0],
+                    PaymentAsset::X402 {
+                        token: accounts.django,
+                        chain_id: None,
+                    },
+                    100,
+                    None,
+                )
+                .unwrap();
+            contract
+                .record_x402_payment(ids[0], H256::from([1; 32]), true)
+                .unwrap();
+            assert!(contract.get_trending_services(10).is_empty());
+            assert_eq!(contract.get_service(ids[0]).unwrap().successful_requests, 5);
+        }
+
+        #[ink::test]
+        fn services_of_one_provider_keep_separate_reputation() {
+            let mut contract = ServiceRegistry::new();
+            let accounts = ink::env::test::default_accounts();
+
+            let mut ids = Vec::new();
+            for _ in 0..2 {
+                ids.push(
+                    contract
+                        .register_service(
+                            String::from("Test"),
+                            String::from("Desc"),
+                            ServiceCategory::TextProcessing,
+                            100,
+                            String::from("https://test.com"),
+                            PaymentAsset::Native,
+                            None,
+                        )
+                        .unwrap(),
+                );
+            }
+            contract.set_escrow(accounts.eve).unwrap();
+
+            ink::env::test::set_caller(accounts.eve);
+            contract
+                .record_settlement(1, ids[0], accounts.bob, accounts.alice, 100, true)
+                .unwrap();
+            contract
+                .record_settlement(2, ids[1], accounts.bob, accounts.alice, 100, false)
+                .unwrap();
+            // Half the settlements succeeded, over a fifth of the full volume
+            assert_eq!(contract.get_reputation(accounts.alice), 10);
+            assert_eq!(contract.get_service_reputation(ids[0]), 100);
+            assert_eq!(contract.get_service_reputation(ids[1]), 0);
 
-            // Change caller
             ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.submit_review(ids[1], 101),
+                Err(Error::InvalidInput)
+            );
+            contract.submit_review(ids[1], 60).unwrap();
+            assert_eq!(contract.get_service_reputation(ids[1]), 30);
+            assert_eq!(
+                contract.submit_review(ids[1], 60),
+                Err(Error::NotReviewEligible)
+            );
+            assert!(!contract.is_review_eligible(ids[1], accounts.bob));
 
-            let result = contract.update_service_status(service_id, false);
-            assert_eq!(result, Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.submit_review(ids[0], 100),
+                Err(Error::NotReviewEligible)
+            );
+        }
+
+        #[ink::test]
+        fn failing_services_are_deactivated_until_reinstated() {
+            let mut contract = ServiceRegistry::new();
+            let accounts = ink::env::test::default_accounts();
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract.set_escrow(accounts.eve).unwrap();
+            contract
+                .set_deactivation_policy(Some(DeactivationPolicy {
+                    sample_size: 4,
+                    min_success_bps: 5_000,
+                    max_dispute_bps: 2_500,
+                }))
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.eve);
+            for (escrow_id, success) in [(1, true), (2, false), (3, true)] {
+                contract
+                    .record_settlement(
+                        escrow_id,
+                        service_id,
+                        accounts.bob,
+                        accounts.alice,
+                        100,
+                        success,
+                    )
+                    .unwrap();
+            }
+            assert!(contract.get_service(service_id).unwrap().is_active);
+            contract.record_dispute(service_id).unwrap();
+            assert!(contract.get_service(service_id).unwrap().is_active);
+            contract.record_dispute(service_id).unwrap();
+            assert!(!contract.get_service(service_id).unwrap().is_active);
+            assert!(contract.is_suspended(service_id));
+
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.update_service_status(service_id, true),
+                Err(Error::ServiceSuspended)
+            );
+            assert_eq!(
+                contract.resolve_appeal(service_id, true),
+                Err(Error::NotSuspended)
+            );
+            contract.appeal_deactivation(service_id).unwrap();
+            assert_eq!(
+                contract.appeal_deactivation(service_id),
+                Err(Error::AppealPending)
+            );
+            contract.resolve_appeal(service_id, true).unwrap();
+            assert!(contract.get_service(service_id).unwrap().is_active);
+            assert!(!contract.is_suspended(service_id));
         }
 
         #[ink::test]
-        fn reputation_system_works() {
+        fn settlements_from_escrow_update_stats() {
             let mut contract = ServiceRegistry::new();
             let accounts = ink::env::test::default_accounts();
 
-            contract.update_reputation(accounts.alice, 95).unwrap();
-            assert_eq!(contract.get_reputation(accounts.alice), 95);
+            let service_id = contract
+                .register_service(
+                    String::from("Test"),
+                    String::from("Desc"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://test.com"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+            contract.set_escrow(accounts.eve).unwrap();
+
+            assert_eq!(
+                contract.record_settlement(1, service_id, accounts.bob, accounts.alice, 100, true),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.eve);
+            contract
+                .record_settlement(1, service_id, accounts.bob, accounts.alice, 100, true)
+                .unwrap();
+            contract
+                .record_settlement(2, service_id, accounts.charlie, accounts.alice, 100, false)
+                .unwrap();
+            assert_eq!(
+                contract.record_settlement(3, service_id, accounts.bob, accounts.django, 100, true),
+                Err(Error::InvalidInput)
+            );
+
+            let service = contract.get_service(service_id).unwrap();
+            assert_eq!(service.total_requests, 2);
+            assert_eq!(service.successful_requests, 1);
+            assert_eq!(contract.get_provider_earnings(accounts.alice), 100);
+            assert_eq!(contract.get_reputation(accounts.alice), 10);
+            assert!(contract.is_review_eligible(service_id, accounts.bob));
+            assert!(!contract.is_review_eligible(service_id, accounts.django));
+        }
+    }
+
+    /// Selectors and event topics SDKs and other contracts depend on
+    ///
+    /// Each message is looked up by its pinned selector, so renaming it fails
+    /// to compile; changing its arguments or an event's fields fails here.
+    /// Update these together with `API_VERSION`.
+    #[cfg(test)]
+    mod abi {
+        use super::*;
+        use ink::env::Event;
+        use ink::reflect::DispatchableMessageInfo;
+
+        // Callers attach value to these, so they must stay payable
+        const _: () = assert!(<ServiceRegistry as DispatchableMessageInfo<0xFBF7_4525>>::PAYABLE);
+
+        type RegisterArgs = (
+            String,
+            String,
+            ServiceCategory,
+            Balance,
+            String,
+            PaymentAsset,
+            Option<H160>,
+        );
+
+        #[test]
+        fn message_selectors_are_stable() {
+            assert_eq!(
+                <ServiceRegistry as DispatchableMessageInfo<0xFBF7_4525>>::LABEL,
+                "register_service"
+            );
+            let _: fn(&mut ServiceRegistry, RegisterArgs) -> Result<u64> =
+                <ServiceRegistry as DispatchableMessageInfo<0xFBF7_4525>>::CALLABLE;
+
+            let _: fn(&mut ServiceRegistry, u64) -> Result<Service> =
+                <ServiceRegistry as DispatchableMessageInfo<0xEFE6_C632>>::CALLABLE;
+            let _: fn(&mut ServiceRegistry, (u64, u64, H160, H160, Balance, bool)) -> Result<()> =
+                <ServiceRegistry as DispatchableMessageInfo<0x01C6_204D>>::CALLABLE;
+            let _: fn(&mut ServiceRegistry, H160) -> u32 =
+                <ServiceRegistry as DispatchableMessageInfo<0xF566_AD78>>::CALLABLE;
+            let _: fn(&mut ServiceRegistry, ()) -> ApiVersion =
+                <ServiceRegistry as DispatchableMessageInfo<0xC7E0_CA63>>::CALLABLE;
+        }
+
+        #[test]
+        fn event_topics_are_stable() {
+            assert_eq!(
+                ServiceRegistered::SIGNATURE_TOPIC,
+                Some(ink::blake2x256!(
+                    "ServiceRegistered(u64,H160,String,Balance)"
+                ))
+            );
+            assert_eq!(
+                ReputationUpdated::SIGNATURE_TOPIC,
+                Some(ink::blake2x256!("ReputationUpdated(H160,u32)"))
+            );
+        }
+    }
+
+    /// Storage-access budgets for every message
+    ///
+    /// Run with `cargo test --features bench`. The off-chain
+    /// engine can't measure weight or proof size, so each message is measured
+    /// by the storage cells it reads and writes (every read adds a node to
+    /// the proof) and the bytes it returns. The budgets hold at every size, so
+    /// a message that starts scaling with the number of services fails here.
+    #[cfg(all(test, feature = "bench"))]
+    mod bench {
+        use super::*;
+        use ink::scale::Encode;
+
+        const SIZES: [u64; 3] = [10, 1_000, 100_000];
+        /// Page size used for listing queries
+        const PAGE: u64 = 20;
+        /// Budget for any single-item message
+        const MAX_CELLS: usize = 4;
+        /// Budget for the data returned by one page of a listing
+        const MAX_PAGE_BYTES: usize = 4 * 1024;
+
+        fn provider(i: u64) -> H160 {
+            H160::from_low_u64_be(i + 1)
+        }
+
+        fn register(contract: &mut ServiceRegistry, i: u64) -> u64 {
+            ink::env::test::set_caller(provider(i));
+            contract
+                .register_service(
+                    String::from("Benchmark service"),
+                    String::from("Service registered by the benchmark"),
+                    ServiceCategory::TextProcessing,
+                    100,
+                    String::from("https://bench.example"),
+                    PaymentAsset::X402 {
+                        token: H160::from([0x07; 20]),
+                        chain_id: None,
+                    },
+                    None,
+                )
+                .unwrap()
+        }
+
+        /// Runs `f` and returns the storage reads and writes it performed
+        fn measure<R>(f: impl FnOnce() -> R) -> (usize, usize, R) {
+            let contract = ink::env::test::callee();
+            let (reads_before, writes_before) = ink::env::test::get_contract_storage_rw(contract);
+            let output = f();
+            let (reads, writes) = ink::env::test::get_contract_storage_rw(contract);
+            (reads - reads_before, writes - writes_before, output)
+        }
+
+        fn check(size: u64, message: &str, reads: usize, writes: usize, max_cells: usize) {
+            assert!(
+                reads <= max_cells && writes <= max_cells,
+                "{message} touched {reads} reads / {writes} writes at {size} services"
+            );
+        }
+
+        #[ink::test]
+        fn messages_stay_within_budget() {
+            for size in SIZES {
+                let mut contract = ServiceRegistry::new();
+                contract
+                    .grant_role(Role::Verifier, provider(size - 1))
+                    .unwrap();
+                for i in 0..size {
+                    register(&mut contract, i);
+                }
+                let last = size;
+                let owner = provider(size - 1);
+
+                let (r, w, _) = measure(|| register(&mut contract, size));
+                check(size, "register_service", r, w, MAX_CELLS);
+
+                let (r, w, _) = measure(|| contract.get_service(last).unwrap());
+                check(size, "get_service", r, w, MAX_CELLS);
+
+                ink::env::test::set_caller(owner);
+                let (r, w, _) = measure(|| contract.update_service_status(last, true).unwrap());
+                check(size, "update_service_status", r, w, MAX_CELLS);
+
+                let (r, w, _) = measure(|| contract.update_service_price(last, 200).unwrap());
+                check(size, "update_service_price", r, w, MAX_CELLS);
+
+                let (r, w, _) = measure(|| {
+                    contract
+                        .update_payment_asset(last, PaymentAsset::Native, 100, None)
+                        .unwrap()
+                });
+                check(size, "update_payment_asset", r, w, MAX_CELLS);
+
+                let (r, w, _) = measure(|| contract.set_service_model(last, Some(1)).unwrap());
+                check(size, "set_service_model", r, w, MAX_CELLS);
+
+                let (r, w, _) = measure(|| contract.record_service_request(last, true).unwrap());
+                check(size, "record_service_request", r, w, MAX_CELLS);
+
+                let (r, w, _) = measure(|| contract.get_provider_services(owner));
+                check(size, "get_provider_services", r, w, MAX_CELLS);
+
+                let (r, w, page) = measure(|| contract.get_active_services(PAGE));
+                check(size, "get_active_services", r, w, 2 * PAGE as usize);
+                assert!(page.encoded_size() <= MAX_PAGE_BYTES);
+
+                let (r, w, page) = measure(|| contract.get_x402_services(PAGE));
+                check(size, "get_x402_services", r, w, 2 * PAGE as usize);
+                assert!(page.encoded_size() <= MAX_PAGE_BYTES);
+            }
         }
     }
 }
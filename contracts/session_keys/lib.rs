@@ -0,0 +1,412 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::session_keys::{Error, SessionKeysRef, SessionScope};
+
+#[ink::contract]
+mod session_keys {
+    use hub_types::HubError;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use service_registry::ServiceCategory;
+
+    /// Spend windows are one day long, in milliseconds
+    const DAY: u64 = 86_400_000;
+
+    /// What a session key is allowed to do on behalf of its principal
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct SessionScope {
+        // Maximum escrowed value per day
+        pub max_per_day: Balance,
+        // Restrict the key to one service category, or any when `None`
+        pub category: Option<ServiceCategory>,
+        pub expires_at: u64,
+    }
+
+    /// A granted session key and its spend in the current day
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Session {
+        pub scope: SessionScope,
+        pub day: u64,
+        pub spent_today: Balance,
+    }
+
+    /// Events
+    /// Emitted when a principal grants a session key
+    #[ink(event)]
+    pub struct SessionGranted {
+        #[ink(topic)]
+        principal: H160,
+        #[ink(topic)]
+        key: H160,
+        scope: SessionScope,
+    }
+    /// Emitted when a principal revokes a session key
+    #[ink(event)]
+    pub struct SessionRevoked {
+        #[ink(topic)]
+        principal: H160,
+        #[ink(topic)]
+        key: H160,
+    }
+    /// Emitted when the escrow contract spends through a session key
+    #[ink(event)]
+    pub struct SessionSpend {
+        #[ink(topic)]
+        principal: H160,
+        #[ink(topic)]
+        key: H160,
+        amount: Balance,
+        spent_today: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the key was never granted or has been revoked
+        SessionNotFound,
+        /// Emitted when the session key has expired
+        SessionExpired,
+        /// Emitted when the service category is outside the key's scope
+        CategoryNotAllowed,
+        /// Emitted when the spend would exceed the daily limit
+        DailyLimitExceeded,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::SessionNotFound => HubError::NotFound,
+                Error::SessionExpired => HubError::Expired,
+                Error::CategoryNotAllowed => HubError::InvalidStatus,
+                Error::DailyLimitExceeded => HubError::LimitExceeded,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct SessionKeys {
+        owner: H160,
+        // Only the escrow contract may spend through session keys
+        escrow: Option<H160>,
+        sessions: Mapping<(H160, H160), Session>,
+    }
+
+    impl SessionKeys {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                escrow: None,
+                sessions: Mapping::default(),
+            }
+        }
+
+        /// Set the escrow contract allowed to spend through session keys
+        #[ink(message)]
+        pub fn set_escrow(&mut self, escrow: H160) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            self.escrow = Some(escrow);
+            Ok(())
+        }
+
+        /// Grant (or replace) a session key acting for the caller
+        #[ink(message)]
+        pub fn grant_session(&mut self, key: H160, scope: SessionScope) -> Result<()> {
+            let principal = self.env().caller();
+            if key == principal
+                || scope.max_per_day == 0
+                || scope.expires_at <= self.env().block_timestamp()
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            let session = Session {
+                scope: scope.clone(),
+                day: self.env().block_timestamp() / DAY,
+                spent_today: 0,
+            };
+            self.sessions.insert((principal, key), &session);
+
+            self.env().emit_event(SessionGranted {
+                principal,
+                key,
+                scope,
+            });
+
+            Ok(())
+        }
+
+        /// Revoke one of the caller's session keys
+        #[ink(message)]
+        pub fn revoke_session(&mut self, key: H160) -> Result<()> {
+            let principal = self.env().caller();
+            if !self.sessions.contains((principal, key)) {
+                return Err(Error::SessionNotFound);
+            }
+
+            self.sessions.remove((principal, key));
+
+            self.env().emit_event(SessionRevoked { principal, key });
+
+            Ok(())
+        }
+
+        /// Check a spend against the key's scope and count it towards the
+        /// daily limit
+        #[ink(message)]
+        pub fn authorize_spend(
+            &mut self,
+            principal: H160,
+            key: H160,
+            category: ServiceCategory,
+            amount: Balance,
+        ) -> Result<()> {
+            if Some(self.env().caller()) != self.escrow {
+                return Err(Error::Unauthorized);
+            }
+
+            let mut session = self.check_spend(principal, key, &category, amount)?;
+            let today = self.env().block_timestamp() / DAY;
+            if session.day != today {
+                session.day = today;
+                session.spent_today = 0;
+            }
+            session.spent_today = session
+                .spent_today
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.sessions.insert((principal, key), &session);
+
+            self.env().emit_event(SessionSpend {
+                principal,
+                key,
+                amount,
+                spent_today: session.spent_today,
+            });
+
+            Ok(())
+        }
+
+        /// Check whether a spend would be allowed without recording it
+        #[ink(message)]
+        pub fn can_spend(
+            &self,
+            principal: H160,
+            key: H160,
+            category: ServiceCategory,
+            amount: Balance,
+        ) -> Result<()> {
+            self.check_spend(principal, key, &category, amount)
+                .map(|_| ())
+        }
+
+        /// Value the key may still spend today
+        #[ink(message)]
+        pub fn get_remaining_today(&self, principal: H160, key: H160) -> Balance {
+            let Some(session) = self.sessions.get((principal, key)) else {
+                return 0;
+            };
+            let now = self.env().block_timestamp();
+            if now >= session.scope.expires_at {
+                return 0;
+            }
+            if session.day != now / DAY {
+                return session.scope.max_per_day;
+            }
+            session
+                .scope
+                .max_per_day
+                .saturating_sub(session.spent_today)
+        }
+
+        /// Get a session key granted by a principal
+        #[ink(message)]
+        pub fn get_session(&self, principal: H160, key: H160) -> Option<Session> {
+            self.sessions.get((principal, key))
+        }
+
+        /// Get the escrow contract allowed to spend through session keys
+        #[ink(message)]
+        pub fn get_escrow(&self) -> Option<H160> {
+            self.escrow
+        }
+
+        fn check_spend(
+            &self,
+            principal: H160,
+            key: H160,
+            category: &ServiceCategory,
+            amount: Balance,
+        ) -> Result<Session> {
+            let session = self
+                .sessions
+                .get((principal, key))
+                .ok_or(Error::SessionNotFound)?;
+            let now = self.env().block_timestamp();
+            if now >= session.scope.expires_at {
+                return Err(Error::SessionExpired);
+            }
+            if let Some(allowed) = &session.scope.category {
+                if allowed != category {
+                    return Err(Error::CategoryNotAllowed);
+                }
+            }
+
+            let spent_today = if session.day == now / DAY {
+                session.spent_today
+            } else {
+                0
+            };
+            let total = spent_today.checked_add(amount).ok_or(Error::Overflow)?;
+            if total > session.scope.max_per_day {
+                return Err(Error::DailyLimitExceeded);
+            }
+
+            Ok(session)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn scope() -> SessionScope {
+            SessionScope {
+                max_per_day: 1_000,
+                category: Some(ServiceCategory::Translation),
+                expires_at: 10 * DAY,
+            }
+        }
+
+        fn setup() -> SessionKeys {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = SessionKeys::new();
+            contract.set_escrow(accounts.eve).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.grant_session(accounts.charlie, scope()).unwrap();
+            ink::env::test::set_caller(accounts.eve);
+            contract
+        }
+
+        #[ink::test]
+        fn daily_limit_resets_each_day() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            contract
+                .authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Translation,
+                    600,
+                )
+                .unwrap();
+            assert_eq!(
+                contract.authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Translation,
+                    600,
+                ),
+                Err(Error::DailyLimitExceeded)
+            );
+            assert_eq!(
+                contract.get_remaining_today(accounts.bob, accounts.charlie),
+                400
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(DAY);
+            contract
+                .authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Translation,
+                    600,
+                )
+                .unwrap();
+        }
+
+        #[ink::test]
+        fn scope_is_enforced() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            assert_eq!(
+                contract.authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Computation,
+                    100,
+                ),
+                Err(Error::CategoryNotAllowed)
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10 * DAY);
+            assert_eq!(
+                contract.authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Translation,
+                    100,
+                ),
+                Err(Error::SessionExpired)
+            );
+        }
+
+        #[ink::test]
+        fn only_escrow_can_spend() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                contract.authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Translation,
+                    100,
+                ),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn revoked_key_cannot_spend() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = setup();
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.revoke_session(accounts.charlie).unwrap();
+
+            ink::env::test::set_caller(accounts.eve);
+            assert_eq!(
+                contract.authorize_spend(
+                    accounts.bob,
+                    accounts.charlie,
+                    ServiceCategory::Translation,
+                    100,
+                ),
+                Err(Error::SessionNotFound)
+            );
+        }
+    }
+}
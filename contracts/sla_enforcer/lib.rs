@@ -0,0 +1,654 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod sla_enforcer {
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use payment_escrow::{EscrowStatus, PaymentEscrowRef};
+    use reputation_oracle::ReputationOracleRef;
+    use service_registry::ServiceRegistryRef;
+
+    /// Rebates are expressed in basis points of the escrowed amount
+    const MAX_BPS: u32 = 10_000;
+
+    /// Service level a provider commits to for a service
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Sla {
+        pub max_latency_ms: u32,
+        // Minimum uptime over the measurement window, in basis points
+        pub min_uptime_bps: u32,
+        // Rebates applied to an affected escrow per breached target
+        pub latency_rebate_bps: u32,
+        pub uptime_rebate_bps: u32,
+    }
+
+    /// An SLA together with when the provider declared it
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DeclaredSla {
+        pub sla: Sla,
+        pub declared_at: u64,
+    }
+
+    /// A breach enough oracles agreed on and the rebate it triggered
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Breach {
+        pub escrow_id: u64,
+        pub service_id: u64,
+        // Oracles whose attestations made up the quorum
+        pub oracles: Vec<H160>,
+        pub rebate_bps: u32,
+        pub rebate: Balance,
+        pub attested_at: u64,
+    }
+
+    /// Events
+    /// Emitted when a provider declares or changes a service's SLA
+    #[ink(event)]
+    pub struct SlaDeclared {
+        #[ink(topic)]
+        service_id: u64,
+        sla: Sla,
+    }
+    /// Emitted when an oracle attests a breach
+    #[ink(event)]
+    pub struct BreachAttested {
+        #[ink(topic)]
+        escrow_id: u64,
+        #[ink(topic)]
+        oracle: H160,
+        latency_ms: u32,
+        uptime_bps: u32,
+        rebate_bps: u32,
+    }
+    /// Emitted when a quorum of oracles agreed on a breach and the rebate is applied
+    #[ink(event)]
+    pub struct BreachPenalized {
+        #[ink(topic)]
+        service_id: u64,
+        #[ink(topic)]
+        escrow_id: u64,
+        oracles: Vec<H160>,
+        rebate: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the service is not found
+        ServiceNotFound,
+        /// Emitted when the escrow is not found
+        EscrowNotFound,
+        /// Emitted when the service had no SLA declared when the escrow was created
+        SlaNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the caller is not in the oracle set
+        NotOracle,
+        /// Emitted when the measurements meet the SLA
+        NoBreach,
+        /// Emitted when the escrow was already penalized
+        AlreadyPenalized,
+        /// Emitted when the escrow is no longer pending
+        InvalidStatus,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the oracle already attested the escrow
+        AlreadyAttested,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Overflow => HubError::Overflow,
+                Error::ServiceNotFound => HubError::NotFound,
+                Error::EscrowNotFound => HubError::NotFound,
+                Error::SlaNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::NotOracle => HubError::Unauthorized,
+                Error::NoBreach => HubError::InvalidStatus,
+                Error::AlreadyPenalized => HubError::AlreadyExists,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::AlreadyAttested => HubError::AlreadyExists,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct SlaEnforcer {
+        registry: H160,
+        escrow: H160,
+        oracle: H160,
+        // Matching attestations needed before a rebate is applied
+        quorum: u32,
+        // Every SLA a service declared, oldest first, so escrows keep the
+        // terms they were created under
+        slas: Mapping<u64, Vec<DeclaredSla>>,
+        // Rebate each oracle attested for an escrow
+        attestations: Mapping<(u64, H160), u32>,
+        // Oracles that attested the same rebate for an escrow
+        votes: Mapping<(u64, u32), Vec<H160>>,
+        breaches: Mapping<u64, Breach>,
+        service_breaches: Mapping<u64, Vec<u64>>,
+    }
+
+    impl SlaEnforcer {
+        #[ink(constructor)]
+        pub fn new(registry: H160, escrow: H160, oracle: H160, quorum: u32) -> Self {
+            assert!(quorum > 0, "quorum must be at least one oracle");
+            Self {
+                registry,
+                escrow,
+                oracle,
+                quorum,
+                slas: Mapping::default(),
+                attestations: Mapping::default(),
+                votes: Mapping::default(),
+                breaches: Mapping::default(),
+                service_breaches: Mapping::default(),
+            }
+        }
+
+        /// Declare the SLA for one of the caller's services
+        ///
+        /// Applies to escrows created from now on; pending escrows keep the
+        /// SLA that was in force when they were created.
+        #[ink(message)]
+        pub fn declare_sla(&mut self, service_id: u64, sla: Sla) -> Result<()> {
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service = registry
+                .get_service(service_id)
+                .map_err(|_| Error::ServiceNotFound)?;
+            if service.provider != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if sla.min_uptime_bps > MAX_BPS
+                || sla.latency_rebate_bps > MAX_BPS
+                || sla.uptime_rebate_bps > MAX_BPS
+            {
+                return Err(Error::InvalidInput);
+            }
+
+            let mut slas = self.slas.get(service_id).unwrap_or_default();
+            slas.push(DeclaredSla {
+                sla: sla.clone(),
+                declared_at: self.env().block_timestamp(),
+            });
+            self.slas.insert(service_id, &slas);
+
+            self.env().emit_event(SlaDeclared { service_id, sla });
+
+            Ok(())
+        }
+
+        /// Attest the measured service level for an escrow
+        ///
+        /// Measurements are checked against the SLA in force when the escrow
+        /// was created. Once `quorum` oracles attested the same rebate, it is
+        /// refunded to the payer straight away, with no dispute; until then
+        /// this returns 0. Each oracle attests an escrow once, and each
+        /// escrow can be penalized once.
+        #[ink(message)]
+        pub fn attest_breach(
+            &mut self,
+            escrow_id: u64,
+            latency_ms: u32,
+            uptime_bps: u32,
+        ) -> Result<Balance> {
+            let oracle = self.env().caller();
+            let oracle_set: ReputationOracleRef = FromAddr::from_addr(self.oracle);
+            if !oracle_set.is_oracle(oracle) {
+                return Err(Error::NotOracle);
+            }
+            if self.breaches.contains(escrow_id) {
+                return Err(Error::AlreadyPenalized);
+            }
+            if self.attestations.contains((escrow_id, oracle)) {
+                return Err(Error::AlreadyAttested);
+            }
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let details = escrow
+                .get_escrow(escrow_id)
+                .map_err(|_| Error::EscrowNotFound)?;
+            if details.status != EscrowStatus::Pending {
+                return Err(Error::InvalidStatus);
+            }
+            let sla = self
+                .get_sla_at(details.service_id, details.created_at)
+                .ok_or(Error::SlaNotFound)?;
+
+            let rebate_bps = rebate_bps(&sla, latency_ms, uptime_bps);
+            if rebate_bps == 0 {
+                return Err(Error::NoBreach);
+            }
+
+            self.attestations.insert((escrow_id, oracle), &rebate_bps);
+            let mut oracles = self.votes.get((escrow_id, rebate_bps)).unwrap_or_default();
+            oracles.push(oracle);
+            self.votes.insert((escrow_id, rebate_bps), &oracles);
+            self.env().emit_event(BreachAttested {
+                escrow_id,
+                oracle,
+                latency_ms,
+                uptime_bps,
+                rebate_bps,
+            });
+            if (oracles.len() as u32) < self.quorum {
+                return Ok(0);
+            }
+
+            let rebate = escrow
+                .apply_sla_rebate(escrow_id, rebate_bps)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            let breach = Breach {
+                escrow_id,
+                service_id: details.service_id,
+                oracles: oracles.clone(),
+                rebate_bps,
+                rebate,
+                attested_at: self.env().block_timestamp(),
+            };
+            self.breaches.insert(escrow_id, &breach);
+
+            let mut breaches = self
+                .service_breaches
+                .get(details.service_id)
+                .unwrap_or_default();
+            breaches.push(escrow_id);
+            self.service_breaches.insert(details.service_id, &breaches);
+
+            self.env().emit_event(BreachPenalized {
+                service_id: details.service_id,
+                escrow_id,
+                oracles,
+                rebate,
+            });
+
+            Ok(rebate)
+        }
+
+        /// Get the SLA currently declared for a service
+        #[ink(message)]
+        pub fn get_sla(&self, service_id: u64) -> Option<Sla> {
+            self.slas
+                .get(service_id)?
+                .pop()
+                .map(|declared| declared.sla)
+        }
+
+        /// Get the SLA that was in force for a service at `timestamp`
+        #[ink(message)]
+        pub fn get_sla_at(&self, service_id: u64, timestamp: u64) -> Option<Sla> {
+            self.slas
+                .get(service_id)?
+                .into_iter()
+                .rev()
+                .find(|declared| declared.declared_at <= timestamp)
+                .map(|declared| declared.sla)
+        }
+
+        /// Get the rebate an oracle attested for an escrow, if any
+        #[ink(message)]
+        pub fn get_attestation(&self, escrow_id: u64, oracle: H160) -> Option<u32> {
+            self.attestations.get((escrow_id, oracle))
+        }
+
+        /// Get the number of matching attestations needed for a rebate
+        #[ink(message)]
+        pub fn get_quorum(&self) -> u32 {
+            self.quorum
+        }
+
+        /// Get the breach recorded against an escrow
+        #[ink(message)]
+        pub fn get_breach(&self, escrow_id: u64) -> Option<Breach> {
+            self.breaches.get(escrow_id)
+        }
+
+        /// Get every escrow penalized for a service
+        #[ink(message)]
+        pub fn get_service_breaches(&self, service_id: u64) -> Vec<u64> {
+            self.service_breaches.get(service_id).unwrap_or_default()
+        }
+    }
+
+    /// Rebate owed for the measurements, summed over breached targets
+    fn rebate_bps(sla: &Sla, latency_ms: u32, uptime_bps: u32) -> u32 {
+        let mut rebate = 0u32;
+        if latency_ms > sla.max_latency_ms {
+            rebate = rebate.saturating_add(sla.latency_rebate_bps);
+        }
+        if uptime_bps < sla.min_uptime_bps {
+            rebate = rebate.saturating_add(sla.uptime_rebate_bps);
+        }
+        rebate.min(MAX_BPS)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn sla() -> Sla {
+            Sla {
+                max_latency_ms: 500,
+                min_uptime_bps: 9_900,
+                latency_rebate_bps: 1_000,
+                uptime_rebate_bps: 2_500,
+            }
+        }
+
+        #[test]
+        fn no_rebate_within_sla() {
+            assert_eq!(rebate_bps(&sla(), 500, 9_900), 0);
+        }
+
+        #[test]
+        fn rebates_add_up_per_breached_target() {
+            assert_eq!(rebate_bps(&sla(), 800, 9_900), 1_000);
+            assert_eq!(rebate_bps(&sla(), 400, 9_000), 2_500);
+            assert_eq!(rebate_bps(&sla(), 800, 9_000), 3_500);
+        }
+
+        #[test]
+        fn rebate_is_capped() {
+            let mut sla = sla();
+            sla.latency_rebate_bps = 8_000;
+            sla.uptime_rebate_bps = 8_000;
+            assert_eq!(rebate_bps(&sla, 800, 9_000), MAX_BPS);
+        }
+
+        #[ink::test]
+        fn escrows_keep_the_sla_they_were_created_under() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = SlaEnforcer::new(accounts.alice, accounts.bob, accounts.charlie, 2);
+            let waived = Sla {
+                latency_rebate_bps: 0,
+                uptime_rebate_bps: 0,
+                ..sla()
+            };
+            contract.slas.insert(
+                1,
+                &vec![
+                    DeclaredSla {
+                        sla: sla(),
+                        declared_at: 10,
+                    },
+                    DeclaredSla {
+                        sla: waived.clone(),
+                        declared_at: 20,
+                    },
+                ],
+            );
+
+            assert_eq!(contract.get_sla(1), Some(waived.clone()));
+            assert_eq!(contract.get_sla_at(1, 5), None);
+            assert_eq!(contract.get_sla_at(1, 10), Some(sla()));
+            assert_eq!(contract.get_sla_at(1, 19), Some(sla()));
+            assert_eq!(contract.get_sla_at(1, 20), Some(waived));
+            assert_eq!(contract.get_sla(2), None);
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink::prelude::string::String;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::PaymentEscrow;
+        use reputation_oracle::ReputationOracle;
+        use service_registry::{PaymentAsset, ServiceCategory, ServiceRegistry};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const PRICE: Balance = 1_000_000_000_000;
+
+        fn sla() -> Sla {
+            Sla {
+                max_latency_ms: 500,
+                min_uptime_bps: 9_900,
+                latency_rebate_bps: 1_000,
+                uptime_rebate_bps: 2_500,
+            }
+        }
+
+        #[ink_e2e::test]
+        async fn a_quorum_of_oracles_rebates_under_the_original_sla<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let charlie = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Charlie);
+            let dave = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Dave);
+
+            let registry = client
+                .instantiate(
+                    "service_registry",
+                    &ink_e2e::alice(),
+                    &mut ServiceRegistryRef::new(),
+                )
+                .submit()
+                .await
+                .expect("registry instantiate failed");
+            let mut registry_calls = registry.call_builder::<ServiceRegistry>();
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(86_400_000),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let mut escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let oracle_set = client
+                .instantiate(
+                    "reputation_oracle",
+                    &ink_e2e::alice(),
+                    &mut ReputationOracleRef::new(registry.addr),
+                )
+                .submit()
+                .await
+                .expect("oracle instantiate failed");
+            let mut oracle_calls = oracle_set.call_builder::<ReputationOracle>();
+            let enforcer = client
+                .instantiate(
+                    "sla_enforcer",
+                    &ink_e2e::alice(),
+                    &mut SlaEnforcerRef::new(registry.addr, escrow.addr, oracle_set.addr, 2),
+                )
+                .submit()
+                .await
+                .expect("enforcer instantiate failed");
+            let mut calls = enforcer.call_builder::<SlaEnforcer>();
+
+            for oracle in [charlie, dave] {
+                client
+                    .call(&ink_e2e::alice(), &oracle_calls.set_oracle(oracle, true))
+                    .submit()
+                    .await
+                    .expect("call failed")
+                    .return_value()
+                    .unwrap();
+            }
+            client
+                .call(&ink_e2e::alice(), &escrow_calls.set_sla(enforcer.addr))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let service_id = client
+                .call(
+                    &ink_e2e::bob(),
+                    &registry_calls.register_service(
+                        String::from("Summarizer"),
+                        String::from("Summarizes documents"),
+                        ServiceCategory::TextProcessing,
+                        PRICE,
+                        String::from("https://summarizer.example"),
+                        PaymentAsset::Native,
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            client
+                .call(&ink_e2e::bob(), &calls.declare_sla(service_id, sla()))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let mut escrow_ids = Vec::new();
+            for code in ["first", "second"] {
+                let escrow_id = client
+                    .call(
+                        &ink_e2e::alice(),
+                        &escrow_calls.create_escrow(
+                            ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob),
+                            service_id,
+                            String::from(code),
+                            PaymentAsset::Native,
+                        ),
+                    )
+                    .value(PRICE)
+                    .submit()
+                    .await
+                    .expect("call failed")
+                    .return_value()
+                    .unwrap();
+                escrow_ids.push(escrow_id);
+            }
+
+            // Waiving the rebates now leaves the pending escrows' terms alone
+            let waived = Sla {
+                latency_rebate_bps: 0,
+                uptime_rebate_bps: 0,
+                ..sla()
+            };
+            client
+                .call(&ink_e2e::bob(), &calls.declare_sla(service_id, waived))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let outsider = client
+                .call(
+                    &ink_e2e::bob(),
+                    &calls.attest_breach(escrow_ids[0], 800, 9_900),
+                )
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(outsider, Err(Error::NotOracle));
+
+            // One oracle alone moves nothing
+            let pending = client
+                .call(
+                    &ink_e2e::charlie(),
+                    &calls.attest_breach(escrow_ids[0], 800, 9_900),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value();
+            assert_eq!(pending, Ok(0));
+            let repeated = client
+                .call(
+                    &ink_e2e::charlie(),
+                    &calls.attest_breach(escrow_ids[0], 900, 9_900),
+                )
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(repeated, Err(Error::AlreadyAttested));
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.amount, PRICE);
+
+            let rebate = client
+                .call(
+                    &ink_e2e::dave(),
+                    &calls.attest_breach(escrow_ids[0], 700, 9_950),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value();
+            assert_eq!(rebate, Ok(PRICE / 10));
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.amount, PRICE - PRICE / 10);
+            let breach = client
+                .call(&ink_e2e::alice(), &calls.get_breach(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(breach.oracles, vec![charlie, dave]);
+            assert_eq!(breach.rebate_bps, 1_000);
+
+            // Oracles that disagree on the breach don't make a quorum
+            client
+                .call(
+                    &ink_e2e::charlie(),
+                    &calls.attest_breach(escrow_ids[1], 800, 9_900),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let split = client
+                .call(
+                    &ink_e2e::dave(),
+                    &calls.attest_breach(escrow_ids[1], 800, 9_000),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value();
+            assert_eq!(split, Ok(0));
+            let breach = client
+                .call(&ink_e2e::alice(), &calls.get_breach(escrow_ids[1]))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(breach, None);
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,171 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::token_registry::{Error, TokenInfo, TokenRegistryRef};
+
+#[ink::contract]
+mod token_registry {
+    use hub_types::{HubError, MAX_DECIMALS};
+    use ink::prelude::string::String;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+
+    /// Longest token symbol accepted, in bytes
+    const MAX_SYMBOL_LEN: usize = 16;
+
+    /// Metadata of a token hub listings and escrows may be priced in
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TokenInfo {
+        pub symbol: String,
+        pub decimals: u8,
+    }
+
+    /// Events
+    /// Emitted when a token is registered or its metadata replaced
+    #[ink(event)]
+    pub struct TokenRegistered {
+        #[ink(topic)]
+        token: H160,
+        symbol: String,
+        decimals: u8,
+    }
+    /// Emitted when a token is removed
+    #[ink(event)]
+    pub struct TokenRemoved {
+        #[ink(topic)]
+        token: H160,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the token is not registered
+        TokenNotFound,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::TokenNotFound => HubError::NotFound,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct TokenRegistry {
+        owner: H160,
+        tokens: Mapping<H160, TokenInfo>,
+    }
+
+    impl TokenRegistry {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                tokens: Mapping::default(),
+            }
+        }
+
+        /// Register a token, or replace its metadata
+        #[ink(message)]
+        pub fn register_token(&mut self, token: H160, symbol: String, decimals: u8) -> Result<()> {
+            self.ensure_owner()?;
+            if decimals > MAX_DECIMALS || symbol.is_empty() || symbol.len() > MAX_SYMBOL_LEN {
+                return Err(Error::InvalidInput);
+            }
+
+            self.tokens.insert(
+                token,
+                &TokenInfo {
+                    symbol: symbol.clone(),
+                    decimals,
+                },
+            );
+            self.env().emit_event(TokenRegistered {
+                token,
+                symbol,
+                decimals,
+            });
+
+            Ok(())
+        }
+
+        /// Remove a token
+        #[ink(message)]
+        pub fn remove_token(&mut self, token: H160) -> Result<()> {
+            self.ensure_owner()?;
+            if self.tokens.take(token).is_none() {
+                return Err(Error::TokenNotFound);
+            }
+            self.env().emit_event(TokenRemoved { token });
+            Ok(())
+        }
+
+        /// Get a token's metadata
+        #[ink(message)]
+        pub fn get_token(&self, token: H160) -> Option<TokenInfo> {
+            self.tokens.get(token)
+        }
+
+        /// Get a token's decimals
+        #[ink(message)]
+        pub fn get_decimals(&self, token: H160) -> Option<u8> {
+            self.tokens.get(token).map(|info| info.decimals)
+        }
+
+        /// Hand the registry over to another account
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: H160) -> Result<()> {
+            self.ensure_owner()?;
+            self.owner = new_owner;
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn owner_registers_token_decimals() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TokenRegistry::new();
+
+            assert_eq!(
+                contract.register_token(accounts.django, String::from("USDC"), 39),
+                Err(Error::InvalidInput)
+            );
+            contract
+                .register_token(accounts.django, String::from("USDC"), 6)
+                .unwrap();
+            assert_eq!(contract.get_decimals(accounts.django), Some(6));
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                contract.remove_token(accounts.django),
+                Err(Error::Unauthorized)
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.remove_token(accounts.django).unwrap();
+            assert_eq!(contract.get_decimals(accounts.django), None);
+        }
+    }
+}
@@ -0,0 +1,806 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod vouchers {
+    use hub_types::{HubError, PaymentAsset};
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::FromAddr;
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use payment_escrow::PaymentEscrowRef;
+    use service_registry::{Service, ServiceCategory, ServiceRegistryRef};
+
+    /// Upper bound on vouchers issued in one batch
+    const MAX_BATCH: usize = 100;
+
+    /// Prepaid service credit that can fund escrows
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Voucher {
+        pub id: u64,
+        pub issuer: H160,
+        pub holder: H160,
+        pub face_value: Balance,
+        // Credit not yet locked in an escrow
+        pub balance: Balance,
+        // Restrict redemption to one service or one category
+        pub service_id: Option<u64>,
+        pub category: Option<ServiceCategory>,
+        // Unspent credit returns to the issuer after this time
+        pub expires_at: Option<u64>,
+    }
+
+    /// An escrow funded from a voucher
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Redemption {
+        pub voucher_id: u64,
+        pub holder: H160,
+        pub amount: Balance,
+    }
+
+    /// Events
+    /// Emitted when a voucher is issued
+    #[ink(event)]
+    pub struct VoucherIssued {
+        #[ink(topic)]
+        voucher_id: u64,
+        #[ink(topic)]
+        issuer: H160,
+        #[ink(topic)]
+        holder: H160,
+        face_value: Balance,
+    }
+    /// Emitted when a voucher changes hands
+    #[ink(event)]
+    pub struct VoucherTransferred {
+        #[ink(topic)]
+        voucher_id: u64,
+        from: H160,
+        to: H160,
+    }
+    /// Emitted when voucher credit funds an escrow
+    #[ink(event)]
+    pub struct VoucherRedeemed {
+        #[ink(topic)]
+        voucher_id: u64,
+        #[ink(topic)]
+        escrow_id: u64,
+        amount: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when an input is invalid
+        InvalidInput,
+        /// Emitted when the transferred amount is invalid
+        InvalidAmount,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the voucher is not found
+        VoucherNotFound,
+        /// Emitted when the escrow was not funded from a voucher
+        RedemptionNotFound,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the voucher has expired
+        Expired,
+        /// Emitted when the voucher has not expired yet
+        NotExpired,
+        /// Emitted when the voucher does not cover the service
+        RestrictionMismatch,
+        /// Emitted when the voucher balance is below the service price
+        InsufficientBalance,
+        /// Emitted when the service is not found in the registry
+        ServiceNotFound,
+        /// Emitted when the service is not accepting new escrows
+        ServiceInactive,
+        /// Emitted when a call into the escrow contract fails
+        EscrowCallFailed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InvalidInput => HubError::InvalidInput,
+                Error::InvalidAmount => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::VoucherNotFound => HubError::NotFound,
+                Error::RedemptionNotFound => HubError::NotFound,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::Expired => HubError::Expired,
+                Error::NotExpired => HubError::TooEarly,
+                Error::RestrictionMismatch => HubError::InvalidStatus,
+                Error::InsufficientBalance => HubError::InsufficientFunds,
+                Error::ServiceNotFound => HubError::NotFound,
+                Error::ServiceInactive => HubError::Inactive,
+                Error::EscrowCallFailed => HubError::CrossContractCallFailed,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Vouchers {
+        registry: H160,
+        // This contract is the payer of every voucher-funded escrow
+        escrow: H160,
+        vouchers: Mapping<u64, Voucher>,
+        voucher_count: u64,
+        holder_vouchers: Mapping<H160, Vec<u64>>,
+        redemptions: Mapping<u64, Redemption>,
+    }
+
+    impl Vouchers {
+        #[ink(constructor)]
+        pub fn new(registry: H160, escrow: H160) -> Self {
+            Self {
+                registry,
+                escrow,
+                vouchers: Mapping::default(),
+                voucher_count: 0,
+                holder_vouchers: Mapping::default(),
+                redemptions: Mapping::default(),
+            }
+        }
+
+        /// Buy a voucher worth the transferred value for `holder`
+        #[ink(message, payable)]
+        pub fn issue(
+            &mut self,
+            holder: H160,
+            service_id: Option<u64>,
+            category: Option<ServiceCategory>,
+            expires_at: Option<u64>,
+        ) -> Result<u64> {
+            let face_value = self.transferred();
+            if face_value == 0 {
+                return Err(Error::InvalidAmount);
+            }
+            self.insert_voucher(holder, face_value, service_id, category, expires_at)
+        }
+
+        /// Buy one voucher of `face_value` for each holder, e.g. for an airdrop
+        ///
+        /// The transferred value must cover every voucher exactly.
+        #[ink(message, payable)]
+        pub fn issue_batch(
+            &mut self,
+            holders: Vec<H160>,
+            face_value: Balance,
+            service_id: Option<u64>,
+            category: Option<ServiceCategory>,
+            expires_at: Option<u64>,
+        ) -> Result<Vec<u64>> {
+            if holders.is_empty() || holders.len() > MAX_BATCH || face_value == 0 {
+                return Err(Error::InvalidInput);
+            }
+            let total = face_value
+                .checked_mul(holders.len() as Balance)
+                .ok_or(Error::Overflow)?;
+            if self.transferred() != total {
+                return Err(Error::InvalidAmount);
+            }
+
+            let mut ids = Vec::new();
+            for holder in holders {
+                ids.push(self.insert_voucher(
+                    holder,
+                    face_value,
+                    service_id,
+                    category.clone(),
+                    expires_at,
+                )?);
+            }
+            Ok(ids)
+        }
+
+        /// Give a voucher to another account
+        #[ink(message)]
+        pub fn transfer(&mut self, voucher_id: u64, to: H160) -> Result<()> {
+            let from = self.env().caller();
+            let mut voucher = self.get_voucher(voucher_id)?;
+            if voucher.holder != from {
+                return Err(Error::Unauthorized);
+            }
+
+            let mut from_vouchers = self.holder_vouchers.get(from).unwrap_or_default();
+            from_vouchers.retain(|id| *id != voucher_id);
+            self.holder_vouchers.insert(from, &from_vouchers);
+
+            let mut to_vouchers = self.holder_vouchers.get(to).unwrap_or_default();
+            to_vouchers.push(voucher_id);
+            self.holder_vouchers.insert(to, &to_vouchers);
+
+            voucher.holder = to;
+            self.vouchers.insert(voucher_id, &voucher);
+
+            self.env().emit_event(VoucherTransferred {
+                voucher_id,
+                from,
+                to,
+            });
+
+            Ok(())
+        }
+
+        /// Fund an escrow for a registered service from voucher credit
+        ///
+        /// The service's listed price is deducted from the voucher. The
+        /// holder releases or refunds the escrow through this contract.
+        #[ink(message)]
+        pub fn redeem(
+            &mut self,
+            voucher_id: u64,
+            service_id: u64,
+            payment_code: String,
+        ) -> Result<u64> {
+            let holder = self.env().caller();
+            let mut voucher = self.get_voucher(voucher_id)?;
+            if voucher.holder != holder {
+                return Err(Error::Unauthorized);
+            }
+            if voucher
+                .expires_at
+                .is_some_and(|expires_at| self.env().block_timestamp() > expires_at)
+            {
+                return Err(Error::Expired);
+            }
+
+            let registry: ServiceRegistryRef = FromAddr::from_addr(self.registry);
+            let service = registry
+                .get_service(service_id)
+                .map_err(|_| Error::ServiceNotFound)?;
+            if !service.is_active {
+                return Err(Error::ServiceInactive);
+            }
+            if !covers(&voucher, &service) {
+                return Err(Error::RestrictionMismatch);
+            }
+            if service.price > voucher.balance {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            let escrow_id = escrow
+                .call_mut()
+                .create_escrow(
+                    service.provider,
+                    service_id,
+                    payment_code,
+                    PaymentAsset::Native,
+                )
+                .transferred_value(service.price.into())
+                .invoke()
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            voucher.balance -= service.price;
+            self.vouchers.insert(voucher_id, &voucher);
+            self.redemptions.insert(
+                escrow_id,
+                &Redemption {
+                    voucher_id,
+                    holder,
+                    amount: service.price,
+                },
+            );
+
+            self.env().emit_event(VoucherRedeemed {
+                voucher_id,
+                escrow_id,
+                amount: service.price,
+            });
+
+            Ok(escrow_id)
+        }
+
+        /// Release a voucher-funded escrow to the provider
+        #[ink(message)]
+        pub fn release(&mut self, escrow_id: u64) -> Result<()> {
+            self.holder_redemption(escrow_id)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .release_payment(escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            self.redemptions.remove(escrow_id);
+
+            Ok(())
+        }
+
+        /// Refund a voucher-funded escrow, returning the credit to the voucher
+        #[ink(message)]
+        pub fn refund(&mut self, escrow_id: u64) -> Result<()> {
+            let redemption = self.holder_redemption(escrow_id)?;
+
+            let mut escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+            escrow
+                .refund(escrow_id)
+                .map_err(|_| Error::EscrowCallFailed)?;
+
+            let mut voucher = self.get_voucher(redemption.voucher_id)?;
+            voucher.balance = voucher
+                .balance
+                .checked_add(redemption.amount)
+                .ok_or(Error::Overflow)?;
+            self.vouchers.insert(redemption.voucher_id, &voucher);
+            self.redemptions.remove(escrow_id);
+
+            Ok(())
+        }
+
+        /// Return the unspent credit of an expired voucher to its issuer
+        #[ink(message)]
+        pub fn reclaim_expired(&mut self, voucher_id: u64) -> Result<Balance> {
+            let mut voucher = self.get_voucher(voucher_id)?;
+            if voucher.issuer != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            match voucher.expires_at {
+                Some(expires_at) if self.env().block_timestamp() > expires_at => {}
+                _ => return Err(Error::NotExpired),
+            }
+
+            let amount = voucher.balance;
+            if amount > 0 && self.env().transfer(voucher.issuer, amount.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+
+            voucher.balance = 0;
+            self.vouchers.insert(voucher_id, &voucher);
+
+            Ok(amount)
+        }
+
+        /// Get voucher details
+        #[ink(message)]
+        pub fn get_voucher(&self, voucher_id: u64) -> Result<Voucher> {
+            self.vouchers.get(voucher_id).ok_or(Error::VoucherNotFound)
+        }
+
+        /// Get the voucher an open escrow was funded from, if any
+        #[ink(message)]
+        pub fn get_redemption(&self, escrow_id: u64) -> Option<Redemption> {
+            self.redemptions.get(escrow_id)
+        }
+
+        /// Get all vouchers held by an account
+        #[ink(message)]
+        pub fn get_holder_vouchers(&self, holder: H160) -> Vec<u64> {
+            self.holder_vouchers.get(holder).unwrap_or_default()
+        }
+
+        /// Get total voucher count
+        #[ink(message)]
+        pub fn get_voucher_count(&self) -> u64 {
+            self.voucher_count
+        }
+
+        fn insert_voucher(
+            &mut self,
+            holder: H160,
+            face_value: Balance,
+            service_id: Option<u64>,
+            category: Option<ServiceCategory>,
+            expires_at: Option<u64>,
+        ) -> Result<u64> {
+            let issuer = self.env().caller();
+            self.voucher_count = self.voucher_count.checked_add(1).ok_or(Error::Overflow)?;
+            let voucher_id = self.voucher_count;
+
+            let voucher = Voucher {
+                id: voucher_id,
+                issuer,
+                holder,
+                face_value,
+                balance: face_value,
+                service_id,
+                category,
+                expires_at,
+            };
+            self.vouchers.insert(voucher_id, &voucher);
+
+            let mut held = self.holder_vouchers.get(holder).unwrap_or_default();
+            held.push(voucher_id);
+            self.holder_vouchers.insert(holder, &held);
+
+            self.env().emit_event(VoucherIssued {
+                voucher_id,
+                issuer,
+                holder,
+                face_value,
+            });
+
+            Ok(voucher_id)
+        }
+
+        /// The redemption behind an escrow, if the caller is its holder
+        fn holder_redemption(&self, escrow_id: u64) -> Result<Redemption> {
+            let redemption = self
+                .redemptions
+                .get(escrow_id)
+                .ok_or(Error::RedemptionNotFound)?;
+            if redemption.holder != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            Ok(redemption)
+        }
+
+        fn transferred(&self) -> Balance {
+            self.env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default()
+        }
+    }
+
+    /// Whether a voucher's restrictions allow it to pay for a service
+    ///
+    /// Vouchers hold native credit, so only natively priced services qualify.
+    fn covers(voucher: &Voucher, service: &Service) -> bool {
+        service.payment_asset == PaymentAsset::Native
+            && voucher.service_id.is_none_or(|id| id == service.id)
+            && voucher
+                .category
+                .as_ref()
+                .is_none_or(|category| *category == service.category)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::{balance_of, deploy_at, paying};
+        use ink::{ToAddr, U256};
+
+        const PRICE: Balance = 40;
+        const FACE_VALUE: Balance = 100;
+        const EXPIRY: u64 = 1_000;
+
+        /// Vouchers over a registry in which bob lists one text service,
+        /// and an escrow the tests never reach
+        fn vouchers() -> (Vouchers, u64) {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xF0; 20]), 0);
+
+            ink::env::test::set_caller(accounts.alice);
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, ServiceRegistryRef>();
+            let mut registry = ServiceRegistryRef::new()
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            ink::env::test::set_caller(accounts.bob);
+            let service_id = registry
+                .register_service(
+                    String::from("Summarizer"),
+                    String::from("Summarizes documents"),
+                    ServiceCategory::TextProcessing,
+                    PRICE,
+                    String::from("https://summarizer.example"),
+                    PaymentAsset::Native,
+                    None,
+                )
+                .unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            let vouchers = Vouchers::new(registry.to_addr(), H160::from([0xE5; 20]));
+            (vouchers, service_id)
+        }
+
+        #[ink::test]
+        fn vouchers_are_issued_for_the_transferred_value() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut vouchers, _) = vouchers();
+
+            let unpaid = paying(accounts.alice, 0, || {
+                vouchers.issue(accounts.charlie, None, None, None)
+            });
+            assert_eq!(unpaid, Err(Error::InvalidAmount));
+            let voucher_id = paying(accounts.alice, FACE_VALUE, || {
+                vouchers.issue(accounts.charlie, None, None, Some(EXPIRY))
+            })
+            .unwrap();
+            let voucher = vouchers.get_voucher(voucher_id).unwrap();
+            assert_eq!(voucher.issuer, accounts.alice);
+            assert_eq!(voucher.holder, accounts.charlie);
+            assert_eq!(voucher.balance, FACE_VALUE);
+
+            let holders = vec![accounts.django, accounts.eve];
+            let underpaid = paying(accounts.alice, FACE_VALUE, || {
+                vouchers.issue_batch(holders.clone(), FACE_VALUE, None, None, None)
+            });
+            assert_eq!(underpaid, Err(Error::InvalidAmount));
+            let empty = paying(accounts.alice, 0, || {
+                vouchers.issue_batch(Vec::new(), FACE_VALUE, None, None, None)
+            });
+            assert_eq!(empty, Err(Error::InvalidInput));
+            let ids = paying(accounts.alice, 2 * FACE_VALUE, || {
+                vouchers.issue_batch(holders, FACE_VALUE, None, None, None)
+            })
+            .unwrap();
+            assert_eq!(vouchers.get_holder_vouchers(accounts.eve), vec![ids[1]]);
+            assert_eq!(vouchers.get_voucher_count(), 3);
+
+            // Only the holder passes a voucher on
+            ink::env::test::set_caller(accounts.django);
+            assert_eq!(
+                vouchers.transfer(voucher_id, accounts.django),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.charlie);
+            vouchers.transfer(voucher_id, accounts.django).unwrap();
+            assert_eq!(
+                vouchers.get_holder_vouchers(accounts.django),
+                vec![ids[0], voucher_id]
+            );
+            assert!(vouchers.get_holder_vouchers(accounts.charlie).is_empty());
+        }
+
+        #[ink::test]
+        fn redemptions_must_fit_the_voucher() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut vouchers, service_id) = vouchers();
+            let code = || String::from("code");
+            let open = paying(accounts.alice, FACE_VALUE, || {
+                vouchers.issue(accounts.charlie, None, None, Some(EXPIRY))
+            })
+            .unwrap();
+            let images_only = paying(accounts.alice, FACE_VALUE, || {
+                vouchers.issue(
+                    accounts.charlie,
+                    None,
+                    Some(ServiceCategory::ImageGeneration),
+                    None,
+                )
+            })
+            .unwrap();
+            let other_service = paying(accounts.alice, FACE_VALUE, || {
+                vouchers.issue(accounts.charlie, Some(service_id + 1), None, None)
+            })
+            .unwrap();
+            let too_small = paying(accounts.alice, PRICE - 1, || {
+                vouchers.issue(accounts.charlie, None, None, None)
+            })
+            .unwrap();
+
+            assert_eq!(
+                vouchers.redeem(open, service_id, code()),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                vouchers.redeem(open + 10, service_id, code()),
+                Err(Error::VoucherNotFound)
+            );
+            assert_eq!(
+                vouchers.redeem(open, service_id + 1, code()),
+                Err(Error::ServiceNotFound)
+            );
+            assert_eq!(
+                vouchers.redeem(images_only, service_id, code()),
+                Err(Error::RestrictionMismatch)
+            );
+            assert_eq!(
+                vouchers.redeem(other_service, service_id, code()),
+                Err(Error::RestrictionMismatch)
+            );
+            assert_eq!(
+                vouchers.redeem(too_small, service_id, code()),
+                Err(Error::InsufficientBalance)
+            );
+            ink::env::test::set_block_timestamp(EXPIRY + 1);
+            assert_eq!(
+                vouchers.redeem(open, service_id, code()),
+                Err(Error::Expired)
+            );
+
+            assert_eq!(vouchers.release(1), Err(Error::RedemptionNotFound));
+            assert_eq!(vouchers.refund(1), Err(Error::RedemptionNotFound));
+        }
+
+        #[ink::test]
+        fn issuers_reclaim_expired_credit() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut vouchers, _) = vouchers();
+            let voucher_id = paying(accounts.bob, FACE_VALUE, || {
+                vouchers.issue(accounts.charlie, None, None, Some(EXPIRY))
+            })
+            .unwrap();
+            let perpetual = paying(accounts.bob, FACE_VALUE, || {
+                vouchers.issue(accounts.charlie, None, None, None)
+            })
+            .unwrap();
+
+            ink::env::test::set_block_timestamp(EXPIRY);
+            assert_eq!(vouchers.reclaim_expired(voucher_id), Err(Error::NotExpired));
+            ink::env::test::set_block_timestamp(EXPIRY + 1);
+            assert_eq!(vouchers.reclaim_expired(perpetual), Err(Error::NotExpired));
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(
+                vouchers.reclaim_expired(voucher_id),
+                Err(Error::Unauthorized)
+            );
+
+            let before = balance_of(accounts.bob);
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(vouchers.reclaim_expired(voucher_id), Ok(FACE_VALUE));
+            assert_eq!(balance_of(accounts.bob), before + FACE_VALUE);
+            assert_eq!(vouchers.get_voucher(voucher_id).unwrap().balance, 0);
+            assert_eq!(vouchers.reclaim_expired(voucher_id), Ok(0));
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink::env::DefaultEnvironment;
+        use ink_e2e::{ContractsBackend, Sr25519Keyring};
+        use payment_escrow::{EscrowStatus, PaymentEscrow};
+        use service_registry::ServiceRegistry;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const PRICE: Balance = 1_000_000_000_000;
+
+        #[ink_e2e::test]
+        async fn voucher_credit_funds_escrows_once<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Bob);
+            let charlie = ink_e2e::address::<DefaultEnvironment>(Sr25519Keyring::Charlie);
+
+            let registry = client
+                .instantiate(
+                    "service_registry",
+                    &ink_e2e::alice(),
+                    &mut ServiceRegistryRef::new(),
+                )
+                .submit()
+                .await
+                .expect("registry instantiate failed");
+            let mut registry_calls = registry.call_builder::<ServiceRegistry>();
+            // Voucher escrows name the vouchers contract as payer; with a one
+            // millisecond timeout it may refund a holder's credit a block later
+            let escrow = client
+                .instantiate(
+                    "payment_escrow",
+                    &ink_e2e::alice(),
+                    &mut PaymentEscrowRef::new(1),
+                )
+                .submit()
+                .await
+                .expect("escrow instantiate failed");
+            let escrow_calls = escrow.call_builder::<PaymentEscrow>();
+            let contract = client
+                .instantiate(
+                    "vouchers",
+                    &ink_e2e::alice(),
+                    &mut VouchersRef::new(registry.addr, escrow.addr),
+                )
+                .submit()
+                .await
+                .expect("vouchers instantiate failed");
+            let mut calls = contract.call_builder::<Vouchers>();
+
+            let service_id = client
+                .call(
+                    &ink_e2e::bob(),
+                    &registry_calls.register_service(
+                        String::from("Summarizer"),
+                        String::from("Summarizes documents"),
+                        ServiceCategory::TextProcessing,
+                        PRICE,
+                        String::from("https://summarizer.example"),
+                        PaymentAsset::Native,
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let voucher_id = client
+                .call(
+                    &ink_e2e::alice(),
+                    &calls.issue(charlie, None, Some(ServiceCategory::TextProcessing), None),
+                )
+                .value(2 * PRICE)
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+
+            let mut escrow_ids = Vec::new();
+            for code in ["first", "second"] {
+                let escrow_id = client
+                    .call(
+                        &ink_e2e::charlie(),
+                        &calls.redeem(voucher_id, service_id, String::from(code)),
+                    )
+                    .submit()
+                    .await
+                    .expect("call failed")
+                    .return_value()
+                    .unwrap();
+                escrow_ids.push(escrow_id);
+            }
+            let spent = client
+                .call(
+                    &ink_e2e::charlie(),
+                    &calls.redeem(voucher_id, service_id, String::from("third")),
+                )
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(spent, Err(Error::InsufficientBalance));
+
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.payee, bob);
+            assert_eq!(details.amount, PRICE);
+
+            let result = client
+                .call(&ink_e2e::bob(), &calls.release(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(result, Err(Error::Unauthorized));
+            client
+                .call(&ink_e2e::charlie(), &calls.release(escrow_ids[0]))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let result = client
+                .call(&ink_e2e::charlie(), &calls.release(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(result, Err(Error::RedemptionNotFound));
+            let details = client
+                .call(&ink_e2e::alice(), &escrow_calls.get_escrow(escrow_ids[0]))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(details.status, EscrowStatus::Completed);
+
+            // Refunding returns the credit to the voucher
+            client
+                .call(&ink_e2e::charlie(), &calls.refund(escrow_ids[1]))
+                .submit()
+                .await
+                .expect("call failed")
+                .return_value()
+                .unwrap();
+            let voucher = client
+                .call(&ink_e2e::alice(), &calls.get_voucher(voucher_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .unwrap();
+            assert_eq!(voucher.balance, PRICE);
+
+            Ok(())
+        }
+    }
+}
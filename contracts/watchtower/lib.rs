@@ -0,0 +1,719 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+#[ink::contract]
+mod watchtower {
+    use arbitration::{ArbitrationRef, DisputeStatus};
+    use hub_types::HubError;
+    use ink::env::call::FromAddr;
+    use ink::prelude::vec::Vec;
+    use ink::primitives::H160;
+    use ink::storage::Mapping;
+    use payment_escrow::{EscrowStatus, PaymentEscrowRef};
+
+    /// Reliability is expressed in basis points
+    const MAX_BPS: u64 = 10_000;
+    /// Upper bound on registered watchtowers so assignment stays bounded
+    const MAX_WATCHTOWERS: usize = 100;
+    /// Share of an operator's stake moved to the reward pool per missed duty
+    const MISSED_DUTY_SLASH_BPS: u64 = 1_000;
+
+    /// What a duty watches
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum DutyKind {
+        // An escrow approaching its timeout
+        EscrowExpiry,
+        // A dispute approaching the end of its response window
+        DisputeTimeout,
+    }
+
+    /// Different statuses of a duty
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum DutyStatus {
+        Open,
+        Performed,
+        Missed,
+    }
+
+    /// A registered watchtower operator and its track record
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Operator {
+        pub account: H160,
+        pub stake: Balance,
+        pub performed: u32,
+        pub missed: u32,
+        pub open_duties: u32,
+        pub registered_at: u64,
+    }
+
+    /// A keeper call a watchtower must make before a deadline
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Duty {
+        pub id: u64,
+        pub kind: DutyKind,
+        pub target_id: u64,
+        pub poster: H160,
+        pub assignee: H160,
+        pub deadline: u64,
+        pub reward: Balance,
+        pub status: DutyStatus,
+    }
+
+    /// Events
+    /// Emitted when an operator registers or leaves
+    #[ink(event)]
+    pub struct WatchtowerUpdated {
+        #[ink(topic)]
+        operator: H160,
+        registered: bool,
+    }
+    /// Emitted when a duty is posted and assigned
+    #[ink(event)]
+    pub struct DutyAssigned {
+        #[ink(topic)]
+        duty_id: u64,
+        #[ink(topic)]
+        assignee: H160,
+        kind: DutyKind,
+        target_id: u64,
+        deadline: u64,
+    }
+    /// Emitted when a watchtower performs its keeper call in time
+    #[ink(event)]
+    pub struct DutyPerformed {
+        #[ink(topic)]
+        duty_id: u64,
+        #[ink(topic)]
+        assignee: H160,
+        reward: Balance,
+    }
+    /// Emitted when a watchtower lets a duty lapse
+    #[ink(event)]
+    pub struct DutyMissed {
+        #[ink(topic)]
+        duty_id: u64,
+        #[ink(topic)]
+        assignee: H160,
+        slashed: Balance,
+    }
+
+    /// Errors
+    #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Emitted when the stake is below the minimum
+        InsufficientStake,
+        /// Emitted when there is an arithmetic overflow
+        Overflow,
+        /// Emitted when the operator is already registered
+        AlreadyRegistered,
+        /// Emitted when the operator is not registered
+        NotRegistered,
+        /// Emitted when no watchtower is available to take a duty
+        NoWatchtower,
+        /// Emitted when the watchtower limit has been reached
+        TooManyWatchtowers,
+        /// Emitted when the duty is not found
+        DutyNotFound,
+        /// Emitted when the watched escrow or dispute is not found
+        TargetNotFound,
+        /// Emitted when the target is no longer waiting on a deadline
+        TargetSettled,
+        /// Emitted when the duty or operator is in the wrong status
+        InvalidStatus,
+        /// Emitted when the caller is not authorized
+        Unauthorized,
+        /// Emitted when the keeper call is outside the duty window
+        OutsideWindow,
+        /// Emitted when the duty deadline has not passed yet
+        DeadlineNotPassed,
+        /// Emitted when the transfer fails
+        TransferFailed,
+    }
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl From<Error> for HubError {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::InsufficientStake => HubError::InvalidAmount,
+                Error::Overflow => HubError::Overflow,
+                Error::AlreadyRegistered => HubError::AlreadyExists,
+                Error::NotRegistered => HubError::Unauthorized,
+                Error::NoWatchtower => HubError::NotFound,
+                Error::TooManyWatchtowers => HubError::LimitExceeded,
+                Error::DutyNotFound => HubError::NotFound,
+                Error::TargetNotFound => HubError::NotFound,
+                Error::TargetSettled => HubError::InvalidStatus,
+                Error::InvalidStatus => HubError::InvalidStatus,
+                Error::Unauthorized => HubError::Unauthorized,
+                Error::OutsideWindow => HubError::TooEarly,
+                Error::DeadlineNotPassed => HubError::TooEarly,
+                Error::TransferFailed => HubError::TransferFailed,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct Watchtower {
+        owner: H160,
+        escrow: H160,
+        arbitration: H160,
+        min_stake: Balance,
+        // How long before a deadline the keeper call may be made
+        lead_time: u64,
+        // Paid from the protocol pool on top of each duty's own reward
+        base_reward: Balance,
+        reward_pool: Balance,
+        operators: Mapping<H160, Operator>,
+        operator_list: Vec<H160>,
+        duties: Mapping<u64, Duty>,
+        duty_count: u64,
+        operator_duties: Mapping<H160, Vec<u64>>,
+    }
+
+    impl Watchtower {
+        #[ink(constructor)]
+        pub fn new(
+            escrow: H160,
+            arbitration: H160,
+            min_stake: Balance,
+            lead_time: u64,
+            base_reward: Balance,
+        ) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                escrow,
+                arbitration,
+                min_stake,
+                lead_time,
+                base_reward,
+                reward_pool: 0,
+                operators: Mapping::default(),
+                operator_list: Vec::new(),
+                duties: Mapping::default(),
+                duty_count: 0,
+                operator_duties: Mapping::default(),
+            }
+        }
+
+        /// Register as a watchtower, staking the transferred value
+        #[ink(message, payable)]
+        pub fn register(&mut self) -> Result<()> {
+            let account = self.env().caller();
+            let stake = self.transferred();
+            if stake < self.min_stake {
+                return Err(Error::InsufficientStake);
+            }
+            if self.operators.contains(account) {
+                return Err(Error::AlreadyRegistered);
+            }
+            if self.operator_list.len() >= MAX_WATCHTOWERS {
+                return Err(Error::TooManyWatchtowers);
+            }
+
+            let operator = Operator {
+                account,
+                stake,
+                performed: 0,
+                missed: 0,
+                open_duties: 0,
+                registered_at: self.env().block_timestamp(),
+            };
+            self.operators.insert(account, &operator);
+            self.operator_list.push(account);
+
+            self.env().emit_event(WatchtowerUpdated {
+                operator: account,
+                registered: true,
+            });
+
+            Ok(())
+        }
+
+        /// Leave and take the stake back once no duties are open
+        #[ink(message)]
+        pub fn deregister(&mut self) -> Result<()> {
+            let account = self.env().caller();
+            let operator = self.operators.get(account).ok_or(Error::NotRegistered)?;
+            if operator.open_duties > 0 {
+                return Err(Error::InvalidStatus);
+            }
+
+            self.operators.remove(account);
+            self.operator_list.retain(|a| *a != account);
+            if self.env().transfer(account, operator.stake.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+
+            self.env().emit_event(WatchtowerUpdated {
+                operator: account,
+                registered: false,
+            });
+
+            Ok(())
+        }
+
+        /// Add the transferred value to the protocol reward pool
+        #[ink(message, payable)]
+        pub fn fund_rewards(&mut self) -> Result<()> {
+            self.reward_pool = self
+                .reward_pool
+                .checked_add(self.transferred())
+                .ok_or(Error::Overflow)?;
+            Ok(())
+        }
+
+        /// Ask for an escrow or dispute to be watched
+        ///
+        /// The deadline is read from the target itself and the duty goes to
+        /// the most reliable registered watchtower. Any transferred value is
+        /// added to the reward.
+        #[ink(message, payable)]
+        pub fn post_duty(&mut self, kind: DutyKind, target_id: u64) -> Result<u64> {
+            let poster = self.env().caller();
+            let reward = self.transferred();
+            let deadline = self.target_deadline(kind, target_id)?;
+            if deadline <= self.env().block_timestamp() {
+                return Err(Error::TargetSettled);
+            }
+
+            let assignee = self.most_reliable().ok_or(Error::NoWatchtower)?;
+
+            self.duty_count = self.duty_count.checked_add(1).ok_or(Error::Overflow)?;
+            let duty_id = self.duty_count;
+
+            let duty = Duty {
+                id: duty_id,
+                kind,
+                target_id,
+                poster,
+                assignee,
+                deadline,
+                reward,
+                status: DutyStatus::Open,
+            };
+            self.duties.insert(duty_id, &duty);
+
+            let mut operator = self.operators.get(assignee).ok_or(Error::NotRegistered)?;
+            operator.open_duties = operator.open_duties.checked_add(1).ok_or(Error::Overflow)?;
+            self.operators.insert(assignee, &operator);
+
+            let mut duties = self.operator_duties.get(assignee).unwrap_or_default();
+            duties.push(duty_id);
+            self.operator_duties.insert(assignee, &duties);
+
+            self.env().emit_event(DutyAssigned {
+                duty_id,
+                assignee,
+                kind,
+                target_id,
+                deadline,
+            });
+
+            Ok(duty_id)
+        }
+
+        /// Make the keeper call for a duty within `lead_time` of its deadline
+        ///
+        /// The emitted event is the alert parties subscribe to; it proves the
+        /// watchtower was online at the right time.
+        #[ink(message)]
+        pub fn perform_duty(&mut self, duty_id: u64) -> Result<Balance> {
+            let caller = self.env().caller();
+            let mut duty = self.get_duty(duty_id)?;
+            if duty.assignee != caller {
+                return Err(Error::Unauthorized);
+            }
+            if duty.status != DutyStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            let now = self.env().block_timestamp();
+            if now > duty.deadline || now < duty.deadline.saturating_sub(self.lead_time) {
+                return Err(Error::OutsideWindow);
+            }
+
+            let bonus = self.base_reward.min(self.reward_pool);
+            let payout = duty.reward.checked_add(bonus).ok_or(Error::Overflow)?;
+            if payout > 0 && self.env().transfer(caller, payout.into()).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            self.reward_pool -= bonus;
+
+            duty.status = DutyStatus::Performed;
+            self.duties.insert(duty_id, &duty);
+
+            let mut operator = self.operators.get(caller).ok_or(Error::NotRegistered)?;
+            operator.performed = operator.performed.saturating_add(1);
+            operator.open_duties = operator.open_duties.saturating_sub(1);
+            self.operators.insert(caller, &operator);
+
+            self.env().emit_event(DutyPerformed {
+                duty_id,
+                assignee: caller,
+                reward: payout,
+            });
+
+            Ok(payout)
+        }
+
+        /// Record a lapsed duty against its watchtower and refund the poster
+        ///
+        /// Anyone may call this once the deadline has passed. A share of the
+        /// watchtower's stake is slashed into the reward pool, so the
+        /// operators who do show up are paid by those who don't.
+        #[ink(message)]
+        pub fn mark_missed(&mut self, duty_id: u64) -> Result<()> {
+            let mut duty = self.get_duty(duty_id)?;
+            if duty.status != DutyStatus::Open {
+                return Err(Error::InvalidStatus);
+            }
+            if self.env().block_timestamp() <= duty.deadline {
+                return Err(Error::DeadlineNotPassed);
+            }
+
+            if duty.reward > 0
+                && self
+                    .env()
+                    .transfer(duty.poster, duty.reward.into())
+                    .is_err()
+            {
+                return Err(Error::TransferFailed);
+            }
+
+            duty.status = DutyStatus::Missed;
+            self.duties.insert(duty_id, &duty);
+
+            let mut slashed = 0;
+            if let Some(mut operator) = self.operators.get(duty.assignee) {
+                slashed = operator
+                    .stake
+                    .saturating_mul(Balance::from(MISSED_DUTY_SLASH_BPS))
+                    / Balance::from(MAX_BPS);
+                operator.stake -= slashed;
+                operator.missed = operator.missed.saturating_add(1);
+                operator.open_duties = operator.open_duties.saturating_sub(1);
+                self.operators.insert(duty.assignee, &operator);
+                self.reward_pool = self.reward_pool.saturating_add(slashed);
+            }
+
+            self.env().emit_event(DutyMissed {
+                duty_id,
+                assignee: duty.assignee,
+                slashed,
+            });
+
+            Ok(())
+        }
+
+        /// Reliability of a watchtower in basis points
+        ///
+        /// Smoothed so new operators start at 50% rather than at either
+        /// extreme.
+        #[ink(message)]
+        pub fn get_reliability(&self, operator: H160) -> u32 {
+            self.operators
+                .get(operator)
+                .map(|operator| reliability(&operator))
+                .unwrap_or(0)
+        }
+
+        /// Set the minimum stake, lead time and base reward
+        #[ink(message)]
+        pub fn set_params(
+            &mut self,
+            min_stake: Balance,
+            lead_time: u64,
+            base_reward: Balance,
+        ) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            self.min_stake = min_stake;
+            self.lead_time = lead_time;
+            self.base_reward = base_reward;
+            Ok(())
+        }
+
+        /// Get operator details
+        #[ink(message)]
+        pub fn get_operator(&self, operator: H160) -> Option<Operator> {
+            self.operators.get(operator)
+        }
+
+        /// Get all registered watchtowers
+        #[ink(message)]
+        pub fn get_watchtowers(&self) -> Vec<H160> {
+            self.operator_list.clone()
+        }
+
+        /// Get duty details
+        #[ink(message)]
+        pub fn get_duty(&self, duty_id: u64) -> Result<Duty> {
+            self.duties.get(duty_id).ok_or(Error::DutyNotFound)
+        }
+
+        /// Get all duties assigned to a watchtower
+        #[ink(message)]
+        pub fn get_operator_duties(&self, operator: H160) -> Vec<u64> {
+            self.operator_duties.get(operator).unwrap_or_default()
+        }
+
+        /// Get the protocol reward pool
+        #[ink(message)]
+        pub fn get_reward_pool(&self) -> Balance {
+            self.reward_pool
+        }
+
+        fn target_deadline(&self, kind: DutyKind, target_id: u64) -> Result<u64> {
+            match kind {
+                DutyKind::EscrowExpiry => {
+                    let escrow: PaymentEscrowRef = FromAddr::from_addr(self.escrow);
+                    let details = escrow
+                        .get_escrow(target_id)
+                        .map_err(|_| Error::TargetNotFound)?;
+                    if details.status != EscrowStatus::Pending {
+                        return Err(Error::TargetSettled);
+                    }
+                    Ok(details
+                        .created_at
+                        .saturating_add(escrow.get_escrow_timeout()))
+                }
+                DutyKind::DisputeTimeout => {
+                    let arbitration: ArbitrationRef = FromAddr::from_addr(self.arbitration);
+                    let dispute = arbitration
+                        .get_dispute(target_id)
+                        .map_err(|_| Error::TargetNotFound)?;
+                    if dispute.status != DisputeStatus::AwaitingResponse {
+                        return Err(Error::TargetSettled);
+                    }
+                    Ok(dispute.response_deadline)
+                }
+            }
+        }
+
+        fn most_reliable(&self) -> Option<H160> {
+            self.operator_list
+                .iter()
+                .filter_map(|account| self.operators.get(account))
+                .max_by_key(|operator| {
+                    (
+                        reliability(operator),
+                        core::cmp::Reverse(operator.open_duties),
+                    )
+                })
+                .map(|operator| operator.account)
+        }
+
+        fn transferred(&self) -> Balance {
+            self.env()
+                .transferred_value()
+                .try_into()
+                .unwrap_or_default()
+        }
+    }
+
+    /// Laplace-smoothed share of duties performed, in basis points
+    fn reliability(operator: &Operator) -> u32 {
+        let performed = u64::from(operator.performed);
+        let total = performed + u64::from(operator.missed);
+        ((performed + 1) * MAX_BPS / (total + 2)) as u32
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use hub_types::testing::{balance_of, deploy_at, paying};
+        use hub_types::PaymentAsset;
+        use ink::prelude::string::String;
+        use ink::{ToAddr, U256};
+
+        const STAKE: Balance = 500;
+        const BASE_REWARD: Balance = 50;
+        const ESCROW_TIMEOUT: u64 = 1_000;
+        const LEAD_TIME: u64 = 100;
+
+        /// Alice opens an escrow paying bob, due `ESCROW_TIMEOUT` from now
+        fn open_escrow(escrow: &mut PaymentEscrowRef) -> u64 {
+            ink::env::test::set_caller(ink::env::test::default_accounts().alice);
+            ink::env::test::set_value_transferred(U256::from(100));
+            let escrow_id = escrow
+                .create_escrow(
+                    ink::env::test::default_accounts().bob,
+                    1,
+                    String::from("watched"),
+                    PaymentAsset::Native,
+                )
+                .unwrap();
+            ink::env::test::set_value_transferred(U256::zero());
+            escrow_id
+        }
+
+        /// A watchtower over an off-chain escrow, with a funded reward pool
+        fn watchtower() -> (Watchtower, PaymentEscrowRef) {
+            let accounts = ink::env::test::default_accounts();
+            deploy_at(H160::from([0xA7; 20]), 0);
+
+            ink::env::test::set_caller(accounts.alice);
+            let code_hash =
+                ink::env::test::upload_code::<ink::env::DefaultEnvironment, PaymentEscrowRef>();
+            let escrow = PaymentEscrowRef::new(ESCROW_TIMEOUT)
+                .code_hash(code_hash)
+                .endowment(U256::zero())
+                .salt_bytes(None)
+                .instantiate();
+            let mut watchtower = Watchtower::new(
+                escrow.to_addr(),
+                H160::from([0xA8; 20]),
+                STAKE,
+                LEAD_TIME,
+                BASE_REWARD,
+            );
+            paying(accounts.alice, 4 * BASE_REWARD, || {
+                watchtower.fund_rewards()
+            })
+            .unwrap();
+            (watchtower, escrow)
+        }
+
+        #[ink::test]
+        fn operators_stake_to_register_and_withdraw_on_leaving() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut watchtower, mut escrow) = watchtower();
+            let escrow_id = open_escrow(&mut escrow);
+
+            assert_eq!(
+                watchtower.post_duty(DutyKind::EscrowExpiry, escrow_id),
+                Err(Error::NoWatchtower)
+            );
+            assert_eq!(
+                paying(accounts.alice, STAKE - 1, || watchtower.register()),
+                Err(Error::InsufficientStake)
+            );
+            paying(accounts.charlie, STAKE, || watchtower.register()).unwrap();
+            assert_eq!(
+                paying(accounts.charlie, STAKE, || watchtower.register()),
+                Err(Error::AlreadyRegistered)
+            );
+            assert_eq!(watchtower.get_watchtowers(), vec![accounts.charlie]);
+            assert_eq!(
+                watchtower.get_operator(accounts.charlie).unwrap().stake,
+                STAKE
+            );
+
+            ink::env::test::set_caller(accounts.alice);
+            let duty_id = watchtower
+                .post_duty(DutyKind::EscrowExpiry, escrow_id)
+                .unwrap();
+            let duty = watchtower.get_duty(duty_id).unwrap();
+            assert_eq!(duty.assignee, accounts.charlie);
+            assert_eq!(duty.deadline, ESCROW_TIMEOUT);
+
+            // The stake stays locked while a duty is open
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(watchtower.deregister(), Err(Error::NotRegistered));
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(watchtower.deregister(), Err(Error::InvalidStatus));
+            assert_eq!(watchtower.perform_duty(duty_id), Err(Error::OutsideWindow));
+
+            ink::env::test::set_block_timestamp(ESCROW_TIMEOUT - LEAD_TIME);
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(watchtower.perform_duty(duty_id), Err(Error::Unauthorized));
+            ink::env::test::set_caller(accounts.charlie);
+            let before = balance_of(accounts.charlie);
+            assert_eq!(watchtower.perform_duty(duty_id), Ok(BASE_REWARD));
+            assert_eq!(balance_of(accounts.charlie), before + BASE_REWARD);
+            assert_eq!(watchtower.get_reward_pool(), 3 * BASE_REWARD);
+            assert_eq!(watchtower.perform_duty(duty_id), Err(Error::InvalidStatus));
+
+            let before = balance_of(accounts.charlie);
+            watchtower.deregister().unwrap();
+            assert_eq!(balance_of(accounts.charlie), before + STAKE);
+            assert_eq!(watchtower.get_operator(accounts.charlie), None);
+            assert!(watchtower.get_watchtowers().is_empty());
+        }
+
+        #[ink::test]
+        fn missed_duties_slash_the_stake_and_deprioritize_the_operator() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut watchtower, mut escrow) = watchtower();
+            let escrow_id = open_escrow(&mut escrow);
+            paying(accounts.bob, STAKE, || watchtower.register()).unwrap();
+            paying(accounts.charlie, STAKE, || watchtower.register()).unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            let duty_id = watchtower
+                .post_duty(DutyKind::EscrowExpiry, escrow_id)
+                .unwrap();
+            let assignee = watchtower.get_duty(duty_id).unwrap().assignee;
+            let other = if assignee == accounts.bob {
+                accounts.charlie
+            } else {
+                accounts.bob
+            };
+
+            assert_eq!(
+                watchtower.mark_missed(duty_id),
+                Err(Error::DeadlineNotPassed)
+            );
+            ink::env::test::set_block_timestamp(ESCROW_TIMEOUT + 1);
+            ink::env::test::set_caller(assignee);
+            assert_eq!(watchtower.perform_duty(duty_id), Err(Error::OutsideWindow));
+
+            // Anyone may record the lapse
+            ink::env::test::set_caller(accounts.django);
+            watchtower.mark_missed(duty_id).unwrap();
+            assert_eq!(watchtower.mark_missed(duty_id), Err(Error::InvalidStatus));
+            assert_eq!(
+                watchtower.get_duty(duty_id).unwrap().status,
+                DutyStatus::Missed
+            );
+            let slashed = STAKE / 10;
+            let operator = watchtower.get_operator(assignee).unwrap();
+            assert_eq!(operator.stake, STAKE - slashed);
+            assert_eq!(operator.missed, 1);
+            assert_eq!(operator.open_duties, 0);
+            assert_eq!(watchtower.get_reward_pool(), 4 * BASE_REWARD + slashed);
+            assert!(watchtower.get_reliability(assignee) < watchtower.get_reliability(other));
+
+            // New duties go to the more reliable operator
+            let escrow_id = open_escrow(&mut escrow);
+            ink::env::test::set_caller(accounts.alice);
+            let duty_id = watchtower
+                .post_duty(DutyKind::EscrowExpiry, escrow_id)
+                .unwrap();
+            assert_eq!(watchtower.get_duty(duty_id).unwrap().assignee, other);
+
+            // Leaving returns only what is left of the stake
+            let before = balance_of(assignee);
+            ink::env::test::set_caller(assignee);
+            watchtower.deregister().unwrap();
+            assert_eq!(balance_of(assignee), before + STAKE - slashed);
+        }
+
+        #[ink::test]
+        fn only_the_owner_sets_params() {
+            let accounts = ink::env::test::default_accounts();
+            let (mut watchtower, _) = watchtower();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(
+                watchtower.set_params(STAKE, LEAD_TIME, 0),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller(accounts.alice);
+            watchtower.set_params(2 * STAKE, LEAD_TIME, 0).unwrap();
+            assert_eq!(
+                paying(accounts.bob, STAKE, || watchtower.register()),
+                Err(Error::InsufficientStake)
+            );
+        }
+    }
+}